@@ -2,8 +2,18 @@ use quote::{format_ident, quote};
 use std::io::Write;
 
 pub fn generate<W: Write>(modules: Vec<&str>, out: &mut W) {
-    let modules_tokens = modules.into_iter().map(|module| {
+    generate_modules(modules.into_iter().map(|module| (module, true)), out);
+}
+
+/// Generate the declarations of the dialect `modules`, each with whether it is behind a cargo
+/// feature named like the module
+pub fn generate_modules<'a, W: Write>(
+    modules: impl IntoIterator<Item = (&'a str, bool)>,
+    out: &mut W,
+) {
+    let modules_tokens = modules.into_iter().map(|(module, feature_gated)| {
         let module_ident = format_ident!("{}", module);
+        let feature_gate = feature_gated.then(|| quote!(#[cfg(feature = #module)]));
 
         quote! {
             #[allow(non_camel_case_types)]
@@ -13,7 +23,9 @@ pub fn generate<W: Write>(modules: Vec<&str>, out: &mut W) {
             #[allow(clippy::unnecessary_cast)]
             #[allow(clippy::bad_bit_mask)]
             #[allow(clippy::suspicious_else_formatting)]
-            #[cfg(feature = #module)]
+            #[allow(clippy::large_enum_variant)]
+            #[allow(deprecated)]
+            #feature_gate
             pub mod #module_ident;
         }
     });
@@ -1,26 +1,91 @@
 use std::path::PathBuf;
 
 use clap::Parser;
-use mavlink_bindgen::{emit_cargo_build_messages, format_generated_code, generate, BindGenError};
+use mavlink_bindgen::{
+    emit_cargo_build_messages, format_generated_binding, format_generated_code, read_message_list,
+    BindGenError, Generator,
+};
 
 #[derive(Parser)]
 /// Generate Rust bindings from MAVLink message dialect XML files.
 struct Cli {
     /// Path to the directory containing the MAVLink dialect definitions.
-    definitions_dir: PathBuf,
+    #[arg(required_unless_present = "dialect")]
+    definitions_dir: Option<PathBuf>,
     /// Path to the directory where the code is generated into, must already exist.
-    destination_dir: PathBuf,
+    #[arg(required_unless_present = "dialect")]
+    destination_dir: Option<PathBuf>,
+    /// generate only this dialect definition into the single file `--out`, instead of the
+    /// dialects of `definitions_dir`
+    #[arg(
+        long,
+        requires = "out",
+        conflicts_with_all = ["definitions_dir", "destination_dir", "emit_cargo_build_messages"]
+    )]
+    dialect: Option<PathBuf>,
+    /// file the `--dialect` is generated into
+    #[arg(long, requires = "dialect")]
+    out: Option<PathBuf>,
+    /// resolve the cargo features of the `--dialect` as if exactly these were enabled, instead of
+    /// leaving them to the crate declaring the generated module
+    #[arg(long, value_delimiter = ',', requires = "dialect")]
+    features: Option<Vec<String>>,
     /// format code generated code
     #[arg(long)]
     format_generated_code: bool,
     /// prints cargo build messages indicating when the code has to be rebuild
     #[arg(long)]
     emit_cargo_build_messages: bool,
+    /// only generate typed structs for these messages, others are kept as raw payloads
+    #[arg(long, value_delimiter = ',')]
+    messages: Option<Vec<String>>,
+    /// only generate typed structs for the messages listed in this file, see `--messages`
+    #[arg(long, conflicts_with = "messages")]
+    messages_file: Option<PathBuf>,
+    /// directory of custom dialect definitions, generated without cargo feature gates and able
+    /// to include the definitions of `definitions_dir`, may be repeated. With `--dialect`, a
+    /// directory its includes are also read from
+    #[arg(long)]
+    custom_dialects_dir: Vec<PathBuf>,
 }
 
 pub fn main() -> Result<(), BindGenError> {
     let args = Cli::parse();
-    let result = generate(args.definitions_dir, args.destination_dir)?;
+    let messages = match &args.messages_file {
+        Some(path) => Some(read_message_list(path)?),
+        None => args.messages,
+    };
+    let mut generator = Generator::new();
+    for dir in args.custom_dialects_dir {
+        generator = generator.with_custom_dialects_dir(dir);
+    }
+    if let Some(messages) = &messages {
+        let messages: Vec<&str> = messages.iter().map(String::as_str).collect();
+        generator = generator.with_messages(&messages);
+    }
+
+    if let (Some(dialect), Some(out)) = (args.dialect, args.out) {
+        let features: Option<Vec<&str>> = args
+            .features
+            .as_ref()
+            .map(|features| features.iter().map(String::as_str).collect());
+        let binding = generator.generate_dialect(dialect, out, features.as_deref())?;
+
+        if args.format_generated_code {
+            format_generated_binding(&binding);
+        }
+
+        return Ok(());
+    }
+
+    // both are required without `--dialect`
+    let (Some(definitions_dir), Some(destination_dir)) =
+        (args.definitions_dir, args.destination_dir)
+    else {
+        unreachable!()
+    };
+    generator = generator.with_definitions_dir(definitions_dir);
+    let result = generator.generate(destination_dir)?;
 
     if args.format_generated_code {
         format_generated_code(&result);
@@ -0,0 +1,233 @@
+//! Comparison of two MAVLink profiles, to check whether a new version of a dialect is compatible
+//! with code generated from an older one.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+
+use crate::parser::{extra_crc, MavEnum, MavMessage, MavProfile};
+
+/// Difference between two versions of a dialect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialectChange {
+    MessageAdded {
+        message: String,
+        id: u32,
+    },
+    MessageRemoved {
+        message: String,
+        id: u32,
+    },
+    MessageIdChanged {
+        message: String,
+        old: u32,
+        new: u32,
+    },
+    /// The CRC_EXTRA of the message changed, so frames of both versions are incompatible
+    CrcExtraChanged {
+        message: String,
+        old: u8,
+        new: u8,
+    },
+    FieldAdded {
+        message: String,
+        field: String,
+    },
+    FieldRemoved {
+        message: String,
+        field: String,
+    },
+    /// The type, enum or extension status of the field changed
+    FieldChanged {
+        message: String,
+        field: String,
+    },
+    EnumAdded {
+        name: String,
+    },
+    EnumRemoved {
+        name: String,
+    },
+    EnumEntryAdded {
+        name: String,
+        entry: String,
+    },
+    EnumEntryRemoved {
+        name: String,
+        entry: String,
+    },
+    EnumEntryValueChanged {
+        name: String,
+        entry: String,
+        old: Option<u32>,
+        new: Option<u32>,
+    },
+}
+
+impl DialectChange {
+    /// Whether code or peers using the old version may be broken by this change.
+    ///
+    /// Additions are not breaking, except through the changes they cause to the CRC_EXTRA of
+    /// their message which are reported separately.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(
+            self,
+            Self::MessageAdded { .. }
+                | Self::FieldAdded { .. }
+                | Self::EnumAdded { .. }
+                | Self::EnumEntryAdded { .. }
+        )
+    }
+}
+
+impl Display for DialectChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MessageAdded { message, id } => write!(f, "added message {message} ({id})"),
+            Self::MessageRemoved { message, id } => write!(f, "removed message {message} ({id})"),
+            Self::MessageIdChanged { message, old, new } => {
+                write!(f, "changed id of message {message} from {old} to {new}")
+            }
+            Self::CrcExtraChanged { message, old, new } => {
+                write!(
+                    f,
+                    "changed CRC_EXTRA of message {message} from {old} to {new}"
+                )
+            }
+            Self::FieldAdded { message, field } => write!(f, "added field {message}.{field}"),
+            Self::FieldRemoved { message, field } => write!(f, "removed field {message}.{field}"),
+            Self::FieldChanged { message, field } => write!(f, "changed field {message}.{field}"),
+            Self::EnumAdded { name } => write!(f, "added enum {name}"),
+            Self::EnumRemoved { name } => write!(f, "removed enum {name}"),
+            Self::EnumEntryAdded { name, entry } => write!(f, "added entry {name}.{entry}"),
+            Self::EnumEntryRemoved { name, entry } => write!(f, "removed entry {name}.{entry}"),
+            Self::EnumEntryValueChanged {
+                name,
+                entry,
+                old,
+                new,
+            } => write!(
+                f,
+                "changed value of entry {name}.{entry} from {old:?} to {new:?}"
+            ),
+        }
+    }
+}
+
+/// List the changes from the `old` to the `new` profile, sorted by message and enum name.
+///
+/// Enums are named as in the generated code, messages, fields and enum entries as in the definitions.
+///
+/// Extension fields are only compared if the profiles were parsed with the `emit-extensions`
+/// feature enabled.
+pub fn diff_profiles(old: &MavProfile, new: &MavProfile) -> Vec<DialectChange> {
+    let mut changes = vec![];
+
+    for name in sorted_keys(&old.messages, &new.messages) {
+        match (old.messages.get(name), new.messages.get(name)) {
+            (Some(old), Some(new)) => diff_messages(old, new, &mut changes),
+            (Some(old), None) => changes.push(DialectChange::MessageRemoved {
+                message: name.clone(),
+                id: old.id,
+            }),
+            (None, Some(new)) => changes.push(DialectChange::MessageAdded {
+                message: name.clone(),
+                id: new.id,
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    for name in sorted_keys(&old.enums, &new.enums) {
+        match (old.enums.get(name), new.enums.get(name)) {
+            (Some(old), Some(new)) => diff_enums(old, new, &mut changes),
+            (Some(_), None) => changes.push(DialectChange::EnumRemoved { name: name.clone() }),
+            (None, Some(_)) => changes.push(DialectChange::EnumAdded { name: name.clone() }),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    changes
+}
+
+fn sorted_keys<'a, T>(
+    old: &'a HashMap<String, T>,
+    new: &'a HashMap<String, T>,
+) -> BTreeSet<&'a String> {
+    old.keys().chain(new.keys()).collect()
+}
+
+fn diff_messages(old: &MavMessage, new: &MavMessage, changes: &mut Vec<DialectChange>) {
+    let message = &old.name;
+    if old.id != new.id {
+        changes.push(DialectChange::MessageIdChanged {
+            message: message.clone(),
+            old: old.id,
+            new: new.id,
+        });
+    }
+    let (old_crc, new_crc) = (extra_crc(old), extra_crc(new));
+    if old_crc != new_crc {
+        changes.push(DialectChange::CrcExtraChanged {
+            message: message.clone(),
+            old: old_crc,
+            new: new_crc,
+        });
+    }
+
+    for field in &old.fields {
+        match new.fields.iter().find(|new| new.name == field.name) {
+            Some(new_field) => {
+                if new_field.mavtype != field.mavtype
+                    || new_field.enumtype != field.enumtype
+                    || new_field.is_extension != field.is_extension
+                {
+                    changes.push(DialectChange::FieldChanged {
+                        message: message.clone(),
+                        field: field.name.clone(),
+                    });
+                }
+            }
+            None => changes.push(DialectChange::FieldRemoved {
+                message: message.clone(),
+                field: field.name.clone(),
+            }),
+        }
+    }
+    for field in &new.fields {
+        if !old.fields.iter().any(|old| old.name == field.name) {
+            changes.push(DialectChange::FieldAdded {
+                message: message.clone(),
+                field: field.name.clone(),
+            });
+        }
+    }
+}
+
+fn diff_enums(old: &MavEnum, new: &MavEnum, changes: &mut Vec<DialectChange>) {
+    let name = &old.name;
+    for entry in &old.entries {
+        match new.entries.iter().find(|new| new.name == entry.name) {
+            Some(new_entry) if new_entry.value != entry.value => {
+                changes.push(DialectChange::EnumEntryValueChanged {
+                    name: name.clone(),
+                    entry: entry.name.clone(),
+                    old: entry.value,
+                    new: new_entry.value,
+                });
+            }
+            Some(_) => {}
+            None => changes.push(DialectChange::EnumEntryRemoved {
+                name: name.clone(),
+                entry: entry.name.clone(),
+            }),
+        }
+    }
+    for entry in &new.entries {
+        if !old.entries.iter().any(|old| old.name == entry.name) {
+            changes.push(DialectChange::EnumEntryAdded {
+                name: name.clone(),
+                entry: entry.name.clone(),
+            });
+        }
+    }
+}
@@ -26,4 +26,16 @@ pub enum BindGenError {
         source: std::io::Error,
         dest_path: std::path::PathBuf,
     },
+    /// Represents a failure to create the directory of the Rust files of the messages of a dialect.
+    #[error("Could not create Rust bindings directory {dest_path}: {source}")]
+    CouldNotCreateRustBindingsDirectory {
+        source: std::io::Error,
+        dest_path: std::path::PathBuf,
+    },
+    /// Represents a failure to read a file listing the messages to generate.
+    #[error("Could not read message list {path}: {source}")]
+    CouldNotReadMessageList {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
 }
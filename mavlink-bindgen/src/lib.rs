@@ -1,11 +1,13 @@
 pub use crate::error::BindGenError;
-use std::fs::{read_dir, File};
-use std::io::BufWriter;
+use std::collections::HashSet;
+use std::fs::{create_dir_all, read_dir, read_to_string, File};
+use std::io::{BufWriter, Write};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub mod binder;
+pub mod diff;
 pub mod error;
 pub mod parser;
 mod util;
@@ -30,53 +32,171 @@ pub fn generate<P1: AsRef<Path>, P2: AsRef<Path>>(
     definitions_dir: P1,
     destination_dir: P2,
 ) -> Result<GeneratedBindings, BindGenError> {
-    _generate(definitions_dir.as_ref(), destination_dir.as_ref())
+    Generator::new()
+        .with_definitions_dir(definitions_dir.as_ref())
+        .generate(destination_dir)
 }
 
-fn _generate(
-    definitions_dir: &Path,
-    destination_dir: &Path,
+/// Generate Rust MAVLink dialect bindings like [`generate`], with typed structs only for the
+/// messages named in `messages`.
+///
+/// Other messages of the dialects are still accepted, as `MavMessage::Raw` payloads that can be
+/// forwarded unchanged. This keeps the generated code small for applications such as routers that
+/// only need to understand a few messages.
+pub fn generate_subset<P1: AsRef<Path>, P2: AsRef<Path>>(
+    definitions_dir: P1,
+    destination_dir: P2,
+    messages: &[&str],
 ) -> Result<GeneratedBindings, BindGenError> {
-    let mut bindings = vec![];
+    Generator::new()
+        .with_definitions_dir(definitions_dir.as_ref())
+        .with_messages(messages)
+        .generate(destination_dir)
+}
 
-    for entry_maybe in read_dir(definitions_dir).map_err(|source| {
-        BindGenError::CouldNotReadDefinitionsDirectory {
-            source,
-            path: definitions_dir.to_path_buf(),
-        }
-    })? {
-        let entry = entry_maybe.map_err(|source| {
-            BindGenError::CouldNotReadDirectoryEntryInDefinitionsDirectory {
-                source,
-                path: definitions_dir.to_path_buf(),
-            }
-        })?;
+/// Read the names of the messages to pass to [`generate_subset`] from the file at `path`.
+///
+/// Names are separated by whitespace or commas, and everything after a `#` on a line is a
+/// comment.
+pub fn read_message_list<P: AsRef<Path>>(path: P) -> Result<Vec<String>, BindGenError> {
+    let path = path.as_ref();
+    let list = read_to_string(path).map_err(|source| BindGenError::CouldNotReadMessageList {
+        source,
+        path: path.to_path_buf(),
+    })?;
+    Ok(list
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
 
-        let definition_file = PathBuf::from(entry.file_name());
-        let module_name = util::to_module_name(&definition_file);
+/// Builder generating the dialects of several definition directories, such as custom dialects
+/// along with the dialects bundled with `mavlink`.
+///
+/// ```no_run
+/// # fn main() -> Result<(), mavlink_bindgen::BindGenError> {
+/// mavlink_bindgen::Generator::new()
+///     .with_custom_dialects_dir("definitions")
+///     .with_definitions_dir("mavlink/message_definitions/v1.0")
+///     .generate(std::env::var("OUT_DIR").unwrap())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Every definition file of the directories is generated into its own module. Includes are read
+/// from the directory of the including file, or else from the first directory added that has
+/// them, so custom dialects can include `common.xml`. A file named like a file of a directory
+/// added before it is skipped, which allows custom definitions to replace bundled ones.
+#[derive(Debug, Clone, Default)]
+pub struct Generator {
+    /// Directories with whether their modules are behind a cargo feature named like the module
+    directories: Vec<(PathBuf, bool)>,
+    messages: Option<HashSet<String>>,
+}
 
-        let definition_rs = PathBuf::from(&module_name).with_extension("rs");
+impl Generator {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        let dest_path = destination_dir.join(definition_rs);
-        let mut outf = BufWriter::new(File::create(&dest_path).map_err(|source| {
-            BindGenError::CouldNotCreateRustBindingsFile {
-                source,
-                dest_path: dest_path.clone(),
-            }
-        })?);
+    /// Add a directory of definitions whose modules are each behind a cargo feature named like
+    /// the module, as the dialects of `mavlink` are
+    pub fn with_definitions_dir<P: Into<PathBuf>>(mut self, definitions_dir: P) -> Self {
+        self.directories.push((definitions_dir.into(), true));
+        self
+    }
 
-        // generate code
-        parser::generate(definitions_dir, &definition_file, &mut outf)?;
+    /// Add a directory of definitions whose modules are always compiled, for custom dialects
+    /// that have no cargo feature
+    pub fn with_custom_dialects_dir<P: Into<PathBuf>>(mut self, definitions_dir: P) -> Self {
+        self.directories.push((definitions_dir.into(), false));
+        self
+    }
 
-        bindings.push(GeneratedBinding {
-            module_name,
-            mavlink_xml: entry.path(),
-            rust_module: dest_path,
-        });
+    /// Only generate typed structs for the messages named in `messages`, see [`generate_subset`]
+    pub fn with_messages(mut self, messages: &[&str]) -> Self {
+        self.messages = Some(messages.iter().map(|name| name.to_string()).collect());
+        self
     }
 
-    // output mod.rs
-    {
+    /// Generate the dialects into `destination_dir`, along with the `mod.rs` declaring them.
+    ///
+    /// Each dialect is written to `<module>.rs`, with the enums and `MavMessage`, and the module
+    /// of each of its messages to `<module>/<message>.rs`, so that incremental builds only
+    /// recompile the messages that changed.
+    ///
+    /// If successful returns paths of generated bindings linked to their dialect definitions files.
+    pub fn generate<P: AsRef<Path>>(
+        &self,
+        destination_dir: P,
+    ) -> Result<GeneratedBindings, BindGenError> {
+        let destination_dir = destination_dir.as_ref();
+        let include_dirs: Vec<PathBuf> = self
+            .directories
+            .iter()
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        let mut bindings = vec![];
+        let mut modules = vec![];
+
+        for (definitions_dir, feature_gated) in &self.directories {
+            for entry_maybe in read_dir(definitions_dir).map_err(|source| {
+                BindGenError::CouldNotReadDefinitionsDirectory {
+                    source,
+                    path: definitions_dir.to_path_buf(),
+                }
+            })? {
+                let entry = entry_maybe.map_err(|source| {
+                    BindGenError::CouldNotReadDirectoryEntryInDefinitionsDirectory {
+                        source,
+                        path: definitions_dir.to_path_buf(),
+                    }
+                })?;
+
+                let definition_file = PathBuf::from(entry.file_name());
+                let module_name = util::to_module_name(&definition_file);
+                if modules.iter().any(|(module, _)| *module == module_name) {
+                    continue;
+                }
+
+                let definition_rs = PathBuf::from(&module_name).with_extension("rs");
+                let dest_path = destination_dir.join(definition_rs);
+
+                // generate code
+                let dialect = parser::generate_split(
+                    definitions_dir,
+                    &include_dirs,
+                    &definition_file,
+                    self.messages.as_ref(),
+                )?;
+                write_rust_file(&dest_path, &dialect.dialect)?;
+
+                // the modules of the messages are in the directory named like the dialect
+                let messages_dir = destination_dir.join(&module_name);
+                create_dir_all(&messages_dir).map_err(|source| {
+                    BindGenError::CouldNotCreateRustBindingsDirectory {
+                        source,
+                        dest_path: messages_dir.clone(),
+                    }
+                })?;
+                for (message_module, code) in &dialect.messages {
+                    let message_rs = PathBuf::from(message_module).with_extension("rs");
+                    write_rust_file(&messages_dir.join(message_rs), code)?;
+                }
+
+                modules.push((module_name.clone(), *feature_gated));
+                bindings.push(GeneratedBinding {
+                    module_name,
+                    mavlink_xml: entry.path(),
+                    rust_module: dest_path,
+                });
+            }
+        }
+
+        // output mod.rs
         let dest_path = destination_dir.join("mod.rs");
         let mut outf = File::create(&dest_path).map_err(|source| {
             BindGenError::CouldNotCreateRustBindingsFile {
@@ -86,11 +206,10 @@ fn _generate(
         })?;
 
         // generate code
-        binder::generate(
-            bindings
+        binder::generate_modules(
+            modules
                 .iter()
-                .map(|binding| binding.module_name.deref())
-                .collect(),
+                .map(|(module, feature_gated)| (module.deref(), *feature_gated)),
             &mut outf,
         );
 
@@ -99,20 +218,138 @@ fn _generate(
             mod_rs: dest_path,
         })
     }
+
+    /// Generate the dialect of the single `definition_file` into the file `dest_path`, with the
+    /// modules of its messages inline, so that it can be checked in or declared as a module
+    /// without a build script.
+    ///
+    /// Includes are read from the directory of `definition_file`, or else from the directories
+    /// added. If `features` is given, the cargo features of the generated code, such as `serde`,
+    /// are resolved as if exactly these features were enabled. Otherwise they are features of the
+    /// crate declaring the module.
+    ///
+    /// If successful returns the path of the generated binding linked to its definition file.
+    pub fn generate_dialect<P1: AsRef<Path>, P2: AsRef<Path>>(
+        &self,
+        definition_file: P1,
+        dest_path: P2,
+        features: Option<&[&str]>,
+    ) -> Result<GeneratedBinding, BindGenError> {
+        let definition_file = definition_file.as_ref();
+        let dest_path = dest_path.as_ref();
+        let definitions_dir = definition_file.parent().unwrap_or(Path::new(""));
+        let definition_name = definition_file
+            .file_name()
+            .map_or(definition_file, Path::new);
+        let include_dirs: Vec<PathBuf> = self
+            .directories
+            .iter()
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        let features: Option<HashSet<String>> =
+            features.map(|features| features.iter().map(|name| name.to_string()).collect());
+
+        let mut outf = BufWriter::new(File::create(dest_path).map_err(|source| {
+            BindGenError::CouldNotCreateRustBindingsFile {
+                source,
+                dest_path: dest_path.to_path_buf(),
+            }
+        })?);
+        parser::generate_standalone(
+            definitions_dir,
+            &include_dirs,
+            definition_name,
+            self.messages.as_ref(),
+            features.as_ref(),
+            &mut outf,
+        )?;
+
+        Ok(GeneratedBinding {
+            module_name: util::to_module_name(definition_name),
+            mavlink_xml: definition_file.to_path_buf(),
+            rust_module: dest_path.to_path_buf(),
+        })
+    }
+}
+
+fn write_rust_file(dest_path: &Path, code: &str) -> Result<(), BindGenError> {
+    let mut outf = BufWriter::new(File::create(dest_path).map_err(|source| {
+        BindGenError::CouldNotCreateRustBindingsFile {
+            source,
+            dest_path: dest_path.to_path_buf(),
+        }
+    })?);
+    writeln!(outf, "{code}").map_err(|source| BindGenError::CouldNotCreateRustBindingsFile {
+        source,
+        dest_path: dest_path.to_path_buf(),
+    })
+}
+
+/// Generate a Rust MAVLink dialect binding from the XML definition `xml` into `destination_dir`.
+///
+/// The binding is written to `<module_name>.rs` as the public module `module_name`, so it can be
+/// used with `include!(concat!(env!("OUT_DIR"), "/<module_name>.rs"))`. This allows small
+/// dialects, for example for tests, to be defined without separate definition files. Included
+/// definition files are read relative to the current directory.
+///
+/// If successful returns the path of the generated binding.
+pub fn generate_from_str<P: AsRef<Path>>(
+    xml: &str,
+    module_name: &str,
+    destination_dir: P,
+) -> Result<PathBuf, BindGenError> {
+    let dest_path = destination_dir
+        .as_ref()
+        .join(PathBuf::from(module_name).with_extension("rs"));
+    let mut outf = BufWriter::new(File::create(&dest_path).map_err(|source| {
+        BindGenError::CouldNotCreateRustBindingsFile {
+            source,
+            dest_path: dest_path.clone(),
+        }
+    })?);
+
+    parser::generate_from_str(Path::new(""), xml, module_name, &mut outf)?;
+
+    Ok(dest_path)
+}
+
+/// List the changes between the dialect defined in the `old_definition` file and the one defined in
+/// the `new_definition` file, including the definitions they include.
+///
+/// Included definition files are read from the directory of the file including them.
+pub fn diff_definitions<P1: AsRef<Path>, P2: AsRef<Path>>(
+    old_definition: P1,
+    new_definition: P2,
+) -> Result<Vec<diff::DialectChange>, BindGenError> {
+    let old = parse_definition(old_definition.as_ref())?;
+    let new = parse_definition(new_definition.as_ref())?;
+    Ok(diff::diff_profiles(&old, &new))
+}
+
+fn parse_definition(definition: &Path) -> Result<parser::MavProfile, BindGenError> {
+    let definitions_dir = definition.parent().unwrap_or(Path::new(""));
+    let definition_file = definition.file_name().map_or(definition, Path::new);
+    parser::parse_profile(definitions_dir, definition_file, &mut HashSet::new())
 }
 
 /// Formats generated code using `rustfmt`.
 pub fn format_generated_code(result: &GeneratedBindings) {
-    if let Err(error) = Command::new("rustfmt")
-        .args(
-            result
-                .bindings
-                .iter()
-                .map(|binding| binding.rust_module.clone()),
-        )
-        .arg(result.mod_rs.clone())
-        .status()
-    {
+    format_rust_files(
+        result
+            .bindings
+            .iter()
+            .map(|binding| binding.rust_module.clone())
+            .chain([result.mod_rs.clone()]),
+    );
+}
+
+/// Formats a binding generated by [`Generator::generate_dialect`] using `rustfmt`.
+pub fn format_generated_binding(binding: &GeneratedBinding) {
+    format_rust_files([binding.rust_module.clone()]);
+}
+
+fn format_rust_files(paths: impl IntoIterator<Item = PathBuf>) {
+    if let Err(error) = Command::new("rustfmt").args(paths).status() {
         eprintln!("{error}");
     }
 }
@@ -4,13 +4,13 @@ use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fs::File;
-use std::io::{BufReader, Write};
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use quick_xml::{events::Event, Reader};
 
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
 use quote::{format_ident, quote};
 
 #[cfg(feature = "serde")]
@@ -56,6 +56,19 @@ impl MavProfile {
     /// update this enum with information about whether it is a bitmask, and what
     /// is the desired width of such.
     fn update_enums(mut self) -> Self {
+        // fields of the enums marked as bitmasks hold flags even without `display="bitmask"`
+        for msg in self.messages.values_mut() {
+            for field in &mut msg.fields {
+                let is_bitmask_enum = field
+                    .enumtype
+                    .as_ref()
+                    .and_then(|enum_name| self.enums.get(enum_name))
+                    .is_some_and(|enm| enm.bitmask);
+                if is_bitmask_enum && field.display.is_none() {
+                    field.display = Some("bitmask".to_string());
+                }
+            }
+        }
         for msg in self.messages.values() {
             for field in &msg.fields {
                 if let Some(enum_name) = &field.enumtype {
@@ -72,9 +85,44 @@ impl MavProfile {
                 }
             }
         }
+        // bitmasks used by no field get the smallest type holding all their flags
+        for enm in self.enums.values_mut() {
+            if enm.bitmask && enm.bitfield.is_none() {
+                enm.bitfield = Some(enm.smallest_bitfield_type());
+            }
+        }
         self
     }
 
+    /// Split the profile into the profile of the messages named in `messages`, which get typed
+    /// structs, and the other messages, which are kept as raw payloads.
+    ///
+    /// Only the enums used by the typed messages are kept.
+    pub fn subset(mut self, messages: &HashSet<String>) -> (Self, Vec<MavMessage>) {
+        let mut raw: Vec<MavMessage> = self
+            .messages
+            .values()
+            .filter(|msg| !messages.contains(&msg.name))
+            .cloned()
+            .collect();
+        raw.sort_by_key(|msg| msg.id);
+        self.messages.retain(|name, _| messages.contains(name));
+
+        let used_enums: HashSet<&String> = self
+            .messages
+            .values()
+            .flat_map(|msg| msg.fields.iter())
+            .filter_map(|field| field.enumtype.as_ref())
+            .collect();
+        self.enums = self
+            .enums
+            .iter()
+            .filter(|(name, _)| used_enums.contains(name))
+            .map(|(name, enm)| (name.clone(), enm.clone()))
+            .collect();
+        (self, raw)
+    }
+
     //TODO verify this is no longer necessary since we're supporting both mavlink1 and mavlink2
     //    ///If we are not using Mavlink v2, remove messages with id's > 254
     //    fn update_messages(mut self) -> Self {
@@ -90,14 +138,65 @@ impl MavProfile {
         quote!(#![doc = "This file was automatically generated, do not edit"])
     }
 
-    /// Emit rust messages
-    fn emit_msgs(&self) -> Vec<TokenStream> {
-        self.messages.values().map(|d| d.emit_rust()).collect()
+    /// Emit the declarations of the modules of the messages and the re-exports of their structs,
+    /// with the code of the messages inside them if `inline`, or else to be read from the files
+    /// of [`emit_message_files`](Self::emit_message_files)
+    fn emit_message_modules(&self, inline: bool) -> Vec<TokenStream> {
+        self.messages
+            .values()
+            .map(|msg| {
+                let module = msg.emit_module_name();
+                let msg_name = msg.emit_struct_name();
+                let wip_cfg = emit_wip_cfg(msg.wip);
+                let body = if inline {
+                    let msg_code = msg.emit_rust();
+                    quote!({
+                        use super::*;
+                        #msg_code
+                    })
+                } else {
+                    quote!(;)
+                };
+                quote! {
+                    #wip_cfg
+                    mod #module #body
+                    #wip_cfg
+                    pub use self::#module::#msg_name;
+                }
+            })
+            .collect()
+    }
+
+    /// Emit the code of the module of each message, with the name of the module
+    fn emit_message_files(&self) -> Vec<(String, TokenStream)> {
+        self.messages
+            .values()
+            .map(|msg| {
+                let msg_code = msg.emit_rust();
+                let code = quote! {
+                    use super::*;
+                    #msg_code
+                };
+                (msg.emit_module_name().to_string(), code)
+            })
+            .collect()
     }
 
     /// Emit rust enums
+    ///
+    /// Enums marked as work in progress are only gated if no stable message uses them.
     fn emit_enums(&self) -> Vec<TokenStream> {
-        self.enums.values().map(|d| d.emit_rust()).collect()
+        let stable_enums: HashSet<&String> = self
+            .messages
+            .values()
+            .filter(|msg| !msg.wip)
+            .flat_map(|msg| msg.fields.iter())
+            .filter_map(|field| field.enumtype.as_ref())
+            .collect();
+        self.enums
+            .values()
+            .map(|d| d.emit_rust(d.wip && !stable_enums.contains(&d.name)))
+            .collect()
     }
 
     /// Get list of original message names
@@ -111,6 +210,15 @@ impl MavProfile {
             .collect()
     }
 
+    /// Emit the `cfg` of the messages marked as work in progress, in the order of
+    /// [`emit_enum_names`](Self::emit_enum_names)
+    fn emit_message_cfgs(&self) -> Vec<TokenStream> {
+        self.messages
+            .values()
+            .map(|msg| emit_wip_cfg(msg.wip))
+            .collect()
+    }
+
     /// Emit message names with "_DATA" at the end
     fn emit_struct_names(&self) -> Vec<TokenStream> {
         self.messages
@@ -119,25 +227,43 @@ impl MavProfile {
             .collect()
     }
 
-    fn emit_rust(&self) -> TokenStream {
+    /// Emit the Rust code of the profile, with the `raw` messages kept as raw payloads.
+    ///
+    /// Each message is in its own module, to be split in a separate codegen unit. The modules are
+    /// in the emitted code if `inline_messages`, otherwise they are read from the files of
+    /// [`emit_message_files`](Self::emit_message_files).
+    fn emit_rust(&self, raw: &[MavMessage], inline_messages: bool) -> TokenStream {
         //TODO verify that id_width of u8 is OK even in mavlink v1
         let id_width = format_ident!("u32");
 
         let comment = self.emit_comments();
-        let msgs = self.emit_msgs();
+        let msgs = self.emit_message_modules(inline_messages);
         let enum_names = self.emit_enum_names();
         let struct_names = self.emit_struct_names();
         let enums = self.emit_enums();
-
-        let mav_message = self.emit_mav_message(&enum_names, &struct_names);
-        let mav_message_parse = self.emit_mav_message_parse(&enum_names, &struct_names);
-        let mav_message_crc = self.emit_mav_message_crc(&id_width, &struct_names);
-        let mav_message_name = self.emit_mav_message_name(&enum_names, &struct_names);
-        let mav_message_id = self.emit_mav_message_id(&enum_names, &struct_names);
-        let mav_message_id_from_name = self.emit_mav_message_id_from_name(&struct_names);
+        let raw = RawMessages(raw);
+
+        let mav_message = self.emit_mav_message(&enum_names, &struct_names, &raw);
+        let mav_message_parse = self.emit_mav_message_parse(&enum_names, &struct_names, &raw);
+        let mav_message_crc = self.emit_mav_message_crc(&id_width);
+        let mav_message_name = self.emit_mav_message_name(&enum_names, &struct_names, &raw);
+        let mav_message_id = self.emit_mav_message_id(&enum_names, &struct_names, &raw);
+        let mav_message_id_from_name = self.emit_mav_message_id_from_name();
+        let mav_message_name_from_id = self.emit_mav_message_name_from_id();
+        let mav_message_max_payload_len = self.emit_mav_message_max_payload_len();
         let mav_message_default_from_id =
-            self.emit_mav_message_default_from_id(&enum_names, &struct_names);
-        let mav_message_serialize = self.emit_mav_message_serialize(&enum_names);
+            self.emit_mav_message_default_from_id(&enum_names, &struct_names, &raw);
+        let mav_message_serialize = self.emit_mav_message_serialize(&enum_names, &raw);
+        let mav_message_target_system_id = self.emit_mav_message_target_id("target_system", &raw);
+        let mav_message_target_component_id =
+            self.emit_mav_message_target_id("target_component", &raw);
+        let mav_message_field_access =
+            self.emit_mav_message_field_access(&enum_names, &struct_names, &raw);
+        let mav_message_display = self.emit_mav_message_display(&enum_names, &raw);
+        let mav_message_pymavlink = self.emit_mav_message_pymavlink(&enum_names, &raw);
+        let mav_message_fields = self.emit_mav_message_fields(&enum_names, &struct_names, &raw);
+        let raw_message_info = raw.emit_info();
+        let message_info = self.emit_message_info(raw.0);
 
         quote! {
             #comment
@@ -161,6 +287,10 @@ impl MavProfile {
 
             #(#msgs)*
 
+            #raw_message_info
+
+            #message_info
+
             #[derive(Clone, PartialEq, Debug)]
             #mav_message
 
@@ -169,20 +299,214 @@ impl MavProfile {
                 #mav_message_name
                 #mav_message_id
                 #mav_message_id_from_name
+                #mav_message_name_from_id
+                #mav_message_max_payload_len
                 #mav_message_default_from_id
                 #mav_message_serialize
                 #mav_message_crc
+                #mav_message_target_system_id
+                #mav_message_target_component_id
+            }
+
+            #mav_message_display
+
+            #mav_message_pymavlink
+
+            #mav_message_fields
+
+            #mav_message_field_access
+        }
+    }
+
+    fn emit_mav_message_fields(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            Self::Raw(raw) => message_info(raw.id()).map_or(&[], |info| info.fields),
+        });
+        quote! {
+            impl MavMessage {
+                /// Metadata of the fields of the message from the MAVLink definitions
+                pub fn fields(&self) -> &'static [::mavlink_core::FieldInfo] {
+                    match self {
+                        #(#cfgs Self::#enums(..) => #structs::FIELDS,)*
+                        #raw_arm
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_pymavlink(&self, enums: &[TokenStream], raw: &RawMessages) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            // the fields of raw messages are unknown
+            Self::Raw(_) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("mavpackettype", self.message_name())?;
+                map.end()
+            }
+        });
+        quote! {
+            #[cfg(feature = "serde")]
+            impl ::mavlink_core::SerializePymavlink for MavMessage {
+                fn serialize_pymavlink<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    match self {
+                        #(#cfgs Self::#enums(body) => body.serialize_pymavlink(serializer),)*
+                        #raw_arm
+                    }
+                }
             }
         }
     }
 
-    fn emit_mav_message(&self, enums: &[TokenStream], structs: &[TokenStream]) -> TokenStream {
+    fn emit_mav_message_display(&self, enums: &[TokenStream], raw: &RawMessages) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            Self::Raw(raw) => write!(f, "{} {{ payload: {:?} }}", self.message_name(), raw.payload()),
+        });
+        quote! {
+            impl ::core::fmt::Display for MavMessage {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#cfgs Self::#enums(body) => body.fmt(f),)*
+                        #raw_arm
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit the access to the fields of any message by name, dispatched to the message structs
+    fn emit_mav_message_field_access(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_names_arm = raw.emit_if_any(quote! {
+            Self::Raw(_) => &[],
+        });
+        let raw_get_arm = raw.emit_if_any(quote! {
+            Self::Raw(_) => None,
+        });
+        let raw_set_arm = raw.emit_if_any(quote! {
+            Self::Raw(_) => Err(::mavlink_core::MavFieldError::UnknownField),
+        });
+        quote! {
+            #[cfg(feature = "field-access")]
+            impl MavMessage {
+                /// Names of the fields of the message, as in the MAVLink definitions
+                pub fn field_names(&self) -> &'static [&'static str] {
+                    match self {
+                        #(#cfgs Self::#enums(..) => #structs::FIELD_NAMES,)*
+                        #raw_names_arm
+                    }
+                }
+
+                /// Value of the field `name` of the message, or `None` if it has no such field
+                pub fn get_field(&self, name: &str) -> Option<::mavlink_core::MavFieldValue<'_>> {
+                    match self {
+                        #(#cfgs Self::#enums(body) => body.get_field(name),)*
+                        #raw_get_arm
+                    }
+                }
+
+                /// Set the field `name` of the message to `value`, which must have the type of the field
+                pub fn set_field(
+                    &mut self,
+                    name: &str,
+                    value: ::mavlink_core::MavFieldValue<'_>,
+                ) -> Result<(), ::mavlink_core::MavFieldError> {
+                    match self {
+                        #(#cfgs Self::#enums(body) => body.set_field(name, value),)*
+                        #raw_set_arm
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit the public table of the properties of all messages, including the `raw` ones
+    fn emit_message_info(&self, raw: &[MavMessage]) -> TokenStream {
+        // with whether they are raw messages, which have no struct with the metadata of the fields
+        let mut messages: Vec<(&MavMessage, bool)> = self
+            .messages
+            .values()
+            .map(|msg| (msg, false))
+            .chain(raw.iter().map(|msg| (msg, true)))
+            .collect();
+        messages.sort_by_key(|(msg, _)| msg.id);
+        let infos = messages.iter().map(|&(msg, is_raw)| {
+            let id = msg.id;
+            let name = &msg.name;
+            let extra_crc = extra_crc(msg);
+            let min_payload_len = msg.v1_len() as u8;
+            let max_payload_len = msg.encoded_len() as u8;
+            let extended_payload_len = msg.extended_len() as u8;
+            let fields = if is_raw {
+                let field_infos = msg.fields.iter().map(|field| field.emit_info());
+                quote!(&[#(#field_infos),*])
+            } else {
+                let msg_name = msg.emit_struct_name();
+                quote!(#msg_name::FIELDS)
+            };
+            let wip_cfg = emit_wip_cfg(msg.wip);
+            quote! {
+                #wip_cfg
+                ::mavlink_core::MessageInfo {
+                    id: #id,
+                    name: #name,
+                    extra_crc: #extra_crc,
+                    min_payload_len: #min_payload_len,
+                    max_payload_len: #max_payload_len,
+                    extended_payload_len: #extended_payload_len,
+                    fields: #fields,
+                },
+            }
+        });
+        quote! {
+            /// Properties of all messages of this dialect, sorted by message id
+            pub static MESSAGE_INFO: &[::mavlink_core::MessageInfo] = &[#(#infos)*];
+
+            /// Properties of the message `id`, found by binary search in [`MESSAGE_INFO`]
+            pub fn message_info(id: u32) -> Option<&'static ::mavlink_core::MessageInfo> {
+                MESSAGE_INFO
+                    .binary_search_by_key(&id, |info| info.id)
+                    .ok()
+                    .map(|index| &MESSAGE_INFO[index])
+            }
+        }
+    }
+
+    fn emit_mav_message(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let variant_attrs = self
+            .messages
+            .values()
+            .map(|msg| emit_stability(msg.deprecated.as_ref(), msg.wip));
+        let raw_variant = raw.emit_if_any(quote! {
+            /// Message without a typed struct, kept as its raw payload
+            #[cfg_attr(feature = "serde", serde(skip))]
+            Raw(::mavlink_core::RawMessage),
+        });
         quote! {
             #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
             #[cfg_attr(feature = "serde", serde(tag = "type"))]
             #[repr(u32)]
             pub enum MavMessage {
-                #(#enums(#structs),)*
+                #(#variant_attrs #enums(#structs),)*
+                #raw_variant
             }
         }
     }
@@ -191,64 +515,122 @@ impl MavProfile {
         &self,
         enums: &[TokenStream],
         structs: &[TokenStream],
+        raw: &RawMessages,
     ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
         let id_width = format_ident!("u32");
+        let raw_arm = raw.emit_if_any(quote! {
+            id if raw_message_info(id).is_some() => {
+                Ok(Self::Raw(::mavlink_core::RawMessage::new(id, payload)))
+            },
+        });
 
         quote! {
             fn parse(version: MavlinkVersion, id: #id_width, payload: &[u8]) -> Result<Self, ::mavlink_core::error::ParserError> {
                 match id {
-                    #(#structs::ID => #structs::deser(version, payload).map(Self::#enums),)*
+                    #(#cfgs #structs::ID => #structs::deser(version, payload).map(Self::#enums),)*
+                    #raw_arm
                     _ => {
                         Err(::mavlink_core::error::ParserError::UnknownMessage { id })
                     },
                 }
             }
+
+            fn parse_lossy(version: MavlinkVersion, id: #id_width, payload: &[u8], unknown_enums: &mut u32) -> Result<Self, ::mavlink_core::error::ParserError> {
+                match id {
+                    #(#cfgs #structs::ID => #structs::deser_lossy(version, payload, unknown_enums).map(Self::#enums),)*
+                    _ => Self::parse(version, id, payload),
+                }
+            }
+
+            fn parse_strict(version: MavlinkVersion, id: #id_width, payload: &[u8]) -> Result<Self, ::mavlink_core::error::ParserError> {
+                if let Some(info) = message_info(id) {
+                    let needed = usize::from(info.min_payload_len);
+                    if payload.len() < needed {
+                        return Err(::mavlink_core::error::ParserError::Truncated {
+                            needed,
+                            available: payload.len(),
+                        });
+                    }
+                }
+                Self::parse(version, id, payload)
+            }
         }
     }
 
-    fn emit_mav_message_crc(&self, id_width: &Ident, structs: &[TokenStream]) -> TokenStream {
+    fn emit_mav_message_crc(&self, id_width: &Ident) -> TokenStream {
         quote! {
             fn extra_crc(id: #id_width) -> u8 {
-                match id {
-                    #(#structs::ID => #structs::EXTRA_CRC,)*
-                    _ => {
-                        0
-                    },
-                }
+                message_info(id).map_or(0, |info| info.extra_crc)
             }
         }
     }
 
-    fn emit_mav_message_name(&self, enums: &[TokenStream], structs: &[TokenStream]) -> TokenStream {
+    fn emit_mav_message_name(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            Self::Raw(raw) => message_info(raw.id()).map_or("UNKNOWN", |info| info.name),
+        });
         quote! {
             fn message_name(&self) -> &'static str {
                 match self {
-                    #(Self::#enums(..) => #structs::NAME,)*
+                    #(#cfgs Self::#enums(..) => #structs::NAME,)*
+                    #raw_arm
                 }
             }
         }
     }
 
-    fn emit_mav_message_id(&self, enums: &[TokenStream], structs: &[TokenStream]) -> TokenStream {
+    fn emit_mav_message_id(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
         let id_width = format_ident!("u32");
+        let raw_arm = raw.emit_if_any(quote! {
+            Self::Raw(raw) => raw.id(),
+        });
         quote! {
             fn message_id(&self) -> #id_width {
                 match self {
-                    #(Self::#enums(..) => #structs::ID,)*
+                    #(#cfgs Self::#enums(..) => #structs::ID,)*
+                    #raw_arm
                 }
             }
         }
     }
 
-    fn emit_mav_message_id_from_name(&self, structs: &[TokenStream]) -> TokenStream {
+    fn emit_mav_message_id_from_name(&self) -> TokenStream {
         quote! {
-            fn message_id_from_name(name: &str) -> Result<u32, &'static str> {
-                match name {
-                    #(#structs::NAME => Ok(#structs::ID),)*
-                    _ => {
-                        Err("Invalid message name.")
-                    }
-                }
+            fn message_id_from_name(name: &str) -> Result<u32, ::mavlink_core::error::ParserError> {
+                MESSAGE_INFO
+                    .iter()
+                    .find(|info| info.name == name)
+                    .map(|info| info.id)
+                    .ok_or(::mavlink_core::error::ParserError::UnknownMessageName)
+            }
+        }
+    }
+
+    fn emit_mav_message_name_from_id(&self) -> TokenStream {
+        quote! {
+            fn message_name_from_id(id: u32) -> Option<&'static str> {
+                message_info(id).map(|info| info.name)
+            }
+        }
+    }
+
+    fn emit_mav_message_max_payload_len(&self) -> TokenStream {
+        quote! {
+            fn max_payload_len(id: u32) -> Option<usize> {
+                message_info(id).map(|info| usize::from(info.max_payload_len))
             }
         }
     }
@@ -257,30 +639,144 @@ impl MavProfile {
         &self,
         enums: &[TokenStream],
         structs: &[TokenStream],
+        raw: &RawMessages,
     ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            id if raw_message_info(id).is_some() => {
+                let len = message_info(id).map_or(0, |info| usize::from(info.min_payload_len));
+                Ok(Self::Raw(::mavlink_core::RawMessage::new(id, &[0; 255][..len])))
+            },
+        });
         quote! {
-            fn default_message_from_id(id: u32) -> Result<Self, &'static str> {
+            fn default_message_from_id(id: u32) -> Result<Self, ::mavlink_core::error::ParserError> {
                 match id {
-                    #(#structs::ID => Ok(Self::#enums(#structs::default())),)*
+                    #(#cfgs #structs::ID => Ok(Self::#enums(#structs::default())),)*
+                    #raw_arm
                     _ => {
-                        Err("Invalid message id.")
+                        Err(::mavlink_core::error::ParserError::UnknownMessage { id })
                     }
                 }
             }
         }
     }
 
-    fn emit_mav_message_serialize(&self, enums: &Vec<TokenStream>) -> TokenStream {
+    /// Emit the `Message` accessor for the `target_system` or `target_component` field
+    fn emit_mav_message_target_id(&self, field_name: &str, raw: &RawMessages) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let fn_name = format_ident!("{}_id", field_name);
+        let field = format_ident!("{}", field_name);
+        let enums = self
+            .messages
+            .values()
+            .filter(|msg| msg.has_target_field(field_name))
+            .map(|msg| format_ident!("{}", msg.name))
+            .collect::<Vec<Ident>>();
+
+        let offset = format_ident!("{}_offset", field_name);
+        let raw_arm = raw.emit_if_any(quote! {
+            // payloads have their trailing zeroes removed
+            Self::Raw(raw) => raw_message_info(raw.id())
+                .and_then(|info| info.#offset)
+                .map(|offset| raw.payload().get(offset).copied().unwrap_or(0)),
+        });
+
+        quote! {
+            #[allow(unreachable_patterns)]
+            fn #fn_name(&self) -> Option<u8> {
+                match self {
+                    #(#cfgs Self::#enums(inner) => Some(inner.#field),)*
+                    #raw_arm
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_serialize(
+        &self,
+        enums: &Vec<TokenStream>,
+        raw: &RawMessages,
+    ) -> TokenStream {
+        let cfgs = self.emit_message_cfgs();
+        let raw_arm = raw.emit_if_any(quote! {
+            Self::Raw(raw) => {
+                let v1_len = message_info(raw.id()).map_or(raw.payload().len(), |info| usize::from(info.min_payload_len));
+                raw.ser(version, v1_len, bytes)
+            },
+        });
         quote! {
             fn ser(&self, version: MavlinkVersion, bytes: &mut [u8]) -> usize {
                 match self {
-                    #(Self::#enums(body) => body.ser(version, bytes),)*
+                    #(#cfgs Self::#enums(body) => body.ser(version, bytes),)*
+                    #raw_arm
                 }
             }
         }
     }
 }
 
+/// Messages of a profile that are kept as raw payloads instead of getting typed structs
+struct RawMessages<'a>(&'a [MavMessage]);
+
+impl RawMessages<'_> {
+    fn emit_if_any(&self, tokens: TokenStream) -> TokenStream {
+        if self.0.is_empty() {
+            quote!()
+        } else {
+            tokens
+        }
+    }
+
+    /// Emit the lookup of the offsets of the target fields of the raw messages, their other
+    /// properties being in the `MESSAGE_INFO` table
+    fn emit_info(&self) -> TokenStream {
+        let infos = self.0.iter().map(|msg| {
+            let id = msg.id;
+            let target_system_offset = emit_option(msg.target_offset("target_system"));
+            let target_component_offset = emit_option(msg.target_offset("target_component"));
+            quote! {
+                #id => Some(RawMessageInfo {
+                    target_system_offset: #target_system_offset,
+                    target_component_offset: #target_component_offset,
+                }),
+            }
+        });
+        self.emit_if_any(quote! {
+            struct RawMessageInfo {
+                target_system_offset: Option<usize>,
+                target_component_offset: Option<usize>,
+            }
+
+            fn raw_message_info(id: u32) -> Option<RawMessageInfo> {
+                match id {
+                    #(#infos)*
+                    _ => None,
+                }
+            }
+        })
+    }
+}
+
+fn emit_option(value: Option<usize>) -> TokenStream {
+    match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
+}
+
+/// Doc comment of a description, ended with a period unless it already ends a sentence
+#[cfg(feature = "emit-description")]
+fn emit_doc_sentence(description: &str) -> TokenStream {
+    let description = description.trim();
+    let doc = if description.ends_with(['.', '!', '?']) {
+        description.to_string()
+    } else {
+        format!("{description}.")
+    };
+    quote!(#[doc = #doc])
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MavEnum {
@@ -289,11 +785,17 @@ pub struct MavEnum {
     pub entries: Vec<MavEnumEntry>,
     /// If contains Some, the string represents the type witdh for bitflags
     pub bitfield: Option<String>,
+    /// Whether the enum is marked with `bitmask="true"` in the definitions
+    pub bitmask: bool,
+    pub deprecated: Option<MavDeprecated>,
+    /// Whether the enum is marked `<wip>`, as work in progress
+    pub wip: bool,
 }
 
 impl MavEnum {
     fn try_combine(&mut self, enm: &Self) {
         if self.name == enm.name {
+            self.bitmask |= enm.bitmask;
             for enum_entry in &enm.entries {
                 let found_entry = self.entries.iter().find(|elem| {
                     elem.name == enum_entry.name && elem.value.unwrap() == enum_entry.value.unwrap()
@@ -306,41 +808,60 @@ impl MavEnum {
         }
     }
 
+    /// Smallest unsigned type holding the values of all the entries
+    fn smallest_bitfield_type(&self) -> String {
+        let max = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.value)
+            .max()
+            .unwrap_or(0);
+        if max <= u32::from(u8::MAX) {
+            "u8".to_string()
+        } else if max <= u32::from(u16::MAX) {
+            "u16".to_string()
+        } else {
+            "u32".to_string()
+        }
+    }
+
     fn emit_defs(&self) -> Vec<TokenStream> {
         let mut cnt = 0u32;
         self.entries
             .iter()
             .map(|enum_entry| {
                 let name = format_ident!("{}", enum_entry.name.clone());
+                let stability = emit_stability(enum_entry.deprecated.as_ref(), enum_entry.wip);
                 let value;
 
                 #[cfg(feature = "emit-description")]
-                let description = if let Some(description) = enum_entry.description.as_ref() {
-                    quote!(#[doc = #description])
-                } else {
-                    quote!()
-                };
+                let description = enum_entry
+                    .description
+                    .as_deref()
+                    .map(emit_doc_sentence)
+                    .unwrap_or_default();
 
                 #[cfg(not(feature = "emit-description"))]
                 let description = quote!();
 
-                if enum_entry.value.is_none() {
-                    cnt += 1;
-                    value = quote!(#cnt);
-                } else {
-                    let tmp_value = enum_entry.value.unwrap();
+                if let Some(tmp_value) = enum_entry.value {
                     cnt = cnt.max(tmp_value);
                     let tmp = TokenStream::from_str(&tmp_value.to_string()).unwrap();
                     value = quote!(#tmp);
+                } else {
+                    cnt += 1;
+                    value = quote!(#cnt);
                 };
                 if self.bitfield.is_some() {
                     quote! {
                         #description
+                        #stability
                         const #name = #value;
                     }
                 } else {
                     quote! {
                         #description
+                        #stability
                         #name = #value,
                     }
                 }
@@ -354,22 +875,88 @@ impl MavEnum {
     }
 
     fn emit_const_default(&self) -> TokenStream {
-        let default = format_ident!("{}", self.entries[0].name);
+        // the first entry that exists without the `unstable` feature
+        let default = self
+            .entries
+            .iter()
+            .find(|entry| !entry.wip)
+            .unwrap_or(&self.entries[0]);
+        let default = format_ident!("{}", default.name);
         quote!(pub const DEFAULT: Self = Self::#default;)
     }
 
-    fn emit_rust(&self) -> TokenStream {
+    /// Emit the list of the values of the enum and the conversions from and to their names
+    fn emit_name_conversions(&self) -> TokenStream {
+        let idents = self
+            .entries
+            .iter()
+            .map(|entry| format_ident!("{}", entry.name))
+            .collect::<Vec<Ident>>();
+        let cfgs = self
+            .entries
+            .iter()
+            .map(|entry| emit_wip_cfg(entry.wip))
+            .collect::<Vec<TokenStream>>();
+        let names = self.entries.iter().map(|entry| &entry.name);
+        let from_name = quote! {
+            /// Value named `name` in the MAVLink definitions
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#cfgs #names => Some(Self::#idents),)*
+                    _ => None,
+                }
+            }
+        };
+        let as_name = if self.bitfield.is_some() {
+            let names = self.entries.iter().map(|entry| &entry.name);
+            quote! {
+                /// Name of the flag in the MAVLink definitions, or `None` if the value isn't a
+                /// single flag of the definitions
+                pub fn as_name(&self) -> Option<&'static str> {
+                    const NAMES: &[&str] = &[#(#cfgs #names),*];
+                    Self::ALL
+                        .iter()
+                        .zip(NAMES)
+                        .find(|(flag, _)| *flag == self)
+                        .map(|(_, name)| *name)
+                }
+            }
+        } else {
+            let names = self.entries.iter().map(|entry| &entry.name);
+            quote! {
+                /// Name of the value in the MAVLink definitions
+                pub fn as_name(&self) -> &'static str {
+                    match self {
+                        #(#cfgs Self::#idents => #names,)*
+                    }
+                }
+            }
+        };
+        quote! {
+            /// All the values of the MAVLink definitions, in their order, iterated with
+            /// `ALL.iter()`
+            pub const ALL: &'static [Self] = &[#(#cfgs Self::#idents),*];
+
+            #from_name
+            #as_name
+        }
+    }
+
+    /// Emit the enum, gated behind the `unstable` feature if `wip_gated`
+    fn emit_rust(&self, wip_gated: bool) -> TokenStream {
         let defs = self.emit_defs();
         let enum_name = self.emit_name();
+        let deprecated = self.deprecated.as_ref().map(MavDeprecated::emit_attribute);
+        let wip_cfg = emit_wip_cfg(wip_gated);
         let const_default = self.emit_const_default();
+        let name_conversions = self.emit_name_conversions();
 
         #[cfg(feature = "emit-description")]
-        let description = if let Some(description) = self.description.as_ref() {
-            let desc = description.to_string();
-            quote!(#[doc = #desc])
-        } else {
-            quote!()
-        };
+        let description = self
+            .description
+            .as_deref()
+            .map(emit_doc_sentence)
+            .unwrap_or_default();
 
         #[cfg(not(feature = "emit-description"))]
         let description = quote!();
@@ -381,6 +968,8 @@ impl MavEnum {
                 bitflags!{
                     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
                     #description
+                    #deprecated
+                    #wip_cfg
                     pub struct #enum_name: #width {
                         #(#defs)*
                     }
@@ -393,6 +982,8 @@ impl MavEnum {
                 #[cfg_attr(feature = "serde", serde(tag = "type"))]
                 #[repr(u32)]
                 #description
+                #deprecated
+                #wip_cfg
                 pub enum #enum_name {
                     #(#defs)*
                 }
@@ -402,10 +993,13 @@ impl MavEnum {
         quote! {
             #enum_def
 
+            #wip_cfg
             impl #enum_name {
                 #const_default
+                #name_conversions
             }
 
+            #wip_cfg
             impl Default for #enum_name {
                 fn default() -> Self {
                     Self::DEFAULT
@@ -422,6 +1016,48 @@ pub struct MavEnumEntry {
     pub name: String,
     pub description: Option<String>,
     pub params: Option<Vec<String>>,
+    pub deprecated: Option<MavDeprecated>,
+    /// Whether the entry is marked `<wip>`, as work in progress
+    pub wip: bool,
+}
+
+/// `<deprecated>` annotation of a message, enum or enum entry
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavDeprecated {
+    pub since: String,
+    pub replaced_by: Option<String>,
+    pub note: Option<String>,
+}
+
+impl MavDeprecated {
+    /// Emit `#[deprecated]`, with the date of `since` in the note since it isn't a crate version
+    fn emit_attribute(&self) -> TokenStream {
+        let mut note = format!("Deprecated since {}", self.since);
+        if let Some(replaced_by) = &self.replaced_by {
+            note.push_str(&format!(", replaced by {replaced_by}"));
+        }
+        if let Some(text) = &self.note {
+            note.push_str(&format!(": {text}"));
+        }
+        quote!(#[deprecated(note = #note)])
+    }
+}
+
+/// Emit `#[cfg(feature = "unstable")]` if `wip`, for the items marked as work in progress
+fn emit_wip_cfg(wip: bool) -> TokenStream {
+    if wip {
+        quote!(#[cfg(feature = "unstable")])
+    } else {
+        quote!()
+    }
+}
+
+/// Emit the attributes of items marked deprecated or work in progress in the definitions
+fn emit_stability(deprecated: Option<&MavDeprecated>, wip: bool) -> TokenStream {
+    let deprecated = deprecated.map(MavDeprecated::emit_attribute);
+    let wip_cfg = emit_wip_cfg(wip);
+    quote!(#deprecated #wip_cfg)
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -431,9 +1067,51 @@ pub struct MavMessage {
     pub name: String,
     pub description: Option<String>,
     pub fields: Vec<MavField>,
+    /// Length of the extension fields left out of `fields` when the extensions aren't emitted
+    pub omitted_extensions_len: usize,
+    pub deprecated: Option<MavDeprecated>,
+    /// Whether the message is marked `<wip>`, as work in progress
+    pub wip: bool,
+    /// Names of the fields in the order of the definition, `fields` being in wire order
+    pub definition_order: Vec<String>,
 }
 
 impl MavMessage {
+    /// Length of the payload without extension fields, as sent with MAVLink 1
+    fn v1_len(&self) -> usize {
+        self.fields
+            .iter()
+            .filter(|field| !field.is_extension)
+            .map(|field| field.mavtype.len())
+            .sum()
+    }
+
+    /// Length of the payload with all the parsed fields
+    fn encoded_len(&self) -> usize {
+        self.fields.iter().map(|field| field.mavtype.len()).sum()
+    }
+
+    /// Length of the payload with all the fields of the definition, including the extension
+    /// fields whether they are emitted or not
+    fn extended_len(&self) -> usize {
+        self.encoded_len() + self.omitted_extensions_len
+    }
+
+    /// Offset in the payload of the `target_system` or `target_component` field
+    fn target_offset(&self, field_name: &str) -> Option<usize> {
+        let mut offset = 0;
+        for field in &self.fields {
+            if field.name == field_name
+                && field.mavtype == MavType::UInt8
+                && field.enumtype.is_none()
+            {
+                return Some(offset);
+            }
+            offset += field.mavtype.len();
+        }
+        None
+    }
+
     /// Return Token of "MESSAGE_NAME_DATA
     /// for mavlink struct data
     fn emit_struct_name(&self) -> TokenStream {
@@ -441,6 +1119,11 @@ impl MavMessage {
         quote!(#name)
     }
 
+    /// Name of the module of the message, its name in lowercase
+    fn emit_module_name(&self) -> Ident {
+        format_ident!("{}", self.name.to_lowercase())
+    }
+
     fn emit_name_types(&self) -> (Vec<TokenStream>, usize) {
         let mut encoded_payload_len: usize = 0;
         let field_toks = self
@@ -492,9 +1175,8 @@ impl MavMessage {
         let mut ts = TokenStream::new();
         let desc = format!("id: {}", self.id);
         ts.extend(quote!(#[doc = #desc]));
-        if let Some(val) = self.description.clone() {
-            let doc = &format!("{val}.");
-            ts.extend(quote!(#[doc = #doc]));
+        if let Some(val) = self.description.as_deref() {
+            ts.extend(emit_doc_sentence(val));
         }
         ts
     }
@@ -565,6 +1247,149 @@ impl MavMessage {
         }
     }
 
+    /// Whether the message has a `target_system` or `target_component` field holding an id
+    fn has_target_field(&self, field_name: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|f| f.name == field_name && f.mavtype == MavType::UInt8 && f.enumtype.is_none())
+    }
+
+    /// Emit the lookup of the fields contained in a payload
+    fn emit_field_presence(&self) -> TokenStream {
+        let mut end = 0;
+        let arms = self.fields.iter().map(|field| {
+            end += field.mavtype.len();
+            let name = field.mavlink_name();
+            quote!(#name => payload_len >= #end,)
+        });
+        quote! {
+            /// Whether the field `name` is fully contained in a payload of `payload_len` bytes, as
+            /// opposed to being filled with zeros when parsing.
+            ///
+            /// An extension field missing from a MAVLink 2 payload was either unknown to the
+            /// sender or zero, as MAVLink 2 removes the trailing zeros of payloads.
+            #[allow(unused_variables)]
+            pub fn is_field_present(name: &str, payload_len: usize) -> bool {
+                match name {
+                    #(#arms)*
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Emit the `Display` of the message, printing the name and value of each field, with its
+    /// unit and with the names of enum values
+    fn emit_display_impl(&self) -> TokenStream {
+        let msg_name = self.emit_struct_name();
+        let open = format!("{} {{", self.name);
+        let fields = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| field.emit_display(index == 0));
+        quote! {
+            impl ::core::fmt::Display for #msg_name {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(#open)?;
+                    #(#fields)*
+                    f.write_str(" }")
+                }
+            }
+        }
+    }
+
+    /// Emit the `TargetedMessage` implementation of messages with a `target_system` field
+    fn emit_targeted_impl(&self) -> TokenStream {
+        if !self.has_target_field("target_system") {
+            return quote!();
+        }
+        let msg_name = self.emit_struct_name();
+        let target_component = if self.has_target_field("target_component") {
+            quote!(Some(self.target_component))
+        } else {
+            quote!(None)
+        };
+        quote! {
+            impl ::mavlink_core::TargetedMessage for #msg_name {
+                fn target_system(&self) -> u8 {
+                    self.target_system
+                }
+
+                fn target_component(&self) -> Option<u8> {
+                    #target_component
+                }
+            }
+        }
+    }
+
+    fn emit_field_access(&self) -> TokenStream {
+        let msg_name = self.emit_struct_name();
+        let names = self.fields.iter().map(|field| field.mavlink_name());
+        let get_arms = self.fields.iter().map(|field| field.emit_get_field_arm());
+        let set_arms = self.fields.iter().map(|field| field.emit_set_field_arm());
+        quote! {
+            #[cfg(feature = "field-access")]
+            impl #msg_name {
+                /// Names of the fields, as in the MAVLink definitions
+                pub const FIELD_NAMES: &'static [&'static str] = &[#(#names),*];
+
+                /// Value of the field `name`, or `None` if the message has no such field
+                pub fn get_field(&self, name: &str) -> Option<::mavlink_core::MavFieldValue<'_>> {
+                    match name {
+                        #(#get_arms)*
+                        _ => None,
+                    }
+                }
+
+                /// Set the field `name` to `value`, which must have the type of the field.
+                ///
+                /// Enum and bitmask fields are set from their integer value. Arrays may be set
+                /// from a shorter slice, the rest of the array is then cleared.
+                #[allow(unused_variables)]
+                pub fn set_field(
+                    &mut self,
+                    name: &str,
+                    value: ::mavlink_core::MavFieldValue<'_>,
+                ) -> Result<(), ::mavlink_core::MavFieldError> {
+                    match (name, value) {
+                        #(#set_arms)*
+                        _ => Err(::mavlink_core::MavFieldError::UnknownField),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit the serialization of the message as pymavlink represents it
+    fn emit_pymavlink_impl(&self) -> TokenStream {
+        let msg_name = self.emit_struct_name();
+        let fields: Vec<&MavField> = if self.definition_order.is_empty() {
+            self.fields.iter().collect()
+        } else {
+            // the extension fields may have been left out
+            self.definition_order
+                .iter()
+                .filter_map(|name| self.fields.iter().find(|field| &field.name == name))
+                .collect()
+        };
+        let len = fields.len() + 1;
+        let names = fields.iter().map(|field| field.mavlink_name());
+        let values = fields.iter().map(|field| field.emit_pymavlink_value());
+        quote! {
+            #[cfg(feature = "serde")]
+            impl ::mavlink_core::SerializePymavlink for #msg_name {
+                fn serialize_pymavlink<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    use serde::ser::SerializeMap;
+                    let mut map = serializer.serialize_map(Some(#len))?;
+                    map.serialize_entry("mavpackettype", Self::NAME)?;
+                    #(map.serialize_entry(#names, &#values)?;)*
+                    map.end()
+                }
+            }
+        }
+    }
+
     fn emit_default_impl(&self) -> TokenStream {
         let msg_name = self.emit_struct_name();
         quote! {
@@ -590,11 +1415,21 @@ impl MavMessage {
         let name = self.name.clone();
         let extra_crc = extra_crc(self);
         let (name_types, msg_encoded_len) = self.emit_name_types();
+        let msg_extended_len = self.extended_len();
 
         let deser_vars = self.emit_deserialize_vars();
         let serialize_vars = self.emit_serialize_vars();
         let const_default = self.emit_const_default();
         let default_impl = self.emit_default_impl();
+        let field_access = self.emit_field_access();
+        let targeted_impl = self.emit_targeted_impl();
+        let display_impl = self.emit_display_impl();
+        let pymavlink_impl = self.emit_pymavlink_impl();
+        let field_presence = self.emit_field_presence();
+        let field_infos = self.fields.iter().map(|field| field.emit_info());
+        let is_valid_fns = self.fields.iter().filter_map(|field| field.emit_is_valid());
+        let stability = emit_stability(self.deprecated.as_ref(), self.wip);
+        let wip_cfg = emit_wip_cfg(self.wip);
 
         #[cfg(feature = "emit-description")]
         let description = self.emit_description();
@@ -604,19 +1439,56 @@ impl MavMessage {
 
         quote! {
             #description
+            #stability
             #[derive(Debug, Clone, PartialEq)]
             #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
             pub struct #msg_name {
                 #(#name_types)*
             }
 
+            #wip_cfg
             impl #msg_name {
                 pub const ENCODED_LEN: usize = #msg_encoded_len;
+                /// Length of the payload with all the fields of the definition, including the
+                /// extension fields left out of the struct if the dialect was generated without
+                /// the `emit-extensions` feature
+                pub const EXTENDED_LEN: usize = #msg_extended_len;
+                /// Metadata of the fields from the MAVLink definitions, in the order of the struct
+                pub const FIELDS: &'static [::mavlink_core::FieldInfo] = &[#(#field_infos),*];
                 #const_default
+
+                #field_presence
+
+                #(#is_valid_fns)*
+
+                /// Deserialize the payload, replacing unknown enum values by their default and
+                /// counting them in `__unknown_enums` if it is set, failing otherwise
+                #[allow(unused_mut, unused_variables)]
+                fn deser_counting_unknown_enums(
+                    _version: MavlinkVersion,
+                    __input: &[u8],
+                    mut __unknown_enums: Option<&mut u32>,
+                ) -> Result<Self, ::mavlink_core::error::ParserError> {
+                    #deser_vars
+                }
             }
 
+            #wip_cfg
             #default_impl
 
+            #wip_cfg
+            #field_access
+
+            #wip_cfg
+            #targeted_impl
+
+            #wip_cfg
+            #display_impl
+
+            #wip_cfg
+            #pymavlink_impl
+
+            #wip_cfg
             impl MessageData for #msg_name {
                 type Message = MavMessage;
 
@@ -625,8 +1497,12 @@ impl MavMessage {
                 const EXTRA_CRC: u8 = #extra_crc;
                 const ENCODED_LEN: usize = #msg_encoded_len;
 
-                fn deser(_version: MavlinkVersion, __input: &[u8]) -> Result<Self, ::mavlink_core::error::ParserError> {
-                    #deser_vars
+                fn deser(version: MavlinkVersion, __input: &[u8]) -> Result<Self, ::mavlink_core::error::ParserError> {
+                    Self::deser_counting_unknown_enums(version, __input, None)
+                }
+
+                fn deser_lossy(version: MavlinkVersion, __input: &[u8], unknown_enums: &mut u32) -> Result<Self, ::mavlink_core::error::ParserError> {
+                    Self::deser_counting_unknown_enums(version, __input, Some(unknown_enums))
                 }
 
                 fn ser(&self, version: MavlinkVersion, bytes: &mut [u8]) -> usize {
@@ -645,6 +1521,11 @@ pub struct MavField {
     pub description: Option<String>,
     pub enumtype: Option<String>,
     pub display: Option<String>,
+    pub units: Option<String>,
+    pub increment: Option<String>,
+    pub min_value: Option<String>,
+    pub max_value: Option<String>,
+    pub invalid: Option<String>,
     pub is_extension: bool,
 }
 
@@ -675,9 +1556,12 @@ impl MavField {
     #[cfg(feature = "emit-description")]
     fn emit_description(&self) -> TokenStream {
         let mut ts = TokenStream::new();
-        if let Some(val) = self.description.clone() {
-            let desc = format!("{val}.");
-            ts.extend(quote!(#[doc = #desc]));
+        if let Some(val) = self.description.as_deref() {
+            ts.extend(emit_doc_sentence(val));
+        }
+        if let Some(units) = &self.units {
+            let units = format!("Units: `{units}`");
+            ts.extend(quote!(#[doc = ""] #[doc = #units]));
         }
         ts
     }
@@ -689,6 +1573,183 @@ impl MavField {
         quote!(pub #name: #fieldtype,)
     }
 
+    /// Emit `is_valid_<field>`, checking that the field isn't the `invalid` value of the
+    /// definitions. Enum fields are skipped.
+    ///
+    /// For arrays, `[value:]` makes the array invalid if its first element is `value`, and
+    /// `[value]` if all its elements are.
+    fn emit_is_valid(&self) -> Option<TokenStream> {
+        if self.enumtype.is_some() {
+            return None;
+        }
+        let invalid = self.invalid.as_deref()?.trim();
+        let name = self.emit_name();
+        let check = match &self.mavtype {
+            MavType::Array(element, _) => {
+                let value = invalid.strip_prefix('[')?.strip_suffix(']')?.trim();
+                match value.strip_suffix(':') {
+                    Some(value) => {
+                        element.emit_differs_from(value.trim(), quote!(self.#name[0]))?
+                    }
+                    None => {
+                        let differs = element.emit_differs_from(value, quote!((*value)))?;
+                        quote!(self.#name.iter().any(|value| #differs))
+                    }
+                }
+            }
+            mavtype => mavtype.emit_differs_from(invalid, quote!(self.#name))?,
+        };
+        let fn_name = format_ident!("is_valid_{}", self.name);
+        let doc = format!(
+            "Whether `{}` holds a value, as opposed to the invalid value `{invalid}`",
+            self.name
+        );
+        Some(quote! {
+            #[doc = #doc]
+            pub fn #fn_name(&self) -> bool {
+                #check
+            }
+        })
+    }
+
+    /// Emit the `FieldInfo` of the field
+    fn emit_info(&self) -> TokenStream {
+        fn emit_number(value: &Option<String>) -> TokenStream {
+            match value.as_deref().map(str::parse::<f64>) {
+                Some(Ok(value)) if value.is_finite() => quote!(Some(#value)),
+                _ => quote!(None),
+            }
+        }
+        fn emit_str(value: &Option<String>) -> TokenStream {
+            match value {
+                Some(value) => quote!(Some(#value)),
+                None => quote!(None),
+            }
+        }
+        let name = self.mavlink_name();
+        let units = emit_str(&self.units);
+        let display = emit_str(&self.display);
+        let increment = emit_number(&self.increment);
+        let min_value = emit_number(&self.min_value);
+        let max_value = emit_number(&self.max_value);
+        quote! {
+            ::mavlink_core::FieldInfo {
+                name: #name,
+                units: #units,
+                display: #display,
+                increment: #increment,
+                min_value: #min_value,
+                max_value: #max_value,
+            }
+        }
+    }
+
+    /// Name of the field in the MAVLink definitions, `type` being renamed to `mavtype` in Rust
+    fn mavlink_name(&self) -> &str {
+        if self.name == "mavtype" {
+            "type"
+        } else {
+            &self.name
+        }
+    }
+
+    /// Emit the printing of the field by the `Display` of its message
+    fn emit_display(&self, first: bool) -> TokenStream {
+        let name = self.emit_name();
+        let separator = if first { " " } else { ", " };
+        let units = self
+            .units
+            .as_ref()
+            .map(|units| format!(" {}", units.replace('{', "{{").replace('}', "}}")))
+            .unwrap_or_default();
+        let (value, placeholder) = match &self.mavtype {
+            MavType::Array(ty, _) if **ty == MavType::Char => (
+                quote!(::mavlink_core::utils::DisplayChars(&self.#name)),
+                "{}",
+            ),
+            // enums print the names of their values
+            _ if matches!(self.mavtype, MavType::Array(_, _)) || self.enumtype.is_some() => {
+                (quote!(self.#name), "{:?}")
+            }
+            _ => (quote!(self.#name), "{}"),
+        };
+        let format = format!("{separator}{}: {placeholder}{units}", self.mavlink_name());
+        quote!(write!(f, #format, #value)?;)
+    }
+
+    /// Whether the field holds the flags of a bitmask enum
+    fn is_bitmask(&self) -> bool {
+        self.enumtype.is_some() && self.display.as_deref() == Some("bitmask")
+    }
+
+    /// Emit the arm of `get_field` returning the value of the field
+    fn emit_get_field_arm(&self) -> TokenStream {
+        let name = self.emit_name();
+        let mavlink_name = self.mavlink_name();
+        let variant = self.mavtype.field_value_variant();
+        let value = if matches!(self.mavtype, MavType::Array(_, _)) {
+            quote!(&self.#name)
+        } else if self.is_bitmask() {
+            quote!(self.#name.bits())
+        } else if self.enumtype.is_some() {
+            let ty = format_ident!("{}", self.mavtype.rust_type());
+            quote!(self.#name as #ty)
+        } else {
+            quote!(self.#name)
+        };
+        quote!(#mavlink_name => Some(::mavlink_core::MavFieldValue::#variant(#value)),)
+    }
+
+    /// Emit the value of the field as pymavlink represents it
+    fn emit_pymavlink_value(&self) -> TokenStream {
+        let name = self.emit_name();
+        match &self.mavtype {
+            MavType::Array(ty, _) if **ty == MavType::Char => {
+                quote!(::mavlink_core::pymavlink_str(&self.#name))
+            }
+            // serde only serializes arrays of up to 32 elements
+            MavType::Array(_, _) => quote!(self.#name[..]),
+            MavType::Char => quote!(char::from(self.#name)),
+            _ if self.is_bitmask() => quote!(self.#name.bits()),
+            _ if self.enumtype.is_some() => {
+                let ty = format_ident!("{}", self.mavtype.rust_type());
+                quote!((self.#name as #ty))
+            }
+            _ => quote!(self.#name),
+        }
+    }
+
+    /// Emit the arms of `set_field` setting the field from a value of its type
+    fn emit_set_field_arm(&self) -> TokenStream {
+        let name = self.emit_name();
+        let mavlink_name = self.mavlink_name();
+        let variant = self.mavtype.field_value_variant();
+        let set = if matches!(self.mavtype, MavType::Array(_, _)) {
+            quote!(::mavlink_core::set_field_array(&mut self.#name, value))
+        } else if let Some(enum_name) = &self.enumtype {
+            let enum_name = format_ident!("{}", enum_name);
+            let value = if self.is_bitmask() {
+                quote!(#enum_name::from_bits(value))
+            } else {
+                let from = format_ident!("from_{}", self.mavtype.rust_type());
+                quote!(FromPrimitive::#from(value))
+            };
+            quote! {{
+                self.#name = #value.ok_or(::mavlink_core::MavFieldError::InvalidValue)?;
+                Ok(())
+            }}
+        } else {
+            quote! {{
+                self.#name = value;
+                Ok(())
+            }}
+        };
+        quote! {
+            (#mavlink_name, ::mavlink_core::MavFieldValue::#variant(value)) => #set,
+            (#mavlink_name, _) => Err(::mavlink_core::MavFieldError::TypeMismatch),
+        }
+    }
+
     /// Emit writer
     fn rust_writer(&self) -> TokenStream {
         let mut name = "self.".to_string() + &self.name.clone();
@@ -743,13 +1804,23 @@ impl MavField {
                     panic!("Display option not implemented");
                 }
             } else {
-                // handle enum by FromPrimitive
+                // handle enum by FromPrimitive, unknown values are replaced by the default in
+                // lossy mode
                 let tmp = self.mavtype.rust_reader(&quote!(let tmp), buf);
                 let val = format_ident!("from_{}", &self.mavtype.rust_type());
+                let enum_name_ident = format_ident!("{}", enum_name);
                 quote!(
                     #tmp
-                    #name = FromPrimitive::#val(tmp)
-                        .ok_or(::mavlink_core::error::ParserError::InvalidEnum { enum_type: #enum_name, value: tmp as u32 })?;
+                    #name = match FromPrimitive::#val(tmp) {
+                        Some(value) => value,
+                        None => match &mut __unknown_enums {
+                            Some(count) => {
+                                **count += 1;
+                                #enum_name_ident::DEFAULT
+                            }
+                            None => return Err(::mavlink_core::error::ParserError::InvalidEnum { enum_type: #enum_name, value: tmp as u32 }),
+                        },
+                    };
                 )
             }
         } else {
@@ -877,6 +1948,76 @@ impl MavType {
         }
     }
 
+    /// Variant of `MavFieldValue` holding a value of this type
+    fn field_value_variant(&self) -> Ident {
+        use self::MavType::*;
+        let variant = match self {
+            UInt8 | UInt8MavlinkVersion => "U8",
+            Int8 => "I8",
+            Char => "Char",
+            UInt16 => "U16",
+            Int16 => "I16",
+            UInt32 => "U32",
+            Int32 => "I32",
+            Float => "F32",
+            UInt64 => "U64",
+            Int64 => "I64",
+            Double => "F64",
+            Array(t, _) => return format_ident!("{}Array", t.field_value_variant()),
+        };
+        format_ident!("{}", variant)
+    }
+
+    /// Emit whether `expr`, of this type, differs from `value`, an `invalid` value of the
+    /// definitions such as `NaN`, `UINT16_MAX` or `-1`. Returns `None` if the value can't be of
+    /// this type.
+    fn emit_differs_from(&self, value: &str, expr: TokenStream) -> Option<TokenStream> {
+        use self::MavType::*;
+        let (min, max) = match self {
+            Float | Double => {
+                if value.eq_ignore_ascii_case("nan") {
+                    return Some(quote!(!#expr.is_nan()));
+                }
+                let value: f64 = value.parse().ok()?;
+                let value = if matches!(self, Float) {
+                    proc_macro2::Literal::f32_unsuffixed(value as f32)
+                } else {
+                    proc_macro2::Literal::f64_unsuffixed(value)
+                };
+                return Some(quote!(#expr != #value));
+            }
+            UInt8 | UInt8MavlinkVersion => (0, u8::MAX.into()),
+            Int8 => (i8::MIN.into(), i8::MAX.into()),
+            UInt16 => (0, u16::MAX.into()),
+            Int16 => (i16::MIN.into(), i16::MAX.into()),
+            UInt32 => (0, u32::MAX.into()),
+            Int32 => (i32::MIN.into(), i32::MAX.into()),
+            UInt64 => (0, u64::MAX.into()),
+            Int64 => (i64::MIN.into(), i64::MAX.into()),
+            Char | Array(..) => return None,
+        };
+        let value: i128 = match value {
+            "UINT8_MAX" => u8::MAX.into(),
+            "UINT16_MAX" => u16::MAX.into(),
+            "UINT32_MAX" => u32::MAX.into(),
+            "UINT64_MAX" => u64::MAX.into(),
+            "INT8_MAX" => i8::MAX.into(),
+            "INT16_MAX" => i16::MAX.into(),
+            "INT32_MAX" => i32::MAX.into(),
+            "INT64_MAX" => i64::MAX.into(),
+            "INT8_MIN" => i8::MIN.into(),
+            "INT16_MIN" => i16::MIN.into(),
+            "INT32_MIN" => i32::MIN.into(),
+            "INT64_MIN" => i64::MIN.into(),
+            value => value.parse().ok()?,
+        };
+        if value < min || value > max {
+            return None;
+        }
+        let value = proc_macro2::Literal::i128_unsuffixed(value);
+        Some(quote!(#expr != #value))
+    }
+
     /// Size of a given Mavtype
     fn len(&self) -> usize {
         use self::MavType::*;
@@ -1023,6 +2164,37 @@ const fn identify_element(s: &[u8]) -> Option<MavXmlElement> {
     }
 }
 
+/// Record the `<deprecated>` or `<wip>` element `id` on the message, enum or entry `parent`
+fn annotate(
+    parent: Option<MavXmlElement>,
+    id: MavXmlElement,
+    bytes: &quick_xml::events::BytesStart,
+    message: &mut MavMessage,
+    mavenum: &mut MavEnum,
+    entry: &mut MavEnumEntry,
+) {
+    let (deprecated, wip) = match parent {
+        Some(MavXmlElement::Message) => (&mut message.deprecated, &mut message.wip),
+        Some(MavXmlElement::Enum) => (&mut mavenum.deprecated, &mut mavenum.wip),
+        Some(MavXmlElement::Entry) => (&mut entry.deprecated, &mut entry.wip),
+        _ => return,
+    };
+    if id == MavXmlElement::Wip {
+        *wip = true;
+        return;
+    }
+    let mut annotation = MavDeprecated::default();
+    for attr in bytes.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attr.value).to_string();
+        match attr.key.into_inner() {
+            b"since" => annotation.since = value,
+            b"replaced_by" if !value.is_empty() => annotation.replaced_by = Some(value),
+            _ => (),
+        }
+    }
+    *deprecated = Some(annotation);
+}
+
 fn is_valid_parent(p: Option<MavXmlElement>, s: MavXmlElement) -> bool {
     use self::MavXmlElement::*;
     match s {
@@ -1048,10 +2220,68 @@ pub fn parse_profile(
     definitions_dir: &Path,
     definition_file: &Path,
     parsed_files: &mut HashSet<PathBuf>,
+) -> Result<MavProfile, BindGenError> {
+    parse_profile_with_include_dirs(definitions_dir, &[], definition_file, parsed_files)
+}
+
+/// Parse a MAVLink profile like [`parse_profile`], reading included definition files from
+/// `definitions_dir`, or else from the first of `include_dirs` that has them.
+///
+/// The includes of an included file are read from its own directory first.
+pub fn parse_profile_with_include_dirs(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    definition_file: &Path,
+    parsed_files: &mut HashSet<PathBuf>,
 ) -> Result<MavProfile, BindGenError> {
     let in_path = Path::new(&definitions_dir).join(definition_file);
     parsed_files.insert(in_path.clone()); // Keep track of which files have been parsed
 
+    let file = File::open(&in_path).map_err(|e| BindGenError::CouldNotReadDefinitionFile {
+        source: e,
+        path: in_path.clone(),
+    })?;
+    parse_profile_from_reader(
+        definitions_dir,
+        include_dirs,
+        BufReader::new(file),
+        parsed_files,
+    )
+}
+
+/// Directory of the included definition file `include`, see [`parse_profile_with_include_dirs`]
+fn include_dir<'a>(
+    definitions_dir: &'a Path,
+    include_dirs: &'a [PathBuf],
+    include: &Path,
+) -> &'a Path {
+    if definitions_dir.join(include).exists() {
+        return definitions_dir;
+    }
+    include_dirs
+        .iter()
+        .map(PathBuf::as_path)
+        .find(|dir| dir.join(include).exists())
+        .unwrap_or(definitions_dir)
+}
+
+/// Parse a MAVLink profile from the XML definition `xml`.
+///
+/// Included definition files are read from `definitions_dir`.
+pub fn parse_profile_str(
+    definitions_dir: &Path,
+    xml: &str,
+    parsed_files: &mut HashSet<PathBuf>,
+) -> Result<MavProfile, BindGenError> {
+    parse_profile_from_reader(definitions_dir, &[], xml.as_bytes(), parsed_files)
+}
+
+fn parse_profile_from_reader<R: BufRead>(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    input: R,
+    parsed_files: &mut HashSet<PathBuf>,
+) -> Result<MavProfile, BindGenError> {
     let mut stack: Vec<MavXmlElement> = vec![];
 
     let mut profile = MavProfile::default();
@@ -1062,13 +2292,8 @@ pub fn parse_profile(
     let mut include = PathBuf::new();
     let mut paramid: Option<usize> = None;
 
-    let mut xml_filter = MavXmlFilter::default();
     let mut events: Vec<Result<Event, quick_xml::Error>> = Vec::new();
-    let file = File::open(&in_path).map_err(|e| BindGenError::CouldNotReadDefinitionFile {
-        source: e,
-        path: in_path.clone(),
-    })?;
-    let mut reader = Reader::from_reader(BufReader::new(file));
+    let mut reader = Reader::from_reader(input);
     reader.config_mut().trim_text(true);
 
     let mut buf = Vec::new();
@@ -1083,7 +2308,6 @@ pub fn parse_profile(
         }
         buf.clear();
     }
-    xml_filter.filter(&mut events);
     let mut is_in_extension = false;
     for e in events {
         match e {
@@ -1129,14 +2353,20 @@ pub fn parse_profile(
 
                 stack.push(id);
 
+                if matches!(id, MavXmlElement::Deprecated | MavXmlElement::Wip) {
+                    let parent = stack.get(stack.len() - 2).copied();
+                    annotate(parent, id, &bytes, &mut message, &mut mavenum, &mut entry);
+                }
+
                 for attr in bytes.attributes() {
                     let attr = attr.unwrap();
                     match stack.last() {
-                        Some(&MavXmlElement::Enum) => {
-                            if attr.key.into_inner() == b"name" {
-                                mavenum.name = to_pascal_case(attr.value);
-                                //mavenum.name = attr.value.clone();
-                            }
+                        Some(&MavXmlElement::Enum) if attr.key.into_inner() == b"name" => {
+                            mavenum.name = to_pascal_case(attr.value);
+                            //mavenum.name = attr.value.clone();
+                        }
+                        Some(&MavXmlElement::Enum) if attr.key.into_inner() == b"bitmask" => {
+                            mavenum.bitmask = attr.value.as_ref() == b"true";
                         }
                         Some(&MavXmlElement::Entry) => {
                             match attr.key.into_inner() {
@@ -1202,6 +2432,26 @@ pub fn parse_profile(
                                     field.display =
                                         Some(String::from_utf8_lossy(&attr.value).to_string());
                                 }
+                                b"units" => {
+                                    field.units =
+                                        Some(String::from_utf8_lossy(&attr.value).to_string());
+                                }
+                                b"increment" => {
+                                    field.increment =
+                                        Some(String::from_utf8_lossy(&attr.value).to_string());
+                                }
+                                b"minValue" => {
+                                    field.min_value =
+                                        Some(String::from_utf8_lossy(&attr.value).to_string());
+                                }
+                                b"maxValue" => {
+                                    field.max_value =
+                                        Some(String::from_utf8_lossy(&attr.value).to_string());
+                                }
+                                b"invalid" => {
+                                    field.invalid =
+                                        Some(String::from_utf8_lossy(&attr.value).to_string());
+                                }
                                 _ => (),
                             }
                         }
@@ -1222,6 +2472,11 @@ pub fn parse_profile(
                 b"extensions" => {
                     is_in_extension = true;
                 }
+                name @ (b"deprecated" | b"wip") => {
+                    let id = identify_element(name).unwrap();
+                    let parent = stack.last().copied();
+                    annotate(parent, id, &bytes, &mut message, &mut mavenum, &mut entry);
+                }
                 b"entry" => {
                     entry = MavEnumEntry::default();
                     for attr in bytes.attributes() {
@@ -1280,9 +2535,22 @@ pub fn parse_profile(
                     (Some(&Dialect), Some(&Mavlink)) => {
                         eprintln!("TODO: dialect {s:?}");
                     }
-                    (Some(Deprecated), _) => {
-                        eprintln!("TODO: deprecated {s:?}");
+                    (Some(&Deprecated), Some(&Message)) => {
+                        if let Some(deprecated) = message.deprecated.as_mut() {
+                            deprecated.note = Some(s.replace('\n', " "));
+                        }
+                    }
+                    (Some(&Deprecated), Some(&Enum)) => {
+                        if let Some(deprecated) = mavenum.deprecated.as_mut() {
+                            deprecated.note = Some(s.replace('\n', " "));
+                        }
+                    }
+                    (Some(&Deprecated), Some(&Entry)) => {
+                        if let Some(deprecated) = entry.deprecated.as_mut() {
+                            deprecated.note = Some(s.replace('\n', " "));
+                        }
                     }
+                    (Some(&Wip), _) => {}
                     data => {
                         panic!("unexpected text data {data:?} reading {s:?}");
                     }
@@ -1308,9 +2576,22 @@ pub fn parse_profile(
 
                         // Update msg fields and add the new message
                         let mut msg = message.clone();
+                        msg.definition_order = message
+                            .fields
+                            .iter()
+                            .map(|field| field.name.clone())
+                            .collect();
                         msg.fields.clear();
                         msg.fields.extend(not_extension_fields);
+                        #[cfg(feature = "emit-extensions")]
                         msg.fields.extend(extension_fields);
+                        #[cfg(not(feature = "emit-extensions"))]
+                        {
+                            msg.omitted_extensions_len = extension_fields
+                                .iter()
+                                .map(|field| field.mavtype.len())
+                                .sum();
+                        }
 
                         profile.add_message(&msg);
                     }
@@ -1318,10 +2599,15 @@ pub fn parse_profile(
                         profile.add_enum(&mavenum);
                     }
                     Some(&MavXmlElement::Include) => {
-                        let include_file = Path::new(&definitions_dir).join(include.clone());
+                        let include_dir = include_dir(definitions_dir, include_dirs, &include);
+                        let include_file = include_dir.join(&include);
                         if !parsed_files.contains(&include_file) {
-                            let included_profile =
-                                parse_profile(definitions_dir, &include, parsed_files)?;
+                            let included_profile = parse_profile_with_include_dirs(
+                                include_dir,
+                                include_dirs,
+                                &include,
+                                parsed_files,
+                            )?;
                             for message in included_profile.messages.values() {
                                 profile.add_message(message);
                             }
@@ -1354,16 +2640,268 @@ pub fn generate<W: Write>(
     definition_file: &Path,
     output_rust: &mut W,
 ) -> Result<(), BindGenError> {
-    let mut parsed_files: HashSet<PathBuf> = HashSet::new();
-    let profile = parse_profile(definitions_dir, definition_file, &mut parsed_files)?;
+    generate_with_include_dirs(definitions_dir, &[], definition_file, None, output_rust)
+}
+
+/// Generate rust representation of mavlink message set, with typed structs only for the messages
+/// named in `messages`. The other messages of the set are parsed as raw payloads.
+pub fn generate_subset<W: Write>(
+    definitions_dir: &Path,
+    definition_file: &Path,
+    messages: &HashSet<String>,
+    output_rust: &mut W,
+) -> Result<(), BindGenError> {
+    generate_with_include_dirs(
+        definitions_dir,
+        &[],
+        definition_file,
+        Some(messages),
+        output_rust,
+    )
+}
+
+/// Generate rust representation of mavlink message set like [`generate`], or like
+/// [`generate_subset`] if `messages` is given, reading included definition files as
+/// [`parse_profile_with_include_dirs`] does
+pub fn generate_with_include_dirs<W: Write>(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    definition_file: &Path,
+    messages: Option<&HashSet<String>>,
+    output_rust: &mut W,
+) -> Result<(), BindGenError> {
+    let (profile, raw) =
+        parse_profile_subset(definitions_dir, include_dirs, definition_file, messages)?;
 
     // rust file
-    let rust_tokens = profile.emit_rust();
+    let rust_tokens = profile.emit_rust(&raw, true);
     writeln!(output_rust, "{rust_tokens}").unwrap();
 
     Ok(())
 }
 
+/// Rust code of a message set split into a module per message, see [`generate_split`]
+#[derive(Debug, Clone)]
+pub struct SplitDialect {
+    /// Code of the dialect module, with the enums and the `MavMessage` dispatching to the
+    /// messages
+    pub dialect: String,
+    /// Name and code of the module of each message, declared by the dialect module. Their files
+    /// are read from the directory named like the dialect module.
+    pub messages: Vec<(String, String)>,
+}
+
+/// Generate rust representation of mavlink message set like [`generate_with_include_dirs`], with
+/// the code of each message in its own file.
+///
+/// Incremental builds only recompile the modules of the messages that changed, and the messages
+/// can be compiled in parallel.
+pub fn generate_split(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    definition_file: &Path,
+    messages: Option<&HashSet<String>>,
+) -> Result<SplitDialect, BindGenError> {
+    let (profile, raw) =
+        parse_profile_subset(definitions_dir, include_dirs, definition_file, messages)?;
+
+    Ok(SplitDialect {
+        dialect: profile.emit_rust(&raw, false).to_string(),
+        messages: profile
+            .emit_message_files()
+            .into_iter()
+            .map(|(module, code)| (module, code.to_string()))
+            .collect(),
+    })
+}
+
+/// Parse the profile of `definition_file`, split into the typed messages named in `messages` and
+/// the raw ones if given
+fn parse_profile_subset(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    definition_file: &Path,
+    messages: Option<&HashSet<String>>,
+) -> Result<(MavProfile, Vec<MavMessage>), BindGenError> {
+    let mut parsed_files: HashSet<PathBuf> = HashSet::new();
+    let profile = parse_profile_with_include_dirs(
+        definitions_dir,
+        include_dirs,
+        definition_file,
+        &mut parsed_files,
+    )?;
+    Ok(match messages {
+        Some(messages) => profile.subset(messages),
+        None => (profile, vec![]),
+    })
+}
+
+/// Generate rust representation of the mavlink message set defined by `xml`, wrapped in the
+/// public module `module_name`
+pub fn generate_from_str<W: Write>(
+    definitions_dir: &Path,
+    xml: &str,
+    module_name: &str,
+    output_rust: &mut W,
+) -> Result<(), BindGenError> {
+    let mut parsed_files: HashSet<PathBuf> = HashSet::new();
+    let profile = parse_profile_str(definitions_dir, xml, &mut parsed_files)?;
+
+    let module_ident = format_ident!("{}", module_name);
+    let rust_tokens = profile.emit_rust(&[], true);
+    let module_tokens = quote! {
+        #[allow(non_camel_case_types)]
+        #[allow(clippy::derive_partial_eq_without_eq)]
+        #[allow(clippy::field_reassign_with_default)]
+        #[allow(non_snake_case)]
+        #[allow(clippy::unnecessary_cast)]
+        #[allow(clippy::bad_bit_mask)]
+        #[allow(clippy::suspicious_else_formatting)]
+        #[allow(clippy::large_enum_variant)]
+        #[allow(deprecated)]
+        pub mod #module_ident {
+            #rust_tokens
+        }
+    };
+    writeln!(output_rust, "{module_tokens}").unwrap();
+
+    Ok(())
+}
+
+/// Generate rust representation of mavlink message set like [`generate_with_include_dirs`], as a
+/// single file to be declared as a module of another crate, such as with `mod generated;`.
+///
+/// If `features` is given, the cargo features of the generated code are resolved as if exactly
+/// these features were enabled, so that the file does not depend on the features of the crate
+/// declaring it. Otherwise the `cfg`s are kept.
+pub fn generate_standalone<W: Write>(
+    definitions_dir: &Path,
+    include_dirs: &[PathBuf],
+    definition_file: &Path,
+    messages: Option<&HashSet<String>>,
+    features: Option<&HashSet<String>>,
+    output_rust: &mut W,
+) -> Result<(), BindGenError> {
+    let (profile, raw) =
+        parse_profile_subset(definitions_dir, include_dirs, definition_file, messages)?;
+
+    let mut rust_tokens = profile.emit_rust(&raw, true);
+    if let Some(features) = features {
+        rust_tokens = resolve_features(rust_tokens, features);
+    }
+    let file_tokens = quote! {
+        #![allow(non_camel_case_types)]
+        #![allow(clippy::derive_partial_eq_without_eq)]
+        #![allow(clippy::field_reassign_with_default)]
+        #![allow(non_snake_case)]
+        #![allow(clippy::unnecessary_cast)]
+        #![allow(clippy::bad_bit_mask)]
+        #![allow(clippy::suspicious_else_formatting)]
+        #![allow(clippy::large_enum_variant)]
+        #![allow(deprecated)]
+        #rust_tokens
+    };
+    writeln!(output_rust, "{file_tokens}").unwrap();
+
+    Ok(())
+}
+
+/// Resolve the `#[cfg(feature = "..")]` and `#[cfg_attr(feature = "..", ..)]` attributes of
+/// `tokens` as if exactly the cargo `features` were enabled.
+///
+/// Items of disabled features are kept behind an always false `#[cfg(any())]`, as removing them
+/// would require parsing the items. Attributes with other predicates are kept.
+fn resolve_features(tokens: TokenStream, features: &HashSet<String>) -> TokenStream {
+    let mut resolved = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                let attribute = match tokens.peek() {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                        resolve_feature_attribute(group.stream(), features)
+                    }
+                    _ => None,
+                };
+                match attribute {
+                    Some(attribute) => {
+                        tokens.next();
+                        resolved.extend(attribute);
+                    }
+                    None => resolved.extend([TokenTree::Punct(punct)]),
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut resolved_group = Group::new(
+                    group.delimiter(),
+                    resolve_features(group.stream(), features),
+                );
+                resolved_group.set_span(group.span());
+                resolved.extend([TokenTree::Group(resolved_group)]);
+            }
+            token => resolved.extend([token]),
+        }
+    }
+    resolved
+}
+
+/// The attributes replacing the attribute with the content `attribute`, if it is a `cfg` or
+/// `cfg_attr` on a cargo feature
+fn resolve_feature_attribute(
+    attribute: TokenStream,
+    features: &HashSet<String>,
+) -> Option<TokenStream> {
+    let mut tokens = attribute.into_iter();
+    let (Some(TokenTree::Ident(name)), Some(TokenTree::Group(arguments)), None) =
+        (tokens.next(), tokens.next(), tokens.next())
+    else {
+        return None;
+    };
+    if arguments.delimiter() != Delimiter::Parenthesis {
+        return None;
+    }
+
+    // split the arguments on the commas outside of groups
+    let mut arguments = arguments
+        .stream()
+        .into_iter()
+        .fold(vec![vec![]], |mut arguments, token| {
+            match &token {
+                TokenTree::Punct(punct) if punct.as_char() == ',' => arguments.push(vec![]),
+                _ => arguments.last_mut().unwrap().push(token),
+            }
+            arguments
+        })
+        .into_iter();
+    let enabled = match arguments.next()?.as_slice() {
+        [TokenTree::Ident(feature), TokenTree::Punct(eq), TokenTree::Literal(value)]
+            if feature == "feature" && eq.as_char() == '=' =>
+        {
+            let value = value.to_string();
+            features.contains(value.strip_prefix('"')?.strip_suffix('"')?)
+        }
+        _ => return None,
+    };
+
+    if name == "cfg" {
+        Some(if enabled {
+            quote!()
+        } else {
+            quote!(#[cfg(any())])
+        })
+    } else if name == "cfg_attr" {
+        let attributes = arguments
+            .filter(|attribute| enabled && !attribute.is_empty())
+            .map(|attribute| {
+                let attribute: TokenStream = attribute.into_iter().collect();
+                resolve_features(quote!(#[#attribute]), features)
+            });
+        Some(quote!(#(#attributes)*))
+    } else {
+        None
+    }
+}
+
 /// CRC operates over names of the message and names of its fields
 /// Hence we have to preserve the original uppercase names delimited with an underscore
 /// For field names, we replace "type" with "mavtype" to make it rust compatible (this is
@@ -1399,74 +2937,6 @@ pub fn extra_crc(msg: &MavMessage) -> u8 {
     ((crcval & 0xFF) ^ (crcval >> 8)) as u8
 }
 
-#[cfg(not(feature = "emit-extensions"))]
-struct ExtensionFilter {
-    pub is_in: bool,
-}
-
-struct MavXmlFilter {
-    #[cfg(not(feature = "emit-extensions"))]
-    extension_filter: ExtensionFilter,
-}
-
-impl Default for MavXmlFilter {
-    fn default() -> Self {
-        Self {
-            #[cfg(not(feature = "emit-extensions"))]
-            extension_filter: ExtensionFilter { is_in: false },
-        }
-    }
-}
-
-impl MavXmlFilter {
-    pub fn filter(&mut self, elements: &mut Vec<Result<Event, quick_xml::Error>>) {
-        // List of filters
-        elements.retain(|x| self.filter_extension(x));
-    }
-
-    #[cfg(feature = "emit-extensions")]
-    pub fn filter_extension(&mut self, _element: &Result<Event, quick_xml::Error>) -> bool {
-        true
-    }
-
-    /// Ignore extension fields
-    #[cfg(not(feature = "emit-extensions"))]
-    pub fn filter_extension(&mut self, element: &Result<Event, quick_xml::Error>) -> bool {
-        match element {
-            Ok(content) => {
-                match content {
-                    Event::Start(bytes) | Event::Empty(bytes) => {
-                        let Some(id) = identify_element(bytes.name().into_inner()) else {
-                            panic!(
-                                "unexpected element {:?}",
-                                String::from_utf8_lossy(bytes.name().into_inner())
-                            );
-                        };
-                        if id == MavXmlElement::Extensions {
-                            self.extension_filter.is_in = true;
-                        }
-                    }
-                    Event::End(bytes) => {
-                        let Some(id) = identify_element(bytes.name().into_inner()) else {
-                            panic!(
-                                "unexpected element {:?}",
-                                String::from_utf8_lossy(bytes.name().into_inner())
-                            );
-                        };
-
-                        if id == MavXmlElement::Message {
-                            self.extension_filter.is_in = false;
-                        }
-                    }
-                    _ => {}
-                }
-                !self.extension_filter.is_in
-            }
-            Err(error) => panic!("Failed to filter XML: {error}"),
-        }
-    }
-}
-
 fn to_pascal_case(text: impl AsRef<[u8]>) -> String {
     text.as_ref()
         .split(|c| *c == b'_')
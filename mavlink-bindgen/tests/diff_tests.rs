@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use mavlink_bindgen::diff::{diff_profiles, DialectChange};
+use mavlink_bindgen::parser::parse_profile_str;
+
+const OLD_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <enums>
+    <enum name="TEST_STATE">
+      <entry value="0" name="TEST_STATE_IDLE"/>
+      <entry value="1" name="TEST_STATE_ACTIVE"/>
+      <entry value="2" name="TEST_STATE_ERROR"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="TEST_STATUS">
+      <field type="uint8_t" name="state" enum="TEST_STATE">Current state</field>
+      <field type="float" name="value">Value</field>
+    </message>
+    <message id="42001" name="TEST_REMOVED">
+      <field type="uint8_t" name="value">Value</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+const NEW_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <enums>
+    <enum name="TEST_STATE">
+      <entry value="0" name="TEST_STATE_IDLE"/>
+      <entry value="1" name="TEST_STATE_ACTIVE"/>
+      <entry value="3" name="TEST_STATE_ERROR"/>
+      <entry value="4" name="TEST_STATE_DONE"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="TEST_STATUS">
+      <field type="uint8_t" name="state" enum="TEST_STATE">Current state</field>
+      <field type="double" name="value">Value</field>
+    </message>
+    <message id="42002" name="TEST_ADDED">
+      <field type="uint8_t" name="value">Value</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+fn parse(xml: &str) -> mavlink_bindgen::parser::MavProfile {
+    parse_profile_str(Path::new(""), xml, &mut HashSet::new()).expect("Failed to parse dialect")
+}
+
+#[test]
+fn test_diff_identical_profiles() {
+    assert!(diff_profiles(&parse(OLD_DIALECT), &parse(OLD_DIALECT)).is_empty());
+}
+
+#[test]
+fn test_diff_profiles() {
+    let changes = diff_profiles(&parse(OLD_DIALECT), &parse(NEW_DIALECT));
+
+    let status = "TEST_STATUS".to_string();
+    let expected_crc_change = changes.iter().any(|change| {
+        matches!(change, DialectChange::CrcExtraChanged { message, .. } if *message == status)
+    });
+    assert!(expected_crc_change);
+    let changes: Vec<_> = changes
+        .into_iter()
+        .filter(|change| !matches!(change, DialectChange::CrcExtraChanged { .. }))
+        .collect();
+
+    assert_eq!(
+        changes,
+        vec![
+            DialectChange::MessageAdded {
+                message: "TEST_ADDED".to_string(),
+                id: 42002
+            },
+            DialectChange::MessageRemoved {
+                message: "TEST_REMOVED".to_string(),
+                id: 42001
+            },
+            DialectChange::FieldChanged {
+                message: status,
+                field: "value".to_string()
+            },
+            DialectChange::EnumEntryValueChanged {
+                name: "TestState".to_string(),
+                entry: "TEST_STATE_ERROR".to_string(),
+                old: Some(2),
+                new: Some(3)
+            },
+            DialectChange::EnumEntryAdded {
+                name: "TestState".to_string(),
+                entry: "TEST_STATE_DONE".to_string()
+            },
+        ]
+    );
+    assert!(!changes[0].is_breaking());
+    assert!(changes[1].is_breaking());
+}
+
+#[test]
+fn test_diff_definitions() {
+    let dir = std::env::temp_dir().join("mavlink-bindgen-diff-definitions");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("old.xml"), OLD_DIALECT).unwrap();
+    fs::write(dir.join("new.xml"), NEW_DIALECT).unwrap();
+
+    let changes = mavlink_bindgen::diff_definitions(dir.join("old.xml"), dir.join("new.xml"))
+        .expect("Failed to compare dialects");
+    assert!(changes.iter().any(DialectChange::is_breaking));
+    assert!(
+        mavlink_bindgen::diff_definitions(dir.join("new.xml"), dir.join("new.xml"))
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,179 @@
+use std::fs;
+
+const TEST_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <version>3</version>
+  <enums>
+    <enum name="TEST_STATE">
+      <entry value="0" name="TEST_STATE_IDLE"/>
+      <entry value="1" name="TEST_STATE_ACTIVE"/>
+      <entry value="2" name="TEST_STATE_LEGACY">
+        <deprecated since="2024-01" replaced_by="TEST_STATE_ACTIVE"/>
+      </entry>
+      <entry value="3" name="TEST_STATE_DRAFT">
+        <wip/>
+      </entry>
+    </enum>
+    <enum name="TEST_FLAGS" bitmask="true">
+      <entry value="1" name="TEST_FLAGS_A"/>
+      <entry value="2" name="TEST_FLAGS_B"/>
+    </enum>
+    <enum name="TEST_UNUSED_FLAGS" bitmask="true">
+      <entry value="1" name="TEST_UNUSED_FLAGS_A"/>
+      <entry value="256" name="TEST_UNUSED_FLAGS_B"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="TEST_STATUS">
+      <description>Minimal message for tests.</description>
+      <field type="uint8_t" name="target_system">System ID</field>
+      <field type="uint8_t" name="state" enum="TEST_STATE">Current state</field>
+      <field type="float" name="value" units="m/s" minValue="-10" maxValue="10" increment="0.5">Value</field>
+      <field type="uint16_t" name="flags" enum="TEST_FLAGS">Flags</field>
+      <field type="int16_t" name="temperature" invalid="INT16_MAX">Temperature</field>
+      <field type="float[4]" name="quaternion" invalid="[NaN:]">Attitude</field>
+      <field type="uint16_t[4]" name="voltages" invalid="[UINT16_MAX]">Voltages</field>
+      <field type="uint8_t" name="count" invalid="-1">Count</field>
+    </message>
+    <message id="42001" name="TEST_OLD_STATUS">
+      <deprecated since="2024-01" replaced_by="TEST_STATUS">Sent at a fixed rate</deprecated>
+      <field type="uint8_t" name="state">State</field>
+    </message>
+    <message id="42002" name="TEST_DRAFT_STATUS">
+      <wip/>
+      <field type="uint8_t" name="state">State</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+/// Generate the test dialect into a directory of its own for `test`
+fn generate(test: &str) -> String {
+    let destination_dir = std::env::temp_dir().join(format!("mavlink-bindgen-{test}"));
+    fs::create_dir_all(&destination_dir).unwrap();
+
+    let path = mavlink_bindgen::generate_from_str(TEST_DIALECT, "test_dialect", &destination_dir)
+        .expect("Failed to generate dialect");
+    assert_eq!(path, destination_dir.join("test_dialect.rs"));
+    fs::read_to_string(path).unwrap()
+}
+
+/// Whether `generated` contains `code`, regardless of how the tokens are spaced
+fn contains(generated: &str, code: &str) -> bool {
+    let strip = |code: &str| code.split_whitespace().collect::<String>();
+    strip(generated).contains(&strip(code))
+}
+
+#[test]
+fn test_generate_from_str() {
+    let generated = generate("generate-from-str");
+    assert!(contains(&generated, "pub mod test_dialect"));
+    assert!(contains(&generated, "pub struct TEST_STATUS_DATA"));
+    assert!(contains(&generated, "pub enum TestState"));
+    assert!(contains(&generated, "pub enum MavMessage"));
+}
+
+#[test]
+fn test_bitmask_enums() {
+    let generated = generate("bitmask-enums");
+    // enums marked as bitmasks are flags even if their fields don't have display="bitmask"
+    assert!(contains(&generated, "pub struct TestFlags: u16"));
+    assert!(contains(&generated, "TestFlags::from_bits"));
+    assert!(contains(&generated, "pub struct TestUnusedFlags: u16"));
+    assert!(!contains(&generated, "pub enum TestFlags"));
+}
+
+#[test]
+fn test_field_info() {
+    let generated = generate("field-info");
+    assert!(contains(
+        &generated,
+        r#"name: "value", units: Some("m/s"), display: None, increment: Some(0.5f64),
+           min_value: Some(-10f64), max_value: Some(10f64)"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"name: "flags", units: None, display: Some("bitmask")"#
+    ));
+}
+
+#[test]
+fn test_invalid_values() {
+    let generated = generate("invalid-values");
+    assert!(contains(
+        &generated,
+        "fn is_valid_temperature(&self) -> bool { self.temperature != 32767 }"
+    ));
+    assert!(contains(
+        &generated,
+        "fn is_valid_quaternion(&self) -> bool { !self.quaternion[0].is_nan() }"
+    ));
+    assert!(contains(
+        &generated,
+        "fn is_valid_voltages(&self) -> bool { self.voltages.iter().any(|value| (*value) != 65535) }"
+    ));
+    // values that can't be of the type of the field are ignored
+    assert!(!contains(&generated, "is_valid_count"));
+}
+
+#[cfg(feature = "emit-description")]
+#[test]
+fn test_emit_description() {
+    let generated = generate("emit-description");
+    assert!(contains(
+        &generated,
+        r#"#[doc = "Minimal message for tests."]"#
+    ));
+    assert!(contains(&generated, r#"#[doc = "Current state."]"#));
+    assert!(contains(&generated, r#"#[doc = "Units: `m/s`"]"#));
+}
+
+#[test]
+fn test_deprecated_and_wip() {
+    let generated = generate("deprecated-and-wip");
+    assert!(contains(
+        &generated,
+        r#"#[deprecated(note = "Deprecated since 2024-01, replaced by TEST_STATUS: Sent at a fixed rate")]
+           #[derive(Debug, Clone, PartialEq)]
+           #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+           pub struct TEST_OLD_STATUS_DATA"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"#[deprecated(note = "Deprecated since 2024-01, replaced by TEST_STATE_ACTIVE")]
+           TEST_STATE_LEGACY = 2"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"#[cfg(feature = "unstable")] TEST_STATE_DRAFT = 3"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"#[cfg(feature = "unstable")]
+           #[derive(Debug, Clone, PartialEq)]
+           #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+           pub struct TEST_DRAFT_STATUS_DATA"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"#[cfg(feature = "unstable")] TEST_DRAFT_STATUS(TEST_DRAFT_STATUS_DATA)"#
+    ));
+}
+
+#[test]
+fn test_pymavlink_serialization() {
+    let generated = generate("pymavlink");
+    // fields in the order of the definition rather than in wire order
+    assert!(contains(
+        &generated,
+        r#"map.serialize_entry("mavpackettype", Self::NAME)?;
+           map.serialize_entry("target_system", &self.target_system)?;
+           map.serialize_entry("state", &(self.state as u8))?;
+           map.serialize_entry("value", &self.value)?;
+           map.serialize_entry("flags", &self.flags.bits())?;"#
+    ));
+    assert!(contains(
+        &generated,
+        r#"map.serialize_entry("quaternion", &self.quaternion[..])?;"#
+    ));
+}
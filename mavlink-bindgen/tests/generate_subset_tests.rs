@@ -0,0 +1,72 @@
+use std::fs;
+
+const TEST_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <enums>
+    <enum name="TEST_STATE">
+      <entry value="0" name="TEST_STATE_IDLE"/>
+      <entry value="1" name="TEST_STATE_ACTIVE"/>
+    </enum>
+    <enum name="TEST_MODE">
+      <entry value="0" name="TEST_MODE_MANUAL"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="TEST_STATUS">
+      <field type="uint8_t" name="state" enum="TEST_STATE">Current state</field>
+    </message>
+    <message id="42001" name="TEST_COMMAND">
+      <field type="float" name="param">Parameter</field>
+      <field type="uint8_t" name="target_system">System ID</field>
+      <field type="uint8_t" name="mode" enum="TEST_MODE">Mode</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+#[test]
+fn test_generate_subset() {
+    let definitions_dir = std::env::temp_dir().join("mavlink-bindgen-generate-subset");
+    let destination_dir = std::env::temp_dir().join("mavlink-bindgen-generate-subset-out");
+    fs::create_dir_all(&definitions_dir).unwrap();
+    fs::create_dir_all(&destination_dir).unwrap();
+    fs::write(definitions_dir.join("test.xml"), TEST_DIALECT).unwrap();
+
+    let result =
+        mavlink_bindgen::generate_subset(&definitions_dir, &destination_dir, &["TEST_STATUS"])
+            .expect("Failed to generate dialect");
+    assert_eq!(result.bindings.len(), 1);
+
+    let generated = fs::read_to_string(&result.bindings[0].rust_module).unwrap();
+    let status = fs::read_to_string(destination_dir.join("test/test_status.rs")).unwrap();
+    assert!(status.contains("pub struct TEST_STATUS_DATA"));
+    assert!(generated.contains("pub use self :: test_status :: TEST_STATUS_DATA"));
+    assert!(generated.contains("pub enum TestState"));
+    // the other message and the enums only it uses are not generated
+    assert!(!generated.contains("TEST_COMMAND_DATA"));
+    assert!(!destination_dir.join("test/test_command.rs").exists());
+    assert!(!generated.contains("TestMode"));
+    // but it is still parsed, as a raw payload
+    assert!(generated.contains("Raw (:: mavlink_core :: RawMessage)"));
+    assert!(generated.contains("name : \"TEST_COMMAND\""));
+    assert!(generated.contains("target_system_offset : Some (4usize)"));
+    // along with the metadata of its fields
+    assert!(generated.contains("fields : TEST_STATUS_DATA :: FIELDS"));
+    assert!(generated
+        .contains("fields : & [:: mavlink_core :: FieldInfo { name : \"param\" , units : None"));
+}
+
+#[test]
+fn test_read_message_list() {
+    let path = std::env::temp_dir().join("mavlink-bindgen-message-list.txt");
+    fs::write(
+        &path,
+        "# messages of the firmware\nHEARTBEAT\nCOMMAND_LONG, COMMAND_ACK # commands\n\n",
+    )
+    .unwrap();
+
+    let messages = mavlink_bindgen::read_message_list(&path).expect("Failed to read list");
+    assert_eq!(messages, ["HEARTBEAT", "COMMAND_LONG", "COMMAND_ACK"]);
+
+    assert!(mavlink_bindgen::read_message_list(path.with_extension("missing")).is_err());
+}
@@ -0,0 +1,125 @@
+use std::fs;
+
+const BUNDLED_COMMON: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <messages>
+    <message id="0" name="HEARTBEAT">
+      <field type="uint8_t" name="state">State</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+const BUNDLED_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <include>common.xml</include>
+  <messages>
+    <message id="42001" name="BUNDLED_STATUS">
+      <field type="uint8_t" name="state">State</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+const CUSTOM_DIALECT: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <include>common.xml</include>
+  <messages>
+    <message id="42000" name="CUSTOM_STATUS">
+      <field type="float" name="value">Value</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+#[test]
+fn test_generate_custom_dialects() {
+    let root = std::env::temp_dir().join("mavlink-bindgen-generator");
+    let bundled_dir = root.join("bundled");
+    let custom_dir = root.join("custom");
+    let destination_dir = root.join("out");
+    for dir in [&bundled_dir, &custom_dir, &destination_dir] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(bundled_dir.join("common.xml"), BUNDLED_COMMON).unwrap();
+    fs::write(bundled_dir.join("bundled.xml"), BUNDLED_DIALECT).unwrap();
+    fs::write(custom_dir.join("custom.xml"), CUSTOM_DIALECT).unwrap();
+    // replaces the bundled definition
+    fs::write(custom_dir.join("bundled.xml"), CUSTOM_DIALECT).unwrap();
+
+    let result = mavlink_bindgen::Generator::new()
+        .with_custom_dialects_dir(&custom_dir)
+        .with_definitions_dir(&bundled_dir)
+        .generate(&destination_dir)
+        .expect("Failed to generate dialects");
+    let mut modules: Vec<_> = result
+        .bindings
+        .iter()
+        .map(|binding| binding.module_name.as_str())
+        .collect();
+    modules.sort_unstable();
+    assert_eq!(modules, ["bundled", "common", "custom"]);
+
+    // includes of custom dialects are read from the other directories
+    let custom = fs::read_to_string(destination_dir.join("custom.rs")).unwrap();
+    assert!(custom.contains("pub use self :: custom_status :: CUSTOM_STATUS_DATA"));
+    assert!(custom.contains("pub use self :: heartbeat :: HEARTBEAT_DATA"));
+    let heartbeat = fs::read_to_string(destination_dir.join("custom/heartbeat.rs")).unwrap();
+    assert!(heartbeat.contains("pub struct HEARTBEAT_DATA"));
+
+    let bundled = fs::read_to_string(destination_dir.join("bundled.rs")).unwrap();
+    assert!(bundled.contains("pub use self :: custom_status :: CUSTOM_STATUS_DATA"));
+    assert!(!bundled.contains("BUNDLED_STATUS_DATA"));
+
+    // only the modules of the definitions directories are behind a feature
+    let mod_rs = fs::read_to_string(result.mod_rs).unwrap();
+    assert!(mod_rs.contains("# [cfg (feature = \"common\")] pub mod common ;"));
+    assert!(mod_rs.contains("# [allow (deprecated)] pub mod custom ;"));
+    assert!(mod_rs.contains("# [allow (deprecated)] pub mod bundled ;"));
+}
+
+#[test]
+fn test_generate_dialect() {
+    let root = std::env::temp_dir().join("mavlink-bindgen-generate-dialect");
+    let bundled_dir = root.join("bundled");
+    let custom_dir = root.join("custom");
+    for dir in [&bundled_dir, &custom_dir] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(bundled_dir.join("common.xml"), BUNDLED_COMMON).unwrap();
+    fs::write(custom_dir.join("custom.xml"), CUSTOM_DIALECT).unwrap();
+    let generator = mavlink_bindgen::Generator::new().with_definitions_dir(&bundled_dir);
+
+    let binding = generator
+        .generate_dialect(
+            custom_dir.join("custom.xml"),
+            root.join("generated.rs"),
+            None,
+        )
+        .expect("Failed to generate dialect");
+    assert_eq!(binding.module_name, "custom");
+    assert_eq!(binding.rust_module, root.join("generated.rs"));
+
+    // a single file to declare as a module, with the messages inline and the includes read from
+    // the directories added
+    let generated = fs::read_to_string(&binding.rust_module).unwrap();
+    assert!(generated.starts_with("# ! [allow (non_camel_case_types)]"));
+    assert!(generated.contains("mod custom_status { use super :: * ;"));
+    assert!(generated.contains("pub struct HEARTBEAT_DATA"));
+    assert!(generated.contains("# [cfg (feature = \"serde\")]"));
+    assert!(!root.join("custom").join("custom_status.rs").exists());
+
+    let binding = generator
+        .generate_dialect(
+            custom_dir.join("custom.xml"),
+            root.join("resolved.rs"),
+            Some(&["serde", "defmt"]),
+        )
+        .expect("Failed to generate dialect");
+    let resolved = fs::read_to_string(binding.rust_module).unwrap();
+    assert!(!resolved.contains("feature ="));
+    assert!(resolved.contains("use serde :: { Serialize , Deserialize } ;"));
+    assert!(resolved.contains("Serialize , Deserialize)] # [serde (tag = \"type\")]"));
+    // items of the features not given are compiled out
+    assert!(resolved.contains("# [cfg (any ())] impl MavMessage {"));
+}
@@ -1,50 +1,114 @@
 //! Async Serial MAVLINK connection
 
 use core::ops::DerefMut;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 use std::io;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::sync::Mutex;
 use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialStream};
 
 use super::AsyncConnectable;
 use crate::{
-    async_peek_reader::AsyncPeekReader, connectable::SerialConnectable, MavHeader, MavlinkVersion,
-    Message,
+    async_peek_reader::AsyncPeekReader, connectable::SerialConnectable, MavFrame, MavFrameInfo,
+    MavHeader, MavlinkVersion, Message, RawFrame, SerialDataBits, SerialFlowControl, SerialParity,
+    SerialStopBits, SourceFilter, TargetFilter,
 };
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg_async, write_versioned_msg_async};
+use crate::{
+    peek_versioned_frame_info_async, read_versioned_frame_async, read_versioned_raw_message_async,
+    write_versioned_msg_async,
+};
 #[cfg(feature = "signing")]
 use crate::{
-    read_versioned_msg_async_signed, write_versioned_msg_async_signed, SigningConfig, SigningData,
+    peek_versioned_frame_info_async_signed, read_versioned_frame_async_signed,
+    read_versioned_raw_message_async_signed, write_versioned_msg_async_signed, SigningConfig,
+    SigningData,
 };
 
 use super::AsyncMavConnection;
 
 pub struct AsyncSerialConnection {
-    port: Mutex<AsyncPeekReader<SerialStream>>,
-    sequence: Mutex<u8>,
+    port: SerialHandle,
+    reader: Mutex<AsyncPeekReader<SerialHandle>>,
+    writer: Mutex<SerialWrite>,
     protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
 
+struct SerialWrite {
+    port: SerialHandle,
+    sequence: u8,
+}
+
+/// Shared handle to the serial port, only locked for the duration of each poll, so that a pending
+/// read doesn't hold back the writes
+#[derive(Clone)]
+struct SerialHandle(Arc<std::sync::Mutex<SerialStream>>);
+
+impl AsyncRead for SerialHandle {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SerialHandle {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_shutdown(cx)
+    }
+}
+
 #[async_trait::async_trait]
 impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncSerialConnection {
     async fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
-        let mut port = self.port.lock().await;
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
 
-        #[cfg(not(feature = "signing"))]
-        let result = read_versioned_msg_async(port.deref_mut(), self.protocol_version).await;
-        #[cfg(feature = "signing")]
-        let result = read_versioned_msg_async_signed(
-            port.deref_mut(),
-            self.protocol_version,
-            self.signing_data.as_ref(),
-        )
-        .await;
-        result
+    async fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
+        let mut port = self.reader.lock().await;
+
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result = read_versioned_frame_async(port.deref_mut(), self.protocol_version).await;
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_async_signed(
+                port.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            )
+            .await;
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                result => return result,
+            }
+        }
     }
 
     async fn send(
@@ -52,23 +116,22 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncSerialConnection {
         header: &MavHeader,
         data: &M,
     ) -> Result<usize, crate::error::MessageWriteError> {
-        let mut port = self.port.lock().await;
-        let mut sequence = self.sequence.lock().await;
+        let mut lock = self.writer.lock().await;
 
         let header = MavHeader {
-            sequence: *sequence,
+            sequence: lock.sequence,
             system_id: header.system_id,
             component_id: header.component_id,
         };
 
-        *sequence = sequence.wrapping_add(1);
+        lock.sequence = lock.sequence.wrapping_add(1);
 
         #[cfg(not(feature = "signing"))]
         let result =
-            write_versioned_msg_async(port.reader_mut(), self.protocol_version, header, data).await;
+            write_versioned_msg_async(&mut lock.port, self.protocol_version, header, data).await;
         #[cfg(feature = "signing")]
         let result = write_versioned_msg_async_signed(
-            port.reader_mut(),
+            &mut lock.port,
             self.protocol_version,
             header,
             data,
@@ -78,6 +141,43 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncSerialConnection {
         result
     }
 
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut port = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            read_versioned_raw_message_async::<M, _>(port.deref_mut(), self.protocol_version).await;
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_async_signed::<M, _>(
+            port.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let mut lock = self.writer.lock().await;
+        let bytes = frame.raw_bytes();
+        lock.port.write_all(bytes).await?;
+        Ok(bytes.len())
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut port = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            peek_versioned_frame_info_async::<M, _>(port.deref_mut(), self.protocol_version).await;
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_async_signed::<M, _>(
+            port.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -86,31 +186,88 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncSerialConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
     }
 }
 
+impl AsyncSerialConnection {
+    /// Open the serial port described by `connectable`
+    pub fn open(connectable: &SerialConnectable) -> io::Result<Self> {
+        let mut port = tokio_serial::new(&connectable.port_name, connectable.baud_rate as u32)
+            .open_native_async()?;
+        let config = &connectable.config;
+        port.set_data_bits(match config.data_bits {
+            SerialDataBits::Five => tokio_serial::DataBits::Five,
+            SerialDataBits::Six => tokio_serial::DataBits::Six,
+            SerialDataBits::Seven => tokio_serial::DataBits::Seven,
+            SerialDataBits::Eight => tokio_serial::DataBits::Eight,
+        })?;
+        port.set_parity(match config.parity {
+            SerialParity::None => tokio_serial::Parity::None,
+            SerialParity::Odd => tokio_serial::Parity::Odd,
+            SerialParity::Even => tokio_serial::Parity::Even,
+        })?;
+        port.set_stop_bits(match config.stop_bits {
+            SerialStopBits::One => tokio_serial::StopBits::One,
+            SerialStopBits::Two => tokio_serial::StopBits::Two,
+        })?;
+        port.set_flow_control(match config.flow_control {
+            SerialFlowControl::None => tokio_serial::FlowControl::None,
+            SerialFlowControl::Software => tokio_serial::FlowControl::Software,
+            SerialFlowControl::Hardware => tokio_serial::FlowControl::Hardware,
+        })?;
+        if let Some(level) = connectable.dtr {
+            port.write_data_terminal_ready(level)?;
+        }
+        if let Some(level) = connectable.rts {
+            port.write_request_to_send(level)?;
+        }
+
+        let port = SerialHandle(Arc::new(std::sync::Mutex::new(port)));
+        Ok(Self {
+            reader: Mutex::new(AsyncPeekReader::new(port.clone())),
+            writer: Mutex::new(SerialWrite {
+                port: port.clone(),
+                sequence: 0,
+            }),
+            port,
+            protocol_version: connectable.protocol_version,
+            target_filter: None,
+            source_filter: None,
+            #[cfg(feature = "signing")]
+            signing_data: None,
+        })
+    }
+
+    /// Set the DTR (Data Terminal Ready) line, used by some boards to enter or leave their bootloader
+    pub async fn set_dtr(&self, level: bool) -> io::Result<()> {
+        let mut port = self.port.0.lock().unwrap();
+        Ok(port.write_data_terminal_ready(level)?)
+    }
+
+    /// Set the RTS (Request To Send) line, used by some boards for resets and by some radios to wake up
+    pub async fn set_rts(&self, level: bool) -> io::Result<()> {
+        let mut port = self.port.0.lock().unwrap();
+        Ok(port.write_request_to_send(level)?)
+    }
+}
+
 #[async_trait]
 impl AsyncConnectable for SerialConnectable {
     async fn connect_async<M>(&self) -> io::Result<Box<dyn AsyncMavConnection<M> + Sync + Send>>
     where
         M: Message + Sync + Send,
     {
-        let mut port =
-            tokio_serial::new(&self.port_name, self.baud_rate as u32).open_native_async()?;
-        port.set_data_bits(tokio_serial::DataBits::Eight)?;
-        port.set_parity(tokio_serial::Parity::None)?;
-        port.set_stop_bits(tokio_serial::StopBits::One)?;
-        port.set_flow_control(tokio_serial::FlowControl::None)?;
-
-        Ok(Box::new(AsyncSerialConnection {
-            port: Mutex::new(AsyncPeekReader::new(port)),
-            sequence: Mutex::new(0),
-            protocol_version: MavlinkVersion::V2,
-            #[cfg(feature = "signing")]
-            signing_data: None,
-        }))
+        Ok(Box::new(AsyncSerialConnection::open(self)?))
     }
 }
@@ -6,24 +6,36 @@ use super::{AsyncConnectable, AsyncMavConnection};
 use crate::connectable::FileConnectable;
 use crate::error::{MessageReadError, MessageWriteError};
 
-use crate::{async_peek_reader::AsyncPeekReader, MavHeader, MavlinkVersion, Message};
+use crate::{
+    async_peek_reader::AsyncPeekReader, MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message,
+    RawFrame, SourceFilter, TargetFilter,
+};
 
 use async_trait::async_trait;
-use tokio::fs::File;
-use tokio::io;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{self, AsyncWriteExt};
 use tokio::sync::Mutex;
 
 #[cfg(not(feature = "signing"))]
-use crate::read_versioned_msg_async;
+use crate::{
+    peek_versioned_frame_info_async, read_versioned_frame_async, read_versioned_raw_message_async,
+    write_versioned_msg,
+};
 
 #[cfg(feature = "signing")]
-use crate::{read_versioned_msg_async_signed, SigningConfig, SigningData};
+use crate::{
+    peek_versioned_frame_info_async_signed, read_versioned_frame_async_signed,
+    read_versioned_raw_message_async_signed, write_versioned_msg_signed, SigningConfig,
+    SigningData,
+};
 
 pub async fn open(file_path: &str) -> io::Result<AsyncFileConnection> {
     let file = File::open(file_path).await?;
     Ok(AsyncFileConnection {
         file: Mutex::new(AsyncPeekReader::new(file)),
         protocol_version: MavlinkVersion::V2,
+        target_filter: None,
+        source_filter: None,
         #[cfg(feature = "signing")]
         signing_data: None,
     })
@@ -33,6 +45,9 @@ pub struct AsyncFileConnection {
     file: Mutex<AsyncPeekReader<File>>,
     protocol_version: MavlinkVersion,
 
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
@@ -40,26 +55,33 @@ pub struct AsyncFileConnection {
 #[async_trait::async_trait]
 impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncFileConnection {
     async fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
+
+    async fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let mut file = self.file.lock().await;
 
         loop {
             #[cfg(not(feature = "signing"))]
-            let result = read_versioned_msg_async(file.deref_mut(), self.protocol_version).await;
+            let result = read_versioned_frame_async(file.deref_mut(), self.protocol_version).await;
             #[cfg(feature = "signing")]
-            let result = read_versioned_msg_async_signed(
+            let result = read_versioned_frame_async_signed(
                 file.deref_mut(),
                 self.protocol_version,
                 self.signing_data.as_ref(),
             )
             .await;
             match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
                 ok @ Ok(..) => {
                     return ok;
                 }
-                Err(MessageReadError::Io(e)) => {
-                    if e.kind() == io::ErrorKind::UnexpectedEof {
-                        return Err(MessageReadError::Io(e));
-                    }
+                Err(MessageReadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(MessageReadError::Io(e));
                 }
                 _ => {}
             }
@@ -70,6 +92,137 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncFileConnection {
         Ok(0)
     }
 
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut file = self.file.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            read_versioned_raw_message_async::<M, _>(file.deref_mut(), self.protocol_version).await;
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_async_signed::<M, _>(
+            file.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    async fn send_raw(&self, _frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        Ok(0)
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut file = self.file.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            peek_versioned_frame_info_async::<M, _>(file.deref_mut(), self.protocol_version).await;
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_async_signed::<M, _>(
+            file.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn get_protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
+
+/// Open `file_path` for appending, creating it if needed, to record the sent messages as a
+/// telemetry log
+pub async fn create(file_path: &str) -> io::Result<AsyncFileOutConnection> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)
+        .await?;
+    Ok(AsyncFileOutConnection {
+        file: Mutex::new(file),
+        protocol_version: MavlinkVersion::V2,
+        #[cfg(feature = "signing")]
+        signing_data: None,
+    })
+}
+
+/// Connection recording the sent messages to a telemetry log.
+///
+/// Messages are written with the header they are sent with, so received messages can be recorded
+/// as they were received.
+pub struct AsyncFileOutConnection {
+    file: Mutex<File>,
+    protocol_version: MavlinkVersion,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+impl AsyncFileOutConnection {
+    /// Write `frame` as a single record, so concurrent sends don't interleave
+    async fn write_frame(&self, frame: &[u8]) -> io::Result<()> {
+        let mut record = Vec::with_capacity(8 + frame.len());
+        record.extend_from_slice(&crate::tlog::now_us().to_be_bytes());
+        record.extend_from_slice(frame);
+        let mut file = self.file.lock().await;
+        file.write_all(&record).await?;
+        file.flush().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncFileOutConnection {
+    async fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
+    async fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        let mut frame = Vec::new();
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg(&mut frame, self.protocol_version, *header, data)?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_signed(
+            &mut frame,
+            self.protocol_version,
+            *header,
+            data,
+            self.signing_data.as_ref(),
+        )?;
+        self.write_frame(&frame).await?;
+        Ok(len)
+    }
+
+    async fn recv_raw(&self) -> Result<RawFrame, MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let bytes = frame.raw_bytes();
+        self.write_frame(bytes).await?;
+        Ok(bytes.len())
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -78,6 +231,10 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncFileConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, _filter: Option<TargetFilter>) {}
+
+    fn set_source_filter(&mut self, _filter: Option<SourceFilter>) {}
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -90,6 +247,9 @@ impl AsyncConnectable for FileConnectable {
     where
         M: Message + Sync + Send,
     {
+        if self.output {
+            return Ok(Box::new(create(&self.address).await?));
+        }
         Ok(Box::new(open(&self.address).await?))
     }
 }
@@ -1,19 +1,47 @@
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use tokio::io;
 
-use crate::{connectable::ConnectionAddress, MavFrame, MavHeader, MavlinkVersion, Message};
+use crate::{
+    connectable::{ConnectionAddress, ConnectionOptions},
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
 
 #[cfg(feature = "tcp")]
 mod tcp;
+#[cfg(feature = "tcp")]
+mod tcp_server;
+#[cfg(feature = "tcp")]
+pub use tcp_server::AsyncTcpServerConnection;
 
 #[cfg(feature = "udp")]
 mod udp;
 
 #[cfg(feature = "direct-serial")]
 mod direct_serial;
+#[cfg(feature = "direct-serial")]
+pub use direct_serial::AsyncSerialConnection;
 
 mod file;
 
+mod stdio;
+
+#[cfg(all(unix, feature = "unix-socket"))]
+mod unix;
+
+mod multi;
+pub use multi::AsyncMultiConnection;
+
+mod sink;
+pub use sink::AsyncMavSink;
+
+mod stream;
+pub use stream::AsyncMavStream;
+mod split;
+pub use split::{into_split, AsyncMavReader, AsyncMavWriter};
+
 #[cfg(feature = "signing")]
 use crate::SigningConfig;
 
@@ -25,6 +53,42 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
     /// Yield until a valid frame is received, ignoring invalid messages.
     async fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError>;
 
+    /// Return the header information of the next valid frame without removing it from the
+    /// receive path, so it can be routed before being parsed.
+    ///
+    /// Frames that [`recv`](Self::recv) would drop afterwards, such as unknown messages or messages
+    /// rejected by the target filter, are reported as well. Connections that cannot peek return
+    /// [`io::ErrorKind::Unsupported`].
+    async fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        Err(crate::error::MessageReadError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    /// Receive a mavlink message, failing with [`MessageReadError::Timeout`] if none is received
+    /// within `timeout`.
+    ///
+    /// [`MessageReadError::Timeout`]: crate::error::MessageReadError::Timeout
+    async fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_deadline(Instant::now() + timeout).await
+    }
+
+    /// Receive a mavlink message, failing with [`MessageReadError::Timeout`] if none is received
+    /// before `deadline`.
+    ///
+    /// [`MessageReadError::Timeout`]: crate::error::MessageReadError::Timeout
+    async fn recv_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        tokio::time::timeout_at(deadline.into(), self.recv())
+            .await
+            .map_err(|_| crate::error::MessageReadError::Timeout)?
+    }
+
     /// Send a mavlink message
     async fn send(
         &self,
@@ -35,6 +99,22 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
     fn set_protocol_version(&mut self, version: MavlinkVersion);
     fn get_protocol_version(&self) -> MavlinkVersion;
 
+    /// Only deliver broadcasts and messages addressed to the component described by `filter`,
+    /// discarding traffic targeted elsewhere. Passing `None` disables filtering.
+    ///
+    /// Connections that don't filter what they receive ignore the filter.
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        let _ = filter;
+    }
+
+    /// Discard the frames sent by the systems ignored by `filter`, such as our own traffic
+    /// echoed back by a bridged link. Passing `None` disables filtering.
+    ///
+    /// Connections that don't filter what they receive ignore the filter.
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        let _ = filter;
+    }
+
     /// Write whole frame
     async fn send_frame(
         &self,
@@ -43,7 +123,10 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
         self.send(&frame.header, &frame.msg).await
     }
 
-    /// Read whole frame
+    /// Read whole frame, with the signature of a signed MAVLink 2 frame.
+    ///
+    /// The connections of this crate keep the signature, the default implementation builds the
+    /// frame from [`recv`](Self::recv) and leaves it empty.
     async fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let (header, msg) = self.recv().await?;
         let protocol_version = self.get_protocol_version();
@@ -51,6 +134,7 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
             header,
             msg,
             protocol_version,
+            signature: None,
         })
     }
 
@@ -60,6 +144,25 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
         self.send(&header, data).await
     }
 
+    /// Receive the next valid frame without parsing its message, so it can be forwarded byte for
+    /// byte with [`send_raw`](Self::send_raw).
+    ///
+    /// Frames go through the protocol version and signature checks of the connection, but not
+    /// through its filters.
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        Err(crate::error::MessageReadError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    /// Write a frame unchanged, keeping its sequence number and signature
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let _ = frame;
+        Err(crate::error::MessageWriteError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
     /// Setup secret key used for message signing, or disable message signing
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>);
@@ -71,20 +174,60 @@ pub trait AsyncMavConnection<M: Message + Sync + Send> {
 ///
 ///  * `tcpin:<addr>:<port>` to create a TCP server, listening for incoming connections
 ///  * `tcpout:<addr>:<port>` to create a TCP client
+///  * `tcpserver:<addr>:<port>` to create a TCP server accepting any number of clients
 ///  * `udpin:<addr>:<port>` to create a UDP server, listening for incoming packets
 ///  * `udpout:<addr>:<port>` to create a UDP client
 ///  * `udpbcast:<addr>:<port>` to create a UDP broadcast
-///  * `serial:<port>:<baudrate>` to create a serial connection
+///  * `serial:<port>:<baudrate>` to create a serial connection, optionally followed by a framing
+///    other than 8N1 such as `:8E1`, and by `:rtscts` or `:xonxoff` for flow control
 ///  * `file:<path>` to extract file data
+///  * `fileout:<path>` to append the sent messages to a telemetry log
+///  * `stdio:` to read from the standard input and write to the standard output
+///  * `unix:<path>` to connect to a Unix domain socket
+///  * `unixin:<path>` to create a Unix domain socket, waiting for a client to connect
 ///
 /// The type of the connection is determined at runtime based on the address type, so the
 /// connection is returned as a trait object.
 pub async fn connect_async<M: Message + Sync + Send>(
     address: &str,
 ) -> io::Result<Box<dyn AsyncMavConnection<M> + Sync + Send>> {
-    ConnectionAddress::parse_address(address)?
-        .connect_async::<M>()
-        .await
+    let result = match ConnectionAddress::parse_address(address) {
+        Ok(address) => address.connect_async::<M>().await,
+        Err(error) => Err(error),
+    };
+    #[cfg(feature = "tracing")]
+    crate::instrument::connection_opened(address, &result);
+    result
+}
+
+/// Connect asynchronously to a MAVLink node by address string like [`connect_async`], opening the
+/// socket or port with `options`.
+pub async fn connect_async_with_options<M: Message + Sync + Send>(
+    address: &str,
+    options: ConnectionOptions,
+) -> io::Result<Box<dyn AsyncMavConnection<M> + Sync + Send>> {
+    let result = match ConnectionAddress::parse_address(address) {
+        Ok(address) => address.with_options(options).connect_async::<M>().await,
+        Err(error) => Err(error),
+    };
+    #[cfg(feature = "tracing")]
+    crate::instrument::connection_opened(address, &result);
+    result
+}
+
+/// Returns every socket address the given address resolves to, to be tried in turn.
+#[cfg(feature = "tcp")]
+pub(crate) fn get_socket_addrs<T: std::net::ToSocketAddrs>(
+    address: T,
+) -> Result<Vec<std::net::SocketAddr>, io::Error> {
+    let addrs: Vec<_> = address.to_socket_addrs()?.collect();
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Host address lookup failed",
+        ));
+    }
+    Ok(addrs)
 }
 
 /// Returns the socket address for the given address.
@@ -121,6 +264,9 @@ impl AsyncConnectable for ConnectionAddress {
             Self::Udp(connectable) => connectable.connect_async::<M>().await,
             Self::Serial(connectable) => connectable.connect_async::<M>().await,
             Self::File(connectable) => connectable.connect_async::<M>().await,
+            Self::Stdio(connectable) => connectable.connect_async::<M>().await,
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Self::Unix(connectable) => connectable.connect_async::<M>().await,
         }
     }
 }
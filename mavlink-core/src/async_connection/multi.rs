@@ -0,0 +1,106 @@
+//! Receiving from several async MAVLink connections at once
+
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::Poll;
+use std::io::ErrorKind;
+
+use crate::error::MessageReadError;
+use crate::{MavHeader, Message};
+
+use super::AsyncMavConnection;
+
+/// A set of async connections returning the next message from whichever connection receives
+/// one first.
+///
+/// Messages are tagged with the index returned by [`add`](Self::add), which also gives access
+/// to the connection to reply.
+pub struct AsyncMultiConnection<M: Message + Sync + Send> {
+    connections: Vec<Box<dyn AsyncMavConnection<M> + Sync + Send>>,
+    failed: Vec<AtomicBool>,
+    next: AtomicUsize,
+}
+
+impl<M: Message + Sync + Send> Default for AsyncMultiConnection<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message + Sync + Send> AsyncMultiConnection<M> {
+    pub fn new() -> Self {
+        Self {
+            connections: Vec::new(),
+            failed: Vec::new(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add `connection` to the set and return the index its messages are tagged with
+    pub fn add(&mut self, connection: Box<dyn AsyncMavConnection<M> + Sync + Send>) -> usize {
+        self.connections.push(connection);
+        self.failed.push(AtomicBool::new(false));
+        self.connections.len() - 1
+    }
+
+    /// The connection added with index `index`
+    pub fn connection(&self, index: usize) -> Option<&(dyn AsyncMavConnection<M> + Sync + Send)> {
+        self.connections.get(index).map(|connection| &**connection)
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Receive the next message from any of the connections, tagged with the index of the
+    /// connection it came from.
+    ///
+    /// Errors are reported with the index of the failing connection, a connection is no longer
+    /// polled after an I/O error. Once no connection is left to poll, because the set is empty or
+    /// all of them failed, an [`ErrorKind::NotConnected`] error is returned with the index
+    /// [`len`](Self::len). The pending receptions of the other connections are dropped
+    /// once a message is returned, which the connections of this crate handle without losing
+    /// data.
+    pub async fn recv_any(&self) -> (usize, Result<(MavHeader, M), MessageReadError>) {
+        let count = self.connections.len();
+        // rotate the polling order so a busy connection can't starve the others
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut receptions: Vec<_> = self
+            .connections
+            .iter()
+            .zip(&self.failed)
+            .map(|(connection, failed)| {
+                (!failed.load(Ordering::Relaxed)).then(|| connection.recv())
+            })
+            .collect();
+        if receptions.iter().all(Option::is_none) {
+            return (
+                count,
+                Err(MessageReadError::Io(ErrorKind::NotConnected.into())),
+            );
+        }
+
+        poll_fn(|cx| {
+            for offset in 0..count {
+                let index = (start + offset) % count;
+                let Some(reception) = &mut receptions[index] else {
+                    continue;
+                };
+                if let Poll::Ready(result) = core::future::Future::poll(reception.as_mut(), cx) {
+                    if let Err(MessageReadError::Io(e)) = &result {
+                        if !matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+                            self.failed[index].store(true, Ordering::Relaxed);
+                        }
+                    }
+                    return Poll::Ready((index, result));
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
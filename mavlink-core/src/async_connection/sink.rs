@@ -0,0 +1,91 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::sync::Arc;
+
+use futures_sink::Sink;
+
+use super::AsyncMavConnection;
+use crate::error::MessageWriteError;
+use crate::{MavHeader, Message};
+
+type SendFuture = Pin<Box<dyn Future<Output = Result<usize, MessageWriteError>> + Send + 'static>>;
+
+/// [`Sink`] sending messages through an async connection, so that outgoing messages can be
+/// driven from a channel or another stream with the `forward` and `send_all` combinators.
+///
+/// ```ignore
+/// let connection: Arc<dyn AsyncMavConnection<MavMessage> + Sync + Send> =
+///     Arc::from(mavlink::connect_async("udpout:127.0.0.1:14550").await?);
+/// telemetry.map(Ok).forward(AsyncMavSink::new(connection)).await?;
+/// ```
+///
+/// A message is sent once the sink is ready again or flushed, one at a time, and the error of a
+/// send is returned by the following `poll_ready`, `poll_flush` or `poll_close`. The
+/// connection is shared, so it can still be used to receive while the sink is driven.
+pub struct AsyncMavSink<M, C: ?Sized> {
+    connection: Arc<C>,
+    pending: Option<SendFuture>,
+    _message: PhantomData<fn(M)>,
+}
+
+impl<M, C> AsyncMavSink<M, C>
+where
+    M: Message + Sync + Send + 'static,
+    C: AsyncMavConnection<M> + Sync + Send + ?Sized + 'static,
+{
+    pub fn new(connection: Arc<C>) -> Self {
+        Self {
+            connection,
+            pending: None,
+            _message: PhantomData,
+        }
+    }
+
+    /// The connection the messages are sent through
+    pub fn connection(&self) -> &Arc<C> {
+        &self.connection
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MessageWriteError>> {
+        if let Some(pending) = &mut self.pending {
+            let result = futures_core::ready!(pending.as_mut().poll(cx));
+            self.pending = None;
+            result?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<M, C> Sink<(MavHeader, M)> for AsyncMavSink<M, C>
+where
+    M: Message + Sync + Send + 'static,
+    C: AsyncMavConnection<M> + Sync + Send + ?Sized + 'static,
+{
+    type Error = MessageWriteError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn start_send(
+        self: Pin<&mut Self>,
+        (header, message): (MavHeader, M),
+    ) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let connection = this.connection.clone();
+        this.pending = Some(Box::pin(
+            async move { connection.send(&header, &message).await },
+        ));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+}
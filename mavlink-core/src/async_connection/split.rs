@@ -0,0 +1,111 @@
+//! Independent receiving and sending halves of an async connection
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{MessageReadError, MessageWriteError};
+use crate::{MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message};
+
+use super::{AsyncMavConnection, AsyncMavSink, AsyncMavStream};
+
+type SharedConnection<M> = Arc<dyn AsyncMavConnection<M> + Sync + Send>;
+
+/// Split `connection` into a receiving and a sending half that can be moved to different tasks.
+///
+/// Connections lock their reading and writing sides separately, so a task waiting for messages on
+/// the reader does not delay the messages sent with the writer.
+pub fn into_split<M: Message + Sync + Send>(
+    connection: Box<dyn AsyncMavConnection<M> + Sync + Send>,
+) -> (AsyncMavReader<M>, AsyncMavWriter<M>) {
+    let connection: SharedConnection<M> = Arc::from(connection);
+    (
+        AsyncMavReader {
+            connection: Arc::clone(&connection),
+        },
+        AsyncMavWriter { connection },
+    )
+}
+
+/// Receiving half of an async connection, created by [`into_split`]
+pub struct AsyncMavReader<M: Message + Sync + Send> {
+    connection: SharedConnection<M>,
+}
+
+impl<M: Message + Sync + Send> AsyncMavReader<M> {
+    /// Receive a mavlink message, see [`AsyncMavConnection::recv`]
+    pub async fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.connection.recv().await
+    }
+
+    /// Receive a mavlink message, see [`AsyncMavConnection::recv_timeout`]
+    pub async fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(MavHeader, M), MessageReadError> {
+        self.connection.recv_timeout(timeout).await
+    }
+
+    /// Receive a mavlink message, see [`AsyncMavConnection::recv_deadline`]
+    pub async fn recv_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<(MavHeader, M), MessageReadError> {
+        self.connection.recv_deadline(deadline).await
+    }
+
+    /// Read whole frame
+    pub async fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
+        self.connection.recv_frame().await
+    }
+
+    /// Header information of the next valid frame, see [`AsyncMavConnection::peek`]
+    pub async fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        self.connection.peek().await
+    }
+
+    pub fn protocol_version(&self) -> MavlinkVersion {
+        self.connection.get_protocol_version()
+    }
+
+    /// [`Stream`](futures_core::Stream) of the received messages, see [`AsyncMavStream`]
+    pub fn into_stream(self) -> AsyncMavStream<M, dyn AsyncMavConnection<M> + Sync + Send>
+    where
+        M: 'static,
+    {
+        AsyncMavStream::new(self.connection)
+    }
+}
+
+/// Sending half of an async connection, created by [`into_split`]
+pub struct AsyncMavWriter<M: Message + Sync + Send> {
+    connection: SharedConnection<M>,
+}
+
+impl<M: Message + Sync + Send> AsyncMavWriter<M> {
+    /// Send a mavlink message
+    pub async fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        self.connection.send(header, data).await
+    }
+
+    /// Write whole frame
+    pub async fn send_frame(&self, frame: &MavFrame<M>) -> Result<usize, MessageWriteError> {
+        self.connection.send_frame(frame).await
+    }
+
+    /// Send a message with default header
+    pub async fn send_default(&self, data: &M) -> Result<usize, MessageWriteError> {
+        self.connection.send_default(data).await
+    }
+
+    pub fn protocol_version(&self) -> MavlinkVersion {
+        self.connection.get_protocol_version()
+    }
+
+    /// [`Sink`](futures_sink::Sink) of the messages to send, see [`AsyncMavSink`]
+    pub fn into_sink(self) -> AsyncMavSink<M, dyn AsyncMavConnection<M> + Sync + Send>
+    where
+        M: 'static,
+    {
+        AsyncMavSink::new(self.connection)
+    }
+}
@@ -0,0 +1,76 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::sync::Arc;
+
+use futures_core::Stream;
+
+use super::AsyncMavConnection;
+use crate::error::MessageReadError;
+use crate::{MavHeader, Message};
+
+type RecvFuture<M> =
+    Pin<Box<dyn Future<Output = Result<(MavHeader, M), MessageReadError>> + Send + 'static>>;
+
+/// [`Stream`] of the messages received by an async connection, so that they can be composed
+/// with the combinators of `StreamExt` instead of a manual [`recv`](AsyncMavConnection::recv)
+/// loop.
+///
+/// ```ignore
+/// let connection: Arc<dyn AsyncMavConnection<MavMessage> + Sync + Send> =
+///     Arc::from(mavlink::connect_async("udpin:0.0.0.0:14550").await?);
+/// let mut heartbeats = AsyncMavStream::new(connection)
+///     .filter_map(|result| async move {
+///         match result {
+///             Ok((header, MavMessage::HEARTBEAT(heartbeat))) => Some((header, heartbeat)),
+///             _ => None,
+///         }
+///     });
+/// ```
+///
+/// Every result of `recv` is yielded, including errors, and the stream never ends. The connection
+/// is shared, so it can still be used to send while the stream is polled.
+pub struct AsyncMavStream<M, C: ?Sized> {
+    connection: Arc<C>,
+    pending: Option<RecvFuture<M>>,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M, C> AsyncMavStream<M, C>
+where
+    M: Message + Sync + Send + 'static,
+    C: AsyncMavConnection<M> + Sync + Send + ?Sized + 'static,
+{
+    pub fn new(connection: Arc<C>) -> Self {
+        Self {
+            connection,
+            pending: None,
+            _message: PhantomData,
+        }
+    }
+
+    /// The connection the messages are received from
+    pub fn connection(&self) -> &Arc<C> {
+        &self.connection
+    }
+}
+
+impl<M, C> Stream for AsyncMavStream<M, C>
+where
+    M: Message + Sync + Send + 'static,
+    C: AsyncMavConnection<M> + Sync + Send + ?Sized + 'static,
+{
+    type Item = Result<(MavHeader, M), MessageReadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let pending = this.pending.get_or_insert_with(|| {
+            let connection = this.connection.clone();
+            Box::pin(async move { connection.recv().await })
+        });
+        let result = futures_core::ready!(pending.as_mut().poll(cx));
+        this.pending = None;
+        Poll::Ready(Some(result))
+    }
+}
@@ -1,28 +1,47 @@
 //! Async TCP MAVLink connection
 
-use super::{get_socket_addr, AsyncConnectable, AsyncMavConnection};
+use super::{get_socket_addrs, AsyncConnectable, AsyncMavConnection};
 use crate::async_peek_reader::AsyncPeekReader;
-use crate::connectable::TcpConnectable;
-use crate::{MavHeader, MavlinkVersion, Message};
+use crate::connectable::{ConnectionOptions, TcpConnectable};
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
 
 use async_trait::async_trait;
 use core::ops::DerefMut;
-use tokio::io;
+use socket2::SockRef;
+use tokio::io::{self, AsyncWriteExt};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg_async, write_versioned_msg_async};
+use crate::{
+    peek_versioned_frame_info_async, read_versioned_frame_async, read_versioned_raw_message_async,
+    write_versioned_msg_async,
+};
 #[cfg(feature = "signing")]
 use crate::{
-    read_versioned_msg_async_signed, write_versioned_msg_async_signed, SigningConfig, SigningData,
+    peek_versioned_frame_info_async_signed, read_versioned_frame_async_signed,
+    read_versioned_raw_message_async_signed, write_versioned_msg_async_signed, SigningConfig,
+    SigningData,
 };
 
-pub async fn tcpout<T: std::net::ToSocketAddrs>(address: T) -> io::Result<AsyncTcpConnection> {
-    let addr = get_socket_addr(address)?;
-
-    let socket = TcpStream::connect(addr).await?;
+pub async fn tcpout<T: std::net::ToSocketAddrs>(
+    address: T,
+    options: &ConnectionOptions,
+) -> io::Result<AsyncTcpConnection> {
+    let addrs = get_socket_addrs(address)?;
+
+    // every address is tried in turn, within the timeout for all of them
+    let socket = match options.connect_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, TcpStream::connect(&addrs[..]))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Connection timed out"))??,
+        None => TcpStream::connect(&addrs[..]).await?,
+    };
+    apply_options(&socket, options)?;
 
     let (reader, writer) = socket.into_split();
 
@@ -33,45 +52,51 @@ pub async fn tcpout<T: std::net::ToSocketAddrs>(address: T) -> io::Result<AsyncT
             sequence: 0,
         }),
         protocol_version: MavlinkVersion::V2,
+        target_filter: None,
+        source_filter: None,
         #[cfg(feature = "signing")]
         signing_data: None,
     })
 }
 
-pub async fn tcpin<T: std::net::ToSocketAddrs>(address: T) -> io::Result<AsyncTcpConnection> {
-    let addr = get_socket_addr(address)?;
-    let listener = TcpListener::bind(addr).await?;
+pub async fn tcpin<T: std::net::ToSocketAddrs>(
+    address: T,
+    options: &ConnectionOptions,
+) -> io::Result<AsyncTcpConnection> {
+    let addrs = get_socket_addrs(address)?;
+    let listener = TcpListener::bind(&addrs[..]).await?;
 
     //For now we only accept one incoming stream: this yields until we get one
-    match listener.accept().await {
-        Ok((socket, _)) => {
-            let (reader, writer) = socket.into_split();
-            return Ok(AsyncTcpConnection {
-                reader: Mutex::new(AsyncPeekReader::new(reader)),
-                writer: Mutex::new(TcpWrite {
-                    socket: writer,
-                    sequence: 0,
-                }),
-                protocol_version: MavlinkVersion::V2,
-                #[cfg(feature = "signing")]
-                signing_data: None,
-            });
-        }
-        Err(e) => {
-            //TODO don't println in lib
-            println!("listener err: {e}");
-        }
+    let (socket, _) = listener.accept().await?;
+    apply_options(&socket, options)?;
+    let (reader, writer) = socket.into_split();
+    Ok(AsyncTcpConnection {
+        reader: Mutex::new(AsyncPeekReader::new(reader)),
+        writer: Mutex::new(TcpWrite {
+            socket: writer,
+            sequence: 0,
+        }),
+        protocol_version: MavlinkVersion::V2,
+        target_filter: None,
+        source_filter: None,
+        #[cfg(feature = "signing")]
+        signing_data: None,
+    })
+}
+
+pub(super) fn apply_options(socket: &TcpStream, options: &ConnectionOptions) -> io::Result<()> {
+    if let Some(nodelay) = options.nodelay {
+        socket.set_nodelay(nodelay)?;
     }
-    Err(io::Error::new(
-        io::ErrorKind::NotConnected,
-        "No incoming connections!",
-    ))
+    options.apply_buffer_sizes(SockRef::from(socket))
 }
 
 pub struct AsyncTcpConnection {
     reader: Mutex<AsyncPeekReader<OwnedReadHalf>>,
     writer: Mutex<TcpWrite>,
     protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
@@ -84,17 +109,31 @@ struct TcpWrite {
 #[async_trait::async_trait]
 impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncTcpConnection {
     async fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
+
+    async fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let mut reader = self.reader.lock().await;
-        #[cfg(not(feature = "signing"))]
-        let result = read_versioned_msg_async(reader.deref_mut(), self.protocol_version).await;
-        #[cfg(feature = "signing")]
-        let result = read_versioned_msg_async_signed(
-            reader.deref_mut(),
-            self.protocol_version,
-            self.signing_data.as_ref(),
-        )
-        .await;
-        result
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result =
+                read_versioned_frame_async(reader.deref_mut(), self.protocol_version).await;
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_async_signed(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            )
+            .await;
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                result => return result,
+            }
+        }
     }
 
     async fn send(
@@ -126,6 +165,45 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncTcpConnection {
         result
     }
 
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            read_versioned_raw_message_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let mut lock = self.writer.lock().await;
+        let bytes = frame.raw_bytes();
+        lock.socket.write_all(bytes).await?;
+        Ok(bytes.len())
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            peek_versioned_frame_info_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -134,6 +212,14 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncTcpConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -146,11 +232,14 @@ impl AsyncConnectable for TcpConnectable {
     where
         M: Message + Sync + Send,
     {
-        let conn = if self.is_out {
-            tcpout(&self.address).await
+        let mut conn: Box<dyn AsyncMavConnection<M> + Sync + Send> = if self.multiple_clients {
+            Box::new(super::tcp_server::tcpserver::<M, _>(&self.address, &self.options).await?)
+        } else if self.is_out {
+            Box::new(tcpout(&self.address, &self.options).await?)
         } else {
-            tcpin(&self.address).await
+            Box::new(tcpin(&self.address, &self.options).await?)
         };
-        Ok(Box::new(conn?))
+        conn.set_protocol_version(self.protocol_version);
+        Ok(conn)
     }
 }
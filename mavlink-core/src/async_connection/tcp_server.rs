@@ -0,0 +1,307 @@
+//! Async TCP MAVLink server connection accepting any number of clients
+
+use std::sync::Arc;
+
+use super::tcp::apply_options;
+use super::{get_socket_addrs, AsyncMavConnection};
+use crate::connectable::ConnectionOptions;
+use crate::error::{MessageReadError, MessageWriteError};
+use crate::mav_parser::ParserState;
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
+
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::{AbortHandle, JoinHandle};
+
+#[cfg(not(feature = "signing"))]
+use crate::write_versioned_msg_async;
+#[cfg(feature = "signing")]
+use crate::{write_versioned_msg_async_signed, SigningConfig, SigningData};
+
+/// Frames received from all clients and waiting for [`AsyncMavConnection::recv`]
+const INCOMING_FRAMES: usize = 64;
+
+/// Frames waiting to be written to a client, which is disconnected once they are exceeded
+const OUTGOING_FRAMES: usize = 64;
+
+pub async fn tcpserver<M: Message, T: std::net::ToSocketAddrs>(
+    address: T,
+    options: &ConnectionOptions,
+) -> io::Result<AsyncTcpServerConnection> {
+    let addrs = get_socket_addrs(address)?;
+    let listener = TcpListener::bind(&addrs[..]).await?;
+    let (sender, receiver) = mpsc::channel(INCOMING_FRAMES);
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    let acceptor = tokio::spawn(accept_clients(
+        listener,
+        options.clone(),
+        M::extra_crc,
+        sender,
+        Arc::clone(&clients),
+    ));
+
+    Ok(AsyncTcpServerConnection {
+        incoming: Mutex::new(Incoming {
+            frames: receiver,
+            peeked: None,
+        }),
+        writer: Mutex::new(0),
+        clients,
+        acceptor,
+        protocol_version: MavlinkVersion::V2,
+        target_filter: None,
+        source_filter: None,
+        #[cfg(feature = "signing")]
+        signing_data: None,
+    })
+}
+
+/// Accept clients until the connection is dropped, each read by its own task
+async fn accept_clients(
+    listener: TcpListener,
+    options: ConnectionOptions,
+    extra_crc: fn(u32) -> u8,
+    sender: mpsc::Sender<RawFrame>,
+    clients: Arc<Mutex<Vec<Client>>>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                if let Err(e) = apply_options(&socket, &options) {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %e, "failed to configure incoming connection");
+                    #[cfg(not(feature = "tracing"))]
+                    let _ = e;
+                    continue;
+                }
+                let (mut reader, writer) = socket.into_split();
+                let sender = sender.clone();
+                let reader_task = tokio::spawn(async move {
+                    let mut parser = ParserState::new(None, extra_crc);
+                    let mut buf = [0u8; 1024];
+                    // the task ends when the client disconnects or the connection is dropped
+                    while let Ok(len @ 1..) = reader.read(&mut buf).await {
+                        for frame in parser.push_bytes(&buf[..len]) {
+                            if sender.send(frame).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+                let (frames, outgoing) = mpsc::channel(OUTGOING_FRAMES);
+                let writer_task = tokio::spawn(write_frames(writer, outgoing));
+                clients.lock().await.push(Client {
+                    frames,
+                    reader_task: reader_task.abort_handle(),
+                    writer_task: writer_task.abort_handle(),
+                });
+            }
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "failed to accept incoming connection");
+                #[cfg(not(feature = "tracing"))]
+                let _ = e;
+            }
+        }
+    }
+}
+
+/// Write the frames queued for a client until it disconnects or is dropped
+async fn write_frames(mut writer: OwnedWriteHalf, mut frames: mpsc::Receiver<Arc<[u8]>>) {
+    while let Some(bytes) = frames.recv().await {
+        if writer.write_all(&bytes).await.is_err() {
+            return;
+        }
+    }
+}
+
+struct Client {
+    /// Queue of the frames written by the writer task
+    frames: mpsc::Sender<Arc<[u8]>>,
+    reader_task: AbortHandle,
+    writer_task: AbortHandle,
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+        self.writer_task.abort();
+    }
+}
+
+struct Incoming {
+    frames: mpsc::Receiver<RawFrame>,
+    /// Frame returned by [`AsyncMavConnection::peek`], already checked against the protocol
+    /// version and signature
+    peeked: Option<RawFrame>,
+}
+
+/// TCP server accepting any number of simultaneous clients, as expected from a TCP endpoint by
+/// ground stations and routers.
+///
+/// Frames received from every client are delivered by a single [`recv`](AsyncMavConnection::recv)
+/// and sent messages are broadcast to all the connected clients.
+///
+/// Each client is written by its own task, so that a slow client doesn't hold back the others. A
+/// client falling more than 64 frames behind is disconnected.
+pub struct AsyncTcpServerConnection {
+    incoming: Mutex<Incoming>,
+    /// Sequence number of the next sent frame
+    writer: Mutex<u8>,
+    clients: Arc<Mutex<Vec<Client>>>,
+    acceptor: JoinHandle<()>,
+    protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+impl AsyncTcpServerConnection {
+    /// Next frame of the protocol version of the connection with a valid signature, if required
+    async fn next_frame(&self, incoming: &mut Incoming) -> Result<RawFrame, MessageReadError> {
+        if let Some(frame) = incoming.peeked.take() {
+            return Ok(frame);
+        }
+        loop {
+            let frame = incoming
+                .frames
+                .recv()
+                .await
+                .ok_or_else(MessageReadError::eof)?;
+            if frame.protocol_version() != self.protocol_version {
+                continue;
+            }
+            #[cfg(feature = "signing")]
+            if let (RawFrame::V2(message), Some(signing_data)) = (&frame, &self.signing_data) {
+                if let Err(e) = signing_data.check_signature(message) {
+                    if signing_data.config.report_rejected {
+                        return Err(MessageReadError::Signature(e));
+                    }
+                    continue;
+                }
+            }
+            return Ok(frame);
+        }
+    }
+
+    /// Queue `bytes` for every client, returning their length if at least one client took them
+    async fn broadcast(&self, bytes: &[u8]) -> usize {
+        let bytes: Arc<[u8]> = Arc::from(bytes);
+        let mut clients = self.clients.lock().await;
+        // drop the clients that disconnected or whose queue is full
+        clients.retain(|client| client.frames.try_send(Arc::clone(&bytes)).is_ok());
+        if clients.is_empty() {
+            0
+        } else {
+            bytes.len()
+        }
+    }
+
+    /// Count of currently connected clients
+    pub async fn client_count(&self) -> usize {
+        self.clients.lock().await.len()
+    }
+}
+
+impl Drop for AsyncTcpServerConnection {
+    fn drop(&mut self) {
+        self.acceptor.abort();
+        if let Ok(mut clients) = self.clients.try_lock() {
+            clients.clear();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncTcpServerConnection {
+    async fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
+
+    async fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
+        let mut incoming = self.incoming.lock().await;
+        loop {
+            let frame = self.next_frame(&mut incoming).await?;
+            match frame.parse_frame::<M>() {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                result => return result.map_err(MessageReadError::from),
+            }
+        }
+    }
+
+    async fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        let mut sequence = self.writer.lock().await;
+
+        let header = MavHeader {
+            sequence: *sequence,
+            system_id: header.system_id,
+            component_id: header.component_id,
+        };
+
+        *sequence = sequence.wrapping_add(1);
+        let mut buf = Vec::new();
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg_async(&mut buf, self.protocol_version, header, data).await?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_async_signed(
+            &mut buf,
+            self.protocol_version,
+            header,
+            data,
+            self.signing_data.as_ref(),
+        )
+        .await?;
+
+        let sent = self.broadcast(&buf).await;
+        Ok(sent.min(len))
+    }
+
+    async fn recv_raw(&self) -> Result<RawFrame, MessageReadError> {
+        let mut incoming = self.incoming.lock().await;
+        self.next_frame(&mut incoming).await
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let _sequence = self.writer.lock().await;
+        Ok(self.broadcast(frame.raw_bytes()).await)
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        let mut incoming = self.incoming.lock().await;
+        let frame = self.next_frame(&mut incoming).await?;
+        let info = frame.info();
+        incoming.peeked = Some(frame);
+        Ok(info)
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn get_protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
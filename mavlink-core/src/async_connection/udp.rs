@@ -4,6 +4,7 @@ use core::{ops::DerefMut, task::Poll};
 use std::{collections::VecDeque, io::Read, sync::Arc};
 
 use async_trait::async_trait;
+use socket2::SockRef;
 use tokio::{
     io::{self, AsyncRead, ReadBuf},
     net::UdpSocket,
@@ -13,16 +14,22 @@ use tokio::{
 use crate::{
     async_peek_reader::AsyncPeekReader,
     connectable::{UdpConnectable, UdpMode},
-    MavHeader, MavlinkVersion, Message,
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
 };
 
 use super::{get_socket_addr, AsyncConnectable, AsyncMavConnection};
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg_async, write_versioned_msg_async};
+use crate::{
+    peek_versioned_frame_info_async, read_versioned_frame_async, read_versioned_raw_message_async,
+    write_versioned_msg_async,
+};
 #[cfg(feature = "signing")]
 use crate::{
-    read_versioned_msg_async_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+    peek_versioned_frame_info_async_signed, read_versioned_frame_async_signed,
+    read_versioned_raw_message_async_signed, write_versioned_msg_signed, SigningConfig,
+    SigningData,
 };
 
 struct UdpRead {
@@ -81,6 +88,8 @@ pub struct AsyncUdpConnection {
     writer: Mutex<UdpWrite>,
     protocol_version: MavlinkVersion,
     server: bool,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
@@ -105,6 +114,8 @@ impl AsyncUdpConnection {
                 sequence: 0,
             }),
             protocol_version: MavlinkVersion::V2,
+            target_filter: None,
+            source_filter: None,
             #[cfg(feature = "signing")]
             signing_data: None,
         })
@@ -114,13 +125,20 @@ impl AsyncUdpConnection {
 #[async_trait::async_trait]
 impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncUdpConnection {
     async fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
+
+    async fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let mut reader = self.reader.lock().await;
 
         loop {
             #[cfg(not(feature = "signing"))]
-            let result = read_versioned_msg_async(reader.deref_mut(), self.protocol_version).await;
+            let result =
+                read_versioned_frame_async(reader.deref_mut(), self.protocol_version).await;
             #[cfg(feature = "signing")]
-            let result = read_versioned_msg_async_signed(
+            let result = read_versioned_frame_async_signed(
                 reader.deref_mut(),
                 self.protocol_version,
                 self.signing_data.as_ref(),
@@ -131,8 +149,11 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncUdpConnection {
                     self.writer.lock().await.dest = addr;
                 }
             }
-            if let ok @ Ok(..) = result {
-                return ok;
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                result => return result,
             }
         }
     }
@@ -182,6 +203,51 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncUdpConnection {
         Ok(len)
     }
 
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            read_versioned_raw_message_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        if self.server {
+            if let addr @ Some(_) = reader.reader_ref().last_recv_address {
+                self.writer.lock().await.dest = addr;
+            }
+        }
+        result
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let state = self.writer.lock().await;
+        match state.dest {
+            Some(addr) => Ok(state.socket.send_to(frame.raw_bytes(), addr).await?),
+            None => Ok(0),
+        }
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            peek_versioned_frame_info_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -190,6 +256,14 @@ impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncUdpConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -210,7 +284,11 @@ impl AsyncConnectable for UdpConnectable {
         if matches!(self.mode, UdpMode::Udpcast) {
             socket.set_broadcast(true)?;
         }
-        Ok(Box::new(AsyncUdpConnection::new(socket, server, dest)?))
+        self.options.apply_buffer_sizes(SockRef::from(&socket))?;
+        let mut conn: Box<dyn AsyncMavConnection<M> + Sync + Send> =
+            Box::new(AsyncUdpConnection::new(socket, server, dest)?);
+        conn.set_protocol_version(self.protocol_version);
+        Ok(conn)
     }
 }
 
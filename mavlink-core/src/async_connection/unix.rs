@@ -0,0 +1,214 @@
+//! Async MAVLink connection over a Unix domain stream socket
+
+use core::ops::DerefMut;
+
+use super::{AsyncConnectable, AsyncMavConnection};
+use crate::async_peek_reader::AsyncPeekReader;
+use crate::connectable::UnixConnectable;
+use crate::error::MessageReadError;
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
+
+use async_trait::async_trait;
+use tokio::io::{self, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex;
+
+#[cfg(not(feature = "signing"))]
+use crate::{
+    peek_versioned_frame_info_async, read_versioned_frame_async, read_versioned_raw_message_async,
+    write_versioned_msg_async,
+};
+#[cfg(feature = "signing")]
+use crate::{
+    peek_versioned_frame_info_async_signed, read_versioned_frame_async_signed,
+    read_versioned_raw_message_async_signed, write_versioned_msg_async_signed, SigningConfig,
+    SigningData,
+};
+
+/// Connect to the socket at `path`
+pub async fn unix(path: &str) -> io::Result<AsyncUnixConnection> {
+    let socket = UnixStream::connect(path).await?;
+    Ok(AsyncUnixConnection::new(socket))
+}
+
+/// Create a socket at `path`, yielding until a client connects.
+///
+/// Binding fails if a file already exists at `path`, such as a socket left by a previous run.
+pub async fn unixin(path: &str) -> io::Result<AsyncUnixConnection> {
+    let listener = UnixListener::bind(path)?;
+    let (socket, _) = listener.accept().await?;
+    Ok(AsyncUnixConnection::new(socket))
+}
+
+pub struct AsyncUnixConnection {
+    reader: Mutex<AsyncPeekReader<OwnedReadHalf>>,
+    writer: Mutex<UnixWrite>,
+    protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+impl AsyncUnixConnection {
+    fn new(socket: UnixStream) -> Self {
+        let (reader, writer) = socket.into_split();
+        Self {
+            reader: Mutex::new(AsyncPeekReader::new(reader)),
+            writer: Mutex::new(UnixWrite {
+                socket: writer,
+                sequence: 0,
+            }),
+            protocol_version: MavlinkVersion::V2,
+            target_filter: None,
+            source_filter: None,
+            #[cfg(feature = "signing")]
+            signing_data: None,
+        }
+    }
+}
+
+struct UnixWrite {
+    socket: OwnedWriteHalf,
+    sequence: u8,
+}
+
+#[async_trait::async_trait]
+impl<M: Message + Sync + Send> AsyncMavConnection<M> for AsyncUnixConnection {
+    async fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.recv_frame()
+            .await
+            .map(|frame| (frame.header, frame.msg))
+    }
+
+    async fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result =
+                read_versioned_frame_async(reader.deref_mut(), self.protocol_version).await;
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_async_signed(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            )
+            .await;
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                // invalid frames are skipped, errors of the underlying stream are reported
+                Err(MessageReadError::Parse(_)) => {}
+                result => return result,
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        header: &MavHeader,
+        data: &M,
+    ) -> Result<usize, crate::error::MessageWriteError> {
+        let mut lock = self.writer.lock().await;
+
+        let header = MavHeader {
+            sequence: lock.sequence,
+            system_id: header.system_id,
+            component_id: header.component_id,
+        };
+
+        lock.sequence = lock.sequence.wrapping_add(1);
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg_async(&mut lock.socket, self.protocol_version, header, data)
+            .await?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_async_signed(
+            &mut lock.socket,
+            self.protocol_version,
+            header,
+            data,
+            self.signing_data.as_ref(),
+        )
+        .await?;
+        Ok(len)
+    }
+
+    async fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            read_versioned_raw_message_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    async fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let mut lock = self.writer.lock().await;
+        let bytes = frame.raw_bytes();
+        lock.socket.write_all(bytes).await?;
+        Ok(bytes.len())
+    }
+
+    async fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        let mut reader = self.reader.lock().await;
+        #[cfg(not(feature = "signing"))]
+        let result =
+            peek_versioned_frame_info_async::<M, _>(reader.deref_mut(), self.protocol_version)
+                .await;
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_async_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        )
+        .await;
+        result
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn get_protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
+
+#[async_trait]
+impl AsyncConnectable for UnixConnectable {
+    async fn connect_async<M>(&self) -> io::Result<Box<dyn AsyncMavConnection<M> + Sync + Send>>
+    where
+        M: Message + Sync + Send,
+    {
+        if self.listen {
+            Ok(Box::new(unixin(&self.path).await?))
+        } else {
+            Ok(Box::new(unix(&self.path).await?))
+        }
+    }
+}
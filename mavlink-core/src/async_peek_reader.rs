@@ -114,27 +114,29 @@ impl<R: AsyncReadExt + Unpin, const BUFFER_SIZE: usize> AsyncPeekReader<R, BUFFE
     }
 
     /// Internal function to fetch data from the internal buffer and/or reader
+    ///
+    /// The bytes are read straight into the internal buffer, so that the ones already read are
+    /// kept when the future is dropped before `amount` bytes are available, as done by
+    /// [`AsyncMavConnection::recv_deadline`](crate::AsyncMavConnection::recv_deadline).
     async fn fetch(&mut self, amount: usize, consume: bool) -> Result<&[u8], MessageReadError> {
-        let buffered = self.top - self.cursor;
-
-        // the caller requested more bytes than we have buffered, fetch them from the reader
-        if buffered < amount {
-            let bytes_read = amount - buffered;
-            assert!(bytes_read < BUFFER_SIZE);
-            let mut buf = [0u8; BUFFER_SIZE];
+        assert!(amount <= BUFFER_SIZE);
 
-            // read needed bytes from reader
-            self.reader.read_exact(&mut buf[..bytes_read]).await?;
-
-            // if some bytes were read, add them to the buffer
+        if self.cursor + amount > BUFFER_SIZE {
+            // reallocate
+            self.buffer.copy_within(self.cursor..self.top, 0);
+            self.top -= self.cursor;
+            self.cursor = 0;
+        }
 
-            if self.buffer.len() - self.top < bytes_read {
-                // reallocate
-                self.buffer.copy_within(self.cursor..self.top, 0);
-                self.cursor = 0;
-                self.top = buffered;
+        // the caller requested more bytes than we have buffered, fetch them from the reader
+        while self.top - self.cursor < amount {
+            let bytes_read = self
+                .reader
+                .read(&mut self.buffer[self.top..self.cursor + amount])
+                .await?;
+            if bytes_read == 0 {
+                return Err(MessageReadError::eof());
             }
-            self.buffer[self.top..self.top + bytes_read].copy_from_slice(&buf[..bytes_read]);
             self.top += bytes_read;
         }
 
@@ -0,0 +1,355 @@
+//! Parsing of camera definition files as defined in <https://mavlink.io/en/services/camera_def.html>
+//!
+//! The location of the definition file of a camera is given by the `cam_definition_uri` field of
+//! its `CAMERA_INFORMATION` message, see [`CameraDefinitionUri`]. Once retrieved, the file is
+//! parsed with [`CameraDefinition::parse`]. Localization sections are not interpreted.
+
+use core::fmt::{Display, Formatter};
+use std::error::Error;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Location of a camera definition file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraDefinitionUri<'a> {
+    /// Path of the file on the camera, to be retrieved with MAVLink FTP
+    Ftp(&'a str),
+    /// URL of the file, to be retrieved with HTTP
+    Http(&'a str),
+}
+
+impl<'a> CameraDefinitionUri<'a> {
+    /// Interpret the `cam_definition_uri` field of `CAMERA_INFORMATION`.
+    ///
+    /// Trailing NUL bytes are ignored. Returns `None` if the camera has no definition file or
+    /// if the scheme is not supported.
+    pub fn parse(uri: &'a [u8]) -> Option<Self> {
+        let len = uri.iter().position(|b| *b == 0).unwrap_or(uri.len());
+        let uri = core::str::from_utf8(&uri[..len]).ok()?;
+        if let Some(path) = uri.strip_prefix("mftp://") {
+            Some(Self::Ftp(path))
+        } else if uri.starts_with("http://") || uri.starts_with("https://") {
+            Some(Self::Http(uri))
+        } else {
+            None
+        }
+    }
+}
+
+/// Errors returned by [`CameraDefinition::parse`]
+#[derive(Debug)]
+pub enum CameraDefinitionError {
+    /// The document is not well-formed XML
+    Xml(quick_xml::Error),
+    /// A required attribute is missing
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+    /// A value does not match the type of its parameter
+    InvalidValue { parameter: String, value: String },
+    /// A parameter has an unknown type
+    InvalidType { parameter: String, r#type: String },
+}
+
+impl Display for CameraDefinitionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Xml(e) => write!(f, "Invalid camera definition XML: {e}"),
+            Self::MissingAttribute { element, attribute } => {
+                write!(f, "Missing attribute {attribute:?} of element {element:?}")
+            }
+            Self::InvalidValue { parameter, value } => {
+                write!(f, "Invalid value {value:?} for parameter {parameter:?}")
+            }
+            Self::InvalidType { parameter, r#type } => {
+                write!(f, "Invalid type {type:?} of parameter {parameter:?}")
+            }
+        }
+    }
+}
+
+impl Error for CameraDefinitionError {}
+
+impl From<quick_xml::Error> for CameraDefinitionError {
+    fn from(e: quick_xml::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for CameraDefinitionError {
+    fn from(e: quick_xml::events::attributes::AttrError) -> Self {
+        Self::Xml(e.into())
+    }
+}
+
+/// Type of a camera parameter
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraParameterType {
+    Bool,
+    Uint8,
+    Int8,
+    Uint16,
+    Int16,
+    Uint32,
+    Int32,
+    Float,
+    Custom,
+}
+
+impl CameraParameterType {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Self::Bool,
+            "uint8" => Self::Uint8,
+            "int8" => Self::Int8,
+            "uint16" => Self::Uint16,
+            "int16" => Self::Int16,
+            "uint32" => Self::Uint32,
+            "int32" => Self::Int32,
+            "float" => Self::Float,
+            "custom" => Self::Custom,
+            _ => return None,
+        })
+    }
+
+    /// Interpret `value` as a value of this type
+    pub fn parse_value(&self, value: &str) -> Option<CameraParameterValue> {
+        let value = value.trim();
+        Some(match self {
+            Self::Bool => CameraParameterValue::Bool(match value {
+                "1" | "true" => true,
+                "0" | "false" => false,
+                _ => return None,
+            }),
+            Self::Uint8 => CameraParameterValue::Integer(value.parse::<u8>().ok()?.into()),
+            Self::Int8 => CameraParameterValue::Integer(value.parse::<i8>().ok()?.into()),
+            Self::Uint16 => CameraParameterValue::Integer(value.parse::<u16>().ok()?.into()),
+            Self::Int16 => CameraParameterValue::Integer(value.parse::<i16>().ok()?.into()),
+            Self::Uint32 => CameraParameterValue::Integer(value.parse::<u32>().ok()?.into()),
+            Self::Int32 => CameraParameterValue::Integer(value.parse::<i32>().ok()?.into()),
+            Self::Float => CameraParameterValue::Float(value.parse().ok()?),
+            Self::Custom => CameraParameterValue::Custom(value.to_string()),
+        })
+    }
+}
+
+/// Value of a camera parameter, interpreted according to the parameter type
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraParameterValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f32),
+    Custom(String),
+}
+
+/// Allowed value of a camera parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraParameterOption {
+    pub name: String,
+    pub value: CameraParameterValue,
+    /// Parameters that are not applicable while this option is selected
+    pub exclusions: Vec<String>,
+}
+
+/// Description of a camera parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraParameter {
+    pub name: String,
+    pub param_type: CameraParameterType,
+    pub default: CameraParameterValue,
+    pub description: String,
+    /// Whether the parameter is shown as a user control
+    pub control: bool,
+    pub read_only: bool,
+    pub write_only: bool,
+    pub min: Option<CameraParameterValue>,
+    pub max: Option<CameraParameterValue>,
+    pub step: Option<CameraParameterValue>,
+    /// Allowed values, empty if any value in the range is allowed
+    pub options: Vec<CameraParameterOption>,
+    /// Parameters to request again after this one was changed
+    pub updates: Vec<String>,
+}
+
+/// Contents of a camera definition file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CameraDefinition {
+    pub version: u32,
+    pub model: String,
+    pub vendor: String,
+    pub parameters: Vec<CameraParameter>,
+}
+
+impl CameraDefinition {
+    /// Parse a camera definition file
+    pub fn parse(xml: &str) -> Result<Self, CameraDefinitionError> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut definition = Self::default();
+        let mut path: Vec<Vec<u8>> = Vec::new();
+        let mut parameter: Option<CameraParameter> = None;
+        let mut option: Option<CameraParameterOption> = None;
+
+        loop {
+            let event = reader.read_event()?;
+            let (element, is_empty) = match &event {
+                Event::Start(element) => (Some(element), false),
+                Event::Empty(element) => (Some(element), true),
+                _ => (None, false),
+            };
+            if let Some(element) = element {
+                let name = element.name().as_ref().to_vec();
+                match (path.last().map(Vec::as_slice), name.as_slice()) {
+                    (Some(b"mavlinkcamera"), b"definition") => {
+                        if let Some(version) = attribute(element, "version")? {
+                            definition.version = version.trim().parse().unwrap_or_default();
+                        }
+                    }
+                    (Some(b"parameters"), b"parameter") => {
+                        parameter = Some(parse_parameter(element)?);
+                    }
+                    (Some(b"options"), b"option") => {
+                        if let Some(parameter) = &parameter {
+                            option = Some(parse_option(parameter, element)?);
+                        }
+                    }
+                    _ => {}
+                }
+                path.push(name);
+                if !is_empty {
+                    continue;
+                }
+            }
+
+            match event {
+                Event::Text(text) => {
+                    let text = text.unescape()?.into_owned();
+                    let parent = path.len().checked_sub(2).map(|i| path[i].as_slice());
+                    match (parent, path.last().map(Vec::as_slice)) {
+                        (Some(b"definition"), Some(b"model")) => definition.model = text,
+                        (Some(b"definition"), Some(b"vendor")) => definition.vendor = text,
+                        (Some(b"parameter"), Some(b"description")) => {
+                            if let Some(parameter) = &mut parameter {
+                                parameter.description = text;
+                            }
+                        }
+                        (Some(b"updates"), Some(b"update")) => {
+                            if let Some(parameter) = &mut parameter {
+                                parameter.updates.push(text);
+                            }
+                        }
+                        (Some(b"exclusions"), Some(b"exclude")) => {
+                            if let Some(option) = &mut option {
+                                option.exclusions.push(text);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Empty(_) | Event::End(_) => match path.pop().as_deref() {
+                    Some(b"option") => {
+                        if let (Some(parameter), Some(option)) = (&mut parameter, option.take()) {
+                            parameter.options.push(option);
+                        }
+                    }
+                    Some(b"parameter") => {
+                        definition.parameters.extend(parameter.take());
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(definition)
+    }
+
+    /// The parameter named `name`
+    pub fn parameter(&self, name: &str) -> Option<&CameraParameter> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.name == name)
+    }
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, CameraDefinitionError> {
+    match element.try_get_attribute(name)? {
+        Some(attribute) => Ok(Some(attribute.unescape_value()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+fn required_attribute(
+    element: &BytesStart,
+    element_name: &'static str,
+    name: &'static str,
+) -> Result<String, CameraDefinitionError> {
+    attribute(element, name)?.ok_or(CameraDefinitionError::MissingAttribute {
+        element: element_name,
+        attribute: name,
+    })
+}
+
+fn flag(element: &BytesStart, name: &str, default: bool) -> Result<bool, CameraDefinitionError> {
+    Ok(attribute(element, name)?.map_or(default, |value| value.trim() != "0"))
+}
+
+fn parse_value(
+    parameter: &str,
+    param_type: CameraParameterType,
+    value: &str,
+) -> Result<CameraParameterValue, CameraDefinitionError> {
+    param_type
+        .parse_value(value)
+        .ok_or_else(|| CameraDefinitionError::InvalidValue {
+            parameter: parameter.to_string(),
+            value: value.to_string(),
+        })
+}
+
+fn parse_parameter(element: &BytesStart) -> Result<CameraParameter, CameraDefinitionError> {
+    let name = required_attribute(element, "parameter", "name")?;
+    let type_name = required_attribute(element, "parameter", "type")?;
+    let param_type = CameraParameterType::from_name(type_name.trim()).ok_or_else(|| {
+        CameraDefinitionError::InvalidType {
+            parameter: name.clone(),
+            r#type: type_name,
+        }
+    })?;
+    let default = required_attribute(element, "parameter", "default")?;
+    let optional_value = |attribute_name| -> Result<_, CameraDefinitionError> {
+        attribute(element, attribute_name)?
+            .map(|value| parse_value(&name, param_type, &value))
+            .transpose()
+    };
+
+    Ok(CameraParameter {
+        default: parse_value(&name, param_type, &default)?,
+        param_type,
+        description: String::new(),
+        control: flag(element, "control", true)?,
+        read_only: flag(element, "readonly", false)?,
+        write_only: flag(element, "writeonly", false)?,
+        min: optional_value("min")?,
+        max: optional_value("max")?,
+        step: optional_value("step")?,
+        options: Vec::new(),
+        updates: Vec::new(),
+        name,
+    })
+}
+
+fn parse_option(
+    parameter: &CameraParameter,
+    element: &BytesStart,
+) -> Result<CameraParameterOption, CameraDefinitionError> {
+    let value = required_attribute(element, "option", "value")?;
+    Ok(CameraParameterOption {
+        name: required_attribute(element, "option", "name")?,
+        value: parse_value(&parameter.name, parameter.param_type, &value)?,
+        exclusions: Vec::new(),
+    })
+}
@@ -0,0 +1,204 @@
+//! Protocol capabilities reported by autopilots in `AUTOPILOT_VERSION`
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
+use crate::connection::{request_message_payload, MavConnection, RequestMessageError};
+#[cfg(feature = "std")]
+use crate::MavHeader;
+use crate::{MavlinkVersion, Message};
+
+const AUTOPILOT_VERSION_ID: u32 = 148;
+
+/// The `capabilities` bitmask of `AUTOPILOT_VERSION`, made of `MAV_PROTOCOL_CAPABILITY` flags
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Read the capabilities from an `AUTOPILOT_VERSION` payload
+    pub fn from_autopilot_version_payload(payload: &[u8]) -> Self {
+        // v2 payloads are truncated, the missing bytes are zeros
+        let mut bits = [0u8; 8];
+        let len = payload.len().min(bits.len());
+        bits[..len].copy_from_slice(&payload[..len]);
+        Self(u64::from_le_bytes(bits))
+    }
+
+    /// Read the capabilities from `message` if it is an `AUTOPILOT_VERSION`
+    pub fn from_message<M: Message>(message: &M) -> Option<Self> {
+        if message.message_id() != AUTOPILOT_VERSION_ID {
+            return None;
+        }
+        let mut payload = [0u8; 255];
+        let len = message.ser(MavlinkVersion::V2, &mut payload);
+        Some(Self::from_autopilot_version_payload(&payload[..len]))
+    }
+
+    const fn has(&self, flag: u64) -> bool {
+        self.0 & flag != 0
+    }
+
+    /// Missions with float positions (`MISSION_ITEM`)
+    pub const fn mission_float(&self) -> bool {
+        self.has(1)
+    }
+
+    pub const fn param_float(&self) -> bool {
+        self.has(2)
+    }
+
+    /// Missions with integer positions (`MISSION_ITEM_INT`)
+    pub const fn mission_int(&self) -> bool {
+        self.has(4)
+    }
+
+    pub const fn command_int(&self) -> bool {
+        self.has(8)
+    }
+
+    /// Parameter values are encoded bytewise in the float field
+    pub const fn param_encode_bytewise(&self) -> bool {
+        self.has(16)
+    }
+
+    /// The MAVLink FTP protocol
+    pub const fn ftp(&self) -> bool {
+        self.has(32)
+    }
+
+    pub const fn set_attitude_target(&self) -> bool {
+        self.has(64)
+    }
+
+    pub const fn set_position_target_local_ned(&self) -> bool {
+        self.has(128)
+    }
+
+    pub const fn set_position_target_global_int(&self) -> bool {
+        self.has(256)
+    }
+
+    pub const fn terrain(&self) -> bool {
+        self.has(512)
+    }
+
+    pub const fn flight_termination(&self) -> bool {
+        self.has(2048)
+    }
+
+    pub const fn compass_calibration(&self) -> bool {
+        self.has(4096)
+    }
+
+    pub const fn mavlink2(&self) -> bool {
+        self.has(8192)
+    }
+
+    /// Geofences transferred with the mission protocol
+    pub const fn mission_fence(&self) -> bool {
+        self.has(16384)
+    }
+
+    /// Rally points transferred with the mission protocol
+    pub const fn mission_rally(&self) -> bool {
+        self.has(32768)
+    }
+
+    /// Parameter values are converted to floats with a C cast
+    pub const fn param_encode_c_cast(&self) -> bool {
+        self.has(131072)
+    }
+}
+
+/// Capabilities of the systems of a network, learned from their `AUTOPILOT_VERSION` messages
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityCache {
+    systems: HashMap<u8, Capabilities>,
+}
+
+#[cfg(feature = "std")]
+impl CapabilityCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Capabilities of `system_id`, if already known
+    pub fn get(&self, system_id: u8) -> Option<Capabilities> {
+        self.systems.get(&system_id).copied()
+    }
+
+    /// Update the cache with a received message, such as an `AUTOPILOT_VERSION` requested by
+    /// another component
+    pub fn handle_message<M: Message>(&mut self, header: &MavHeader, message: &M) {
+        if let Some(capabilities) = Capabilities::from_message(message) {
+            self.systems.insert(header.system_id, capabilities);
+        }
+    }
+
+    /// Return the capabilities of `target_system`, requesting its `AUTOPILOT_VERSION` with
+    /// [`request_capabilities`] if they are not known yet
+    pub fn get_or_request<M, C>(
+        &mut self,
+        connection: &C,
+        header: &MavHeader,
+        target_system: u8,
+        target_component: u8,
+        timeout: Duration,
+    ) -> Result<Capabilities, RequestMessageError>
+    where
+        M: Message,
+        C: MavConnection<M> + ?Sized,
+    {
+        if let Some(capabilities) = self.get(target_system) {
+            return Ok(capabilities);
+        }
+        let capabilities =
+            request_capabilities(connection, header, target_system, target_component, timeout)?;
+        self.systems.insert(target_system, capabilities);
+        Ok(capabilities)
+    }
+
+    /// Forget the capabilities of `system_id`, for example after it rebooted into new firmware
+    pub fn invalidate(&mut self, system_id: u8) {
+        self.systems.remove(&system_id);
+    }
+}
+
+/// Request the `AUTOPILOT_VERSION` of `target_system`/`target_component` using
+/// `MAV_CMD_REQUEST_MESSAGE`, and return the capabilities it reports.
+///
+/// See [`request_message`](crate::request_message) for how the request is answered.
+#[cfg(feature = "std")]
+pub fn request_capabilities<M, C>(
+    connection: &C,
+    header: &MavHeader,
+    target_system: u8,
+    target_component: u8,
+    timeout: Duration,
+) -> Result<Capabilities, RequestMessageError>
+where
+    M: Message,
+    C: MavConnection<M> + ?Sized,
+{
+    let payload = request_message_payload(
+        connection,
+        header,
+        target_system,
+        target_component,
+        AUTOPILOT_VERSION_ID,
+        timeout,
+    )?;
+    Ok(Capabilities::from_autopilot_version_payload(&payload))
+}
@@ -1,5 +1,42 @@
 use core::fmt::Display;
 use std::io;
+use std::time::Duration;
+
+use crate::MavlinkVersion;
+
+/// Settings of the socket or port opened by a connection, each left to the default of the
+/// connection when unset
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+    /// Time to wait for a `tcpout` connection to be established
+    pub connect_timeout: Option<Duration>,
+    /// Time a receive waits for data before failing with [`io::ErrorKind::WouldBlock`] or
+    /// [`io::ErrorKind::TimedOut`] on synchronous connections. `tcpout` connections default to
+    /// 100 ms.
+    pub read_timeout: Option<Duration>,
+    /// Time a send waits for room in the socket buffer on synchronous TCP and UDP connections
+    pub write_timeout: Option<Duration>,
+    /// Disable Nagle's algorithm on TCP connections, sending small frames without delay
+    pub nodelay: Option<bool>,
+    /// Size of the receive buffer of TCP and UDP sockets
+    pub recv_buffer_size: Option<usize>,
+    /// Size of the send buffer of TCP and UDP sockets
+    pub send_buffer_size: Option<usize>,
+}
+
+#[cfg(any(feature = "tcp", feature = "udp"))]
+impl ConnectionOptions {
+    /// Apply the buffer sizes to `socket`
+    pub(crate) fn apply_buffer_sizes(&self, socket: socket2::SockRef<'_>) -> io::Result<()> {
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum UdpMode {
@@ -12,11 +49,68 @@ pub enum UdpMode {
 pub struct UdpConnectable {
     pub(crate) address: String,
     pub(crate) mode: UdpMode,
+    pub(crate) reorder: Option<(usize, Duration)>,
+    pub(crate) rendezvous: Option<(String, Duration)>,
+    pub(crate) write_coalescing: Option<Duration>,
+    pub(crate) options: ConnectionOptions,
+    pub(crate) protocol_version: MavlinkVersion,
 }
 
 impl UdpConnectable {
     pub fn new(address: String, mode: UdpMode) -> Self {
-        Self { address, mode }
+        Self {
+            address,
+            mode,
+            reorder: None,
+            rendezvous: None,
+            write_coalescing: None,
+            options: ConnectionOptions::default(),
+            protocol_version: MavlinkVersion::V2,
+        }
+    }
+
+    /// Open the socket with the timeouts and buffer sizes of `options`
+    pub fn with_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Send and receive `version` frames instead of MAVLink 2 ones
+    pub fn with_protocol_version(mut self, version: MavlinkVersion) -> Self {
+        self.protocol_version = version;
+        self
+    }
+
+    /// Deliver the frames of each source in sequence order on synchronous connections.
+    ///
+    /// Frames received ahead of a missing one are held back until the gap is filled, for at
+    /// most `max_delay` and as long as no more than `window` frames are waiting.
+    pub fn with_reorder_buffer(mut self, window: usize, max_delay: Duration) -> Self {
+        self.reorder = Some((window, max_delay));
+        self
+    }
+
+    /// Reach the peer registered under `session` on the rendezvous server at the address of this
+    /// `udpout` connection, see [`RendezvousServer`](crate::RendezvousServer). Only applies to
+    /// synchronous connections.
+    ///
+    /// The connection registers with the server every `keepalive`, which also keeps the NAT
+    /// mappings open, and sends its traffic directly to the peer once introduced. Like for `udpin`
+    /// connections, the peer address is learned while receiving and messages sent before that are
+    /// dropped.
+    pub fn with_rendezvous(mut self, session: impl Into<String>, keepalive: Duration) -> Self {
+        self.rendezvous = Some((session.into(), keepalive));
+        self
+    }
+
+    /// Batch the frames sent within `window` of each other into a single datagram on synchronous
+    /// connections, as long as they fit in one.
+    ///
+    /// A batch that fails to be written is dropped and its error is returned by the next send.
+    /// Dropping the connection waits for the last batch to be written.
+    pub fn with_write_coalescing(mut self, window: Duration) -> Self {
+        self.write_coalescing = Some(window);
+        self
     }
 }
 impl Display for UdpConnectable {
@@ -30,10 +124,138 @@ impl Display for UdpConnectable {
     }
 }
 
+/// Number of data bits in each character sent on a serial port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialDataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialParity {
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialStopBits {
+    One,
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialFlowControl {
+    None,
+    /// XON/XOFF characters sent in band
+    Software,
+    /// RTS/CTS lines
+    Hardware,
+}
+
+/// Character framing and flow control of a serial port, 8N1 without flow control by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialConfig {
+    pub data_bits: SerialDataBits,
+    pub parity: SerialParity,
+    pub stop_bits: SerialStopBits,
+    pub flow_control: SerialFlowControl,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            data_bits: SerialDataBits::Eight,
+            parity: SerialParity::None,
+            stop_bits: SerialStopBits::One,
+            flow_control: SerialFlowControl::None,
+        }
+    }
+}
+
+impl SerialConfig {
+    /// Parse a framing in the usual notation, such as `8N1` or `8E1`
+    fn parse_framing(framing: &str) -> Option<(SerialDataBits, SerialParity, SerialStopBits)> {
+        let [data_bits, parity, stop_bits] = framing.as_bytes() else {
+            return None;
+        };
+        let data_bits = match data_bits {
+            b'5' => SerialDataBits::Five,
+            b'6' => SerialDataBits::Six,
+            b'7' => SerialDataBits::Seven,
+            b'8' => SerialDataBits::Eight,
+            _ => return None,
+        };
+        let parity = match parity.to_ascii_uppercase() {
+            b'N' => SerialParity::None,
+            b'O' => SerialParity::Odd,
+            b'E' => SerialParity::Even,
+            _ => return None,
+        };
+        let stop_bits = match stop_bits {
+            b'1' => SerialStopBits::One,
+            b'2' => SerialStopBits::Two,
+            _ => return None,
+        };
+        Some((data_bits, parity, stop_bits))
+    }
+
+    /// Apply an option of a `serial:` address, either a framing or a flow control
+    fn apply_option(&mut self, option: &str) -> Option<()> {
+        match option {
+            "rtscts" => self.flow_control = SerialFlowControl::Hardware,
+            "xonxoff" => self.flow_control = SerialFlowControl::Software,
+            framing => {
+                (self.data_bits, self.parity, self.stop_bits) = Self::parse_framing(framing)?;
+            }
+        }
+        Some(())
+    }
+}
+
+impl Display for SerialConfig {
+    /// Options of a `serial:` address differing from the defaults, each preceded by a colon
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let default = Self::default();
+        if (self.data_bits, self.parity, self.stop_bits)
+            != (default.data_bits, default.parity, default.stop_bits)
+        {
+            let data_bits = match self.data_bits {
+                SerialDataBits::Five => 5,
+                SerialDataBits::Six => 6,
+                SerialDataBits::Seven => 7,
+                SerialDataBits::Eight => 8,
+            };
+            let parity = match self.parity {
+                SerialParity::None => 'N',
+                SerialParity::Odd => 'O',
+                SerialParity::Even => 'E',
+            };
+            let stop_bits = match self.stop_bits {
+                SerialStopBits::One => 1,
+                SerialStopBits::Two => 2,
+            };
+            write!(f, ":{data_bits}{parity}{stop_bits}")?;
+        }
+        match self.flow_control {
+            SerialFlowControl::None => Ok(()),
+            SerialFlowControl::Software => write!(f, ":xonxoff"),
+            SerialFlowControl::Hardware => write!(f, ":rtscts"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SerialConnectable {
     pub(crate) port_name: String,
     pub(crate) baud_rate: usize,
+    pub(crate) config: SerialConfig,
+    pub(crate) dtr: Option<bool>,
+    pub(crate) rts: Option<bool>,
+    pub(crate) options: ConnectionOptions,
+    pub(crate) protocol_version: MavlinkVersion,
 }
 
 impl SerialConnectable {
@@ -41,12 +263,51 @@ impl SerialConnectable {
         Self {
             port_name,
             baud_rate,
+            config: SerialConfig::default(),
+            dtr: None,
+            rts: None,
+            options: ConnectionOptions::default(),
+            protocol_version: MavlinkVersion::V2,
         }
     }
+
+    /// Open the port with the read timeout of `options` on synchronous connections
+    pub fn with_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Send and receive `version` frames instead of MAVLink 2 ones
+    pub fn with_protocol_version(mut self, version: MavlinkVersion) -> Self {
+        self.protocol_version = version;
+        self
+    }
+
+    /// Use the framing and flow control of `config` instead of 8N1 without flow control
+    pub fn with_config(mut self, config: SerialConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the DTR line to `level` when the port is opened
+    pub fn with_dtr(mut self, level: bool) -> Self {
+        self.dtr = Some(level);
+        self
+    }
+
+    /// Set the RTS line to `level` when the port is opened
+    pub fn with_rts(mut self, level: bool) -> Self {
+        self.rts = Some(level);
+        self
+    }
 }
 impl Display for SerialConnectable {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "serial:{}:{}", self.port_name, self.baud_rate)
+        write!(
+            f,
+            "serial:{}:{}{}",
+            self.port_name, self.baud_rate, self.config
+        )
     }
 }
 
@@ -54,17 +315,61 @@ impl Display for SerialConnectable {
 pub struct TcpConnectable {
     pub(crate) address: String,
     pub(crate) is_out: bool,
+    pub(crate) multiple_clients: bool,
+    pub(crate) write_coalescing: Option<Duration>,
+    pub(crate) options: ConnectionOptions,
+    pub(crate) protocol_version: MavlinkVersion,
 }
 
 impl TcpConnectable {
     pub fn new(address: String, is_out: bool) -> Self {
-        Self { address, is_out }
+        Self {
+            address,
+            is_out,
+            multiple_clients: false,
+            write_coalescing: None,
+            options: ConnectionOptions::default(),
+            protocol_version: MavlinkVersion::V2,
+        }
+    }
+
+    /// Open the sockets with the timeouts, buffer sizes and delay of `options`
+    pub fn with_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Send and receive `version` frames instead of MAVLink 2 ones
+    pub fn with_protocol_version(mut self, version: MavlinkVersion) -> Self {
+        self.protocol_version = version;
+        self
+    }
+
+    /// Accept any number of clients on an async `tcpin` connection, as done by the `tcpserver`
+    /// address. Messages from all clients are received and sent messages go to every client.
+    ///
+    /// Synchronous connections don't support multiple clients and fail to connect.
+    pub fn with_multiple_clients(mut self) -> Self {
+        self.multiple_clients = true;
+        self
+    }
+
+    /// Batch the frames sent within `window` of each other into a single write on synchronous
+    /// connections.
+    ///
+    /// A batch that fails to be written is dropped and its error is returned by the next send.
+    /// Dropping the connection waits for the last batch to be written.
+    pub fn with_write_coalescing(mut self, window: Duration) -> Self {
+        self.write_coalescing = Some(window);
+        self
     }
 }
 impl Display for TcpConnectable {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if self.is_out {
             write!(f, "tcpout:{}", self.address)
+        } else if self.multiple_clients {
+            write!(f, "tcpserver:{}", self.address)
         } else {
             write!(f, "tcpin:{}", self.address)
         }
@@ -74,23 +379,99 @@ impl Display for TcpConnectable {
 #[derive(Debug, Clone)]
 pub struct FileConnectable {
     pub(crate) address: String,
+    pub(crate) output: bool,
+    #[cfg(feature = "std")]
+    pub(crate) playback: Option<crate::PlaybackControl>,
 }
 
 impl FileConnectable {
     pub fn new(address: String) -> Self {
-        Self { address }
+        Self {
+            address,
+            output: false,
+            #[cfg(feature = "std")]
+            playback: None,
+        }
+    }
+
+    /// Append the sent messages to the file as a telemetry log instead of reading it, creating
+    /// the file if needed.
+    ///
+    /// Each frame is written with the time it was sent at, see [`TlogWriter`](crate::TlogWriter).
+    /// Nothing can be received from the connection.
+    pub fn with_output(mut self) -> Self {
+        self.output = true;
+        self
+    }
+
+    /// Read the file as a telemetry log and deliver its messages at the pace they were recorded
+    /// at on synchronous connections, see [`TlogPlayer`](crate::TlogPlayer).
+    ///
+    /// Without playback, frames are read as fast as possible and the file may hold either a
+    /// telemetry log or raw frames.
+    #[cfg(feature = "std")]
+    pub fn with_playback(mut self, control: crate::PlaybackControl) -> Self {
+        self.playback = Some(control);
+        self
     }
 }
 impl Display for FileConnectable {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "file:{}", self.address)
+        if self.output {
+            write!(f, "fileout:{}", self.address)
+        } else {
+            write!(f, "file:{}", self.address)
+        }
     }
 }
+/// Connection over the standard input and output of the process.
+///
+/// Nothing else may be written to the standard output while the connection is in use, logs
+/// should go to the standard error instead.
+#[derive(Debug, Clone, Default)]
+pub struct StdioConnectable;
+
+impl Display for StdioConnectable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "stdio:")
+    }
+}
+
+/// Connection over a Unix domain stream socket, such as the endpoints of local routers
+#[cfg(all(unix, feature = "unix-socket"))]
+#[derive(Debug, Clone)]
+pub struct UnixConnectable {
+    pub(crate) path: String,
+    pub(crate) listen: bool,
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+impl UnixConnectable {
+    /// Connect to the socket at `path`, or create it and wait for a client if `listen` is set
+    pub fn new(path: String, listen: bool) -> Self {
+        Self { path, listen }
+    }
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+impl Display for UnixConnectable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.listen {
+            write!(f, "unixin:{}", self.path)
+        } else {
+            write!(f, "unix:{}", self.path)
+        }
+    }
+}
+
 pub enum ConnectionAddress {
     Tcp(TcpConnectable),
     Udp(UdpConnectable),
     Serial(SerialConnectable),
     File(FileConnectable),
+    Stdio(StdioConnectable),
+    #[cfg(all(unix, feature = "unix-socket"))]
+    Unix(UnixConnectable),
 }
 
 impl Display for ConnectionAddress {
@@ -100,11 +481,24 @@ impl Display for ConnectionAddress {
             Self::Udp(connectable) => write!(f, "{connectable}"),
             Self::Serial(connectable) => write!(f, "{connectable}"),
             Self::File(connectable) => write!(f, "{connectable}"),
+            Self::Stdio(connectable) => write!(f, "{connectable}"),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Self::Unix(connectable) => write!(f, "{connectable}"),
         }
     }
 }
 
 impl ConnectionAddress {
+    /// Apply `options` to the connections using them, the others ignore them
+    pub fn with_options(self, options: ConnectionOptions) -> Self {
+        match self {
+            Self::Tcp(connectable) => Self::Tcp(connectable.with_options(options)),
+            Self::Udp(connectable) => Self::Udp(connectable.with_options(options)),
+            Self::Serial(connectable) => Self::Serial(connectable.with_options(options)),
+            other => other,
+        }
+    }
+
     pub fn parse_address(address: &str) -> Result<Self, io::Error> {
         let (protocol, address) = address.split_once(':').ok_or(io::Error::new(
             io::ErrorKind::AddrNotAvailable,
@@ -113,33 +507,58 @@ impl ConnectionAddress {
         let conn = match protocol {
             #[cfg(feature = "direct-serial")]
             "serial" => {
-                let (port_name, baud) = address.split_once(':').ok_or(io::Error::new(
+                let (port_name, settings) = address.split_once(':').ok_or(io::Error::new(
                     io::ErrorKind::AddrNotAvailable,
                     "Incomplete port settings",
                 ))?;
-                Self::Serial(SerialConnectable::new(
-                    port_name.to_string(),
-                    baud.parse().map_err(|_| {
-                        io::Error::new(io::ErrorKind::AddrNotAvailable, "Invalid baud rate")
-                    })?,
-                ))
+                let mut settings = settings.split(':');
+                let baud = settings.next().unwrap_or_default();
+                let mut config = SerialConfig::default();
+                for option in settings {
+                    config.apply_option(option).ok_or(io::Error::new(
+                        io::ErrorKind::AddrNotAvailable,
+                        "Invalid serial port option",
+                    ))?;
+                }
+                Self::Serial(
+                    SerialConnectable::new(
+                        port_name.to_string(),
+                        baud.parse().map_err(|_| {
+                            io::Error::new(io::ErrorKind::AddrNotAvailable, "Invalid baud rate")
+                        })?,
+                    )
+                    .with_config(config),
+                )
             }
             #[cfg(feature = "tcp")]
             "tcpin" | "tcpout" => Self::Tcp(TcpConnectable::new(
                 address.to_string(),
                 protocol == "tcpout",
             )),
+            #[cfg(feature = "tcp")]
+            "tcpserver" => {
+                Self::Tcp(TcpConnectable::new(address.to_string(), false).with_multiple_clients())
+            }
             #[cfg(feature = "udp")]
-            "udpin" | "udpout" | "udpcast" => Self::Udp(UdpConnectable::new(
+            "udpin" | "udpout" | "udpcast" | "udpbcast" => Self::Udp(UdpConnectable::new(
                 address.to_string(),
                 match protocol {
                     "udpin" => UdpMode::Udpin,
                     "udpout" => UdpMode::Udpout,
-                    "udpcast" => UdpMode::Udpcast,
+                    "udpcast" | "udpbcast" => UdpMode::Udpcast,
                     _ => unreachable!(),
                 },
             )),
             "file" => Self::File(FileConnectable::new(address.to_string())),
+            "fileout" if !address.is_empty() => {
+                Self::File(FileConnectable::new(address.to_string()).with_output())
+            }
+            "stdio" if address.is_empty() => Self::Stdio(StdioConnectable),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            "unix" | "unixin" if !address.is_empty() => Self::Unix(UnixConnectable::new(
+                address.to_string(),
+                protocol == "unixin",
+            )),
             _ => {
                 return Err(io::Error::new(
                     io::ErrorKind::AddrNotAvailable,
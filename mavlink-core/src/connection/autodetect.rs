@@ -0,0 +1,103 @@
+//! Detection of the serial port and baud rate of a flight controller
+
+use std::io::{ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+use crate::connectable::{ConnectionOptions, SerialConnectable};
+use crate::mav_parser::ParserState;
+use crate::Message;
+
+use super::direct_serial::open_port;
+
+const HEARTBEAT_ID: u32 = 0;
+
+/// Baud rates probed by [`autodetect_serial`], from the most common for flight controllers
+pub const AUTODETECT_BAUD_RATES: [usize; 6] = [115_200, 57_600, 921_600, 460_800, 230_400, 38_400];
+
+/// Prefixes of the names of the USB serial devices under `/dev`
+#[cfg(unix)]
+const DEVICE_PREFIXES: [&str; 4] = ["ttyACM", "ttyUSB", "cu.usbmodem", "cu.usbserial"];
+
+/// Names of the serial ports a flight controller plugged via USB may appear as, sorted by name.
+///
+/// On Unix these are the USB serial devices of `/dev`, on Windows every `COM` port, whether
+/// it exists or not.
+pub fn serial_ports() -> Vec<String> {
+    #[cfg(unix)]
+    let mut ports: Vec<String> = std::fs::read_dir("/dev")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter(|name| {
+                    DEVICE_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+                })
+                .map(|name| format!("/dev/{name}"))
+                .collect()
+        })
+        .unwrap_or_default();
+    #[cfg(not(unix))]
+    let mut ports: Vec<String> = (1..=32).map(|index| format!("COM{index}")).collect();
+    ports.sort();
+    ports
+}
+
+/// Find the first serial port and baud rate over which a `HEARTBEAT` is received, as ground
+/// stations do to find a flight controller plugged via USB.
+///
+/// Every port of [`serial_ports`] is opened at each of `baud_rates` in turn, such as
+/// [`AUTODETECT_BAUD_RATES`], and read for up to `timeout`. Since heartbeats are usually sent
+/// once per second, `timeout` should be somewhat longer. Ports that can't be opened, such as
+/// ports used by another program, are skipped.
+///
+/// The returned connectable uses the protocol version of the received heartbeat.
+pub fn autodetect_serial<M: Message>(
+    baud_rates: &[usize],
+    timeout: Duration,
+) -> Option<SerialConnectable> {
+    serial_ports().into_iter().find_map(|port_name| {
+        baud_rates
+            .iter()
+            .find_map(|baud_rate| probe_serial::<M>(&port_name, *baud_rate, timeout))
+    })
+}
+
+/// Open `port_name` at `baud_rate` and wait up to `timeout` for a valid `HEARTBEAT`, returning
+/// the connectable to connect to it with
+pub fn probe_serial<M: Message>(
+    port_name: &str,
+    baud_rate: usize,
+    timeout: Duration,
+) -> Option<SerialConnectable> {
+    let connectable =
+        SerialConnectable::new(port_name.to_string(), baud_rate).with_options(ConnectionOptions {
+            read_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        });
+    let mut port = open_port(&connectable).ok()?;
+
+    let mut parser = ParserState::new(None, M::extra_crc);
+    let mut buf = [0u8; 512];
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let len = match port.read(&mut buf) {
+            Ok(0) => return None,
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => continue,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        };
+        let heartbeat = parser
+            .push_bytes(&buf[..len])
+            .find(|frame| frame.info().message_id == HEARTBEAT_ID);
+        if let Some(frame) = heartbeat {
+            return Some(
+                connectable
+                    .with_options(ConnectionOptions::default())
+                    .with_protocol_version(frame.protocol_version()),
+            );
+        }
+    }
+    None
+}
@@ -0,0 +1,229 @@
+//! Batching of frames sent in quick succession into a single write
+
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+type WriteFn<T> = Box<dyn Fn(&T, &[u8]) -> io::Result<()> + Send + Sync>;
+
+struct State<T> {
+    buffer: Vec<u8>,
+    destination: Option<T>,
+    deadline: Option<Instant>,
+    /// Error of a batch written in the background, reported by the next push
+    error: Option<io::Error>,
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    condvar: Condvar,
+    write: WriteFn<T>,
+    window: Duration,
+    max_len: usize,
+}
+
+impl<T> Shared<T> {
+    /// Write the queued batch, which is dropped if the write fails
+    fn flush(&self, state: &mut State<T>) -> io::Result<()> {
+        state.deadline = None;
+        if let Some(destination) = state.destination.take() {
+            let result = (self.write)(&destination, &state.buffer);
+            state.buffer.clear();
+            result.map_err(batch_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tell the error of a queued batch apart from the one of the frame being pushed
+fn batch_error(error: io::Error) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        format!("failed to write a batch of queued frames: {error}"),
+    )
+}
+
+/// Writes frames in batches, at most `window` after the first frame of a batch was queued.
+///
+/// A batch is written early once it would grow beyond `max_len` bytes or when the destination
+/// changes. Batches still queued are written when the coalescer is dropped, which waits for the
+/// write.
+///
+/// A batch that fails to be written is dropped, so that an unreachable destination doesn't hold
+/// back the frames to the others, and the error is returned by the next push, however late it
+/// comes. The frame of a push returning an error is not queued.
+pub(crate) struct Coalescer<T: PartialEq + Send + 'static> {
+    shared: Arc<Shared<T>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: PartialEq + Send + 'static> Coalescer<T> {
+    pub(crate) fn new(
+        window: Duration,
+        max_len: usize,
+        write: impl Fn(&T, &[u8]) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                buffer: Vec::with_capacity(max_len),
+                destination: None,
+                deadline: None,
+                error: None,
+                closed: false,
+            }),
+            condvar: Condvar::new(),
+            write: Box::new(write),
+            window,
+            max_len,
+        });
+        let thread = thread::spawn({
+            let shared = shared.clone();
+            move || write_batches(&shared)
+        });
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Drop the queued batch and the error of the previous one, when the destination is replaced
+    pub(crate) fn clear(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.buffer.clear();
+        state.destination = None;
+        state.deadline = None;
+        state.error = None;
+    }
+
+    /// Queue `frame` to be written to `destination`
+    pub(crate) fn push(&self, destination: T, frame: &[u8]) -> io::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(error) = state.error.take() {
+            return Err(error);
+        }
+        let other_destination = state
+            .destination
+            .as_ref()
+            .is_some_and(|queued| *queued != destination);
+        if other_destination || state.buffer.len() + frame.len() > self.shared.max_len {
+            self.shared.flush(&mut state)?;
+        }
+        state.buffer.extend_from_slice(frame);
+        state.destination = Some(destination);
+        if state.deadline.is_none() {
+            state.deadline = Some(Instant::now() + self.shared.window);
+            self.shared.condvar.notify_one();
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialEq + Send + 'static> Drop for Coalescer<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_batches<T>(shared: &Shared<T>) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        if state.closed {
+            // nobody is left to report the error to
+            let _ = shared.flush(&mut state);
+            return;
+        }
+        state = match state.deadline {
+            None => shared.condvar.wait(state).unwrap(),
+            Some(deadline) => {
+                let now = Instant::now();
+                if now < deadline {
+                    shared
+                        .condvar
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                } else {
+                    if let Err(error) = shared.flush(&mut state) {
+                        state.error = Some(error);
+                    }
+                    state
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Written = Arc<Mutex<Vec<(u8, Vec<u8>)>>>;
+
+    /// Coalescer recording the batches written to every destination but `unreachable`
+    fn recording(unreachable: u8) -> (Coalescer<u8>, Written) {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let coalescer = Coalescer::new(Duration::from_secs(60), 4, {
+            let written = written.clone();
+            move |destination: &u8, batch: &[u8]| {
+                if *destination == unreachable {
+                    return Err(io::ErrorKind::ConnectionRefused.into());
+                }
+                written.lock().unwrap().push((*destination, batch.to_vec()));
+                Ok(())
+            }
+        });
+        (coalescer, written)
+    }
+
+    #[test]
+    fn test_failed_batch_dropped() {
+        let (coalescer, written) = recording(1);
+
+        coalescer.push(1, &[1, 2]).unwrap();
+        // the batch of the unreachable destination fails when the destination changes
+        let error = coalescer.push(2, &[3]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+
+        // and doesn't hold back the frames to the other destinations
+        coalescer.push(2, &[3]).unwrap();
+        coalescer.push(3, &[4]).unwrap();
+        drop(coalescer);
+        assert_eq!(*written.lock().unwrap(), [(2, vec![3]), (3, vec![4])]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let (coalescer, written) = recording(0);
+
+        coalescer.push(1, &[1, 2]).unwrap();
+        coalescer.clear();
+        coalescer.push(1, &[3]).unwrap();
+        drop(coalescer);
+        assert_eq!(*written.lock().unwrap(), [(1, vec![3])]);
+    }
+
+    #[test]
+    fn test_background_error_reported() {
+        let coalescer = Coalescer::new(Duration::ZERO, 16, |_: &(), _: &[u8]| {
+            Err(io::ErrorKind::ConnectionReset.into())
+        });
+        coalescer.push((), &[1]).unwrap();
+        let start = Instant::now();
+        let error = loop {
+            match coalescer.push((), &[2]) {
+                Ok(()) => {
+                    assert!(start.elapsed() < Duration::from_secs(5));
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+    }
+}
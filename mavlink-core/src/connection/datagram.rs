@@ -0,0 +1,201 @@
+//! MAVLink connection over message-oriented transports
+
+use crate::connection::MavConnection;
+use crate::error::{MessageReadError, MessageWriteError};
+use crate::peek_reader::PeekReader;
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
+use core::ops::DerefMut;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(feature = "signing"))]
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
+#[cfg(feature = "signing")]
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
+
+/// Transport delivering whole messages, such as WebRTC data channels or WebSockets.
+///
+/// Transports may lose, duplicate or reorder messages, as WebRTC data channels do in
+/// unordered/unreliable mode.
+pub trait DatagramTransport {
+    /// Block until the next message is received, copying it into `buf` and returning its length.
+    /// Messages longer than `buf` may be truncated.
+    fn recv_datagram(&self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Send `datagram` as a single message
+    fn send_datagram(&self, datagram: &[u8]) -> io::Result<()>;
+}
+
+const MAX_DATAGRAM_SIZE: usize = 1500;
+
+struct DatagramRead<T> {
+    transport: Arc<T>,
+    buffer: VecDeque<u8>,
+}
+
+impl<T: DatagramTransport> Read for DatagramRead<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.buffer.is_empty() {
+            self.buffer.read(buf)
+        } else {
+            let mut read_buffer = [0u8; MAX_DATAGRAM_SIZE];
+            let n_buffer = self.transport.recv_datagram(&mut read_buffer)?;
+            let n = (&read_buffer[0..n_buffer]).read(buf)?;
+            self.buffer.extend(&read_buffer[n..n_buffer]);
+            Ok(n)
+        }
+    }
+}
+
+/// A [`MavConnection`] over a [`DatagramTransport`], sending every frame as its own message.
+///
+/// Messages carrying several frames are accepted when receiving.
+pub struct DatagramConnection<T: DatagramTransport> {
+    reader: Mutex<PeekReader<DatagramRead<T>>>,
+    transport: Arc<T>,
+    sequence: Mutex<u8>,
+    protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+impl<T: DatagramTransport> DatagramConnection<T> {
+    pub fn new(transport: T) -> Self {
+        let transport = Arc::new(transport);
+        Self {
+            reader: Mutex::new(PeekReader::new(DatagramRead {
+                transport: transport.clone(),
+                buffer: VecDeque::new(),
+            })),
+            transport,
+            sequence: Mutex::new(0),
+            protocol_version: MavlinkVersion::V2,
+            target_filter: None,
+            source_filter: None,
+            #[cfg(feature = "signing")]
+            signing_data: None,
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<M: Message, T: DatagramTransport> MavConnection<M> for DatagramConnection<T> {
+    fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result = read_versioned_frame(reader.deref_mut(), self.protocol_version);
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_signed(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            );
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                // invalid frames are skipped, errors of the transport are reported
+                Err(MessageReadError::Parse(_)) => {}
+                result => return result,
+            }
+        }
+    }
+
+    fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        let mut sequence = self.sequence.lock().unwrap();
+
+        let header = MavHeader {
+            sequence: *sequence,
+            system_id: header.system_id,
+            component_id: header.component_id,
+        };
+
+        *sequence = sequence.wrapping_add(1);
+        let mut buf = Vec::new();
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg(&mut buf, self.protocol_version, header, data)?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_signed(
+            &mut buf,
+            self.protocol_version,
+            header,
+            data,
+            self.signing_data.as_ref(),
+        )?;
+        self.transport.send_datagram(&buf)?;
+        Ok(len)
+    }
+
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = read_versioned_raw_message::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let bytes = frame.raw_bytes();
+        self.transport.send_datagram(bytes)?;
+        Ok(bytes.len())
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = peek_versioned_frame_info::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
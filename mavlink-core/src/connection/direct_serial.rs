@@ -1,20 +1,31 @@
 //! Serial MAVLINK connection
 
-use crate::connectable::SerialConnectable;
+use crate::connectable::{
+    SerialConnectable, SerialDataBits, SerialFlowControl, SerialParity, SerialStopBits,
+};
 use crate::connection::MavConnection;
 use crate::peek_reader::PeekReader;
-use crate::{MavHeader, MavlinkVersion, Message};
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
 use core::ops::DerefMut;
-use std::io;
+use std::io::{self, Write};
 use std::sync::Mutex;
 
 use crate::error::{MessageReadError, MessageWriteError};
 use serial::{prelude::*, SystemPort};
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg, write_versioned_msg};
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
 #[cfg(feature = "signing")]
-use crate::{read_versioned_msg_signed, write_versioned_msg_signed, SigningConfig, SigningData};
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
 
 use super::Connectable;
 
@@ -22,30 +33,37 @@ pub struct SerialConnection {
     port: Mutex<PeekReader<SystemPort>>,
     sequence: Mutex<u8>,
     protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
 
 impl<M: Message> MavConnection<M> for SerialConnection {
     fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
         let mut port = self.port.lock().unwrap();
         loop {
             #[cfg(not(feature = "signing"))]
-            let result = read_versioned_msg(port.deref_mut(), self.protocol_version);
+            let result = read_versioned_frame(port.deref_mut(), self.protocol_version);
             #[cfg(feature = "signing")]
-            let result = read_versioned_msg_signed(
+            let result = read_versioned_frame_signed(
                 port.deref_mut(),
                 self.protocol_version,
                 self.signing_data.as_ref(),
             );
             match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
                 ok @ Ok(..) => {
                     return ok;
                 }
-                Err(MessageReadError::Io(e)) => {
-                    if e.kind() == io::ErrorKind::UnexpectedEof {
-                        return Err(MessageReadError::Io(e));
-                    }
+                Err(MessageReadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(MessageReadError::Io(e));
                 }
                 _ => {}
             }
@@ -77,6 +95,39 @@ impl<M: Message> MavConnection<M> for SerialConnection {
         result
     }
 
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut port = self.port.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = read_versioned_raw_message::<M, _>(port.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_signed::<M, _>(
+            port.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let mut port = self.port.lock().unwrap();
+        let bytes = frame.raw_bytes();
+        port.reader_mut().write_all(bytes)?;
+        Ok(bytes.len())
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut port = self.port.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = peek_versioned_frame_info::<M, _>(port.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_signed::<M, _>(
+            port.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -85,32 +136,91 @@ impl<M: Message> MavConnection<M> for SerialConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
     }
 }
 
-impl Connectable for SerialConnectable {
-    fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
-        let baud_rate = serial::core::BaudRate::from_speed(self.baud_rate);
-        let settings = serial::core::PortSettings {
-            baud_rate,
-            char_size: serial::Bits8,
-            parity: serial::ParityNone,
-            stop_bits: serial::Stop1,
-            flow_control: serial::FlowNone,
-        };
-
-        let mut port = serial::open(&self.port_name)?;
-        port.configure(&settings)?;
-
-        Ok(Box::new(SerialConnection {
-            port: Mutex::new(PeekReader::new(port)),
+impl SerialConnection {
+    /// Open the serial port described by `connectable`
+    pub fn open(connectable: &SerialConnectable) -> io::Result<Self> {
+        Ok(Self {
+            port: Mutex::new(PeekReader::new(open_port(connectable)?)),
             sequence: Mutex::new(0),
-            protocol_version: MavlinkVersion::V2,
+            protocol_version: connectable.protocol_version,
+            target_filter: None,
+            source_filter: None,
             #[cfg(feature = "signing")]
             signing_data: None,
-        }))
+        })
+    }
+
+    /// Set the DTR (Data Terminal Ready) line, used by some boards to enter or leave their bootloader
+    pub fn set_dtr(&self, level: bool) -> io::Result<()> {
+        let mut port = self.port.lock().unwrap();
+        Ok(port.reader_mut().set_dtr(level)?)
+    }
+
+    /// Set the RTS (Request To Send) line, used by some boards for resets and by some radios to wake up
+    pub fn set_rts(&self, level: bool) -> io::Result<()> {
+        let mut port = self.port.lock().unwrap();
+        Ok(port.reader_mut().set_rts(level)?)
+    }
+}
+
+/// Open and configure the serial port described by `connectable`
+pub(super) fn open_port(connectable: &SerialConnectable) -> io::Result<SystemPort> {
+    let baud_rate = serial::core::BaudRate::from_speed(connectable.baud_rate);
+    let config = &connectable.config;
+    let settings = serial::core::PortSettings {
+        baud_rate,
+        char_size: match config.data_bits {
+            SerialDataBits::Five => serial::Bits5,
+            SerialDataBits::Six => serial::Bits6,
+            SerialDataBits::Seven => serial::Bits7,
+            SerialDataBits::Eight => serial::Bits8,
+        },
+        parity: match config.parity {
+            SerialParity::None => serial::ParityNone,
+            SerialParity::Odd => serial::ParityOdd,
+            SerialParity::Even => serial::ParityEven,
+        },
+        stop_bits: match config.stop_bits {
+            SerialStopBits::One => serial::Stop1,
+            SerialStopBits::Two => serial::Stop2,
+        },
+        flow_control: match config.flow_control {
+            SerialFlowControl::None => serial::FlowNone,
+            SerialFlowControl::Software => serial::FlowSoftware,
+            SerialFlowControl::Hardware => serial::FlowHardware,
+        },
+    };
+
+    let mut port = serial::open(&connectable.port_name)?;
+    port.configure(&settings)?;
+    if let Some(timeout) = connectable.options.read_timeout {
+        port.set_timeout(timeout)?;
+    }
+    if let Some(level) = connectable.dtr {
+        port.set_dtr(level)?;
+    }
+    if let Some(level) = connectable.rts {
+        port.set_rts(level)?;
+    }
+    Ok(port)
+}
+
+impl Connectable for SerialConnectable {
+    fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
+        Ok(Box::new(SerialConnection::open(self)?))
     }
 }
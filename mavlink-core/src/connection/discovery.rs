@@ -0,0 +1,166 @@
+//! Discovery of the cameras and gimbals of a system
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use crate::error::MessageReadError;
+use crate::{MavHeader, MavlinkVersion, Message, MAX_FRAME_SIZE};
+
+use super::request::request_message_command;
+use super::{MavConnection, RequestMessageError};
+
+const HEARTBEAT_ID: u32 = 0;
+const CAMERA_INFORMATION_ID: u32 = 259;
+const GIMBAL_MANAGER_INFORMATION_ID: u32 = 280;
+const GIMBAL_DEVICE_INFORMATION_ID: u32 = 283;
+
+const MAV_TYPE_GIMBAL: u8 = 26;
+const MAV_TYPE_CAMERA: u8 = 30;
+
+/// `MAV_COMP_ID_CAMERA` to `MAV_COMP_ID_CAMERA6`
+const CAMERA_COMPONENTS: [u8; 6] = [100, 101, 102, 103, 104, 105];
+/// `MAV_COMP_ID_GIMBAL` and `MAV_COMP_ID_GIMBAL2` to `MAV_COMP_ID_GIMBAL6`
+const GIMBAL_COMPONENTS: [u8; 6] = [154, 171, 172, 173, 174, 175];
+
+/// Kind of payload device found by [`discover_devices`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeviceKind {
+    Camera,
+    Gimbal,
+}
+
+impl DeviceKind {
+    fn from_component_id(component_id: u8) -> Option<Self> {
+        if CAMERA_COMPONENTS.contains(&component_id) {
+            Some(Self::Camera)
+        } else if GIMBAL_COMPONENTS.contains(&component_id) {
+            Some(Self::Gimbal)
+        } else {
+            None
+        }
+    }
+
+    fn from_mav_type(mav_type: u8) -> Option<Self> {
+        match mav_type {
+            MAV_TYPE_CAMERA => Some(Self::Camera),
+            MAV_TYPE_GIMBAL => Some(Self::Gimbal),
+            _ => None,
+        }
+    }
+
+    /// Information messages requested from devices of this kind
+    fn information_ids(self) -> &'static [u32] {
+        match self {
+            Self::Camera => &[CAMERA_INFORMATION_ID],
+            Self::Gimbal => &[GIMBAL_MANAGER_INFORMATION_ID, GIMBAL_DEVICE_INFORMATION_ID],
+        }
+    }
+}
+
+/// Camera or gimbal found by [`discover_devices`]
+#[derive(Debug, Clone)]
+pub struct DiscoveredDevice<M> {
+    pub system_id: u8,
+    pub component_id: u8,
+    pub kind: DeviceKind,
+    /// `MAV_TYPE` of the heartbeat of the device, if one was received
+    pub mav_type: Option<u8>,
+    /// Information messages the device answered with: `CAMERA_INFORMATION` for cameras,
+    /// `GIMBAL_MANAGER_INFORMATION` and `GIMBAL_DEVICE_INFORMATION` for gimbals
+    pub information: Vec<M>,
+}
+
+/// Find the cameras and gimbals of `target_system`, or of every system if it is `0`.
+///
+/// The information message of each kind of device is requested with `MAV_CMD_REQUEST_MESSAGE`
+/// from every component id of the camera and gimbal ranges, then the connection is read for
+/// `timeout`. Components answering the requests, or sending a heartbeat from one of these
+/// ranges or with the camera or gimbal `MAV_TYPE`, are returned sorted by system and component
+/// id.
+///
+/// Since [`MavConnection::recv`] blocks, the timeout is only checked between received messages.
+pub fn discover_devices<M, C>(
+    connection: &C,
+    header: &MavHeader,
+    target_system: u8,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredDevice<M>>, RequestMessageError>
+where
+    M: Message,
+    C: MavConnection<M> + ?Sized,
+{
+    for component_id in CAMERA_COMPONENTS.into_iter().chain(GIMBAL_COMPONENTS) {
+        let kind = DeviceKind::from_component_id(component_id).unwrap();
+        for message_id in kind.information_ids() {
+            let request = request_message_command::<M>(*message_id, target_system, component_id)?;
+            connection.send(header, &request)?;
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut devices: BTreeMap<_, DiscoveredDevice<M>> = BTreeMap::new();
+    while Instant::now() < deadline {
+        let (msg_header, msg) = match connection.recv() {
+            Ok(received) => received,
+            // connections with a read timeout report it as an error, keep waiting until the deadline
+            Err(MessageReadError::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if target_system != 0 && msg_header.system_id != target_system {
+            continue;
+        }
+
+        let key = (msg_header.system_id, msg_header.component_id);
+        let range_kind = DeviceKind::from_component_id(msg_header.component_id);
+        match msg.message_id() {
+            HEARTBEAT_ID => {
+                let mut payload = [0u8; MAX_FRAME_SIZE];
+                msg.ser(MavlinkVersion::V2, &mut payload);
+                // the type follows the u32 custom_mode
+                let mav_type = payload[4];
+                let Some(kind) = DeviceKind::from_mav_type(mav_type).or(range_kind) else {
+                    continue;
+                };
+                devices
+                    .entry(key)
+                    .or_insert_with(|| new_device(key, kind))
+                    .mav_type = Some(mav_type);
+            }
+            id @ (CAMERA_INFORMATION_ID
+            | GIMBAL_MANAGER_INFORMATION_ID
+            | GIMBAL_DEVICE_INFORMATION_ID) => {
+                let kind = if id == CAMERA_INFORMATION_ID {
+                    DeviceKind::Camera
+                } else {
+                    DeviceKind::Gimbal
+                };
+                let device = devices.entry(key).or_insert_with(|| new_device(key, kind));
+                if !device
+                    .information
+                    .iter()
+                    .any(|info| info.message_id() == id)
+                {
+                    device.information.push(msg);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(devices.into_values().collect())
+}
+
+fn new_device<M>((system_id, component_id): (u8, u8), kind: DeviceKind) -> DiscoveredDevice<M> {
+    DiscoveredDevice {
+        system_id,
+        component_id,
+        kind,
+        mav_type: None,
+        information: Vec::new(),
+    }
+}
@@ -4,60 +4,111 @@ use crate::connectable::FileConnectable;
 use crate::connection::MavConnection;
 use crate::error::{MessageReadError, MessageWriteError};
 use crate::peek_reader::PeekReader;
-use crate::{MavHeader, MavlinkVersion, Message};
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, PlaybackControl, RawFrame,
+    SourceFilter, TargetFilter, TlogPlayer, TlogWriter,
+};
 use core::ops::DerefMut;
-use std::fs::File;
-use std::io;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read};
 use std::sync::Mutex;
 
 #[cfg(not(feature = "signing"))]
-use crate::read_versioned_msg;
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
 #[cfg(feature = "signing")]
-use crate::{read_versioned_msg_signed, SigningConfig, SigningData};
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
 
 use super::Connectable;
 
-pub fn open(file_path: &str) -> io::Result<FileConnection> {
+pub fn open(file_path: &str, playback: Option<PlaybackControl>) -> io::Result<FileConnection> {
     let file = File::open(file_path)?;
+    let reader: Box<dyn Read + Send> = match playback {
+        Some(control) => Box::new(PlaybackRead {
+            player: TlogPlayer::new(file, control),
+            frame: Vec::new(),
+            position: 0,
+        }),
+        None => Box::new(file),
+    };
 
     Ok(FileConnection {
-        file: Mutex::new(PeekReader::new(file)),
+        file: Mutex::new(PeekReader::new(reader)),
         protocol_version: MavlinkVersion::V2,
+        target_filter: None,
+        source_filter: None,
         #[cfg(feature = "signing")]
         signing_data: None,
     })
 }
 
+/// Reader of the frames of a telemetry log, released by a [`TlogPlayer`]
+struct PlaybackRead {
+    player: TlogPlayer<File>,
+    frame: Vec<u8>,
+    position: usize,
+}
+
+impl Read for PlaybackRead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.frame.len() {
+            match self.player.next_record()? {
+                Some(record) => {
+                    self.frame = record.frame;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.frame.len() - self.position);
+        buf[..len].copy_from_slice(&self.frame[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
 pub struct FileConnection {
-    file: Mutex<PeekReader<File>>,
+    file: Mutex<PeekReader<Box<dyn Read + Send>>>,
     protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
 
 impl<M: Message> MavConnection<M> for FileConnection {
     fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         // TODO: fix that unwrap
         // not simple b/c PoisonError is not simple
         let mut file = self.file.lock().unwrap();
 
         loop {
             #[cfg(not(feature = "signing"))]
-            let result = read_versioned_msg(file.deref_mut(), self.protocol_version);
+            let result = read_versioned_frame(file.deref_mut(), self.protocol_version);
             #[cfg(feature = "signing")]
-            let result = read_versioned_msg_signed(
+            let result = read_versioned_frame_signed(
                 file.deref_mut(),
                 self.protocol_version,
                 self.signing_data.as_ref(),
             );
             match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
                 ok @ Ok(..) => {
                     return ok;
                 }
-                Err(MessageReadError::Io(e)) => {
-                    if e.kind() == io::ErrorKind::UnexpectedEof {
-                        return Err(MessageReadError::Io(e));
-                    }
+                Err(MessageReadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(MessageReadError::Io(e));
                 }
                 _ => {}
             }
@@ -68,6 +119,36 @@ impl<M: Message> MavConnection<M> for FileConnection {
         Ok(0)
     }
 
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut file = self.file.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = read_versioned_raw_message::<M, _>(file.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_signed::<M, _>(
+            file.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn send_raw(&self, _frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        Ok(0)
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut file = self.file.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = peek_versioned_frame_info::<M, _>(file.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_signed::<M, _>(
+            file.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -76,6 +157,102 @@ impl<M: Message> MavConnection<M> for FileConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
+
+/// Open `file_path` for appending, creating it if needed, to record the sent messages as a
+/// telemetry log
+pub fn create(file_path: &str) -> io::Result<FileOutConnection> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)?;
+    Ok(FileOutConnection {
+        log: Mutex::new(TlogWriter::new(BufWriter::new(file))),
+        protocol_version: MavlinkVersion::V2,
+        #[cfg(feature = "signing")]
+        signing_data: None,
+    })
+}
+
+/// Connection recording the sent messages to a telemetry log.
+///
+/// Messages are written with the header they are sent with, so received messages can be recorded
+/// as they were received. Every frame is flushed to the file when it is sent.
+pub struct FileOutConnection {
+    log: Mutex<TlogWriter<BufWriter<File>>>,
+    protocol_version: MavlinkVersion,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+impl FileOutConnection {
+    fn write_frame(&self, frame: &[u8]) -> io::Result<()> {
+        let mut log = self.log.lock().unwrap();
+        log.write_frame(crate::tlog::now_us(), frame)?;
+        log.flush()
+    }
+}
+
+impl<M: Message> MavConnection<M> for FileOutConnection {
+    /// Always fails, nothing is ever received from the log
+    fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
+    fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        let mut frame = Vec::new();
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg(&mut frame, self.protocol_version, *header, data)?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_signed(
+            &mut frame,
+            self.protocol_version,
+            *header,
+            data,
+            self.signing_data.as_ref(),
+        )?;
+        self.write_frame(&frame)?;
+        Ok(len)
+    }
+
+    fn recv_raw(&self) -> Result<RawFrame, MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let bytes = frame.raw_bytes();
+        self.write_frame(bytes)?;
+        Ok(bytes.len())
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, MessageReadError> {
+        Err(MessageReadError::eof())
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, _filter: Option<TargetFilter>) {}
+
+    fn set_source_filter(&mut self, _filter: Option<SourceFilter>) {}
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -84,6 +261,9 @@ impl<M: Message> MavConnection<M> for FileConnection {
 
 impl Connectable for FileConnectable {
     fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
-        Ok(Box::new(open(&self.address)?))
+        if self.output {
+            return Ok(Box::new(create(&self.address)?));
+        }
+        Ok(Box::new(open(&self.address, self.playback.clone())?))
     }
 }
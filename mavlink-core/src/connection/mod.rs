@@ -1,22 +1,73 @@
-use crate::{connectable::ConnectionAddress, MavFrame, MavHeader, MavlinkVersion, Message};
+use crate::{
+    connectable::{ConnectionAddress, ConnectionOptions},
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
 
 use core::fmt::Display;
 use std::io::{self};
+use std::time::Duration;
 
 #[cfg(feature = "tcp")]
 mod tcp;
 
+#[cfg(any(feature = "tcp", feature = "udp"))]
+mod coalesce;
+
 #[cfg(feature = "udp")]
 mod udp;
 
+#[cfg(feature = "udp")]
+mod reorder;
+
+#[cfg(feature = "udp")]
+mod rendezvous;
+#[cfg(feature = "udp")]
+pub use rendezvous::RendezvousServer;
+
 #[cfg(feature = "direct-serial")]
 mod direct_serial;
+#[cfg(feature = "direct-serial")]
+pub use direct_serial::SerialConnection;
+
+#[cfg(feature = "direct-serial")]
+mod autodetect;
+#[cfg(feature = "direct-serial")]
+pub use autodetect::{autodetect_serial, probe_serial, serial_ports, AUTODETECT_BAUD_RATES};
 
 #[cfg(feature = "signing")]
 use crate::SigningConfig;
 
 mod file;
 
+mod stdio;
+
+#[cfg(all(unix, feature = "unix-socket"))]
+mod unix;
+
+mod stream;
+pub use stream::StreamConnection;
+
+mod datagram;
+pub use datagram::{DatagramConnection, DatagramTransport};
+
+mod discovery;
+pub use discovery::{discover_devices, DeviceKind, DiscoveredDevice};
+
+mod request;
+pub(crate) use request::request_message_payload;
+pub use request::{request_message, RequestMessageError};
+
+#[cfg(feature = "signing")]
+mod provision;
+#[cfg(all(unix, feature = "signing"))]
+pub use provision::generate_secret_key;
+#[cfg(feature = "signing")]
+pub use provision::{provision_signing, ProvisionSigningError};
+
+mod multi;
+pub use multi::MultiConnection;
+
 /// A MAVLink connection
 pub trait MavConnection<M: Message> {
     /// Receive a mavlink message.
@@ -24,18 +75,49 @@ pub trait MavConnection<M: Message> {
     /// Blocks until a valid frame is received, ignoring invalid messages.
     fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError>;
 
+    /// Return the header information of the next valid frame without removing it from the
+    /// receive path, so it can be routed before being parsed.
+    ///
+    /// Frames that [`recv`](Self::recv) would drop afterwards, such as unknown messages or messages
+    /// rejected by the target filter, are reported as well. Connections that cannot peek return
+    /// [`io::ErrorKind::Unsupported`].
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        Err(crate::error::MessageReadError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
     /// Send a mavlink message
     fn send(&self, header: &MavHeader, data: &M) -> Result<usize, crate::error::MessageWriteError>;
 
     fn set_protocol_version(&mut self, version: MavlinkVersion);
     fn protocol_version(&self) -> MavlinkVersion;
 
+    /// Only deliver broadcasts and messages addressed to the component described by `filter`,
+    /// discarding traffic targeted elsewhere. Passing `None` disables filtering.
+    ///
+    /// Connections that don't filter what they receive ignore the filter.
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        let _ = filter;
+    }
+
+    /// Discard the frames sent by the systems ignored by `filter`, such as our own traffic
+    /// echoed back by a bridged link. Passing `None` disables filtering.
+    ///
+    /// Connections that don't filter what they receive ignore the filter.
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        let _ = filter;
+    }
+
     /// Write whole frame
     fn send_frame(&self, frame: &MavFrame<M>) -> Result<usize, crate::error::MessageWriteError> {
         self.send(&frame.header, &frame.msg)
     }
 
-    /// Read whole frame
+    /// Read whole frame, with the signature of a signed MAVLink 2 frame.
+    ///
+    /// The connections of this crate keep the signature, the default implementation builds the
+    /// frame from [`recv`](Self::recv) and leaves it empty.
     fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let (header, msg) = self.recv()?;
         let protocol_version = self.protocol_version();
@@ -43,6 +125,7 @@ pub trait MavConnection<M: Message> {
             header,
             msg,
             protocol_version,
+            signature: None,
         })
     }
 
@@ -52,6 +135,39 @@ pub trait MavConnection<M: Message> {
         self.send(&header, data)
     }
 
+    /// Receive the next valid frame without parsing its message, so it can be forwarded byte for
+    /// byte with [`send_raw`](Self::send_raw).
+    ///
+    /// Frames go through the protocol version and signature checks of the connection, but not
+    /// through its filters.
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        Err(crate::error::MessageReadError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    /// Write a frame unchanged, keeping its sequence number and signature
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let _ = frame;
+        Err(crate::error::MessageWriteError::Io(
+            io::ErrorKind::Unsupported.into(),
+        ))
+    }
+
+    /// Time a receive waits for data before failing with [`io::ErrorKind::WouldBlock`] or
+    /// [`io::ErrorKind::TimedOut`], `None` if it waits indefinitely
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Change the time a receive waits for data, initially
+    /// [`ConnectionOptions::read_timeout`]. Connections that cannot wait with a timeout return
+    /// [`io::ErrorKind::Unsupported`].
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let _ = timeout;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
     /// Setup secret key used for message signing, or disable message signing
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>);
@@ -66,15 +182,52 @@ pub trait MavConnection<M: Message> {
 ///  * `udpin:<addr>:<port>` to create a UDP server, listening for incoming packets
 ///  * `udpout:<addr>:<port>` to create a UDP client
 ///  * `udpbcast:<addr>:<port>` to create a UDP broadcast
-///  * `serial:<port>:<baudrate>` to create a serial connection
+///  * `serial:<port>:<baudrate>` to create a serial connection, optionally followed by a framing
+///    other than 8N1 such as `:8E1`, and by `:rtscts` or `:xonxoff` for flow control
 ///  * `file:<path>` to extract file data
+///  * `fileout:<path>` to append the sent messages to a telemetry log
+///  * `stdio:` to read from the standard input and write to the standard output
+///  * `unix:<path>` to connect to a Unix domain socket
+///  * `unixin:<path>` to create a Unix domain socket, waiting for a client to connect
 ///
 /// The type of the connection is determined at runtime based on the address type, so the
 /// connection is returned as a trait object.
 pub fn connect<M: Message + Sync + Send>(
     address: &str,
 ) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
-    ConnectionAddress::parse_address(address)?.connect::<M>()
+    let result =
+        ConnectionAddress::parse_address(address).and_then(|address| address.connect::<M>());
+    #[cfg(feature = "tracing")]
+    crate::instrument::connection_opened(address, &result);
+    result
+}
+
+/// Connect to a MAVLink node by address string like [`connect`], opening the socket or port with
+/// `options`.
+pub fn connect_with_options<M: Message + Sync + Send>(
+    address: &str,
+    options: ConnectionOptions,
+) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
+    let result = ConnectionAddress::parse_address(address)
+        .and_then(|address| address.with_options(options).connect::<M>());
+    #[cfg(feature = "tracing")]
+    crate::instrument::connection_opened(address, &result);
+    result
+}
+
+/// Returns every socket address the given address resolves to, to be tried in turn.
+#[cfg(feature = "tcp")]
+pub(crate) fn get_socket_addrs<T: std::net::ToSocketAddrs>(
+    address: &T,
+) -> Result<Vec<std::net::SocketAddr>, io::Error> {
+    let addrs: Vec<_> = address.to_socket_addrs()?.collect();
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Host address lookup failed",
+        ));
+    }
+    Ok(addrs)
 }
 
 /// Returns the socket address for the given address.
@@ -87,6 +240,18 @@ pub(crate) fn get_socket_addr<T: std::net::ToSocketAddrs>(
     ))
 }
 
+/// Description of a connection, built from an address string by
+/// [`ConnectionAddress::parse_address`] or directly with the builder methods of the connectables:
+///
+/// ```ignore
+/// let connection = TcpConnectable::new("127.0.0.1:5760".to_string(), true)
+///     .with_protocol_version(MavlinkVersion::V1)
+///     .with_options(ConnectionOptions {
+///         nodelay: Some(true),
+///         ..Default::default()
+///     })
+///     .connect::<MavMessage>()?;
+/// ```
 pub trait Connectable: Display {
     fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>>;
 }
@@ -101,6 +266,9 @@ impl Connectable for ConnectionAddress {
             Self::Udp(connectable) => connectable.connect::<M>(),
             Self::Serial(connectable) => connectable.connect::<M>(),
             Self::File(connectable) => connectable.connect::<M>(),
+            Self::Stdio(connectable) => connectable.connect::<M>(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Self::Unix(connectable) => connectable.connect::<M>(),
         }
     }
 }
@@ -0,0 +1,143 @@
+//! Receiving from several MAVLink connections at once
+
+use core::mem;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::io::ErrorKind;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::MessageReadError;
+use crate::{MavHeader, Message};
+
+use super::MavConnection;
+
+/// Number of received messages buffered per connection before its reader waits
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Read timeout given to the connections waiting indefinitely, so that their reader checks
+/// regularly whether the set was dropped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type Received<M> = (usize, Result<(MavHeader, M), MessageReadError>);
+
+/// A set of connections returning the next message from whichever connection receives one.
+///
+/// Each connection is read by a background thread, so the connections must be fully configured
+/// (protocol version, signing, target filter) before being added. Messages are tagged with the
+/// index returned by [`add`](Self::add), which also gives access to the connection to reply.
+///
+/// Dropping the set stops the threads and waits for them. Connections without a read timeout are
+/// given one to notice the stop, and a thread reading a connection that cannot wait with a
+/// timeout is left running until its connection receives something.
+pub struct MultiConnection<M: Message> {
+    connections: Vec<Arc<dyn MavConnection<M> + Sync + Send>>,
+    readers: Vec<Reader>,
+    stop: Arc<AtomicBool>,
+    sender: SyncSender<Received<M>>,
+    receiver: Receiver<Received<M>>,
+}
+
+struct Reader {
+    thread: JoinHandle<()>,
+    /// Whether the connection read by the thread has a read timeout, so that the thread notices
+    /// the stop
+    stoppable: bool,
+}
+
+impl<M: Message + Send + 'static> Default for MultiConnection<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message + Send + 'static> MultiConnection<M> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        Self {
+            connections: Vec::new(),
+            readers: Vec::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Start receiving from `connection` and return the index its messages are tagged with
+    pub fn add(&mut self, connection: Box<dyn MavConnection<M> + Sync + Send>) -> usize {
+        let index = self.connections.len();
+        let connection: Arc<dyn MavConnection<M> + Sync + Send> = Arc::from(connection);
+        self.connections.push(connection.clone());
+        let stoppable = match connection.read_timeout() {
+            Ok(Some(_)) => true,
+            Ok(None) => connection
+                .set_read_timeout(Some(STOP_POLL_INTERVAL))
+                .is_ok(),
+            Err(_) => false,
+        };
+
+        let sender = self.sender.clone();
+        let stop = self.stop.clone();
+        let thread = thread::spawn(move || loop {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let result = connection.recv();
+            let stop = match &result {
+                Err(MessageReadError::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    continue
+                }
+                // the connection is unusable after an I/O error
+                Err(MessageReadError::Io(_)) => true,
+                _ => false,
+            };
+            // the receiving side is gone once the set is dropped
+            if sender.send((index, result)).is_err() || stop {
+                break;
+            }
+        });
+        self.readers.push(Reader { thread, stoppable });
+        index
+    }
+
+    /// The connection added with index `index`
+    pub fn connection(&self, index: usize) -> Option<&(dyn MavConnection<M> + Sync + Send)> {
+        self.connections.get(index).map(|connection| &**connection)
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Receive the next message from any of the connections, tagged with the index of the
+    /// connection it came from, never returning while the set is empty.
+    ///
+    /// Blocks until a message is received. Errors are reported with the index of the failing
+    /// connection, a connection is no longer read after an I/O error.
+    pub fn recv_any(&self) -> (usize, Result<(MavHeader, M), MessageReadError>) {
+        self.receiver
+            .recv()
+            .expect("MultiConnection holds a sender of its own channel")
+    }
+}
+
+impl<M: Message> Drop for MultiConnection<M> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // drop the receiving side to release the readers waiting for room in the channel
+        let (_, receiver) = mpsc::sync_channel(0);
+        drop(mem::replace(&mut self.receiver, receiver));
+        for reader in self.readers.drain(..) {
+            if reader.stoppable {
+                let _ = reader.thread.join();
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Provisioning of the signing key of a remote system
+
+use core::fmt::{Display, Formatter};
+use std::error::Error;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use crate::bytes_mut::BytesMut;
+use crate::error::{MessageReadError, MessageWriteError, ParserError};
+use crate::{MavHeader, MavlinkVersion, Message, RawFrame, SigningConfig, MAVLINK_IFLAG_SIGNED};
+
+use super::MavConnection;
+
+const SETUP_SIGNING_ID: u32 = 256;
+
+/// Errors returned by [`provision_signing`]
+#[derive(Debug)]
+pub enum ProvisionSigningError {
+    /// Sending the `SETUP_SIGNING` failed
+    Write(MessageWriteError),
+    /// Receiving from the connection failed
+    Read(MessageReadError),
+    /// The dialect does not define `SETUP_SIGNING`
+    Parse(ParserError),
+    /// No frame signed with the new key was received from the target before the timeout elapsed
+    Timeout,
+}
+
+impl Display for ProvisionSigningError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Write(e) => write!(f, "Failed to send signing setup: {e}"),
+            Self::Read(e) => write!(f, "Failed to receive signed message: {e}"),
+            Self::Parse(e) => write!(f, "Failed to build signing setup: {e}"),
+            Self::Timeout => write!(f, "Timed out waiting for a message signed with the new key"),
+        }
+    }
+}
+
+impl Error for ProvisionSigningError {}
+
+impl From<MessageWriteError> for ProvisionSigningError {
+    fn from(e: MessageWriteError) -> Self {
+        Self::Write(e)
+    }
+}
+
+impl From<MessageReadError> for ProvisionSigningError {
+    fn from(e: MessageReadError) -> Self {
+        Self::Read(e)
+    }
+}
+
+impl From<ParserError> for ProvisionSigningError {
+    fn from(e: ParserError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Generate a random secret key for [`provision_signing`] from the random source of the
+/// operating system
+#[cfg(unix)]
+pub fn generate_secret_key() -> std::io::Result<[u8; 32]> {
+    use std::io::Read;
+
+    let mut key = [0u8; 32];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut key)?;
+    Ok(key)
+}
+
+/// Give `secret_key` to `target_system`/`target_component` with `SETUP_SIGNING`, then sign the
+/// traffic of `connection` with it and wait until the target signs with it as well.
+///
+/// `SETUP_SIGNING` is sent with the given `header` and the current signing state of the
+/// connection. The connection is then set up to sign its messages on `link_id` and to only
+/// accept signed frames. Provisioning succeeds once a frame from the target, usually its next
+/// heartbeat, passes verification with the new key.
+///
+/// The key isn't stored anywhere, it must be kept by the caller to sign later connections. On
+/// failure the connection keeps signing with the new key, since the target may have stored it.
+///
+/// ```ignore
+/// let key = generate_secret_key()?;
+/// provision_signing(&mut connection, &header, 1, 1, key, 0, Duration::from_secs(3))?;
+/// ```
+///
+/// Since [`MavConnection::recv_raw`] blocks, the timeout is only checked between received frames.
+pub fn provision_signing<M, C>(
+    connection: &mut C,
+    header: &MavHeader,
+    target_system: u8,
+    target_component: u8,
+    secret_key: [u8; 32],
+    link_id: u8,
+    timeout: Duration,
+) -> Result<(), ProvisionSigningError>
+where
+    M: Message,
+    C: MavConnection<M> + ?Sized,
+{
+    let config = SigningConfig::new(secret_key, link_id, true, false);
+    let setup = setup_signing_message::<M>(
+        target_system,
+        target_component,
+        &secret_key,
+        config.current_timestamp(),
+    )?;
+    connection.send(header, &setup)?;
+    connection.setup_signing(Some(config));
+
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let frame = match connection.recv_raw() {
+            Ok(frame) => frame,
+            // connections with a read timeout report it as an error, keep waiting until the deadline
+            Err(MessageReadError::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let info = frame.info();
+        let signed = match &frame {
+            RawFrame::V2(message) => message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED != 0,
+            RawFrame::V1(_) => false,
+        };
+        if signed
+            && info.header.system_id == target_system
+            && (target_component == 0 || info.header.component_id == target_component)
+        {
+            return Ok(());
+        }
+    }
+    Err(ProvisionSigningError::Timeout)
+}
+
+/// Build a `SETUP_SIGNING` in the dialect `M`
+fn setup_signing_message<M: Message>(
+    target_system: u8,
+    target_component: u8,
+    secret_key: &[u8; 32],
+    initial_timestamp: u64,
+) -> Result<M, ParserError> {
+    let mut payload = [0u8; 42];
+    let mut buf = BytesMut::new(&mut payload);
+    buf.put_u64_le(initial_timestamp);
+    buf.put_u8(target_system);
+    buf.put_u8(target_component);
+    buf.put_slice(secret_key);
+
+    M::parse(MavlinkVersion::V2, SETUP_SIGNING_ID, &payload)
+}
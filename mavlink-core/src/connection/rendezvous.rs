@@ -0,0 +1,133 @@
+//! NAT traversal of UDP connections through a rendezvous server
+//!
+//! Peers register with the server by regularly sending `MAVRDV:<session>` from the socket used
+//! for MAVLink traffic. The server answers with `MAVRDV-PEER:<address>`, the public address of
+//! the other peer of the session. Peers then send their traffic, and keepalives punching the
+//! NAT mappings, directly to each other.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+const REGISTER: &[u8] = b"MAVRDV:";
+const PEER: &[u8] = b"MAVRDV-PEER:";
+
+/// Rendezvous state of a UDP connection
+pub(crate) struct Rendezvous {
+    server: SocketAddr,
+    session: String,
+    peer: Mutex<Option<SocketAddr>>,
+}
+
+impl Rendezvous {
+    /// Start registering `socket` with `server`, every `keepalive` for as long as the returned
+    /// state is alive
+    pub(crate) fn start(
+        socket: UdpSocket,
+        server: SocketAddr,
+        session: String,
+        keepalive: Duration,
+    ) -> Arc<Self> {
+        let rendezvous = Arc::new(Self {
+            server,
+            session,
+            peer: Mutex::new(None),
+        });
+        let weak = Arc::downgrade(&rendezvous);
+        thread::spawn(move || send_keepalives(socket, weak, keepalive));
+        rendezvous
+    }
+
+    pub(crate) fn peer(&self) -> Option<SocketAddr> {
+        *self.peer.lock().unwrap()
+    }
+
+    /// Handle a datagram received from `address`, returning whether it belonged to the
+    /// rendezvous protocol rather than to the MAVLink stream
+    pub(crate) fn handle(&self, address: SocketAddr, datagram: &[u8]) -> bool {
+        if address == self.server {
+            if let Some(peer) = datagram.strip_prefix(PEER) {
+                if let Some(peer) = core::str::from_utf8(peer)
+                    .ok()
+                    .and_then(|peer| peer.parse().ok())
+                {
+                    *self.peer.lock().unwrap() = Some(peer);
+                }
+            }
+            true
+        } else {
+            datagram.starts_with(REGISTER)
+        }
+    }
+
+    fn register_message(&self) -> Vec<u8> {
+        [REGISTER, self.session.as_bytes()].concat()
+    }
+}
+
+fn send_keepalives(socket: UdpSocket, rendezvous: Weak<Rendezvous>, keepalive: Duration) {
+    while let Some(rendezvous) = rendezvous.upgrade() {
+        let message = rendezvous.register_message();
+        // keepalives are best effort, lost ones are sent again on the next round
+        let _ = socket.send_to(&message, rendezvous.server);
+        if let Some(peer) = rendezvous.peer() {
+            let _ = socket.send_to(&message, peer);
+        }
+        drop(rendezvous);
+        thread::sleep(keepalive);
+    }
+}
+
+/// Rendezvous server introducing the two peers of each session to each other.
+///
+/// Only the two most recently registered addresses of a session are kept, so a peer whose
+/// public address changed replaces its stale registration.
+pub struct RendezvousServer {
+    socket: UdpSocket,
+}
+
+impl RendezvousServer {
+    pub fn bind(address: &str) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(address)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Serve registrations until an error occurs
+    pub fn run(&self) -> io::Result<()> {
+        let mut sessions: HashMap<Vec<u8>, Vec<SocketAddr>> = HashMap::new();
+        let mut buffer = [0u8; 1500];
+        loop {
+            let (len, address) = self.socket.recv_from(&mut buffer)?;
+            let Some(session) = buffer[..len].strip_prefix(REGISTER) else {
+                continue;
+            };
+
+            // most recently registered address last
+            let peers = sessions.entry(session.to_vec()).or_default();
+            peers.retain(|peer| *peer != address);
+            peers.push(address);
+            if peers.len() > 2 {
+                peers.remove(0);
+            }
+
+            if let [first, second] = peers[..] {
+                self.introduce(first, second);
+                self.introduce(second, first);
+            }
+        }
+    }
+
+    fn introduce(&self, address: SocketAddr, peer: SocketAddr) {
+        let message = [PEER, peer.to_string().as_bytes()].concat();
+        // peers register again if the introduction is lost
+        let _ = self.socket.send_to(&message, address);
+    }
+}
@@ -0,0 +1,196 @@
+//! Reordering of received frames based on their per-source sequence numbers
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::MavHeader;
+
+/// Sequence distances at or above this value are considered to be in the past
+const HALF_SEQUENCE_SPACE: u8 = 128;
+
+struct PendingFrame<T> {
+    received: Instant,
+    header: MavHeader,
+    frame: T,
+}
+
+struct SourceState<T> {
+    next_sequence: u8,
+    pending: Vec<PendingFrame<T>>,
+}
+
+impl<T> SourceState<T> {
+    /// Distance of `sequence` ahead of the next expected sequence number
+    fn distance(&self, sequence: u8) -> u8 {
+        sequence.wrapping_sub(self.next_sequence)
+    }
+
+    /// Move all pending frames that continue the sequence to `ready`
+    fn drain(&mut self, ready: &mut VecDeque<(MavHeader, T)>) {
+        while let Some(index) = self
+            .pending
+            .iter()
+            .position(|pending| pending.header.sequence == self.next_sequence)
+        {
+            let pending = self.pending.swap_remove(index);
+            ready.push_back((pending.header, pending.frame));
+            self.next_sequence = self.next_sequence.wrapping_add(1);
+        }
+    }
+
+    /// Give up on the missing frames before the oldest pending one and deliver it
+    fn skip_gap(&mut self, ready: &mut VecDeque<(MavHeader, T)>) {
+        if let Some(oldest) = self
+            .pending
+            .iter()
+            .map(|pending| pending.header.sequence)
+            .min_by_key(|sequence| self.distance(*sequence))
+        {
+            self.next_sequence = oldest;
+            self.drain(ready);
+        }
+    }
+}
+
+/// Buffer delivering the frames of each source (system and component id) in sequence order.
+///
+/// Frames received ahead of a missing one are held back until the gap is filled, until more
+/// than `window` frames are held for that source or until the oldest held frame waited for
+/// `max_delay`. Late and duplicated frames are delivered as they arrive.
+pub(crate) struct ReorderBuffer<T> {
+    window: usize,
+    max_delay: Duration,
+    sources: HashMap<(u8, u8), SourceState<T>>,
+    ready: VecDeque<(MavHeader, T)>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub(crate) fn new(window: usize, max_delay: Duration) -> Self {
+        Self {
+            window,
+            max_delay,
+            sources: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, header: MavHeader, frame: T, now: Instant) {
+        let source = (header.system_id, header.component_id);
+        let Some(state) = self.sources.get_mut(&source) else {
+            self.sources.insert(
+                source,
+                SourceState {
+                    next_sequence: header.sequence.wrapping_add(1),
+                    pending: Vec::new(),
+                },
+            );
+            self.ready.push_back((header, frame));
+            return;
+        };
+
+        let distance = state.distance(header.sequence);
+        if distance == 0 {
+            self.ready.push_back((header, frame));
+            state.next_sequence = state.next_sequence.wrapping_add(1);
+            state.drain(&mut self.ready);
+        } else if distance < HALF_SEQUENCE_SPACE && self.window > 0 {
+            state.pending.push(PendingFrame {
+                received: now,
+                header,
+                frame,
+            });
+            if state.pending.len() > self.window {
+                state.skip_gap(&mut self.ready);
+            }
+        } else {
+            self.ready.push_back((header, frame));
+        }
+    }
+
+    /// Release the frames that have been held back for longer than the maximum delay
+    pub(crate) fn expire(&mut self, now: Instant) {
+        for state in self.sources.values_mut() {
+            while state
+                .pending
+                .iter()
+                .any(|pending| now.duration_since(pending.received) >= self.max_delay)
+            {
+                state.skip_gap(&mut self.ready);
+            }
+        }
+    }
+
+    /// Time at which the oldest held back frame is released, if any frame is held back
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        self.sources
+            .values()
+            .flat_map(|state| &state.pending)
+            .map(|pending| pending.received + self.max_delay)
+            .min()
+    }
+
+    pub(crate) fn front(&self) -> Option<&(MavHeader, T)> {
+        self.ready.front()
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<(MavHeader, T)> {
+        self.ready.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(sequence: u8) -> MavHeader {
+        MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence,
+        }
+    }
+
+    fn drain(buffer: &mut ReorderBuffer<u8>) -> Vec<u8> {
+        std::iter::from_fn(|| buffer.pop().map(|(_, frame)| frame)).collect()
+    }
+
+    #[test]
+    fn test_reorder() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(4, Duration::from_secs(1));
+        for sequence in [254, 0, 255, 1, 3, 2] {
+            buffer.push(header(sequence), sequence, now);
+        }
+        assert_eq!(drain(&mut buffer), [254, 255, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_window_overflow_skips_gap() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(2, Duration::from_secs(1));
+        for sequence in [0, 2, 3, 4] {
+            buffer.push(header(sequence), sequence, now);
+        }
+        assert_eq!(drain(&mut buffer), [0, 2, 3, 4]);
+
+        // the frame arriving after the gap was skipped is still delivered
+        buffer.push(header(1), 1, now);
+        assert_eq!(drain(&mut buffer), [1]);
+    }
+
+    #[test]
+    fn test_expire() {
+        let now = Instant::now();
+        let mut buffer = ReorderBuffer::new(8, Duration::from_millis(10));
+        for sequence in [0, 2, 3] {
+            buffer.push(header(sequence), sequence, now);
+        }
+        assert_eq!(drain(&mut buffer), [0]);
+
+        buffer.expire(now + Duration::from_millis(5));
+        assert_eq!(drain(&mut buffer), []);
+
+        buffer.expire(now + Duration::from_millis(10));
+        assert_eq!(drain(&mut buffer), [2, 3]);
+    }
+}
@@ -0,0 +1,276 @@
+use core::fmt::{Display, Formatter};
+use core::marker::PhantomData;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use crate::bytes::Bytes;
+use crate::bytes_mut::BytesMut;
+use crate::error::{MessageReadError, MessageWriteError, ParserError};
+use crate::{MavHeader, MavlinkVersion, Message, MessageData, MAX_FRAME_SIZE};
+
+use super::MavConnection;
+
+const COMMAND_LONG_ID: u32 = 76;
+const COMMAND_ACK_ID: u32 = 77;
+const MAV_CMD_REQUEST_MESSAGE: u16 = 512;
+const MAV_RESULT_ACCEPTED: u8 = 0;
+const MAV_RESULT_IN_PROGRESS: u8 = 5;
+
+/// Errors returned by [`request_message`] and the helpers built on it
+#[derive(Debug)]
+pub enum RequestMessageError {
+    /// Sending the `COMMAND_LONG` failed
+    Write(MessageWriteError),
+    /// Receiving from the connection failed
+    Read(MessageReadError),
+    /// The dialect does not define `COMMAND_LONG`, or the requested message could not be
+    /// converted into its typed representation
+    Parse(ParserError),
+    /// The target answered the request with a `COMMAND_ACK` result other than `MAV_RESULT_ACCEPTED`
+    /// or `MAV_RESULT_IN_PROGRESS`
+    Rejected { message_id: u32, result: u8 },
+    /// The ack and the requested message were not both received before the timeout elapsed
+    Timeout {
+        message_id: u32,
+        acked: bool,
+        received: bool,
+    },
+}
+
+impl Display for RequestMessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Write(e) => write!(f, "Failed to send message request: {e}"),
+            Self::Read(e) => write!(f, "Failed to receive requested message: {e}"),
+            Self::Parse(e) => write!(f, "Failed to build or decode requested message: {e}"),
+            Self::Rejected { message_id, result } => write!(
+                f,
+                "Request for message {message_id} was rejected with MAV_RESULT {result}"
+            ),
+            Self::Timeout {
+                message_id,
+                acked,
+                received,
+            } => write!(
+                f,
+                "Timed out waiting for message {message_id} (acked: {acked}, received: {received})"
+            ),
+        }
+    }
+}
+
+impl Error for RequestMessageError {}
+
+impl From<MessageWriteError> for RequestMessageError {
+    fn from(e: MessageWriteError) -> Self {
+        Self::Write(e)
+    }
+}
+
+impl From<MessageReadError> for RequestMessageError {
+    fn from(e: MessageReadError) -> Self {
+        Self::Read(e)
+    }
+}
+
+impl From<ParserError> for RequestMessageError {
+    fn from(e: ParserError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Request a single instance of message `D` from `target_system`/`target_component` using
+/// `MAV_CMD_REQUEST_MESSAGE`, and wait for it to arrive.
+///
+/// The request is sent as a `COMMAND_LONG` with the given `header`. The function then reads from
+/// the connection until both the matching `COMMAND_ACK` and an instance of `D` sent by the target
+/// have been received. A `COMMAND_ACK` with `MAV_RESULT_IN_PROGRESS` keeps waiting for the final
+/// ack, any other result than `MAV_RESULT_ACCEPTED` aborts the wait with
+/// [`RequestMessageError::Rejected`].
+///
+/// Each receive waits at most until the timeout, using [`MavConnection::set_read_timeout`], and
+/// the read timeout of the connection is restored afterwards. On connections without read
+/// timeouts, the timeout is only checked between received messages.
+pub fn request_message<M, D, C>(
+    connection: &C,
+    header: &MavHeader,
+    target_system: u8,
+    target_component: u8,
+    timeout: Duration,
+) -> Result<D, RequestMessageError>
+where
+    M: Message,
+    D: MessageData,
+    C: MavConnection<M> + ?Sized,
+{
+    let payload = request_message_payload(
+        connection,
+        header,
+        target_system,
+        target_component,
+        D::ID,
+        timeout,
+    )?;
+    Ok(D::deser(MavlinkVersion::V2, &payload)?)
+}
+
+/// Same as [`request_message`], but return the MAVLink 2 payload of the requested message so
+/// messages can be requested without their typed representation
+pub(crate) fn request_message_payload<M, C>(
+    connection: &C,
+    header: &MavHeader,
+    target_system: u8,
+    target_component: u8,
+    message_id: u32,
+    timeout: Duration,
+) -> Result<Vec<u8>, RequestMessageError>
+where
+    M: Message,
+    C: MavConnection<M> + ?Sized,
+{
+    let request = request_message_command::<M>(message_id, target_system, target_component)?;
+    connection.send(header, &request)?;
+
+    let deadline = Instant::now() + timeout;
+    let read_timeout = ReadTimeoutGuard::new(connection)?;
+    let mut acked = false;
+    let mut received = None;
+    loop {
+        if acked {
+            if let Some(payload) = received.take() {
+                return Ok(payload);
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(RequestMessageError::Timeout {
+                message_id,
+                acked,
+                received: received.is_some(),
+            });
+        }
+
+        read_timeout.limit(deadline)?;
+        let (msg_header, msg) = match connection.recv() {
+            Ok(received) => received,
+            // connections with a read timeout report it as an error, keep waiting until the deadline
+            Err(MessageReadError::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if msg_header.system_id != target_system
+            || (target_component != 0 && msg_header.component_id != target_component)
+        {
+            continue;
+        }
+
+        let mut payload = [0u8; MAX_FRAME_SIZE];
+        let len = msg.ser(MavlinkVersion::V2, &mut payload);
+        match msg.message_id() {
+            COMMAND_ACK_ID => {
+                match request_message_ack_result(&payload[..len], header) {
+                    Some(MAV_RESULT_ACCEPTED) => acked = true,
+                    // the final ack follows once the target is done
+                    Some(MAV_RESULT_IN_PROGRESS) | None => {}
+                    Some(result) => {
+                        return Err(RequestMessageError::Rejected { message_id, result })
+                    }
+                }
+            }
+            id if id == message_id => {
+                received = Some(payload[..len].to_vec());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Restores the read timeout of a connection limited by [`ReadTimeoutGuard::limit`] when dropped
+struct ReadTimeoutGuard<'a, M: Message, C: MavConnection<M> + ?Sized> {
+    connection: &'a C,
+    /// Read timeout to restore, `None` if the connection has no read timeout
+    previous: Option<Option<Duration>>,
+    _message: PhantomData<fn(M)>,
+}
+
+impl<'a, M: Message, C: MavConnection<M> + ?Sized> ReadTimeoutGuard<'a, M, C> {
+    fn new(connection: &'a C) -> Result<Self, MessageReadError> {
+        let previous = match connection.read_timeout() {
+            Ok(timeout) => Some(timeout),
+            Err(e) if e.kind() == ErrorKind::Unsupported => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            connection,
+            previous,
+            _message: PhantomData,
+        })
+    }
+
+    /// Make the next receive return by `deadline`, or by the previous read timeout if earlier
+    fn limit(&self, deadline: Instant) -> Result<(), MessageReadError> {
+        let Some(previous) = self.previous else {
+            return Ok(());
+        };
+        // a zero timeout would disable it
+        let remaining = deadline
+            .saturating_duration_since(Instant::now())
+            .max(Duration::from_millis(1));
+        let timeout = previous.map_or(remaining, |previous| previous.min(remaining));
+        Ok(self.connection.set_read_timeout(Some(timeout))?)
+    }
+}
+
+impl<M: Message, C: MavConnection<M> + ?Sized> Drop for ReadTimeoutGuard<'_, M, C> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous {
+            let _ = self.connection.set_read_timeout(previous);
+        }
+    }
+}
+
+/// Build a `COMMAND_LONG` carrying `MAV_CMD_REQUEST_MESSAGE` for `message_id` in the dialect `M`
+pub(crate) fn request_message_command<M: Message>(
+    message_id: u32,
+    target_system: u8,
+    target_component: u8,
+) -> Result<M, ParserError> {
+    let mut payload = [0u8; 33];
+    let mut buf = BytesMut::new(&mut payload);
+    // param1 holds the requested message id, param2..param7 are unused
+    buf.put_f32_le(message_id as f32);
+    for _ in 0..6 {
+        buf.put_f32_le(0.0);
+    }
+    buf.put_u16_le(MAV_CMD_REQUEST_MESSAGE);
+    buf.put_u8(target_system);
+    buf.put_u8(target_component);
+    buf.put_u8(0); // confirmation
+
+    M::parse(MavlinkVersion::V2, COMMAND_LONG_ID, &payload)
+}
+
+/// Extract the result of a `COMMAND_ACK` payload if it answers a `MAV_CMD_REQUEST_MESSAGE`
+/// sent with `header`
+fn request_message_ack_result(payload: &[u8], header: &MavHeader) -> Option<u8> {
+    // v2 payloads are truncated, pad the extension fields with zeros
+    let mut padded = [0u8; 10];
+    let len = payload.len().min(padded.len());
+    padded[..len].copy_from_slice(&payload[..len]);
+
+    let mut buf = Bytes::new(&padded);
+    let command = buf.get_u16_le();
+    let result = buf.get_u8();
+    let _progress = buf.get_u8();
+    let _result_param2 = buf.get_i32_le();
+    let target_system = buf.get_u8();
+    let target_component = buf.get_u8();
+
+    // the targets are an extension, acks from older implementations leave them unset
+    let for_us = (target_system == 0 || target_system == header.system_id)
+        && (target_component == 0 || target_component == header.component_id);
+    (command == MAV_CMD_REQUEST_MESSAGE && for_us).then_some(result)
+}
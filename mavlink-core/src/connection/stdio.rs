@@ -0,0 +1,14 @@
+//! MAVLink connection over the standard input and output of the process
+
+use std::io;
+
+use crate::connectable::StdioConnectable;
+use crate::Message;
+
+use super::{Connectable, MavConnection, StreamConnection};
+
+impl Connectable for StdioConnectable {
+    fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
+        Ok(Box::new(StreamConnection::new(io::stdin(), io::stdout())))
+    }
+}
@@ -0,0 +1,168 @@
+//! MAVLink connection over arbitrary byte streams
+
+use crate::connection::MavConnection;
+use crate::error::{MessageReadError, MessageWriteError};
+use crate::peek_reader::PeekReader;
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
+use core::ops::DerefMut;
+use std::io::{Read, Write};
+use std::sync::Mutex;
+
+#[cfg(not(feature = "signing"))]
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
+#[cfg(feature = "signing")]
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
+
+/// A [`MavConnection`] over any pair of byte streams, such as PTYs, SSH channels or
+/// radios exposed as streams.
+///
+/// The reader and writer may be two halves of the same stream or two unrelated streams.
+pub struct StreamConnection<R: Read, W: Write> {
+    reader: Mutex<PeekReader<R>>,
+    writer: Mutex<StreamWrite<W>>,
+    protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+    #[cfg(feature = "signing")]
+    signing_data: Option<SigningData>,
+}
+
+struct StreamWrite<W: Write> {
+    stream: W,
+    sequence: u8,
+}
+
+impl<R: Read, W: Write> StreamConnection<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader: Mutex::new(PeekReader::new(reader)),
+            writer: Mutex::new(StreamWrite {
+                stream: writer,
+                sequence: 0,
+            }),
+            protocol_version: MavlinkVersion::V2,
+            target_filter: None,
+            source_filter: None,
+            #[cfg(feature = "signing")]
+            signing_data: None,
+        }
+    }
+}
+
+impl<M: Message, R: Read, W: Write> MavConnection<M> for StreamConnection<R, W> {
+    fn recv(&self) -> Result<(MavHeader, M), MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result = read_versioned_frame(reader.deref_mut(), self.protocol_version);
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_signed(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            );
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                // invalid frames are skipped, errors of the underlying stream are reported
+                Err(MessageReadError::Parse(_)) => {}
+                result => return result,
+            }
+        }
+    }
+
+    fn send(&self, header: &MavHeader, data: &M) -> Result<usize, MessageWriteError> {
+        let mut lock = self.writer.lock().unwrap();
+        let state = &mut *lock;
+
+        let header = MavHeader {
+            sequence: state.sequence,
+            system_id: header.system_id,
+            component_id: header.component_id,
+        };
+
+        state.sequence = state.sequence.wrapping_add(1);
+        #[cfg(not(feature = "signing"))]
+        let len = write_versioned_msg(&mut state.stream, self.protocol_version, header, data)?;
+        #[cfg(feature = "signing")]
+        let len = write_versioned_msg_signed(
+            &mut state.stream,
+            self.protocol_version,
+            header,
+            data,
+            self.signing_data.as_ref(),
+        )?;
+        // buffered streams would otherwise hold back the frame
+        state.stream.flush()?;
+        Ok(len)
+    }
+
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = read_versioned_raw_message::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, MessageWriteError> {
+        let mut lock = self.writer.lock().unwrap();
+        let bytes = frame.raw_bytes();
+        lock.stream.write_all(bytes)?;
+        lock.stream.flush()?;
+        Ok(bytes.len())
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        #[cfg(not(feature = "signing"))]
+        let result = peek_versioned_frame_info::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
+    fn set_protocol_version(&mut self, version: MavlinkVersion) {
+        self.protocol_version = version;
+    }
+
+    fn protocol_version(&self) -> MavlinkVersion {
+        self.protocol_version
+    }
+
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    #[cfg(feature = "signing")]
+    fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
+        self.signing_data = signing_data.map(SigningData::from_config)
+    }
+}
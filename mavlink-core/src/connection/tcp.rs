@@ -1,64 +1,104 @@
 //! TCP MAVLink connection
 
-use crate::connectable::TcpConnectable;
+use crate::connectable::{ConnectionOptions, TcpConnectable};
 use crate::connection::MavConnection;
+use crate::error::MessageReadError;
 use crate::peek_reader::PeekReader;
-use crate::{MavHeader, MavlinkVersion, Message};
+use crate::{
+    MavFrame, MavFrameInfo, MavHeader, MavlinkVersion, Message, RawFrame, SourceFilter,
+    TargetFilter,
+};
 use core::ops::DerefMut;
-use std::io;
+use std::io::{self, Write};
 use std::net::ToSocketAddrs;
-use std::net::{TcpListener, TcpStream};
-use std::sync::Mutex;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use super::{get_socket_addr, Connectable};
+use socket2::SockRef;
+
+use super::coalesce::Coalescer;
+use super::{get_socket_addrs, Connectable};
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg, write_versioned_msg};
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
 
 #[cfg(feature = "signing")]
-use crate::{read_versioned_msg_signed, write_versioned_msg_signed, SigningConfig, SigningData};
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
 
-pub fn tcpout<T: ToSocketAddrs>(address: T) -> io::Result<TcpConnection> {
-    let addr = get_socket_addr(&address)?;
+pub fn tcpout<T: ToSocketAddrs>(
+    address: T,
+    options: &ConnectionOptions,
+) -> io::Result<TcpConnection> {
+    let addrs = get_socket_addrs(&address)?;
 
-    let socket = TcpStream::connect(addr)?;
-    socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+    let socket = match options.connect_timeout {
+        Some(timeout) => connect_timeout(&addrs, timeout)?,
+        None => TcpStream::connect(&addrs[..])?,
+    };
+    let options = ConnectionOptions {
+        read_timeout: options.read_timeout.or(Some(Duration::from_millis(100))),
+        ..options.clone()
+    };
+    apply_options(&socket, &options)?;
 
-    Ok(TcpConnection {
-        reader: Mutex::new(PeekReader::new(socket.try_clone()?)),
-        writer: Mutex::new(TcpWrite {
-            socket,
-            sequence: 0,
-        }),
-        protocol_version: MavlinkVersion::V2,
-        #[cfg(feature = "signing")]
-        signing_data: None,
-    })
+    TcpConnection::new(socket, None, options)
+}
+
+/// Listen on `address`, blocking until a client connects.
+///
+/// When the client disconnects, receiving blocks until the next client is accepted and carries on
+/// with it. Messages sent in the meantime fail or are lost.
+pub fn tcpin<T: ToSocketAddrs>(
+    address: T,
+    options: &ConnectionOptions,
+) -> io::Result<TcpConnection> {
+    let addrs = get_socket_addrs(&address)?;
+    let listener = TcpListener::bind(&addrs[..])?;
+    let socket = accept(&listener)?;
+    apply_options(&socket, options)?;
+    TcpConnection::new(socket, Some(listener), options.clone())
+}
+
+/// Connect to the first of `addrs` accepting the connection within `timeout`, like
+/// [`TcpStream::connect`] does without a timeout
+fn connect_timeout(addrs: &[SocketAddr], timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No address to connect to")))
 }
 
-pub fn tcpin<T: ToSocketAddrs>(address: T) -> io::Result<TcpConnection> {
-    let addr = get_socket_addr(&address)?;
-    let listener = TcpListener::bind(addr)?;
+fn apply_options(socket: &TcpStream, options: &ConnectionOptions) -> io::Result<()> {
+    socket.set_read_timeout(options.read_timeout)?;
+    socket.set_write_timeout(options.write_timeout)?;
+    if let Some(nodelay) = options.nodelay {
+        socket.set_nodelay(nodelay)?;
+    }
+    options.apply_buffer_sizes(SockRef::from(socket))
+}
 
-    //For now we only accept one incoming stream: this blocks until we get one
+/// Accept the next client, retrying after failed attempts
+fn accept(listener: &TcpListener) -> io::Result<TcpStream> {
     for incoming in listener.incoming() {
         match incoming {
-            Ok(socket) => {
-                return Ok(TcpConnection {
-                    reader: Mutex::new(PeekReader::new(socket.try_clone()?)),
-                    writer: Mutex::new(TcpWrite {
-                        socket,
-                        sequence: 0,
-                    }),
-                    protocol_version: MavlinkVersion::V2,
-                    #[cfg(feature = "signing")]
-                    signing_data: None,
-                })
-            }
+            Ok(socket) => return Ok(socket),
             Err(e) => {
-                //TODO don't println in lib
-                println!("listener err: {e}");
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "failed to accept incoming connection");
+                #[cfg(not(feature = "tracing"))]
+                let _ = e;
             }
         }
     }
@@ -68,14 +108,101 @@ pub fn tcpin<T: ToSocketAddrs>(address: T) -> io::Result<TcpConnection> {
     ))
 }
 
+/// Errors reported when the other end of the connection went away
+fn is_disconnection(error: &MessageReadError) -> bool {
+    matches!(
+        error,
+        MessageReadError::Io(e) if matches!(
+            e.kind(),
+            io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+        )
+    )
+}
+
 pub struct TcpConnection {
     reader: Mutex<PeekReader<TcpStream>>,
     writer: Mutex<TcpWrite>,
+    /// Listener of a server connection, accepting a new client after a disconnection
+    listener: Option<TcpListener>,
+    /// Options applied to the sockets of new clients
+    options: ConnectionOptions,
+    coalescer: Option<Coalescer<()>>,
+    /// Socket the coalesced batches are written to, replaced with the writer's one
+    coalesced_socket: Option<Arc<Mutex<TcpStream>>>,
     protocol_version: MavlinkVersion,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
 
+/// Largest batch of coalesced frames written at once
+const MAX_BATCH_SIZE: usize = 16 * 1024;
+
+impl TcpConnection {
+    fn new(
+        socket: TcpStream,
+        listener: Option<TcpListener>,
+        options: ConnectionOptions,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            reader: Mutex::new(PeekReader::new(socket.try_clone()?)),
+            writer: Mutex::new(TcpWrite {
+                socket,
+                sequence: 0,
+            }),
+            listener,
+            options,
+            coalescer: None,
+            coalesced_socket: None,
+            protocol_version: MavlinkVersion::V2,
+            target_filter: None,
+            source_filter: None,
+            #[cfg(feature = "signing")]
+            signing_data: None,
+        })
+    }
+
+    fn with_write_coalescing(mut self, window: Duration) -> io::Result<Self> {
+        let socket = Arc::new(Mutex::new(
+            self.writer.get_mut().unwrap().socket.try_clone()?,
+        ));
+        self.coalesced_socket = Some(socket.clone());
+        self.coalescer = Some(Coalescer::new(window, MAX_BATCH_SIZE, move |_, batch| {
+            socket.lock().unwrap().write_all(batch)
+        }));
+        Ok(self)
+    }
+
+    /// Whether `error` ended the connection with a client that can be replaced by a new one
+    fn should_reaccept(&self, error: &MessageReadError) -> bool {
+        self.listener.is_some() && is_disconnection(error)
+    }
+
+    /// Block until a new client connects, then read from and write to it
+    fn reaccept(&self, reader: &mut PeekReader<TcpStream>) -> io::Result<()> {
+        let Some(listener) = &self.listener else {
+            return Ok(());
+        };
+        let socket = accept(listener)?;
+        apply_options(&socket, &self.options)?;
+        *reader = PeekReader::new(socket.try_clone()?);
+        let mut writer = self.writer.lock().unwrap();
+        if let Some(coalescer) = &self.coalescer {
+            // the frames queued for the previous client are not sent to the new one
+            coalescer.clear();
+        }
+        if let Some(coalesced_socket) = &self.coalesced_socket {
+            *coalesced_socket.lock().unwrap() = socket.try_clone()?;
+        }
+        writer.socket = socket;
+        Ok(())
+    }
+}
+
 struct TcpWrite {
     socket: TcpStream,
     sequence: u8,
@@ -83,16 +210,28 @@ struct TcpWrite {
 
 impl<M: Message> MavConnection<M> for TcpConnection {
     fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let mut reader = self.reader.lock().unwrap();
-        #[cfg(not(feature = "signing"))]
-        let result = read_versioned_msg(reader.deref_mut(), self.protocol_version);
-        #[cfg(feature = "signing")]
-        let result = read_versioned_msg_signed(
-            reader.deref_mut(),
-            self.protocol_version,
-            self.signing_data.as_ref(),
-        );
-        result
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result = read_versioned_frame(reader.deref_mut(), self.protocol_version);
+            #[cfg(feature = "signing")]
+            let result = read_versioned_frame_signed(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            );
+            match result {
+                Err(e) if self.should_reaccept(&e) => self.reaccept(&mut reader)?,
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                result => return result,
+            }
+        }
     }
 
     fn send(&self, header: &MavHeader, data: &M) -> Result<usize, crate::error::MessageWriteError> {
@@ -105,6 +244,21 @@ impl<M: Message> MavConnection<M> for TcpConnection {
         };
 
         lock.sequence = lock.sequence.wrapping_add(1);
+        if let Some(coalescer) = &self.coalescer {
+            let mut buf = Vec::new();
+            #[cfg(not(feature = "signing"))]
+            let len = write_versioned_msg(&mut buf, self.protocol_version, header, data)?;
+            #[cfg(feature = "signing")]
+            let len = write_versioned_msg_signed(
+                &mut buf,
+                self.protocol_version,
+                header,
+                data,
+                self.signing_data.as_ref(),
+            )?;
+            coalescer.push((), &buf)?;
+            return Ok(len);
+        }
         #[cfg(not(feature = "signing"))]
         let result = write_versioned_msg(&mut lock.socket, self.protocol_version, header, data);
         #[cfg(feature = "signing")]
@@ -118,6 +272,54 @@ impl<M: Message> MavConnection<M> for TcpConnection {
         result
     }
 
+    fn recv_raw(&self) -> Result<RawFrame, MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result =
+                read_versioned_raw_message::<M, _>(reader.deref_mut(), self.protocol_version);
+            #[cfg(feature = "signing")]
+            let result = read_versioned_raw_message_signed::<M, _>(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            );
+            match result {
+                Err(e) if self.should_reaccept(&e) => self.reaccept(&mut reader)?,
+                result => return result,
+            }
+        }
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let mut lock = self.writer.lock().unwrap();
+        let bytes = frame.raw_bytes();
+        match &self.coalescer {
+            Some(coalescer) => coalescer.push((), bytes)?,
+            None => lock.socket.write_all(bytes)?,
+        }
+        Ok(bytes.len())
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        loop {
+            #[cfg(not(feature = "signing"))]
+            let result =
+                peek_versioned_frame_info::<M, _>(reader.deref_mut(), self.protocol_version);
+            #[cfg(feature = "signing")]
+            let result = peek_versioned_frame_info_signed::<M, _>(
+                reader.deref_mut(),
+                self.protocol_version,
+                self.signing_data.as_ref(),
+            );
+            match result {
+                Err(e) if self.should_reaccept(&e) => self.reaccept(&mut reader)?,
+                result => return result,
+            }
+        }
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -126,6 +328,23 @@ impl<M: Message> MavConnection<M> for TcpConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    // the reader and writer share the socket, the writer is not locked while receiving
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.writer.lock().unwrap().socket.read_timeout()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.lock().unwrap().socket.set_read_timeout(timeout)
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -134,11 +353,23 @@ impl<M: Message> MavConnection<M> for TcpConnection {
 
 impl Connectable for TcpConnectable {
     fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
+        if self.multiple_clients {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Multiple TCP clients are only supported on async connections",
+            ));
+        }
         let conn = if self.is_out {
-            tcpout(&self.address)
+            tcpout(&self.address, &self.options)
         } else {
-            tcpin(&self.address)
+            tcpin(&self.address, &self.options)
+        };
+        let conn = match self.write_coalescing {
+            Some(window) => conn?.with_write_coalescing(window)?,
+            None => conn?,
         };
-        Ok(Box::new(conn?))
+        let mut conn: Box<dyn MavConnection<M> + Sync + Send> = Box::new(conn);
+        conn.set_protocol_version(self.protocol_version);
+        Ok(conn)
     }
 }
@@ -4,25 +4,54 @@ use std::collections::VecDeque;
 
 use crate::connectable::{UdpConnectable, UdpMode};
 use crate::connection::MavConnection;
+use crate::error::MessageReadError;
 use crate::peek_reader::PeekReader;
-use crate::{MavHeader, MavlinkVersion, Message};
+use crate::{
+    MavFrame, MavFrameInfo, MavFrameSignature, MavHeader, MavlinkVersion, Message, RawFrame,
+    SourceFilter, TargetFilter,
+};
 use core::ops::DerefMut;
 use std::io::{self, Read};
 use std::net::{SocketAddr, UdpSocket};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use socket2::SockRef;
+
+use super::coalesce::Coalescer;
+use super::rendezvous::Rendezvous;
+use super::reorder::ReorderBuffer;
 use super::{get_socket_addr, Connectable};
 
 #[cfg(not(feature = "signing"))]
-use crate::{read_versioned_msg, write_versioned_msg};
+use crate::{
+    peek_versioned_frame_info, read_versioned_frame, read_versioned_raw_message,
+    write_versioned_msg,
+};
 
 #[cfg(feature = "signing")]
-use crate::{read_versioned_msg_signed, write_versioned_msg_signed, SigningConfig, SigningData};
+use crate::{
+    peek_versioned_frame_info_signed, read_versioned_frame_signed,
+    read_versioned_raw_message_signed, write_versioned_msg_signed, SigningConfig, SigningData,
+};
 
 struct UdpRead {
     socket: UdpSocket,
+    /// Read timeout currently set on the socket
+    timeout: Option<Duration>,
     buffer: VecDeque<u8>,
     last_recv_address: Option<SocketAddr>,
+    rendezvous: Option<Arc<Rendezvous>>,
+}
+
+impl UdpRead {
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout != self.timeout {
+            self.socket.set_read_timeout(timeout)?;
+            self.timeout = timeout;
+        }
+        Ok(())
+    }
 }
 
 const MTU_SIZE: usize = 1500;
@@ -32,7 +61,13 @@ impl Read for UdpRead {
             self.buffer.read(buf)
         } else {
             let mut read_buffer = [0u8; MTU_SIZE];
-            let (n_buffer, address) = self.socket.recv_from(&mut read_buffer)?;
+            let (n_buffer, address) = loop {
+                let (n_buffer, address) = self.socket.recv_from(&mut read_buffer)?;
+                match &self.rendezvous {
+                    Some(rendezvous) if rendezvous.handle(address, &read_buffer[..n_buffer]) => {}
+                    _ => break (n_buffer, address),
+                }
+            };
             let n = (&read_buffer[0..n_buffer]).read(buf)?;
             self.buffer.extend(&read_buffer[n..n_buffer]);
 
@@ -46,32 +81,75 @@ struct UdpWrite {
     socket: UdpSocket,
     dest: Option<SocketAddr>,
     sequence: u8,
+    rendezvous: Option<Arc<Rendezvous>>,
 }
 
+/// Frame waiting to be delivered in sequence order, stored as message id, v2 payload and signature
+type ReorderedFrame = (u32, Vec<u8>, Option<MavFrameSignature>);
+
 pub struct UdpConnection {
     reader: Mutex<PeekReader<UdpRead>>,
     writer: Mutex<UdpWrite>,
     protocol_version: MavlinkVersion,
     server: bool,
+    /// Time a receive waits for a frame, kept apart from the timeout of the socket, which also
+    /// wakes up the receive to release the frames held back by the reorder buffer
+    read_timeout: Mutex<Option<Duration>>,
+    target_filter: Option<TargetFilter>,
+    source_filter: Option<SourceFilter>,
+    reorder: Option<Mutex<ReorderBuffer<ReorderedFrame>>>,
+    coalescer: Option<Coalescer<SocketAddr>>,
     #[cfg(feature = "signing")]
     signing_data: Option<SigningData>,
 }
 
+/// Largest batch of coalesced frames, the payload of a UDP datagram fitting in an Ethernet frame
+const MAX_BATCH_SIZE: usize = 1472;
+
 impl UdpConnection {
-    fn new(socket: UdpSocket, server: bool, dest: Option<SocketAddr>) -> io::Result<Self> {
+    fn new(
+        socket: UdpSocket,
+        server: bool,
+        dest: Option<SocketAddr>,
+        reorder: Option<(usize, Duration)>,
+        rendezvous: Option<Arc<Rendezvous>>,
+        write_coalescing: Option<Duration>,
+        read_timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        socket.set_read_timeout(read_timeout)?;
+        let coalescer = match write_coalescing {
+            Some(window) => {
+                let socket = socket.try_clone()?;
+                Some(Coalescer::new(
+                    window,
+                    MAX_BATCH_SIZE,
+                    move |dest, batch| socket.send_to(batch, dest).map(|_| ()),
+                ))
+            }
+            None => None,
+        };
         Ok(Self {
             server,
             reader: Mutex::new(PeekReader::new(UdpRead {
                 socket: socket.try_clone()?,
+                timeout: read_timeout,
                 buffer: VecDeque::new(),
                 last_recv_address: None,
+                rendezvous: rendezvous.clone(),
             })),
             writer: Mutex::new(UdpWrite {
                 socket,
                 dest,
                 sequence: 0,
+                rendezvous,
             }),
             protocol_version: MavlinkVersion::V2,
+            read_timeout: Mutex::new(read_timeout),
+            target_filter: None,
+            source_filter: None,
+            reorder: reorder
+                .map(|(window, max_delay)| Mutex::new(ReorderBuffer::new(window, max_delay))),
+            coalescer,
             #[cfg(feature = "signing")]
             signing_data: None,
         })
@@ -80,13 +158,51 @@ impl UdpConnection {
 
 impl<M: Message> MavConnection<M> for UdpConnection {
     fn recv(&self) -> Result<(MavHeader, M), crate::error::MessageReadError> {
+        self.recv_frame().map(|frame| (frame.header, frame.msg))
+    }
+
+    fn recv_frame(&self) -> Result<MavFrame<M>, crate::error::MessageReadError> {
         let mut reader = self.reader.lock().unwrap();
+        let deadline = self
+            .read_timeout
+            .lock()
+            .unwrap()
+            .map(|timeout| Instant::now() + timeout);
 
         loop {
+            let mut expiry = None;
+            if let Some(reorder) = &self.reorder {
+                let mut reorder = reorder.lock().unwrap();
+                reorder.expire(Instant::now());
+                if let Some((header, (message_id, payload, signature))) = reorder.pop() {
+                    return Ok(MavFrame {
+                        header,
+                        msg: M::parse(MavlinkVersion::V2, message_id, &payload)?,
+                        protocol_version: self.protocol_version,
+                        signature,
+                    });
+                }
+                expiry = reorder.next_expiry();
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(io::Error::from(io::ErrorKind::TimedOut).into());
+            }
+
+            // wait until the deadline of the receive or the release of a held back frame
+            let wake_up = match (deadline, expiry) {
+                (Some(deadline), Some(expiry)) => Some(deadline.min(expiry)),
+                (deadline, expiry) => deadline.or(expiry),
+            };
+            reader.reader_mut().set_timeout(wake_up.map(|wake_up| {
+                wake_up
+                    .saturating_duration_since(Instant::now())
+                    .max(Duration::from_millis(1))
+            }))?;
+
             #[cfg(not(feature = "signing"))]
-            let result = read_versioned_msg(reader.deref_mut(), self.protocol_version);
+            let result = read_versioned_frame::<M, _>(reader.deref_mut(), self.protocol_version);
             #[cfg(feature = "signing")]
-            let result = read_versioned_msg_signed(
+            let result = read_versioned_frame_signed::<M, _>(
                 reader.deref_mut(),
                 self.protocol_version,
                 self.signing_data.as_ref(),
@@ -96,8 +212,33 @@ impl<M: Message> MavConnection<M> for UdpConnection {
                     self.writer.lock().unwrap().dest = addr;
                 }
             }
-            if let ok @ Ok(..) = result {
-                return ok;
+            match result {
+                Ok(frame)
+                    if !SourceFilter::allows(self.source_filter.as_ref(), &frame.header)
+                        || !TargetFilter::allows(self.target_filter.as_ref(), &frame.msg) => {}
+                Ok(frame) => match &self.reorder {
+                    Some(reorder) => {
+                        let mut payload = [0u8; 255];
+                        let len = frame.msg.ser(MavlinkVersion::V2, &mut payload);
+                        reorder.lock().unwrap().push(
+                            frame.header,
+                            (
+                                frame.msg.message_id(),
+                                payload[..len].to_vec(),
+                                frame.signature,
+                            ),
+                            Instant::now(),
+                        );
+                    }
+                    None => return Ok(frame),
+                },
+                // woken up to release the held back frames before the deadline
+                Err(MessageReadError::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) && !deadline.is_some_and(|deadline| Instant::now() >= deadline) => {}
+                Err(e) => return Err(e),
             }
         }
     }
@@ -114,7 +255,11 @@ impl<M: Message> MavConnection<M> for UdpConnection {
 
         state.sequence = state.sequence.wrapping_add(1);
 
-        let len = if let Some(addr) = state.dest {
+        let dest = match &state.rendezvous {
+            Some(rendezvous) => rendezvous.peer(),
+            None => state.dest,
+        };
+        let len = if let Some(addr) = dest {
             let mut buf = Vec::new();
             #[cfg(not(feature = "signing"))]
             write_versioned_msg(&mut buf, self.protocol_version, header, data)?;
@@ -126,7 +271,13 @@ impl<M: Message> MavConnection<M> for UdpConnection {
                 data,
                 self.signing_data.as_ref(),
             )?;
-            state.socket.send_to(&buf, addr)?
+            match &self.coalescer {
+                Some(coalescer) => {
+                    coalescer.push(addr, &buf)?;
+                    buf.len()
+                }
+                None => state.socket.send_to(&buf, addr)?,
+            }
         } else {
             0
         };
@@ -134,6 +285,69 @@ impl<M: Message> MavConnection<M> for UdpConnection {
         Ok(len)
     }
 
+    fn recv_raw(&self) -> Result<RawFrame, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        let timeout = *self.read_timeout.lock().unwrap();
+        reader.reader_mut().set_timeout(timeout)?;
+        #[cfg(not(feature = "signing"))]
+        let result = read_versioned_raw_message::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = read_versioned_raw_message_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        if self.server {
+            if let addr @ Some(_) = reader.reader_ref().last_recv_address {
+                self.writer.lock().unwrap().dest = addr;
+            }
+        }
+        result
+    }
+
+    fn send_raw(&self, frame: &RawFrame) -> Result<usize, crate::error::MessageWriteError> {
+        let state = self.writer.lock().unwrap();
+        let dest = match &state.rendezvous {
+            Some(rendezvous) => rendezvous.peer(),
+            None => state.dest,
+        };
+        let Some(addr) = dest else {
+            return Ok(0);
+        };
+        let bytes = frame.raw_bytes();
+        match &self.coalescer {
+            Some(coalescer) => {
+                coalescer.push(addr, bytes)?;
+                Ok(bytes.len())
+            }
+            None => Ok(state.socket.send_to(bytes, addr)?),
+        }
+    }
+
+    fn peek(&self) -> Result<MavFrameInfo, crate::error::MessageReadError> {
+        let mut reader = self.reader.lock().unwrap();
+        let timeout = *self.read_timeout.lock().unwrap();
+        reader.reader_mut().set_timeout(timeout)?;
+        if let Some(reorder) = &self.reorder {
+            if let Some((header, (message_id, ..))) = reorder.lock().unwrap().front() {
+                return Ok(MavFrameInfo {
+                    header: *header,
+                    message_id: *message_id,
+                    protocol_version: self.protocol_version,
+                });
+            }
+        }
+        #[cfg(not(feature = "signing"))]
+        let result = peek_versioned_frame_info::<M, _>(reader.deref_mut(), self.protocol_version);
+        #[cfg(feature = "signing")]
+        let result = peek_versioned_frame_info_signed::<M, _>(
+            reader.deref_mut(),
+            self.protocol_version,
+            self.signing_data.as_ref(),
+        );
+        result
+    }
+
     fn set_protocol_version(&mut self, version: MavlinkVersion) {
         self.protocol_version = version;
     }
@@ -142,6 +356,30 @@ impl<M: Message> MavConnection<M> for UdpConnection {
         self.protocol_version
     }
 
+    fn set_target_filter(&mut self, filter: Option<TargetFilter>) {
+        self.target_filter = filter;
+    }
+
+    fn set_source_filter(&mut self, filter: Option<SourceFilter>) {
+        self.source_filter = filter;
+    }
+
+    // applied to the socket by the next receive, the reader is locked while receiving
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(*self.read_timeout.lock().unwrap())
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        *self.read_timeout.lock().unwrap() = timeout;
+        Ok(())
+    }
+
     #[cfg(feature = "signing")]
     fn setup_signing(&mut self, signing_data: Option<SigningConfig>) {
         self.signing_data = signing_data.map(SigningData::from_config)
@@ -158,7 +396,36 @@ impl Connectable for UdpConnectable {
         if matches!(self.mode, UdpMode::Udpcast) {
             socket.set_broadcast(true)?;
         }
-        Ok(Box::new(UdpConnection::new(socket, server, dest)?))
+        socket.set_write_timeout(self.options.write_timeout)?;
+        self.options.apply_buffer_sizes(SockRef::from(&socket))?;
+        let rendezvous = match (&self.rendezvous, dest) {
+            (Some((session, keepalive)), Some(server)) if matches!(self.mode, UdpMode::Udpout) => {
+                Some(Rendezvous::start(
+                    socket.try_clone()?,
+                    server,
+                    session.clone(),
+                    *keepalive,
+                ))
+            }
+            (Some(_), _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Rendezvous requires a udpout connection",
+                ))
+            }
+            (None, _) => None,
+        };
+        let mut conn: Box<dyn MavConnection<M> + Sync + Send> = Box::new(UdpConnection::new(
+            socket,
+            server,
+            dest,
+            self.reorder,
+            rendezvous,
+            self.write_coalescing,
+            self.options.read_timeout,
+        )?);
+        conn.set_protocol_version(self.protocol_version);
+        Ok(conn)
     }
 }
 
@@ -171,8 +438,10 @@ mod tests {
         let receiver_socket = UdpSocket::bind("127.0.0.1:5000").unwrap();
         let mut udp_reader = UdpRead {
             socket: receiver_socket.try_clone().unwrap(),
+            timeout: None,
             buffer: VecDeque::new(),
             last_recv_address: None,
+            rendezvous: None,
         };
         let sender_socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         sender_socket.connect("127.0.0.1:5000").unwrap();
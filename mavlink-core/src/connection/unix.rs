@@ -0,0 +1,34 @@
+//! MAVLink connection over a Unix domain stream socket
+
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use crate::connectable::UnixConnectable;
+use crate::Message;
+
+use super::{Connectable, MavConnection, StreamConnection};
+
+/// Connect to the socket at `path`
+pub fn unix(path: &str) -> io::Result<StreamConnection<UnixStream, UnixStream>> {
+    let socket = UnixStream::connect(path)?;
+    Ok(StreamConnection::new(socket.try_clone()?, socket))
+}
+
+/// Create a socket at `path`, blocking until a client connects.
+///
+/// Binding fails if a file already exists at `path`, such as a socket left by a previous run.
+pub fn unixin(path: &str) -> io::Result<StreamConnection<UnixStream, UnixStream>> {
+    let listener = UnixListener::bind(path)?;
+    let (socket, _) = listener.accept()?;
+    Ok(StreamConnection::new(socket.try_clone()?, socket))
+}
+
+impl Connectable for UnixConnectable {
+    fn connect<M: Message>(&self) -> io::Result<Box<dyn MavConnection<M> + Sync + Send>> {
+        if self.listen {
+            Ok(Box::new(unixin(&self.path)?))
+        } else {
+            Ok(Box::new(unix(&self.path)?))
+        }
+    }
+}
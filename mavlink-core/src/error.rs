@@ -4,9 +4,32 @@ use std::error::Error;
 
 #[derive(Debug)]
 pub enum ParserError {
-    InvalidFlag { flag_type: &'static str, value: u32 },
-    InvalidEnum { enum_type: &'static str, value: u32 },
-    UnknownMessage { id: u32 },
+    InvalidFlag {
+        flag_type: &'static str,
+        value: u32,
+    },
+    InvalidEnum {
+        enum_type: &'static str,
+        value: u32,
+    },
+    UnknownMessage {
+        id: u32,
+    },
+    /// No message of the dialect has the given name
+    UnknownMessageName,
+    /// The input ended before the end of a fixed size part of the frame
+    Truncated {
+        needed: usize,
+        available: usize,
+    },
+    /// The frame doesn't start with the start marker of its protocol version
+    InvalidStartMarker {
+        marker: u8,
+    },
+    /// The checksum of the frame doesn't match its content
+    InvalidChecksum {
+        checksum: u16,
+    },
 }
 
 impl Display for ParserError {
@@ -21,6 +44,17 @@ impl Display for ParserError {
                 "Invalid enum value for enum type {enum_type:?}, got {value:?}"
             ),
             Self::UnknownMessage { id } => write!(f, "Unknown message with ID {id:?}"),
+            Self::UnknownMessageName => write!(f, "Unknown message name"),
+            Self::Truncated { needed, available } => write!(
+                f,
+                "Input too short, needed {needed:?} bytes but got {available:?}"
+            ),
+            Self::InvalidStartMarker { marker } => {
+                write!(f, "Invalid start marker of frame, got {marker:#04x}")
+            }
+            Self::InvalidChecksum { checksum } => {
+                write!(f, "Invalid checksum of frame, got {checksum:#06x}")
+            }
         }
     }
 }
@@ -35,6 +69,12 @@ pub enum MessageReadError {
     #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
     Io,
     Parse(ParserError),
+    /// No message was received before the deadline of a receive with a timeout
+    Timeout,
+    /// A frame failed signature verification, only reported when enabled with
+    /// [`SigningConfig::with_rejection_errors`](crate::SigningConfig::with_rejection_errors)
+    #[cfg(feature = "signing")]
+    Signature(crate::SignatureError),
 }
 
 impl MessageReadError {
@@ -54,6 +94,9 @@ impl Display for MessageReadError {
             #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
             Self::Io => write!(f, "Failed to read message"),
             Self::Parse(e) => write!(f, "Failed to read message: {e:#?}"),
+            Self::Timeout => write!(f, "Timed out waiting for message"),
+            #[cfg(feature = "signing")]
+            Self::Signature(e) => write!(f, "Rejected message signature: {e}"),
         }
     }
 }
@@ -80,6 +123,14 @@ pub enum MessageWriteError {
     Io(std::io::Error),
     #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
     Io,
+    /// The message can't be encoded with the requested protocol version, such as a message with
+    /// an ID above 255 with MAVLink 1
+    UnsupportedInVersion { msgid: u32 },
+    /// A frame was asked to be written with another protocol version than its own
+    VersionMismatch {
+        frame: crate::MavlinkVersion,
+        requested: crate::MavlinkVersion,
+    },
 }
 
 impl Display for MessageWriteError {
@@ -89,6 +140,14 @@ impl Display for MessageWriteError {
             Self::Io(e) => write!(f, "Failed to write message: {e:#?}"),
             #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
             Self::Io => write!(f, "Failed to write message"),
+            Self::UnsupportedInVersion { msgid } => write!(
+                f,
+                "Message with ID {msgid:?} can't be written with this protocol version"
+            ),
+            Self::VersionMismatch { frame, requested } => write!(
+                f,
+                "Frame of protocol version {frame:?} can't be written as {requested:?}"
+            ),
         }
     }
 }
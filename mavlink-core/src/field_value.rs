@@ -0,0 +1,107 @@
+//! Values of message fields accessed by name, see the `field-access` feature of `mavlink`
+
+use core::fmt::{Display, Formatter};
+
+/// Value of a message field with its MAVLink type.
+///
+/// Enum fields hold the value of the enum and bitmask fields the bits of the flags, with the
+/// integer type they are sent as. Arrays borrow the array of the message.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MavFieldValue<'a> {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Char(u8),
+    U8Array(&'a [u8]),
+    I8Array(&'a [i8]),
+    U16Array(&'a [u16]),
+    I16Array(&'a [i16]),
+    U32Array(&'a [u32]),
+    I32Array(&'a [i32]),
+    U64Array(&'a [u64]),
+    I64Array(&'a [i64]),
+    F32Array(&'a [f32]),
+    F64Array(&'a [f64]),
+    /// Characters of a string, padded with zeros
+    CharArray(&'a [u8]),
+}
+
+impl MavFieldValue<'_> {
+    /// Value of a number or character as a `f64`, or `None` for arrays.
+    ///
+    /// 64-bit integers above 2^53 lose precision.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::U8(value) | Self::Char(value) => Some(value.into()),
+            Self::I8(value) => Some(value.into()),
+            Self::U16(value) => Some(value.into()),
+            Self::I16(value) => Some(value.into()),
+            Self::U32(value) => Some(value.into()),
+            Self::I32(value) => Some(value.into()),
+            Self::U64(value) => Some(value as f64),
+            Self::I64(value) => Some(value as f64),
+            Self::F32(value) => Some(value.into()),
+            Self::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Characters of a char array up to the first zero, or `None` for other values or invalid
+    /// UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::CharArray(chars) => {
+                let len = chars.iter().position(|c| *c == 0).unwrap_or(chars.len());
+                core::str::from_utf8(&chars[..len]).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Errors returned when setting a message field by name
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MavFieldError {
+    /// The message has no field with this name
+    UnknownField,
+    /// The value doesn't have the type of the field, or is an array longer than the field
+    TypeMismatch,
+    /// The value isn't a valid value of the enum or bitmask of the field
+    InvalidValue,
+}
+
+impl Display for MavFieldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnknownField => write!(f, "Unknown field"),
+            Self::TypeMismatch => write!(f, "Value does not have the type of the field"),
+            Self::InvalidValue => write!(f, "Value is not valid for the enum of the field"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MavFieldError {}
+
+/// Copy `value` to the start of the array `field`, clearing the rest of it, as done by the
+/// generated `set_field` of messages
+#[doc(hidden)]
+pub fn set_field_array<T: Copy + Default>(
+    field: &mut [T],
+    value: &[T],
+) -> Result<(), MavFieldError> {
+    if value.len() > field.len() {
+        return Err(MavFieldError::TypeMismatch);
+    }
+    let (head, tail) = field.split_at_mut(value.len());
+    head.copy_from_slice(value);
+    tail.fill(T::default());
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! Live statistics of the received traffic, as displayed by MAVLink inspectors
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::{MavHeader, Message};
+
+/// Upper bound of the arrival times kept per message to compute its rate
+const MAX_ARRIVALS: usize = 1024;
+
+/// Message stream tracked by an [`Inspector`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamKey {
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_id: u32,
+}
+
+type FieldExtractor<M> = Box<dyn Fn(&M) -> Option<f64> + Send>;
+
+struct FieldWatch<M> {
+    message_id: u32,
+    field: &'static str,
+    extract: FieldExtractor<M>,
+}
+
+/// Statistics of a single message stream
+#[derive(Debug, Clone)]
+pub struct StreamStats<M> {
+    count: u64,
+    first_seen: Instant,
+    last_seen: Instant,
+    last_header: MavHeader,
+    last_message: M,
+    arrivals: VecDeque<Instant>,
+    histories: Vec<(&'static str, VecDeque<(Instant, f64)>)>,
+}
+
+impl<M: Message> StreamStats<M> {
+    /// Count of messages received since the stream was first seen
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn first_seen(&self) -> Instant {
+        self.first_seen
+    }
+
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen
+    }
+
+    pub fn last_header(&self) -> &MavHeader {
+        &self.last_header
+    }
+
+    /// Most recent message, holding the last value of every field
+    pub fn last_message(&self) -> &M {
+        &self.last_message
+    }
+
+    pub fn message_name(&self) -> &'static str {
+        self.last_message.message_name()
+    }
+
+    /// Rate in Hz at `now`, averaged over the rate window of the inspector
+    pub fn rate(&self, now: Instant, window: Duration) -> f32 {
+        if window.is_zero() {
+            return 0.0;
+        }
+        let received = self
+            .arrivals
+            .iter()
+            .filter(|arrival| now.saturating_duration_since(**arrival) < window)
+            .count();
+        received as f32 / window.as_secs_f32()
+    }
+
+    /// Last values of a watched field with their reception time, oldest first
+    pub fn history(&self, field: &str) -> Option<&VecDeque<(Instant, f64)>> {
+        self.histories
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, history)| history)
+    }
+}
+
+/// Aggregator of per system, component and message statistics, the data behind a MAVLink
+/// inspector view.
+///
+/// Every received message is reported with [`message_received`](Self::message_received). For each
+/// stream the inspector keeps the message count, its rate over a sliding window and the last
+/// message, from which the UI can display the current field values.
+///
+/// Fields to plot are registered with [`watch`](Self::watch), the inspector then keeps their last
+/// values in histories of bounded length.
+pub struct Inspector<M> {
+    rate_window: Duration,
+    history_len: usize,
+    watches: Vec<FieldWatch<M>>,
+    streams: BTreeMap<StreamKey, StreamStats<M>>,
+}
+
+impl<M: Message + Clone> Default for Inspector<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message + Clone> Inspector<M> {
+    /// Create an inspector with a rate window of 1 s and field histories of 100 values
+    pub fn new() -> Self {
+        Self {
+            rate_window: Duration::from_secs(1),
+            history_len: 100,
+            watches: Vec::new(),
+            streams: BTreeMap::new(),
+        }
+    }
+
+    /// Average rates over `window`.
+    ///
+    /// At most 1024 arrivals are kept per stream, so faster streams need a shorter window.
+    pub fn with_rate_window(mut self, window: Duration) -> Self {
+        self.rate_window = window;
+        self
+    }
+
+    /// Keep the last `len` values of each watched field
+    pub fn with_history_len(mut self, len: usize) -> Self {
+        self.history_len = len;
+        self
+    }
+
+    /// Record the history of `field` of message `message_id`, as returned by `extract`.
+    ///
+    /// Only messages received after the call are recorded.
+    pub fn watch<F>(&mut self, message_id: u32, field: &'static str, extract: F)
+    where
+        F: Fn(&M) -> Option<f64> + Send + 'static,
+    {
+        self.watches.push(FieldWatch {
+            message_id,
+            field,
+            extract: Box::new(extract),
+        });
+    }
+
+    /// Record the reception of `message` at `now`
+    pub fn message_received(&mut self, header: &MavHeader, message: &M, now: Instant) {
+        let key = StreamKey {
+            system_id: header.system_id,
+            component_id: header.component_id,
+            message_id: message.message_id(),
+        };
+        let stats = self.streams.entry(key).or_insert_with(|| StreamStats {
+            count: 0,
+            first_seen: now,
+            last_seen: now,
+            last_header: *header,
+            last_message: message.clone(),
+            arrivals: VecDeque::new(),
+            histories: Vec::new(),
+        });
+        stats.count += 1;
+        stats.last_seen = now;
+        stats.last_header = *header;
+        stats.last_message = message.clone();
+
+        while let Some(oldest) = stats.arrivals.front() {
+            if stats.arrivals.len() < MAX_ARRIVALS
+                && now.saturating_duration_since(*oldest) < self.rate_window
+            {
+                break;
+            }
+            stats.arrivals.pop_front();
+        }
+        stats.arrivals.push_back(now);
+
+        for watch in self.watches.iter() {
+            if watch.message_id != key.message_id {
+                continue;
+            }
+            let Some(value) = (watch.extract)(message) else {
+                continue;
+            };
+            let history = match stats
+                .histories
+                .iter_mut()
+                .position(|(field, _)| *field == watch.field)
+            {
+                Some(index) => &mut stats.histories[index].1,
+                None => {
+                    stats.histories.push((watch.field, VecDeque::new()));
+                    &mut stats.histories.last_mut().unwrap().1
+                }
+            };
+            if history.len() >= self.history_len {
+                history.pop_front();
+            }
+            if self.history_len > 0 {
+                history.push_back((now, value));
+            }
+        }
+    }
+
+    pub fn rate_window(&self) -> Duration {
+        self.rate_window
+    }
+
+    pub fn get(&self, key: &StreamKey) -> Option<&StreamStats<M>> {
+        self.streams.get(key)
+    }
+
+    /// Rate in Hz of the stream `key` at `now`, 0 if it was never received
+    pub fn rate(&self, key: &StreamKey, now: Instant) -> f32 {
+        self.get(key)
+            .map(|stats| stats.rate(now, self.rate_window))
+            .unwrap_or(0.0)
+    }
+
+    /// All streams, sorted by system, component and message id
+    pub fn streams(&self) -> impl Iterator<Item = (&StreamKey, &StreamStats<M>)> {
+        self.streams.iter()
+    }
+
+    /// Forget the streams not received for `max_age` at `now`, such as those of a disconnected
+    /// vehicle
+    pub fn remove_stale(&mut self, now: Instant, max_age: Duration) {
+        self.streams
+            .retain(|_, stats| now.saturating_duration_since(stats.last_seen) < max_age);
+    }
+
+    pub fn clear(&mut self) {
+        self.streams.clear();
+    }
+}
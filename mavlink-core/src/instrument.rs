@@ -0,0 +1,69 @@
+//! `tracing` events emitted for connections and frames
+
+use crate::error::{MessageReadError, MessageWriteError};
+use crate::{MavHeader, Message};
+
+#[cfg(feature = "signing")]
+use crate::MAVLinkV2MessageRaw;
+
+#[cfg(feature = "std")]
+pub(crate) fn connection_opened<C>(address: &str, result: &std::io::Result<C>) {
+    match result {
+        Ok(_) => tracing::info!(address, "opened connection"),
+        Err(error) => tracing::warn!(address, %error, "failed to open connection"),
+    }
+}
+
+pub(crate) fn frame_received<M: Message>(result: Result<(&MavHeader, &M), &MessageReadError>) {
+    match result {
+        Ok((header, message)) => tracing::trace!(
+            message = message.message_name(),
+            system_id = header.system_id,
+            component_id = header.component_id,
+            sequence = header.sequence,
+            "received frame"
+        ),
+        Err(MessageReadError::Parse(error)) => {
+            tracing::debug!(%error, "failed to parse frame");
+        }
+        Err(_) => {}
+    }
+}
+
+pub(crate) fn frame_sent<M: Message>(
+    header: &MavHeader,
+    message: &M,
+    result: &Result<usize, MessageWriteError>,
+) {
+    match result {
+        Ok(len) => tracing::trace!(
+            message = message.message_name(),
+            system_id = header.system_id,
+            component_id = header.component_id,
+            sequence = header.sequence,
+            len,
+            "sent frame"
+        ),
+        Err(error) => tracing::debug!(
+            message = message.message_name(),
+            %error,
+            "failed to send frame"
+        ),
+    }
+}
+
+pub(crate) fn invalid_checksum(message_id: u32) {
+    tracing::trace!(message_id, "discarded frame with invalid checksum");
+}
+
+#[cfg(feature = "signing")]
+pub(crate) fn signature_rejected(message: &MAVLinkV2MessageRaw, error: crate::SignatureError) {
+    tracing::warn!(
+        message_id = message.message_id(),
+        system_id = message.system_id(),
+        component_id = message.component_id(),
+        link_id = message.signature_link_id(),
+        %error,
+        "rejected frame with invalid signature"
+    );
+}
@@ -38,47 +38,150 @@ use serde::{Deserialize, Serialize};
 pub mod peek_reader;
 use peek_reader::PeekReader;
 
-use crate::{bytes::Bytes, error::ParserError};
+use crate::error::ParserError;
 
 use crc_any::CRCu16;
 
+mod raw_message;
+pub use raw_message::RawMessage;
+
+mod mav_parser;
+#[cfg(feature = "std")]
+pub use mav_parser::MavFrameIterator;
+pub use mav_parser::{Frames, MavParser, RawFrame};
+
+mod capabilities;
+pub use capabilities::Capabilities;
+#[cfg(feature = "std")]
+pub use capabilities::{request_capabilities, CapabilityCache};
+
+mod param_value;
+pub use param_value::{ParamEncoding, ParamValue, ParamValueError};
+
+mod field_value;
+#[doc(hidden)]
+pub use field_value::set_field_array;
+pub use field_value::{MavFieldError, MavFieldValue};
+
+#[cfg(feature = "serde")]
+mod pymavlink;
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use pymavlink::pymavlink_str;
+#[cfg(feature = "serde")]
+pub use pymavlink::{Pymavlink, SerializePymavlink};
+
 pub mod bytes;
 pub mod bytes_mut;
 #[cfg(feature = "std")]
 mod connection;
 pub mod error;
+#[cfg(feature = "tracing")]
+mod instrument;
+#[cfg(all(unix, feature = "std", feature = "signing"))]
+pub use self::connection::generate_secret_key;
+#[cfg(all(feature = "std", feature = "udp"))]
+pub use self::connection::RendezvousServer;
+#[cfg(all(feature = "std", feature = "direct-serial"))]
+pub use self::connection::{
+    autodetect_serial, probe_serial, serial_ports, SerialConnection, AUTODETECT_BAUD_RATES,
+};
 #[cfg(feature = "std")]
-pub use self::connection::{connect, Connectable, MavConnection};
+pub use self::connection::{
+    connect, connect_with_options, discover_devices, request_message, Connectable,
+    DatagramConnection, DatagramTransport, DeviceKind, DiscoveredDevice, MavConnection,
+    MultiConnection, RequestMessageError, StreamConnection,
+};
+#[cfg(all(feature = "std", feature = "signing"))]
+pub use self::connection::{provision_signing, ProvisionSigningError};
 
 #[cfg(feature = "tokio-1")]
 mod async_connection;
+#[cfg(all(feature = "tokio-1", feature = "direct-serial"))]
+pub use self::async_connection::AsyncSerialConnection;
+#[cfg(all(feature = "tokio-1", feature = "tcp"))]
+pub use self::async_connection::AsyncTcpServerConnection;
 #[cfg(feature = "tokio-1")]
-pub use self::async_connection::{connect_async, AsyncConnectable, AsyncMavConnection};
+pub use self::async_connection::{
+    connect_async, connect_async_with_options, into_split, AsyncConnectable, AsyncMavConnection,
+    AsyncMavReader, AsyncMavSink, AsyncMavStream, AsyncMavWriter, AsyncMultiConnection,
+};
 
 #[cfg(feature = "tokio-1")]
 pub mod async_peek_reader;
 #[cfg(feature = "tokio-1")]
 use async_peek_reader::AsyncPeekReader;
+#[cfg(feature = "tokio-1")]
+use tokio::io::AsyncWriteExt;
 
 #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
 pub mod embedded;
 #[cfg(any(feature = "embedded", feature = "embedded-hal-02"))]
 use embedded::{Read, Write};
 
+mod target_filter;
+pub use self::target_filter::TargetFilter;
+mod source_filter;
+pub use self::source_filter::SourceFilter;
+
+#[cfg(feature = "camera-definition")]
+pub mod camera_definition;
+
+#[cfg(feature = "std")]
+mod link_monitor;
+#[cfg(feature = "std")]
+pub use self::link_monitor::{LinkEvent, LinkMonitor, LinkState, LinkTimeouts};
+
+#[cfg(feature = "std")]
+mod sequence_tracker;
+#[cfg(feature = "std")]
+pub use self::sequence_tracker::{SequenceStats, SequenceTracker};
+
+#[cfg(feature = "std")]
+mod inspector;
+#[cfg(feature = "std")]
+pub use self::inspector::{Inspector, StreamKey, StreamStats};
+
+#[cfg(feature = "std")]
+mod telemetry_cache;
+#[cfg(all(feature = "std", feature = "tokio-1"))]
+pub use self::telemetry_cache::TelemetryWatch;
+#[cfg(feature = "std")]
+pub use self::telemetry_cache::{CachedMessage, TelemetryCache};
+
+#[cfg(feature = "std")]
+mod tlog;
+#[cfg(feature = "std")]
+pub use self::tlog::{PlaybackControl, TlogPlayer, TlogReader, TlogRecord, TlogWriter};
+
+#[cfg(feature = "std")]
+mod time_base;
+#[cfg(feature = "std")]
+pub use self::time_base::{MavTimestamp, TimeBase};
+
 #[cfg(not(feature = "signing"))]
 type SigningData = ();
 #[cfg(feature = "signing")]
 mod signing;
+#[cfg(all(feature = "signing", feature = "std"))]
+pub use self::signing::{GpsTimestampSource, SystemClockTimestamp};
 #[cfg(feature = "signing")]
-pub use self::signing::{SigningConfig, SigningData};
+pub use self::signing::{
+    Mavlink2Signer, Sha256Signer, SignatureError, SigningConfig, SigningData, SigningKeyStore,
+    SigningTimestampSource,
+};
 #[cfg(feature = "signing")]
 use sha2::{Digest, Sha256};
 
 #[cfg(any(feature = "std", feature = "tokio-1"))]
 mod connectable;
+#[cfg(all(unix, feature = "unix-socket"))]
+pub use connectable::UnixConnectable;
 #[cfg(any(feature = "std", feature = "tokio-1"))]
 pub use connectable::{
-    ConnectionAddress, FileConnectable, SerialConnectable, TcpConnectable, UdpConnectable,
+    ConnectionAddress, ConnectionOptions, FileConnectable, SerialConfig, SerialConnectable,
+    SerialDataBits, SerialFlowControl, SerialParity, SerialStopBits, StdioConnectable,
+    TcpConnectable, UdpConnectable, UdpMode,
 };
 
 pub const MAX_FRAME_SIZE: usize = 280;
@@ -99,9 +202,102 @@ where
         payload: &[u8],
     ) -> Result<Self, error::ParserError>;
 
-    fn message_id_from_name(name: &str) -> Result<u32, &'static str>;
-    fn default_message_from_id(id: u32) -> Result<Self, &'static str>;
+    /// Parse like [`parse`](Self::parse), except that enum fields holding a value unknown to
+    /// the dialect, such as one added by a newer firmware, are set to the default value of their
+    /// enum instead of failing with [`ParserError::InvalidEnum`](error::ParserError::InvalidEnum).
+    ///
+    /// `unknown_enums` is incremented for each replaced value.
+    fn parse_lossy(
+        version: MavlinkVersion,
+        msgid: u32,
+        payload: &[u8],
+        unknown_enums: &mut u32,
+    ) -> Result<Self, error::ParserError> {
+        let _ = unknown_enums;
+        Self::parse(version, msgid, payload)
+    }
+
+    /// Parse like [`parse`](Self::parse), except that payloads shorter than the fields
+    /// preceding the extensions fail with
+    /// [`ParserError::Truncated`](error::ParserError::Truncated) instead of being filled with
+    /// zeros.
+    ///
+    /// MAVLink 2 senders remove the trailing zeros of payloads, including those of the fields
+    /// preceding the extensions, so this rejects valid MAVLink 2 messages. It is meant for
+    /// MAVLink 1 links and for peers known not to truncate their payloads.
+    fn parse_strict(
+        version: MavlinkVersion,
+        msgid: u32,
+        payload: &[u8],
+    ) -> Result<Self, error::ParserError>;
+
+    /// Return the id of the message `name`, failing with
+    /// [`ParserError::UnknownMessageName`](error::ParserError::UnknownMessageName) if the dialect
+    /// has no such message
+    fn message_id_from_name(name: &str) -> Result<u32, error::ParserError>;
+    /// Return the name of the message `id`, or `None` if the dialect has no such message
+    fn message_name_from_id(id: u32) -> Option<&'static str>;
+    /// Return the length of the payload of the message `id` with all the fields of the generated
+    /// code, the most [`ser`](Self::ser) writes, or `None` if the dialect has no such message
+    fn max_payload_len(id: u32) -> Option<usize>;
+    /// Return the message `id` with default values, failing with
+    /// [`ParserError::UnknownMessage`](error::ParserError::UnknownMessage) if the dialect has no
+    /// such message
+    fn default_message_from_id(id: u32) -> Result<Self, error::ParserError>;
     fn extra_crc(id: u32) -> u8;
+
+    /// Return the `target_system` field of the message, if it has one
+    fn target_system_id(&self) -> Option<u8> {
+        None
+    }
+
+    /// Return the `target_component` field of the message, if it has one
+    fn target_component_id(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// Properties of a message needed to validate its frames without parsing it, and metadata of its
+/// fields, as listed in the `MESSAGE_INFO` table of each dialect
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MessageInfo {
+    pub id: u32,
+    pub name: &'static str,
+    pub extra_crc: u8,
+    /// Length of the payload without extension fields, as sent with MAVLink 1
+    pub min_payload_len: u8,
+    /// Length of the payload with all the fields of the generated code, which includes extension
+    /// fields only if the dialect was generated with the `emit-extensions` feature
+    pub max_payload_len: u8,
+    /// Length of the payload with all the fields of the definition, including the extension
+    /// fields left out of the generated code
+    pub extended_payload_len: u8,
+    /// Metadata of the fields, in the order of the generated struct
+    pub fields: &'static [FieldInfo],
+}
+
+/// Metadata of a message field from the MAVLink definitions, for user interfaces to show and
+/// validate its value
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FieldInfo {
+    /// Name of the field, as in the MAVLink definitions
+    pub name: &'static str,
+    /// Units of the value, such as `m/s`
+    pub units: Option<&'static str>,
+    /// How the value should be displayed, such as `bitmask`
+    pub display: Option<&'static str>,
+    /// Step by which the value should be changed
+    pub increment: Option<f64>,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+impl FieldInfo {
+    /// Whether `value` is within the minimum and maximum values of the field, if it has any
+    pub fn contains(&self, value: f64) -> bool {
+        self.min_value.map_or(true, |min| value >= min)
+            && self.max_value.map_or(true, |max| value <= max)
+    }
 }
 
 pub trait MessageData: Sized {
@@ -114,6 +310,28 @@ pub trait MessageData: Sized {
 
     fn ser(&self, version: MavlinkVersion, payload: &mut [u8]) -> usize;
     fn deser(version: MavlinkVersion, payload: &[u8]) -> Result<Self, ParserError>;
+
+    /// Deserialize like [`deser`](Self::deser), replacing unknown enum values by the default
+    /// value of their enum and counting them in `unknown_enums`, see [`Message::parse_lossy`]
+    fn deser_lossy(
+        version: MavlinkVersion,
+        payload: &[u8],
+        unknown_enums: &mut u32,
+    ) -> Result<Self, ParserError> {
+        let _ = unknown_enums;
+        Self::deser(version, payload)
+    }
+}
+
+/// Message addressed to a system, and usually to one of its components, implemented by the
+/// generated messages with a `target_system` field
+pub trait TargetedMessage: MessageData {
+    /// The `target_system` field, 0 for broadcast
+    fn target_system(&self) -> u8;
+
+    /// The `target_component` field, 0 for broadcast, or `None` if the message is only addressed
+    /// to a system
+    fn target_component(&self) -> Option<u8>;
 }
 
 /// Metadata from a MAVLink packet header
@@ -161,90 +379,202 @@ pub struct MavFrame<M: Message> {
     pub header: MavHeader,
     pub msg: M,
     pub protocol_version: MavlinkVersion,
+    /// Signature of the frame, only sent with MAVLink 2
+    pub signature: Option<MavFrameSignature>,
+}
+
+/// Signature of a MAVLink 2 frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MavFrameSignature {
+    pub link_id: u8,
+    /// Timestamp in units of 10 microseconds since 1st January 2015 GMT, 48 bits are sent
+    pub timestamp: u64,
+    pub value: [u8; 6],
+}
+
+impl MavFrameSignature {
+    fn from_raw(message: &MAVLinkV2MessageRaw) -> Self {
+        let mut value = [0; 6];
+        value.copy_from_slice(message.signature_value());
+        Self {
+            link_id: message.signature_link_id(),
+            timestamp: message.signature_timestamp(),
+            value,
+        }
+    }
+
+    fn write_raw(&self, message: &mut MAVLinkV2MessageRaw) {
+        *message.signature_link_id_mut() = self.link_id;
+        message
+            .signature_timestamp_bytes_mut()
+            .copy_from_slice(&self.timestamp.to_le_bytes()[..6]);
+        message.signature_value_mut().copy_from_slice(&self.value);
+    }
 }
 
 impl<M: Message> MavFrame<M> {
-    /// Serialize MavFrame into a vector, so it can be sent over a socket, for example.
-    /// The resulting buffer will start with the sequence field of the Mavlink frame
-    /// and will not include the initial packet marker, length field, and flags.
-    pub fn ser(&self, buf: &mut [u8]) -> usize {
-        let mut buf = bytes_mut::BytesMut::new(buf);
-
-        // serialize message
-        let mut payload_buf = [0u8; 255];
-        let payload_len = self.msg.ser(self.protocol_version, &mut payload_buf);
-
-        // Currently expects a buffer with the sequence field at the start.
-        // If this is updated to include the initial packet marker, length field, and flags,
-        // uncomment.
-        //
-        // match self.protocol_version {
-        //     MavlinkVersion::V2 => {
-        //         buf.put_u8(MAV_STX_V2);
-        //         buf.put_u8(payload_len as u8);
-        //         but.put_u8(0); // incompatibility flags
-        //         buf.put_u8(0); // compatibility flags
-        //     }
-        //     MavlinkVersion::V1 => {
-        //         buf.put_u8(MAV_STX);
-        //         buf.put_u8(payload_len as u8);
-        //     }
-        // }
-
-        // serialize header
-        buf.put_u8(self.header.sequence);
-        buf.put_u8(self.header.system_id);
-        buf.put_u8(self.header.component_id);
-
-        // message id
-        match self.protocol_version {
+    /// Serialize MavFrame into the bytes of a frame of `version`, from the start marker to the
+    /// checksum or the signature, so it can be sent over a socket, for example.
+    ///
+    /// The signature is only written with MAVLink 2, where it sets the signed flag of the frame.
+    /// Returns the length of the frame.
+    ///
+    /// Messages with an ID above 255 can't be encoded with MAVLink 1 and are rejected with
+    /// [`MessageWriteError::UnsupportedInVersion`](error::MessageWriteError::UnsupportedInVersion).
+    /// `version` must be the [`protocol_version`](Self::protocol_version) of the frame, other
+    /// versions are rejected with
+    /// [`MessageWriteError::VersionMismatch`](error::MessageWriteError::VersionMismatch).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than the frame, up to 280 bytes.
+    pub fn ser(
+        &self,
+        version: MavlinkVersion,
+        buf: &mut [u8],
+    ) -> Result<usize, error::MessageWriteError> {
+        if version != self.protocol_version {
+            return Err(error::MessageWriteError::VersionMismatch {
+                frame: self.protocol_version,
+                requested: version,
+            });
+        }
+        let mut v1;
+        let mut v2;
+        let bytes = match version {
+            MavlinkVersion::V1 => {
+                check_v1_message_id(&self.msg)?;
+                v1 = MAVLinkV1MessageRaw::new();
+                v1.serialize_message(self.header, &self.msg);
+                v1.raw_bytes()
+            }
             MavlinkVersion::V2 => {
-                let bytes: [u8; 4] = self.msg.message_id().to_le_bytes();
-                buf.put_slice(&bytes[..3]);
+                v2 = MAVLinkV2MessageRaw::new();
+                match &self.signature {
+                    Some(signature) => {
+                        v2.serialize_message_for_signing(self.header, &self.msg);
+                        signature.write_raw(&mut v2);
+                    }
+                    None => v2.serialize_message(self.header, &self.msg),
+                }
+                v2.raw_bytes()
             }
+        };
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    /// Deserialize MavFrame from the bytes of a frame of `version` that has been received from,
+    /// for example, a socket. The input should start with the start marker of the frame, bytes
+    /// after its end are ignored.
+    ///
+    /// The checksum is verified but the signature isn't, it is kept in
+    /// [`signature`](Self::signature).
+    pub fn deser(version: MavlinkVersion, input: &[u8]) -> Result<Self, ParserError> {
+        match version {
             MavlinkVersion::V1 => {
-                buf.put_u8(self.msg.message_id() as u8); //TODO check
+                let mut message = MAVLinkV1MessageRaw::new();
+                let len = Self::frame_len(
+                    input,
+                    MAV_STX,
+                    1 + MAVLinkV1MessageRaw::HEADER_SIZE,
+                    |header| 1 + MAVLinkV1MessageRaw::HEADER_SIZE + usize::from(header[1]) + 2,
+                )?;
+                message.0[..len].copy_from_slice(&input[..len]);
+                Self::check_crc(message.checksum(), message.has_valid_crc::<M>())?;
+                let msg = M::parse(version, message.message_id().into(), message.payload())?;
+                Ok(Self {
+                    header: MavFrameInfo::from(&message).header,
+                    msg,
+                    protocol_version: version,
+                    signature: None,
+                })
+            }
+            MavlinkVersion::V2 => {
+                let mut message = MAVLinkV2MessageRaw::new();
+                let len = Self::frame_len(
+                    input,
+                    MAV_STX_V2,
+                    1 + MAVLinkV2MessageRaw::HEADER_SIZE,
+                    |header| {
+                        let signature_size = if header[2] & MAVLINK_IFLAG_SIGNED == 0 {
+                            0
+                        } else {
+                            MAVLinkV2MessageRaw::SIGNATURE_SIZE
+                        };
+                        1 + MAVLinkV2MessageRaw::HEADER_SIZE
+                            + usize::from(header[1])
+                            + 2
+                            + signature_size
+                    },
+                )?;
+                message.0[..len].copy_from_slice(&input[..len]);
+                let flags = message.incompatibility_flags();
+                if flags & !MAVLINK_SUPPORTED_IFLAGS != 0 {
+                    return Err(ParserError::InvalidFlag {
+                        flag_type: "incompatibility_flags",
+                        value: flags.into(),
+                    });
+                }
+                Self::check_crc(message.checksum(), message.has_valid_crc::<M>())?;
+                let msg = M::parse(version, message.message_id(), message.payload())?;
+                let signature = (flags & MAVLINK_IFLAG_SIGNED != 0)
+                    .then(|| MavFrameSignature::from_raw(&message));
+                Ok(Self {
+                    header: MavFrameInfo::from(&message).header,
+                    msg,
+                    protocol_version: version,
+                    signature,
+                })
             }
         }
-
-        buf.put_slice(&payload_buf[..payload_len]);
-        buf.len()
     }
 
-    /// Deserialize MavFrame from a slice that has been received from, for example, a socket.
-    /// The input buffer should start with the sequence field of the Mavlink frame. The
-    /// initial packet marker, length field, and flag fields should be excluded.
-    pub fn deser(version: MavlinkVersion, input: &[u8]) -> Result<Self, ParserError> {
-        let mut buf = Bytes::new(input);
-
-        // Currently expects a buffer with the sequence field at the start.
-        // If this is updated to include the initial packet marker, length field, and flags,
-        // uncomment.
-        // <https://mavlink.io/en/guide/serialization.html#mavlink2_packet_format>
-        // match version {
-        //     MavlinkVersion::V2 => buf.get_u32_le(),
-        //     MavlinkVersion::V1 => buf.get_u16_le().into(),
-        // };
-
-        let sequence = buf.get_u8();
-        let system_id = buf.get_u8();
-        let component_id = buf.get_u8();
-        let header = MavHeader {
-            system_id,
-            component_id,
-            sequence,
-        };
+    /// Length of the frame at the start of `input`, checking its start marker and that the
+    /// input holds the whole frame
+    fn frame_len(
+        input: &[u8],
+        stx: u8,
+        header_len: usize,
+        frame_len: impl FnOnce(&[u8]) -> usize,
+    ) -> Result<usize, ParserError> {
+        if input.len() < header_len {
+            return Err(ParserError::Truncated {
+                needed: header_len,
+                available: input.len(),
+            });
+        }
+        if input[0] != stx {
+            return Err(ParserError::InvalidStartMarker { marker: input[0] });
+        }
+        let len = frame_len(input);
+        if input.len() < len {
+            return Err(ParserError::Truncated {
+                needed: len,
+                available: input.len(),
+            });
+        }
+        Ok(len)
+    }
 
-        let msg_id = match version {
-            MavlinkVersion::V2 => buf.get_u24_le(),
-            MavlinkVersion::V1 => buf.get_u8().into(),
-        };
+    fn check_crc(checksum: u16, valid: bool) -> Result<(), ParserError> {
+        if valid {
+            Ok(())
+        } else {
+            Err(ParserError::InvalidChecksum { checksum })
+        }
+    }
 
-        M::parse(version, msg_id, buf.remaining_bytes()).map(|msg| Self {
-            header,
-            msg,
-            protocol_version: version,
-        })
+    /// Sign the frame as MAVLink 2 with the key, link id and timestamp of `signing_data`,
+    /// replacing its signature
+    #[cfg(feature = "signing")]
+    pub fn sign(&mut self, signing_data: &SigningData) {
+        let mut message = MAVLinkV2MessageRaw::new();
+        message.serialize_message_for_signing(self.header, &self.msg);
+        signing_data.sign_message(&mut message);
+        self.signature = Some(MavFrameSignature::from_raw(&message));
+        self.protocol_version = MavlinkVersion::V2;
     }
 
     /// Return the frame header
@@ -253,6 +583,43 @@ impl<M: Message> MavFrame<M> {
     }
 }
 
+/// Header information of a frame, available without parsing its payload
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MavFrameInfo {
+    pub header: MavHeader,
+    pub message_id: u32,
+    pub protocol_version: MavlinkVersion,
+}
+
+impl From<&MAVLinkV1MessageRaw> for MavFrameInfo {
+    fn from(message: &MAVLinkV1MessageRaw) -> Self {
+        Self {
+            header: MavHeader {
+                sequence: message.sequence(),
+                system_id: message.system_id(),
+                component_id: message.component_id(),
+            },
+            message_id: u32::from(message.message_id()),
+            protocol_version: MavlinkVersion::V1,
+        }
+    }
+}
+
+impl From<&MAVLinkV2MessageRaw> for MavFrameInfo {
+    fn from(message: &MAVLinkV2MessageRaw) -> Self {
+        Self {
+            header: MavHeader {
+                sequence: message.sequence(),
+                system_id: message.system_id(),
+                component_id: message.component_id(),
+            },
+            message_id: message.message_id(),
+            protocol_version: MavlinkVersion::V2,
+        }
+    }
+}
+
 pub fn calculate_crc(data: &[u8], extra_crc: u8) -> u16 {
     let mut crc_calculator = CRCu16::crc16mcrf4cc();
     crc_calculator.digest(data);
@@ -265,10 +632,13 @@ pub fn read_versioned_msg<M: Message, R: Read>(
     r: &mut PeekReader<R>,
     version: MavlinkVersion,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => read_v2_msg(r),
         MavlinkVersion::V1 => read_v1_msg(r),
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|(header, msg)| (header, msg)));
+    result
 }
 
 #[cfg(feature = "tokio-1")]
@@ -276,10 +646,13 @@ pub async fn read_versioned_msg_async<M: Message, R: tokio::io::AsyncReadExt + U
     r: &mut AsyncPeekReader<R>,
     version: MavlinkVersion,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => read_v2_msg_async(r).await,
         MavlinkVersion::V1 => read_v1_msg_async(r).await,
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|(header, msg)| (header, msg)));
+    result
 }
 
 #[cfg(feature = "signing")]
@@ -288,10 +661,13 @@ pub fn read_versioned_msg_signed<M: Message, R: Read>(
     version: MavlinkVersion,
     signing_data: Option<&SigningData>,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => read_v2_msg_inner(r, signing_data),
         MavlinkVersion::V1 => read_v1_msg(r),
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|(header, msg)| (header, msg)));
+    result
 }
 
 #[cfg(all(feature = "tokio-1", feature = "signing"))]
@@ -300,9 +676,176 @@ pub async fn read_versioned_msg_async_signed<M: Message, R: tokio::io::AsyncRead
     version: MavlinkVersion,
     signing_data: Option<&SigningData>,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => read_v2_msg_async_inner(r, signing_data).await,
         MavlinkVersion::V1 => read_v1_msg_async(r).await,
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|(header, msg)| (header, msg)));
+    result
+}
+
+/// Read the next valid frame of `version`, keeping its signature
+pub fn read_versioned_frame<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<MavFrame<M>, error::MessageReadError> {
+    let result =
+        read_versioned_raw_message::<M, _>(r, version).and_then(|frame| Ok(frame.parse_frame()?));
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|frame| (&frame.header, &frame.msg)));
+    result
+}
+
+#[cfg(feature = "tokio-1")]
+pub async fn read_versioned_frame_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<MavFrame<M>, error::MessageReadError> {
+    let result = read_versioned_raw_message_async::<M, _>(r, version)
+        .await
+        .and_then(|frame| Ok(frame.parse_frame()?));
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|frame| (&frame.header, &frame.msg)));
+    result
+}
+
+#[cfg(feature = "signing")]
+pub fn read_versioned_frame_signed<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<MavFrame<M>, error::MessageReadError> {
+    let result = read_versioned_raw_message_signed::<M, _>(r, version, signing_data)
+        .and_then(|frame| Ok(frame.parse_frame()?));
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|frame| (&frame.header, &frame.msg)));
+    result
+}
+
+#[cfg(all(feature = "tokio-1", feature = "signing"))]
+pub async fn read_versioned_frame_async_signed<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<MavFrame<M>, error::MessageReadError> {
+    let result = read_versioned_raw_message_async_signed::<M, _>(r, version, signing_data)
+        .await
+        .and_then(|frame| Ok(frame.parse_frame()?));
+    #[cfg(feature = "tracing")]
+    instrument::frame_received(result.as_ref().map(|frame| (&frame.header, &frame.msg)));
+    result
+}
+
+/// Read the next valid frame of `version` without parsing its message
+pub fn read_versioned_raw_message<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<RawFrame, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => read_v2_raw_message::<M, _>(r).map(RawFrame::V2),
+        MavlinkVersion::V1 => read_v1_raw_message::<M, _>(r).map(RawFrame::V1),
+    }
+}
+
+#[cfg(feature = "tokio-1")]
+pub async fn read_versioned_raw_message_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<RawFrame, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => read_v2_raw_message_async::<M, _>(r).await.map(RawFrame::V2),
+        MavlinkVersion::V1 => read_v1_raw_message_async::<M, _>(r).await.map(RawFrame::V1),
+    }
+}
+
+#[cfg(feature = "signing")]
+pub fn read_versioned_raw_message_signed<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<RawFrame, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => {
+            read_v2_raw_message_inner(r, M::extra_crc, signing_data).map(RawFrame::V2)
+        }
+        MavlinkVersion::V1 => read_v1_raw_message::<M, _>(r).map(RawFrame::V1),
+    }
+}
+
+#[cfg(all(feature = "tokio-1", feature = "signing"))]
+pub async fn read_versioned_raw_message_async_signed<
+    M: Message,
+    R: tokio::io::AsyncReadExt + Unpin,
+>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<RawFrame, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => read_v2_raw_message_async_inner(r, M::extra_crc, signing_data)
+            .await
+            .map(RawFrame::V2),
+        MavlinkVersion::V1 => read_v1_raw_message_async::<M, _>(r).await.map(RawFrame::V1),
+    }
+}
+
+/// Return the header information of the next valid frame, leaving the frame in the reader
+pub fn peek_versioned_frame_info<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<MavFrameInfo, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => peek_v2_raw_message::<M, _>(r).map(|m| MavFrameInfo::from(&m)),
+        MavlinkVersion::V1 => peek_v1_raw_message::<M, _>(r).map(|m| MavFrameInfo::from(&m)),
+    }
+}
+
+#[cfg(feature = "tokio-1")]
+pub async fn peek_versioned_frame_info_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+) -> Result<MavFrameInfo, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => peek_v2_raw_message_async::<M, _>(r)
+            .await
+            .map(|m| MavFrameInfo::from(&m)),
+        MavlinkVersion::V1 => peek_v1_raw_message_async::<M, _>(r)
+            .await
+            .map(|m| MavFrameInfo::from(&m)),
+    }
+}
+
+#[cfg(feature = "signing")]
+pub fn peek_versioned_frame_info_signed<M: Message, R: Read>(
+    r: &mut PeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<MavFrameInfo, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => {
+            peek_v2_raw_message_inner(r, M::extra_crc, signing_data).map(|m| MavFrameInfo::from(&m))
+        }
+        MavlinkVersion::V1 => peek_v1_raw_message::<M, _>(r).map(|m| MavFrameInfo::from(&m)),
+    }
+}
+
+#[cfg(all(feature = "tokio-1", feature = "signing"))]
+pub async fn peek_versioned_frame_info_async_signed<
+    M: Message,
+    R: tokio::io::AsyncReadExt + Unpin,
+>(
+    r: &mut AsyncPeekReader<R>,
+    version: MavlinkVersion,
+    signing_data: Option<&SigningData>,
+) -> Result<MavFrameInfo, error::MessageReadError> {
+    match version {
+        MavlinkVersion::V2 => peek_v2_raw_message_async_inner(r, M::extra_crc, signing_data)
+            .await
+            .map(|m| MavFrameInfo::from(&m)),
+        MavlinkVersion::V1 => peek_v1_raw_message_async::<M, _>(r)
+            .await
+            .map(|m| MavFrameInfo::from(&m)),
     }
 }
 
@@ -381,11 +924,15 @@ impl MAVLinkV1MessageRaw {
 
     #[inline]
     pub fn has_valid_crc<M: Message>(&self) -> bool {
+        self.has_valid_crc_with(M::extra_crc(self.message_id().into()))
+    }
+
+    pub(crate) fn has_valid_crc_with(&self, extra_crc: u8) -> bool {
         let payload_length: usize = self.payload_length().into();
         self.checksum()
             == calculate_crc(
                 &self.0[1..(1 + Self::HEADER_SIZE + payload_length)],
-                M::extra_crc(self.message_id().into()),
+                extra_crc,
             )
     }
 
@@ -446,6 +993,27 @@ impl MAVLinkV1MessageRaw {
 /// V1 maximum size is 263 bytes: `<https://mavlink.io/en/guide/serialization.html>`
 pub fn read_v1_raw_message<M: Message, R: Read>(
     reader: &mut PeekReader<R>,
+) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
+    let message = peek_v1_raw_message_inner(reader, M::extra_crc)?;
+    reader.consume(message.raw_bytes().len());
+    Ok(message)
+}
+
+/// Return a raw buffer with the next valid mavlink message, leaving it in the reader.
+///
+/// Invalid data preceding the message is consumed.
+#[inline]
+pub fn peek_v1_raw_message<M: Message, R: Read>(
+    reader: &mut PeekReader<R>,
+) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
+    peek_v1_raw_message_inner(reader, M::extra_crc)
+}
+
+// takes the `extra_crc` of the dialect instead of being generic over it, to be instantiated once
+// per reader rather than once per dialect and reader
+fn peek_v1_raw_message_inner<R: Read>(
+    reader: &mut PeekReader<R>,
+    extra_crc: fn(u32) -> u8,
 ) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
     loop {
         // search for the magic framing value indicating start of mavlink message
@@ -468,11 +1036,12 @@ pub fn read_v1_raw_message<M: Message, R: Read>(
 
         // retry if CRC failed after previous STX
         // (an STX byte may appear in the middle of a message)
-        if message.has_valid_crc::<M>() {
-            reader.consume(message.raw_bytes().len());
+        if message.has_valid_crc_with(extra_crc(message.message_id().into())) {
             return Ok(message);
         }
 
+        #[cfg(feature = "tracing")]
+        instrument::invalid_checksum(message.message_id().into());
         reader.consume(1);
     }
 }
@@ -482,34 +1051,55 @@ pub fn read_v1_raw_message<M: Message, R: Read>(
 #[cfg(feature = "tokio-1")]
 pub async fn read_v1_raw_message_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
     reader: &mut AsyncPeekReader<R>,
+) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
+    let message = peek_v1_raw_message_async_inner(reader, M::extra_crc).await?;
+    reader.consume(message.raw_bytes().len());
+    Ok(message)
+}
+
+/// Async return a raw buffer with the next valid mavlink message, leaving it in the reader.
+///
+/// Invalid data preceding the message is consumed.
+#[cfg(feature = "tokio-1")]
+pub async fn peek_v1_raw_message_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    reader: &mut AsyncPeekReader<R>,
+) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
+    peek_v1_raw_message_async_inner(reader, M::extra_crc).await
+}
+
+#[cfg(feature = "tokio-1")]
+async fn peek_v1_raw_message_async_inner<R: tokio::io::AsyncReadExt + Unpin>(
+    reader: &mut AsyncPeekReader<R>,
+    extra_crc: fn(u32) -> u8,
 ) -> Result<MAVLinkV1MessageRaw, error::MessageReadError> {
     loop {
-        loop {
-            // search for the magic framing value indicating start of mavlink message
-            if reader.read_u8().await? == MAV_STX {
-                break;
-            }
+        // search for the magic framing value indicating start of mavlink message
+        while reader.peek_exact(1).await?[0] != MAV_STX {
+            reader.consume(1);
         }
 
         let mut message = MAVLinkV1MessageRaw::new();
+        let whole_header_size = MAVLinkV1MessageRaw::HEADER_SIZE + 1;
 
         message.0[0] = MAV_STX;
-        let header = &reader.peek_exact(MAVLinkV1MessageRaw::HEADER_SIZE).await?
-            [..MAVLinkV1MessageRaw::HEADER_SIZE];
+        let header = &reader.peek_exact(whole_header_size).await?[1..whole_header_size];
         message.mut_header().copy_from_slice(header);
-        let packet_length = message.raw_bytes().len() - 1;
-        let payload_and_checksum = &reader.peek_exact(packet_length).await?
-            [MAVLinkV1MessageRaw::HEADER_SIZE..packet_length];
+        let packet_length = message.raw_bytes().len();
+        let payload_and_checksum =
+            &reader.peek_exact(packet_length).await?[whole_header_size..packet_length];
         message
             .mut_payload_and_checksum()
             .copy_from_slice(payload_and_checksum);
 
         // retry if CRC failed after previous STX
         // (an STX byte may appear in the middle of a message)
-        if message.has_valid_crc::<M>() {
-            reader.consume(message.raw_bytes().len() - 1);
+        if message.has_valid_crc_with(extra_crc(message.message_id().into())) {
             return Ok(message);
         }
+
+        #[cfg(feature = "tracing")]
+        instrument::invalid_checksum(message.message_id().into());
+        reader.consume(1);
     }
 }
 
@@ -713,21 +1303,18 @@ impl MAVLinkV2MessageRaw {
         &self.0[checksum_offset..(checksum_offset + 2)]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_link_id(&self) -> u8 {
         let payload_length: usize = self.payload_length().into();
         self.0[1 + Self::HEADER_SIZE + payload_length + 2]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_link_id_mut(&mut self) -> &mut u8 {
         let payload_length: usize = self.payload_length().into();
         &mut self.0[1 + Self::HEADER_SIZE + payload_length + 2]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_timestamp_bytes(&self) -> &[u8] {
         let payload_length: usize = self.payload_length().into();
@@ -735,7 +1322,6 @@ impl MAVLinkV2MessageRaw {
         &self.0[timestamp_start..(timestamp_start + 6)]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_timestamp_bytes_mut(&mut self) -> &mut [u8] {
         let payload_length: usize = self.payload_length().into();
@@ -743,7 +1329,6 @@ impl MAVLinkV2MessageRaw {
         &mut self.0[timestamp_start..(timestamp_start + 6)]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_timestamp(&self) -> u64 {
         let mut timestamp_bytes = [0u8; 8];
@@ -751,7 +1336,6 @@ impl MAVLinkV2MessageRaw {
         u64::from_le_bytes(timestamp_bytes)
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_value(&self) -> &[u8] {
         let payload_length: usize = self.payload_length().into();
@@ -759,7 +1343,6 @@ impl MAVLinkV2MessageRaw {
         &self.0[signature_start..(signature_start + 6)]
     }
 
-    #[cfg(feature = "signing")]
     #[inline]
     pub fn signature_value_mut(&mut self) -> &mut [u8] {
         let payload_length: usize = self.payload_length().into();
@@ -783,24 +1366,31 @@ impl MAVLinkV2MessageRaw {
 
     #[inline]
     pub fn has_valid_crc<M: Message>(&self) -> bool {
+        self.has_valid_crc_with(M::extra_crc(self.message_id()))
+    }
+
+    pub(crate) fn has_valid_crc_with(&self, extra_crc: u8) -> bool {
         let payload_length: usize = self.payload_length().into();
         self.checksum()
             == calculate_crc(
                 &self.0[1..(1 + Self::HEADER_SIZE + payload_length)],
-                M::extra_crc(self.message_id()),
+                extra_crc,
             )
     }
 
+    /// Bytes covered by the signature: the frame from the start marker up to and including the
+    /// signature timestamp.
+    #[inline]
+    pub fn signed_data(&self) -> &[u8] {
+        let payload_length: usize = self.payload_length().into();
+        &self.0[..(1 + Self::HEADER_SIZE + payload_length + 2 + 1 + 6)]
+    }
+
     #[cfg(feature = "signing")]
     pub fn calculate_signature(&self, secret_key: &[u8], target_buffer: &mut [u8; 6]) {
         let mut hasher = Sha256::new();
         hasher.update(secret_key);
-        hasher.update([MAV_STX_V2]);
-        hasher.update(self.header());
-        hasher.update(self.payload());
-        hasher.update(self.checksum_bytes());
-        hasher.update([self.signature_link_id()]);
-        hasher.update(self.signature_timestamp_bytes());
+        hasher.update(self.signed_data());
         target_buffer.copy_from_slice(&hasher.finalize()[0..6]);
     }
 
@@ -892,7 +1482,7 @@ impl MAVLinkV2MessageRaw {
 pub fn read_v2_raw_message<M: Message, R: Read>(
     reader: &mut PeekReader<R>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
-    read_v2_raw_message_inner::<M, R>(reader, None)
+    read_v2_raw_message_inner(reader, M::extra_crc, None)
 }
 
 /// Return a raw buffer with the mavlink message with signing support
@@ -904,12 +1494,52 @@ pub fn read_v2_raw_message_signed<M: Message, R: Read>(
     reader: &mut PeekReader<R>,
     signing_data: Option<&SigningData>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
-    read_v2_raw_message_inner::<M, R>(reader, signing_data)
+    read_v2_raw_message_inner(reader, M::extra_crc, signing_data)
 }
 
+fn read_v2_raw_message_inner<R: Read>(
+    reader: &mut PeekReader<R>,
+    extra_crc: fn(u32) -> u8,
+    signing_data: Option<&SigningData>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    let message = peek_v2_raw_message_inner(reader, extra_crc, signing_data)?;
+    reader.consume(message.raw_bytes().len());
+    #[cfg(feature = "signing")]
+    if let Some(signing_data) = signing_data {
+        signing_data.record_signature(&message);
+    }
+    Ok(message)
+}
+
+/// Return a raw buffer with the next valid mavlink message, leaving it in the reader.
+///
+/// Invalid data preceding the message is consumed.
+#[inline]
+pub fn peek_v2_raw_message<M: Message, R: Read>(
+    reader: &mut PeekReader<R>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    peek_v2_raw_message_inner(reader, M::extra_crc, None)
+}
+
+/// Return a raw buffer with the next valid mavlink message with signing support, leaving it in
+/// the reader.
+///
+/// Invalid data preceding the message, including messages with an invalid signature, is consumed.
+#[cfg(feature = "signing")]
+#[inline]
+pub fn peek_v2_raw_message_signed<M: Message, R: Read>(
+    reader: &mut PeekReader<R>,
+    signing_data: Option<&SigningData>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    peek_v2_raw_message_inner(reader, M::extra_crc, signing_data)
+}
+
+// takes the `extra_crc` of the dialect instead of being generic over it, to be instantiated once
+// per reader rather than once per dialect and reader
 #[allow(unused_variables)]
-fn read_v2_raw_message_inner<M: Message, R: Read>(
+fn peek_v2_raw_message_inner<R: Read>(
     reader: &mut PeekReader<R>,
+    extra_crc: fn(u32) -> u8,
     signing_data: Option<&SigningData>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
     loop {
@@ -938,17 +1568,23 @@ fn read_v2_raw_message_inner<M: Message, R: Read>(
             .mut_payload_and_checksum_and_sign()
             .copy_from_slice(payload_and_checksum_and_sign);
 
-        if message.has_valid_crc::<M>() {
-            // even if the signature turn out to be invalid the valid crc shows that the received data presents a valid message as opposed to random bytes
-            reader.consume(message.raw_bytes().len());
-        } else {
+        if !message.has_valid_crc_with(extra_crc(message.message_id())) {
+            #[cfg(feature = "tracing")]
+            instrument::invalid_checksum(message.message_id());
             reader.consume(1);
             continue;
         }
 
         #[cfg(feature = "signing")]
         if let Some(signing_data) = signing_data {
-            if !signing_data.verify_signature(&message) {
+            if let Err(e) = signing_data.validate_signature(&message) {
+                #[cfg(feature = "tracing")]
+                instrument::signature_rejected(&message, e);
+                // even if the signature turn out to be invalid the valid crc shows that the received data presents a valid message as opposed to random bytes
+                reader.consume(message.raw_bytes().len());
+                if signing_data.config.report_rejected {
+                    return Err(error::MessageReadError::Signature(e));
+                }
                 continue;
             }
         }
@@ -963,54 +1599,98 @@ fn read_v2_raw_message_inner<M: Message, R: Read>(
 pub async fn read_v2_raw_message_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
     reader: &mut AsyncPeekReader<R>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
-    read_v2_raw_message_async_inner::<M, R>(reader, None).await
+    read_v2_raw_message_async_inner(reader, M::extra_crc, None).await
 }
 
 /// Async read a raw buffer with the mavlink message
 /// V2 maximum size is 280 bytes: `<https://mavlink.io/en/guide/serialization.html>`
+#[cfg(feature = "tokio-1")]
+async fn read_v2_raw_message_async_inner<R: tokio::io::AsyncReadExt + Unpin>(
+    reader: &mut AsyncPeekReader<R>,
+    extra_crc: fn(u32) -> u8,
+    signing_data: Option<&SigningData>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    let message = peek_v2_raw_message_async_inner(reader, extra_crc, signing_data).await?;
+    reader.consume(message.raw_bytes().len());
+    #[cfg(feature = "signing")]
+    if let Some(signing_data) = signing_data {
+        signing_data.record_signature(&message);
+    }
+    Ok(message)
+}
+
+/// Async return a raw buffer with the next valid mavlink message, leaving it in the reader.
+///
+/// Invalid data preceding the message is consumed.
+#[cfg(feature = "tokio-1")]
+pub async fn peek_v2_raw_message_async<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    reader: &mut AsyncPeekReader<R>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    peek_v2_raw_message_async_inner(reader, M::extra_crc, None).await
+}
+
+/// Async return a raw buffer with the next valid mavlink message with signing support, leaving it
+/// in the reader.
+///
+/// Invalid data preceding the message, including messages with an invalid signature, is consumed.
+#[cfg(all(feature = "tokio-1", feature = "signing"))]
+pub async fn peek_v2_raw_message_async_signed<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+    reader: &mut AsyncPeekReader<R>,
+    signing_data: Option<&SigningData>,
+) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
+    peek_v2_raw_message_async_inner(reader, M::extra_crc, signing_data).await
+}
+
 #[cfg(feature = "tokio-1")]
 #[allow(unused_variables)]
-async fn read_v2_raw_message_async_inner<M: Message, R: tokio::io::AsyncReadExt + Unpin>(
+async fn peek_v2_raw_message_async_inner<R: tokio::io::AsyncReadExt + Unpin>(
     reader: &mut AsyncPeekReader<R>,
+    extra_crc: fn(u32) -> u8,
     signing_data: Option<&SigningData>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
     loop {
-        loop {
-            // search for the magic framing value indicating start of mavlink message
-            if reader.read_u8().await? == MAV_STX_V2 {
-                break;
-            }
+        // search for the magic framing value indicating start of mavlink message
+        while reader.peek_exact(1).await?[0] != MAV_STX_V2 {
+            reader.consume(1);
         }
 
         let mut message = MAVLinkV2MessageRaw::new();
+        let whole_header_size = MAVLinkV2MessageRaw::HEADER_SIZE + 1;
 
         message.0[0] = MAV_STX_V2;
-        let header = &reader.peek_exact(MAVLinkV2MessageRaw::HEADER_SIZE).await?
-            [..MAVLinkV2MessageRaw::HEADER_SIZE];
+        let header = &reader.peek_exact(whole_header_size).await?[1..whole_header_size];
         message.mut_header().copy_from_slice(header);
 
         if message.incompatibility_flags() & !MAVLINK_SUPPORTED_IFLAGS > 0 {
             // if there are incompatibility flags set that we do not know discard the message
+            reader.consume(1);
             continue;
         }
 
-        let packet_length = message.raw_bytes().len() - 1;
-        let payload_and_checksum_and_sign = &reader.peek_exact(packet_length).await?
-            [MAVLinkV2MessageRaw::HEADER_SIZE..packet_length];
+        let packet_length = message.raw_bytes().len();
+        let payload_and_checksum_and_sign =
+            &reader.peek_exact(packet_length).await?[whole_header_size..packet_length];
         message
             .mut_payload_and_checksum_and_sign()
             .copy_from_slice(payload_and_checksum_and_sign);
 
-        if message.has_valid_crc::<M>() {
-            // even if the signature turn out to be invalid the valid crc shows that the received data presents a valid message as opposed to random bytes
-            reader.consume(message.raw_bytes().len() - 1);
-        } else {
+        if !message.has_valid_crc_with(extra_crc(message.message_id())) {
+            #[cfg(feature = "tracing")]
+            instrument::invalid_checksum(message.message_id());
+            reader.consume(1);
             continue;
         }
 
         #[cfg(feature = "signing")]
         if let Some(signing_data) = signing_data {
-            if !signing_data.verify_signature(&message) {
+            if let Err(e) = signing_data.validate_signature(&message) {
+                #[cfg(feature = "tracing")]
+                instrument::signature_rejected(&message, e);
+                // even if the signature turn out to be invalid the valid crc shows that the received data presents a valid message as opposed to random bytes
+                reader.consume(message.raw_bytes().len());
+                if signing_data.config.report_rejected {
+                    return Err(error::MessageReadError::Signature(e));
+                }
                 continue;
             }
         }
@@ -1026,7 +1706,7 @@ pub async fn read_v2_raw_message_async_signed<M: Message, R: tokio::io::AsyncRea
     reader: &mut AsyncPeekReader<R>,
     signing_data: Option<&SigningData>,
 ) -> Result<MAVLinkV2MessageRaw, error::MessageReadError> {
-    read_v2_raw_message_async_inner::<M, R>(reader, signing_data).await
+    read_v2_raw_message_async_inner(reader, M::extra_crc, signing_data).await
 }
 
 /// Async read a raw buffer with the mavlink message
@@ -1099,7 +1779,7 @@ fn read_v2_msg_inner<M: Message, R: Read>(
     read: &mut PeekReader<R>,
     signing_data: Option<&SigningData>,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    let message = read_v2_raw_message_inner::<M, _>(read, signing_data)?;
+    let message = read_v2_raw_message_inner(read, M::extra_crc, signing_data)?;
 
     Ok((
         MavHeader {
@@ -1133,7 +1813,7 @@ async fn read_v2_msg_async_inner<M: Message, R: tokio::io::AsyncReadExt + Unpin>
     read: &mut AsyncPeekReader<R>,
     signing_data: Option<&SigningData>,
 ) -> Result<(MavHeader, M), error::MessageReadError> {
-    let message = read_v2_raw_message_async_inner::<M, _>(read, signing_data).await?;
+    let message = read_v2_raw_message_async_inner(read, M::extra_crc, signing_data).await?;
 
     Ok((
         MavHeader {
@@ -1161,11 +1841,7 @@ pub async fn read_v2_msg_async<M: Message, R: embedded_io_async::Read>(
             system_id: message.system_id(),
             component_id: message.component_id(),
         },
-        M::parse(
-            MavlinkVersion::V2,
-            u32::from(message.message_id()),
-            message.payload(),
-        )?,
+        M::parse(MavlinkVersion::V2, message.message_id(), message.payload())?,
     ))
 }
 
@@ -1176,10 +1852,13 @@ pub fn write_versioned_msg<M: Message, W: Write>(
     header: MavHeader,
     data: &M,
 ) -> Result<usize, error::MessageWriteError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => write_v2_msg(w, header, data),
         MavlinkVersion::V1 => write_v1_msg(w, header, data),
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_sent(&header, data, &result);
+    result
 }
 
 /// Write a message with signing support using the given mavlink version
@@ -1191,39 +1870,48 @@ pub fn write_versioned_msg_signed<M: Message, W: Write>(
     data: &M,
     signing_data: Option<&SigningData>,
 ) -> Result<usize, error::MessageWriteError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => write_v2_msg_signed(w, header, data, signing_data),
         MavlinkVersion::V1 => write_v1_msg(w, header, data),
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_sent(&header, data, &result);
+    result
 }
 
 /// Async write a message using the given mavlink version
 #[cfg(feature = "tokio-1")]
-pub async fn write_versioned_msg_async<M: Message, W: tokio::io::AsyncWriteExt + Unpin>(
+pub async fn write_versioned_msg_async<M: Message, W: tokio::io::AsyncWrite + Unpin>(
     w: &mut W,
     version: MavlinkVersion,
     header: MavHeader,
     data: &M,
 ) -> Result<usize, error::MessageWriteError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => write_v2_msg_async(w, header, data).await,
         MavlinkVersion::V1 => write_v1_msg_async(w, header, data).await,
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_sent(&header, data, &result);
+    result
 }
 
 /// Async write a message with signing support using the given mavlink version
 #[cfg(all(feature = "tokio-1", feature = "signing"))]
-pub async fn write_versioned_msg_async_signed<M: Message, W: tokio::io::AsyncWriteExt + Unpin>(
+pub async fn write_versioned_msg_async_signed<M: Message, W: tokio::io::AsyncWrite + Unpin>(
     w: &mut W,
     version: MavlinkVersion,
     header: MavHeader,
     data: &M,
     signing_data: Option<&SigningData>,
 ) -> Result<usize, error::MessageWriteError> {
-    match version {
+    let result = match version {
         MavlinkVersion::V2 => write_v2_msg_async_signed(w, header, data, signing_data).await,
         MavlinkVersion::V1 => write_v1_msg_async(w, header, data).await,
-    }
+    };
+    #[cfg(feature = "tracing")]
+    instrument::frame_sent(&header, data, &result);
+    result
 }
 
 /// Async write a message using the given mavlink version
@@ -1269,32 +1957,41 @@ pub fn write_v2_msg_signed<M: Message, W: Write>(
     signing_data: Option<&SigningData>,
 ) -> Result<usize, error::MessageWriteError> {
     let mut message_raw = MAVLinkV2MessageRaw::new();
+    let len = serialize_v2_msg_signed(&mut message_raw, header, data, signing_data);
 
-    let signature_len = if let Some(signing_data) = signing_data {
-        if signing_data.config.sign_outgoing {
+    w.write_all(&message_raw.0[..len])?;
+
+    Ok(len)
+}
+
+/// Serialize a MAVLink v2 message, signing it if `signing_data` asks for outgoing messages to be
+/// signed, and return the length of the resulting frame.
+#[cfg(feature = "signing")]
+fn serialize_v2_msg_signed<M: Message>(
+    message_raw: &mut MAVLinkV2MessageRaw,
+    header: MavHeader,
+    data: &M,
+    signing_data: Option<&SigningData>,
+) -> usize {
+    let signature_len = match signing_data {
+        Some(signing_data) if signing_data.config.sign_outgoing => {
             message_raw.serialize_message_for_signing(header, data);
-            signing_data.sign_message(&mut message_raw);
+            signing_data.sign_message(message_raw);
             MAVLinkV2MessageRaw::SIGNATURE_SIZE
-        } else {
+        }
+        _ => {
             message_raw.serialize_message(header, data);
             0
         }
-    } else {
-        message_raw.serialize_message(header, data);
-        0
     };
 
     let payload_length: usize = message_raw.payload_length().into();
-    let len = 1 + MAVLinkV2MessageRaw::HEADER_SIZE + payload_length + 2 + signature_len;
-
-    w.write_all(&message_raw.0[..len])?;
-
-    Ok(len)
+    1 + MAVLinkV2MessageRaw::HEADER_SIZE + payload_length + 2 + signature_len
 }
 
 /// Async write a MAVLink v2 message to a Write stream.
 #[cfg(feature = "tokio-1")]
-pub async fn write_v2_msg_async<M: Message, W: tokio::io::AsyncWriteExt + Unpin>(
+pub async fn write_v2_msg_async<M: Message, W: tokio::io::AsyncWrite + Unpin>(
     w: &mut W,
     header: MavHeader,
     data: &M,
@@ -1310,33 +2007,16 @@ pub async fn write_v2_msg_async<M: Message, W: tokio::io::AsyncWriteExt + Unpin>
     Ok(len)
 }
 
-/// Write a MAVLink v2 message to a Write stream with signing support.
-#[cfg(feature = "signing")]
-#[cfg(feature = "tokio-1")]
-pub async fn write_v2_msg_async_signed<M: Message, W: tokio::io::AsyncWriteExt + Unpin>(
+/// Async write a MAVLink v2 message to a Write stream with signing support.
+#[cfg(all(feature = "tokio-1", feature = "signing"))]
+pub async fn write_v2_msg_async_signed<M: Message, W: tokio::io::AsyncWrite + Unpin>(
     w: &mut W,
     header: MavHeader,
     data: &M,
     signing_data: Option<&SigningData>,
 ) -> Result<usize, error::MessageWriteError> {
     let mut message_raw = MAVLinkV2MessageRaw::new();
-
-    let signature_len = if let Some(signing_data) = signing_data {
-        if signing_data.config.sign_outgoing {
-            message_raw.serialize_message_for_signing(header, data);
-            signing_data.sign_message(&mut message_raw);
-            MAVLinkV2MessageRaw::SIGNATURE_SIZE
-        } else {
-            message_raw.serialize_message(header, data);
-            0
-        }
-    } else {
-        message_raw.serialize_message(header, data);
-        0
-    };
-
-    let payload_length: usize = message_raw.payload_length().into();
-    let len = 1 + MAVLinkV2MessageRaw::HEADER_SIZE + payload_length + 2 + signature_len;
+    let len = serialize_v2_msg_signed(&mut message_raw, header, data, signing_data);
 
     w.write_all(&message_raw.0[..len]).await?;
 
@@ -1366,12 +2046,25 @@ pub async fn write_v2_msg_async<M: Message>(
     Ok(len)
 }
 
+/// MAVLink 1 frames carry the message ID in a single byte
+fn check_v1_message_id<M: Message>(data: &M) -> Result<(), error::MessageWriteError> {
+    let msgid = data.message_id();
+    if msgid > u32::from(u8::MAX) {
+        return Err(error::MessageWriteError::UnsupportedInVersion { msgid });
+    }
+    Ok(())
+}
+
 /// Write a MAVLink v1 message to a Write stream.
+///
+/// Messages with an ID above 255 can't be encoded with MAVLink 1 and are rejected with
+/// [`MessageWriteError::UnsupportedInVersion`](error::MessageWriteError::UnsupportedInVersion).
 pub fn write_v1_msg<M: Message, W: Write>(
     w: &mut W,
     header: MavHeader,
     data: &M,
 ) -> Result<usize, error::MessageWriteError> {
+    check_v1_message_id(data)?;
     let mut message_raw = MAVLinkV1MessageRaw::new();
     message_raw.serialize_message(header, data);
 
@@ -1385,11 +2078,12 @@ pub fn write_v1_msg<M: Message, W: Write>(
 
 /// Async write a MAVLink v1 message to a Write stream.
 #[cfg(feature = "tokio-1")]
-pub async fn write_v1_msg_async<M: Message, W: tokio::io::AsyncWriteExt + Unpin>(
+pub async fn write_v1_msg_async<M: Message, W: tokio::io::AsyncWrite + Unpin>(
     w: &mut W,
     header: MavHeader,
     data: &M,
 ) -> Result<usize, error::MessageWriteError> {
+    check_v1_message_id(data)?;
     let mut message_raw = MAVLinkV1MessageRaw::new();
     message_raw.serialize_message(header, data);
 
@@ -1411,6 +2105,7 @@ pub async fn write_v1_msg_async<M: Message>(
     header: MavHeader,
     data: &M,
 ) -> Result<usize, error::MessageWriteError> {
+    check_v1_message_id(data)?;
     let mut message_raw = MAVLinkV1MessageRaw::new();
     message_raw.serialize_message(header, data);
 
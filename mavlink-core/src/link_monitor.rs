@@ -0,0 +1,225 @@
+//! Link liveness tracking based on received traffic
+
+use std::time::{Duration, Instant};
+
+use crate::Message;
+
+const HEARTBEAT_ID: u32 = 0;
+
+/// State of a link as seen by a [`LinkMonitor`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LinkState {
+    /// No heartbeat was received yet
+    Unknown,
+    /// Heartbeats are received
+    Up,
+    /// Traffic is received but heartbeats are missing
+    Degraded,
+    /// Nothing was received for longer than the activity timeout
+    Down,
+}
+
+/// Change of the state of a link reported by a [`LinkMonitor`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LinkEvent {
+    LinkUp,
+    LinkDegraded,
+    LinkDown,
+}
+
+/// Timeouts used by a [`LinkMonitor`] to detect degraded and lost links
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LinkTimeouts {
+    /// Time without heartbeat after which the link is degraded
+    pub heartbeat: Duration,
+    /// Time without any received frame after which the link is down
+    pub activity: Duration,
+}
+
+impl Default for LinkTimeouts {
+    /// Timeouts suited to the usual heartbeat rate of 1 Hz
+    fn default() -> Self {
+        Self {
+            heartbeat: Duration::from_millis(2500),
+            activity: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Tracks the liveness of a single link from the heartbeats and other frames received on it.
+///
+/// Received frames are reported with [`frame_received`](Self::frame_received) or
+/// [`message_received`](Self::message_received), and [`check`](Self::check) must be called
+/// regularly to detect timeouts while nothing is received. State changes are returned by these
+/// methods and passed to the callback registered with [`on_event`](Self::on_event).
+pub struct LinkMonitor {
+    timeouts: LinkTimeouts,
+    state: LinkState,
+    last_heartbeat: Option<Instant>,
+    last_activity: Option<Instant>,
+    callback: Option<Box<dyn FnMut(LinkEvent) + Send>>,
+}
+
+impl Default for LinkMonitor {
+    fn default() -> Self {
+        Self::new(LinkTimeouts::default())
+    }
+}
+
+impl LinkMonitor {
+    pub fn new(timeouts: LinkTimeouts) -> Self {
+        Self {
+            timeouts,
+            state: LinkState::Unknown,
+            last_heartbeat: None,
+            last_activity: None,
+            callback: None,
+        }
+    }
+
+    /// Call `callback` for every state change
+    pub fn on_event<F: FnMut(LinkEvent) + Send + 'static>(mut self, callback: F) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn state(&self) -> LinkState {
+        self.state
+    }
+
+    /// Time elapsed at `now` since the last heartbeat was received
+    pub fn since_last_heartbeat(&self, now: Instant) -> Option<Duration> {
+        self.last_heartbeat
+            .map(|heartbeat| now.saturating_duration_since(heartbeat))
+    }
+
+    /// Record the reception of a message
+    pub fn message_received<M: Message>(&mut self, message: &M, now: Instant) -> Option<LinkEvent> {
+        self.frame_received(message.message_id(), now)
+    }
+
+    /// Record the reception of a frame containing message `message_id`
+    pub fn frame_received(&mut self, message_id: u32, now: Instant) -> Option<LinkEvent> {
+        self.last_activity = Some(now);
+        if message_id == HEARTBEAT_ID {
+            self.last_heartbeat = Some(now);
+            return self.transition(LinkState::Up);
+        }
+        match self.state {
+            // traffic without heartbeats is not a healthy link
+            LinkState::Down => self.transition(LinkState::Degraded),
+            _ => self.check(now),
+        }
+    }
+
+    /// Update the state from the time elapsed since the last receptions
+    pub fn check(&mut self, now: Instant) -> Option<LinkEvent> {
+        let elapsed = |last: Option<Instant>| last.map(|last| now.saturating_duration_since(last));
+        match self.state {
+            LinkState::Up | LinkState::Degraded
+                if elapsed(self.last_activity) >= Some(self.timeouts.activity) =>
+            {
+                self.transition(LinkState::Down)
+            }
+            LinkState::Up if elapsed(self.last_heartbeat) >= Some(self.timeouts.heartbeat) => {
+                self.transition(LinkState::Degraded)
+            }
+            _ => None,
+        }
+    }
+
+    fn transition(&mut self, state: LinkState) -> Option<LinkEvent> {
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        let event = match state {
+            LinkState::Up => LinkEvent::LinkUp,
+            LinkState::Degraded => LinkEvent::LinkDegraded,
+            LinkState::Down => LinkEvent::LinkDown,
+            LinkState::Unknown => return None,
+        };
+        if let Some(callback) = &mut self.callback {
+            callback(event);
+        }
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const OTHER_ID: u32 = 33;
+
+    fn timeouts() -> LinkTimeouts {
+        LinkTimeouts {
+            heartbeat: Duration::from_secs(2),
+            activity: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_link_lifecycle() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut monitor = LinkMonitor::new(timeouts());
+
+        assert_eq!(monitor.frame_received(OTHER_ID, at(0)), None);
+        assert_eq!(monitor.state(), LinkState::Unknown);
+        assert_eq!(
+            monitor.frame_received(HEARTBEAT_ID, at(0)),
+            Some(LinkEvent::LinkUp)
+        );
+        assert_eq!(monitor.check(at(1)), None);
+
+        // traffic keeps flowing, but heartbeats are missing
+        assert_eq!(
+            monitor.frame_received(OTHER_ID, at(2)),
+            Some(LinkEvent::LinkDegraded)
+        );
+        assert_eq!(monitor.check(at(6)), None);
+        assert_eq!(monitor.check(at(7)), Some(LinkEvent::LinkDown));
+        assert_eq!(monitor.check(at(8)), None);
+
+        assert_eq!(
+            monitor.frame_received(OTHER_ID, at(9)),
+            Some(LinkEvent::LinkDegraded)
+        );
+        assert_eq!(
+            monitor.frame_received(HEARTBEAT_ID, at(10)),
+            Some(LinkEvent::LinkUp)
+        );
+        assert_eq!(monitor.state(), LinkState::Up);
+    }
+
+    #[test]
+    fn test_down_without_degraded() {
+        let start = Instant::now();
+        let mut monitor = LinkMonitor::new(timeouts());
+        monitor.frame_received(HEARTBEAT_ID, start);
+        assert_eq!(
+            monitor.check(start + Duration::from_secs(10)),
+            Some(LinkEvent::LinkDown)
+        );
+    }
+
+    #[test]
+    fn test_callback() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut monitor = LinkMonitor::new(timeouts()).on_event({
+            let events = events.clone();
+            move |event| events.lock().unwrap().push(event)
+        });
+
+        let start = Instant::now();
+        monitor.frame_received(HEARTBEAT_ID, start);
+        monitor.frame_received(HEARTBEAT_ID, start + Duration::from_secs(1));
+        monitor.check(start + Duration::from_secs(3));
+        assert_eq!(
+            *events.lock().unwrap(),
+            [LinkEvent::LinkUp, LinkEvent::LinkDegraded]
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Transport agnostic parser extracting frames from bytes pushed in chunks of any size
+
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+#[cfg(feature = "std")]
+use crate::error::MessageReadError;
+use crate::error::ParserError;
+use crate::{
+    MAVLinkV1MessageRaw, MAVLinkV2MessageRaw, MavFrame, MavFrameInfo, MavFrameSignature, MavHeader,
+    MavlinkVersion, Message, MAVLINK_IFLAG_SIGNED, MAVLINK_SUPPORTED_IFLAGS, MAV_STX, MAV_STX_V2,
+    MAX_FRAME_SIZE,
+};
+
+/// Frame with a valid checksum returned by a [`MavParser`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RawFrame {
+    V1(MAVLinkV1MessageRaw),
+    V2(MAVLinkV2MessageRaw),
+}
+
+impl RawFrame {
+    pub fn protocol_version(&self) -> MavlinkVersion {
+        match self {
+            Self::V1(_) => MavlinkVersion::V1,
+            Self::V2(_) => MavlinkVersion::V2,
+        }
+    }
+
+    /// Header information of the frame
+    pub fn info(&self) -> MavFrameInfo {
+        match self {
+            Self::V1(message) => MavFrameInfo::from(message),
+            Self::V2(message) => MavFrameInfo::from(message),
+        }
+    }
+
+    /// Bytes of the whole frame, from the start marker to the checksum or signature
+    pub fn raw_bytes(&self) -> &[u8] {
+        match self {
+            Self::V1(message) => message.raw_bytes(),
+            Self::V2(message) => message.raw_bytes(),
+        }
+    }
+
+    /// Parse the message carried by the frame
+    pub fn parse<M: Message>(&self) -> Result<(MavHeader, M), ParserError> {
+        let info = self.info();
+        let payload = match self {
+            Self::V1(message) => message.payload(),
+            Self::V2(message) => message.payload(),
+        };
+        let message = M::parse(info.protocol_version, info.message_id, payload)?;
+        Ok((info.header, message))
+    }
+
+    /// Parse the message carried by the frame, keeping the signature of a signed MAVLink 2 frame
+    pub fn parse_frame<M: Message>(&self) -> Result<MavFrame<M>, ParserError> {
+        let (header, msg) = self.parse()?;
+        let signature = match self {
+            Self::V1(_) => None,
+            Self::V2(message) => (message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED != 0)
+                .then(|| MavFrameSignature::from_raw(message)),
+        };
+        Ok(MavFrame {
+            header,
+            msg,
+            protocol_version: self.protocol_version(),
+            signature,
+        })
+    }
+}
+
+/// Outcome of checking the frame at the start of the buffer
+// only returned to the caller, boxing the frame would cost an allocation per frame
+#[allow(clippy::large_enum_variant)]
+enum Candidate {
+    /// More bytes are needed to check the frame
+    Incomplete,
+    /// The start marker is not followed by a valid frame
+    Invalid,
+    Valid(RawFrame),
+}
+
+/// Parser extracting MAVLink frames from a byte stream, independently of the way the bytes are
+/// received.
+///
+/// Bytes are passed to [`push_bytes`](Self::push_bytes) as they arrive, and the frames they
+/// complete are returned with a valid checksum. Incomplete frames are kept until the next call.
+/// Like the frame readers, the parser resumes the search of a start marker from the byte after
+/// a false one, so a frame starting inside invalid data is still found.
+///
+/// ```ignore
+/// let mut parser = MavParser::<MavMessage>::new();
+/// loop {
+///     let len = serial.read(&mut buf)?;
+///     for frame in parser.push_bytes(&buf[..len]) {
+///         let (header, msg) = frame.parse::<MavMessage>()?;
+///     }
+/// }
+/// ```
+pub struct MavParser<M> {
+    state: ParserState,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M: Message> Default for MavParser<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message> MavParser<M> {
+    /// Parser returning both MAVLink 1 and MAVLink 2 frames
+    pub fn new() -> Self {
+        Self {
+            state: ParserState::new(None, M::extra_crc),
+            message: PhantomData,
+        }
+    }
+
+    /// Parser returning only the frames of `version`, skipping the others as invalid data
+    pub fn with_version(version: MavlinkVersion) -> Self {
+        Self {
+            state: ParserState::new(Some(version), M::extra_crc),
+            message: PhantomData,
+        }
+    }
+
+    /// Push received bytes into the parser and iterate over the frames they complete.
+    ///
+    /// The iterator must be run until it returns `None`, bytes not yet consumed when it is
+    /// dropped are discarded.
+    pub fn push_bytes<'a>(&'a mut self, bytes: &'a [u8]) -> Frames<'a> {
+        self.state.push_bytes(bytes)
+    }
+
+    /// Count of bytes kept from previous calls, as the start of an incomplete frame
+    pub fn buffered(&self) -> usize {
+        self.state.len
+    }
+
+    /// Discard the buffered bytes, such as after reconnecting
+    pub fn reset(&mut self) {
+        self.state.len = 0;
+    }
+}
+
+/// State of a [`MavParser`], independent of the dialect so it can be moved to tasks requiring
+/// `'static` types
+pub(crate) struct ParserState {
+    buffer: [u8; MAX_FRAME_SIZE],
+    len: usize,
+    version: Option<MavlinkVersion>,
+    extra_crc: fn(u32) -> u8,
+}
+
+impl ParserState {
+    pub(crate) fn new(version: Option<MavlinkVersion>, extra_crc: fn(u32) -> u8) -> Self {
+        Self {
+            buffer: [0; MAX_FRAME_SIZE],
+            len: 0,
+            version,
+            extra_crc,
+        }
+    }
+
+    pub(crate) fn push_bytes<'a>(&'a mut self, bytes: &'a [u8]) -> Frames<'a> {
+        Frames {
+            parser: self,
+            input: bytes,
+        }
+    }
+
+    /// Move bytes from `input` into the buffer, returning the count of bytes moved
+    fn fill(&mut self, input: &[u8]) -> usize {
+        let count = input.len().min(self.buffer.len() - self.len);
+        self.buffer[self.len..self.len + count].copy_from_slice(&input[..count]);
+        self.len += count;
+        count
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.buffer.copy_within(count..self.len, 0);
+        self.len -= count;
+    }
+
+    fn is_start_marker(&self, byte: u8) -> bool {
+        match self.version {
+            Some(MavlinkVersion::V1) => byte == MAV_STX,
+            Some(MavlinkVersion::V2) => byte == MAV_STX_V2,
+            None => byte == MAV_STX || byte == MAV_STX_V2,
+        }
+    }
+
+    /// Extract the next valid frame from the buffer, if it holds a complete one
+    fn next_frame(&mut self) -> Option<RawFrame> {
+        loop {
+            let Some(start) = self.buffer[..self.len]
+                .iter()
+                .position(|byte| self.is_start_marker(*byte))
+            else {
+                self.len = 0;
+                return None;
+            };
+            self.discard(start);
+
+            let candidate = if self.buffer[0] == MAV_STX {
+                self.v1_candidate()
+            } else {
+                self.v2_candidate()
+            };
+            match candidate {
+                Candidate::Incomplete => return None,
+                // search again from the byte after the false start marker
+                Candidate::Invalid => self.discard(1),
+                Candidate::Valid(frame) => {
+                    self.discard(frame.raw_bytes().len());
+                    return Some(frame);
+                }
+            }
+        }
+    }
+
+    fn v1_candidate(&self) -> Candidate {
+        let header_end = 1 + MAVLinkV1MessageRaw::HEADER_SIZE;
+        if self.len < header_end {
+            return Candidate::Incomplete;
+        }
+        let mut message = MAVLinkV1MessageRaw::new();
+        message.0[..header_end].copy_from_slice(&self.buffer[..header_end]);
+
+        let frame_len = message.raw_bytes().len();
+        if self.len < frame_len {
+            return Candidate::Incomplete;
+        }
+        message.0[..frame_len].copy_from_slice(&self.buffer[..frame_len]);
+        if !message.has_valid_crc_with((self.extra_crc)(message.message_id().into())) {
+            return Candidate::Invalid;
+        }
+        Candidate::Valid(RawFrame::V1(message))
+    }
+
+    fn v2_candidate(&self) -> Candidate {
+        let header_end = 1 + MAVLinkV2MessageRaw::HEADER_SIZE;
+        if self.len < header_end {
+            return Candidate::Incomplete;
+        }
+        let mut message = MAVLinkV2MessageRaw::new();
+        message.0[..header_end].copy_from_slice(&self.buffer[..header_end]);
+        if message.incompatibility_flags() & !MAVLINK_SUPPORTED_IFLAGS > 0 {
+            return Candidate::Invalid;
+        }
+
+        let frame_len = message.raw_bytes().len();
+        if self.len < frame_len {
+            return Candidate::Incomplete;
+        }
+        message.0[..frame_len].copy_from_slice(&self.buffer[..frame_len]);
+        if !message.has_valid_crc_with((self.extra_crc)(message.message_id())) {
+            return Candidate::Invalid;
+        }
+        Candidate::Valid(RawFrame::V2(message))
+    }
+}
+
+/// Iterator over the frames completed by the bytes pushed with [`MavParser::push_bytes`]
+pub struct Frames<'a> {
+    parser: &'a mut ParserState,
+    input: &'a [u8],
+}
+
+impl Iterator for Frames<'_> {
+    type Item = RawFrame;
+
+    fn next(&mut self) -> Option<RawFrame> {
+        loop {
+            if let Some(frame) = self.parser.next_frame() {
+                return Some(frame);
+            }
+            if self.input.is_empty() {
+                return None;
+            }
+            // the buffer holds a whole frame, so an incomplete frame always leaves room
+            let count = self.parser.fill(self.input);
+            self.input = &self.input[count..];
+        }
+    }
+}
+
+/// Iterator over the frames read from a [`Read`] stream, built on a [`MavParser`].
+///
+/// Frames are returned as they are found in the stream, whatever their protocol version, until
+/// the end of the stream. Read errors are returned as items, so iteration can go on after a read
+/// timeout.
+///
+/// ```ignore
+/// for message in MavFrameIterator::<_, MavMessage>::new(serial).messages() {
+///     let (header, msg) = message?;
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub struct MavFrameIterator<R, M> {
+    reader: R,
+    parser: MavParser<M>,
+    buffer: Box<[u8]>,
+    frames: VecDeque<RawFrame>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, M: Message> MavFrameIterator<R, M> {
+    pub fn new(reader: R) -> Self {
+        Self::with_parser(reader, MavParser::new())
+    }
+
+    /// Iterate with `parser`, such as one limited to a protocol version
+    pub fn with_parser(reader: R, parser: MavParser<M>) -> Self {
+        Self {
+            reader,
+            parser,
+            buffer: vec![0; 4096].into_boxed_slice(),
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Parse the messages of the frames, returning parse errors such as unknown messages as items
+    pub fn messages(self) -> impl Iterator<Item = Result<(MavHeader, M), MessageReadError>> {
+        self.map(|frame| Ok(frame?.parse()?))
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, M: Message> Iterator for MavFrameIterator<R, M> {
+    type Item = io::Result<RawFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Some(Ok(frame));
+            }
+            let len = match self.reader.read(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            self.frames
+                .extend(self.parser.push_bytes(&self.buffer[..len]));
+        }
+    }
+}
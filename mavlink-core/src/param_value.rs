@@ -0,0 +1,253 @@
+//! Parameter values carried in the float `param_value` field of `PARAM_VALUE` and `PARAM_SET`
+
+use core::fmt::{Display, Formatter};
+
+use crate::Capabilities;
+
+const MAV_PARAM_TYPE_UINT8: u8 = 1;
+const MAV_PARAM_TYPE_INT8: u8 = 2;
+const MAV_PARAM_TYPE_UINT16: u8 = 3;
+const MAV_PARAM_TYPE_INT16: u8 = 4;
+const MAV_PARAM_TYPE_UINT32: u8 = 5;
+const MAV_PARAM_TYPE_INT32: u8 = 6;
+const MAV_PARAM_TYPE_REAL32: u8 = 9;
+
+/// Convention used by a component to store integer parameters in the float `param_value` field
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParamEncoding {
+    /// The bytes of the integer are reinterpreted as a float, as done by PX4
+    Bytewise,
+    /// The integer is converted to the nearest float, as done by ArduPilot
+    CCast,
+}
+
+impl ParamEncoding {
+    /// Encoding announced in the `AUTOPILOT_VERSION` capabilities of a component, if any
+    pub fn from_capabilities(capabilities: &Capabilities) -> Option<Self> {
+        if capabilities.param_encode_bytewise() {
+            Some(Self::Bytewise)
+        } else if capabilities.param_encode_c_cast() {
+            Some(Self::CCast)
+        } else {
+            None
+        }
+    }
+}
+
+/// Errors returned when encoding or decoding a [`ParamValue`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParamValueError {
+    /// The `MAV_PARAM_TYPE` is unknown or does not fit in the float field, such as 64-bit types
+    UnsupportedType(u8),
+    /// The integer can't be converted to a float without losing precision
+    NotRepresentable(ParamValue),
+    /// The float received for an integer parameter with [`ParamEncoding::CCast`] is not an
+    /// integer in the range of the parameter type
+    OutOfRange { param_type: u8, value: f32 },
+}
+
+impl Display for ParamValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnsupportedType(param_type) => {
+                write!(f, "Unsupported parameter type {param_type}")
+            }
+            Self::NotRepresentable(value) => {
+                write!(f, "Parameter value {value:?} can't be converted to a float")
+            }
+            Self::OutOfRange { param_type, value } => {
+                write!(
+                    f,
+                    "Value {value} is invalid for parameter type {param_type}"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParamValueError {}
+
+/// Value of a parameter with its type.
+///
+/// Encoding a value as the float sent in `PARAM_SET`, or decoding one received in `PARAM_VALUE`,
+/// requires the [`ParamEncoding`] used by the component, so integers are never reinterpreted with
+/// the wrong convention.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParamValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+}
+
+impl ParamValue {
+    /// `MAV_PARAM_TYPE` of the value
+    pub fn param_type(&self) -> u8 {
+        match self {
+            Self::U8(_) => MAV_PARAM_TYPE_UINT8,
+            Self::I8(_) => MAV_PARAM_TYPE_INT8,
+            Self::U16(_) => MAV_PARAM_TYPE_UINT16,
+            Self::I16(_) => MAV_PARAM_TYPE_INT16,
+            Self::U32(_) => MAV_PARAM_TYPE_UINT32,
+            Self::I32(_) => MAV_PARAM_TYPE_INT32,
+            Self::F32(_) => MAV_PARAM_TYPE_REAL32,
+        }
+    }
+
+    /// Encode the value into the float `param_value` field
+    pub fn encode(&self, encoding: ParamEncoding) -> Result<f32, ParamValueError> {
+        let integer = match *self {
+            Self::U8(value) => i64::from(value),
+            Self::I8(value) => i64::from(value),
+            Self::U16(value) => i64::from(value),
+            Self::I16(value) => i64::from(value),
+            Self::U32(value) => i64::from(value),
+            Self::I32(value) => i64::from(value),
+            Self::F32(value) => return Ok(value),
+        };
+        match encoding {
+            // the integer is stored little-endian in the first bytes, the rest are zeros
+            ParamEncoding::Bytewise => {
+                let mut bytes = [0u8; 4];
+                let len = self.size();
+                bytes[..len].copy_from_slice(&integer.to_le_bytes()[..len]);
+                Ok(f32::from_le_bytes(bytes))
+            }
+            ParamEncoding::CCast => {
+                let value = integer as f32;
+                if value as i64 != integer {
+                    return Err(ParamValueError::NotRepresentable(*self));
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    /// Decode the float `param_value` field of a parameter of type `param_type`
+    pub fn decode(
+        value: f32,
+        param_type: u8,
+        encoding: ParamEncoding,
+    ) -> Result<Self, ParamValueError> {
+        if param_type == MAV_PARAM_TYPE_REAL32 {
+            return Ok(Self::F32(value));
+        }
+        let integer = match encoding {
+            ParamEncoding::Bytewise => {
+                let bytes = value.to_le_bytes();
+                match param_type {
+                    MAV_PARAM_TYPE_UINT8 => i64::from(bytes[0]),
+                    MAV_PARAM_TYPE_INT8 => i64::from(bytes[0] as i8),
+                    MAV_PARAM_TYPE_UINT16 => i64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+                    MAV_PARAM_TYPE_INT16 => i64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+                    MAV_PARAM_TYPE_UINT32 => i64::from(u32::from_le_bytes(bytes)),
+                    MAV_PARAM_TYPE_INT32 => i64::from(i32::from_le_bytes(bytes)),
+                    _ => return Err(ParamValueError::UnsupportedType(param_type)),
+                }
+            }
+            ParamEncoding::CCast => {
+                let integer = value as i64;
+                if integer as f32 != value {
+                    return Err(ParamValueError::OutOfRange { param_type, value });
+                }
+                integer
+            }
+        };
+        let out_of_range = ParamValueError::OutOfRange { param_type, value };
+        Ok(match param_type {
+            MAV_PARAM_TYPE_UINT8 => Self::U8(integer.try_into().map_err(|_| out_of_range)?),
+            MAV_PARAM_TYPE_INT8 => Self::I8(integer.try_into().map_err(|_| out_of_range)?),
+            MAV_PARAM_TYPE_UINT16 => Self::U16(integer.try_into().map_err(|_| out_of_range)?),
+            MAV_PARAM_TYPE_INT16 => Self::I16(integer.try_into().map_err(|_| out_of_range)?),
+            MAV_PARAM_TYPE_UINT32 => Self::U32(integer.try_into().map_err(|_| out_of_range)?),
+            MAV_PARAM_TYPE_INT32 => Self::I32(integer.try_into().map_err(|_| out_of_range)?),
+            _ => return Err(ParamValueError::UnsupportedType(param_type)),
+        })
+    }
+
+    /// Size of the value in bytes
+    fn size(&self) -> usize {
+        match self {
+            Self::U8(_) | Self::I8(_) => 1,
+            Self::U16(_) | Self::I16(_) => 2,
+            Self::U32(_) | Self::I32(_) | Self::F32(_) => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytewise_round_trip() {
+        for value in [
+            ParamValue::U8(200),
+            ParamValue::I8(-3),
+            ParamValue::U16(60000),
+            ParamValue::I16(-1234),
+            ParamValue::U32(0xdead_beef),
+            ParamValue::I32(-123_456_789),
+            ParamValue::F32(1.5),
+        ] {
+            let encoded = value.encode(ParamEncoding::Bytewise).unwrap();
+            let decoded =
+                ParamValue::decode(encoded, value.param_type(), ParamEncoding::Bytewise).unwrap();
+            assert_eq!(decoded, value);
+        }
+        // the bytes of the integer, not its value
+        let encoded = ParamValue::I32(1).encode(ParamEncoding::Bytewise).unwrap();
+        assert_eq!(encoded.to_bits(), 1);
+    }
+
+    #[test]
+    fn test_c_cast() {
+        assert_eq!(
+            ParamValue::I16(-1234).encode(ParamEncoding::CCast),
+            Ok(-1234.0)
+        );
+        assert_eq!(
+            ParamValue::decode(-1234.0, MAV_PARAM_TYPE_INT16, ParamEncoding::CCast),
+            Ok(ParamValue::I16(-1234))
+        );
+
+        // 2^24 + 1 has no float representation
+        let value = ParamValue::U32(16_777_217);
+        assert_eq!(
+            value.encode(ParamEncoding::CCast),
+            Err(ParamValueError::NotRepresentable(value))
+        );
+        assert!(matches!(
+            ParamValue::decode(0.5, MAV_PARAM_TYPE_INT32, ParamEncoding::CCast),
+            Err(ParamValueError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            ParamValue::decode(300.0, MAV_PARAM_TYPE_UINT8, ParamEncoding::CCast),
+            Err(ParamValueError::OutOfRange { .. })
+        ));
+        assert_eq!(
+            ParamValue::decode(1.0, 8, ParamEncoding::CCast),
+            Err(ParamValueError::UnsupportedType(8))
+        );
+    }
+
+    #[test]
+    fn test_encoding_from_capabilities() {
+        assert_eq!(
+            ParamEncoding::from_capabilities(&Capabilities::from_bits(16)),
+            Some(ParamEncoding::Bytewise)
+        );
+        assert_eq!(
+            ParamEncoding::from_capabilities(&Capabilities::from_bits(131072)),
+            Some(ParamEncoding::CCast)
+        );
+        assert_eq!(
+            ParamEncoding::from_capabilities(&Capabilities::default()),
+            None
+        );
+    }
+}
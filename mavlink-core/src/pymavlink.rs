@@ -0,0 +1,43 @@
+//! Serialization of messages with the conventions of pymavlink, see the `serde` feature of
+//! `mavlink`
+
+use serde::{Serialize, Serializer};
+
+/// Messages that can be serialized as pymavlink's `to_dict` represents them, see [`Pymavlink`]
+pub trait SerializePymavlink {
+    fn serialize_pymavlink<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+/// Serializes the wrapped message with the conventions of pymavlink instead of those of its
+/// derived `Serialize`, so that tools ingesting the output of pymavlink or mavlink2rest can read
+/// it unchanged:
+///
+/// - the message is a map starting with its name in `mavpackettype`
+/// - the fields follow in the order of the definitions, named as in the definitions
+/// - enum and bitmask fields are their integer value
+/// - char arrays are strings, up to the first zero
+///
+/// ```ignore
+/// let json = serde_json::to_string(&Pymavlink(&message))?;
+/// // {"mavpackettype":"HEARTBEAT","type":2,"autopilot":3,"base_mode":81,...}
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Pymavlink<'a, M: ?Sized>(pub &'a M);
+
+impl<M: SerializePymavlink + ?Sized> Serialize for Pymavlink<'_, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_pymavlink(serializer)
+    }
+}
+
+/// Characters of a char array up to the first zero, or up to the first invalid UTF-8, as the
+/// generated `serialize_pymavlink` of messages writes them
+#[doc(hidden)]
+pub fn pymavlink_str(chars: &[u8]) -> &str {
+    let len = chars.iter().position(|c| *c == 0).unwrap_or(chars.len());
+    match core::str::from_utf8(&chars[..len]) {
+        Ok(s) => s,
+        // the prefix is valid UTF-8
+        Err(e) => core::str::from_utf8(&chars[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,86 @@
+//! Messages kept as undecoded payloads
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::utils::remove_trailing_zeroes;
+use crate::MavlinkVersion;
+
+/// Message of a dialect that was generated without a typed struct for it.
+///
+/// The payload is kept as received so the message can be forwarded unchanged, including any
+/// extension fields.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RawMessage {
+    id: u32,
+    len: u8,
+    #[cfg_attr(feature = "serde", serde(with = "serde_arrays"))]
+    payload: [u8; 255],
+}
+
+impl RawMessage {
+    /// Keep `payload` as the payload of message `id`, truncated to the maximum payload length
+    pub fn new(id: u32, payload: &[u8]) -> Self {
+        let len = payload.len().min(255);
+        let mut raw = Self {
+            id,
+            len: len as u8,
+            payload: [0; 255],
+        };
+        raw.payload[..len].copy_from_slice(&payload[..len]);
+        raw
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload[..self.len as usize]
+    }
+
+    /// Serialize the payload into `bytes` and return count of bytes written.
+    ///
+    /// MAVLink 1 payloads are padded or truncated to `v1_len`, the length of the message without
+    /// extension fields, MAVLink 2 payloads have their trailing zeroes removed.
+    pub fn ser(&self, version: MavlinkVersion, v1_len: usize, bytes: &mut [u8]) -> usize {
+        let len = match version {
+            MavlinkVersion::V1 => v1_len,
+            MavlinkVersion::V2 => (self.len as usize).max(1),
+        };
+        let payload = &self.payload[..len];
+        bytes[..len].copy_from_slice(payload);
+        match version {
+            MavlinkVersion::V1 => len,
+            MavlinkVersion::V2 => remove_trailing_zeroes(payload),
+        }
+    }
+}
+
+impl PartialEq for RawMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.payload() == other.payload()
+    }
+}
+
+impl Eq for RawMessage {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_message_ser() {
+        let raw = RawMessage::new(42, &[1, 2, 0, 0]);
+        assert_eq!(raw.payload(), &[1, 2, 0, 0]);
+
+        let mut bytes = [0xff; 16];
+        assert_eq!(raw.ser(MavlinkVersion::V2, 6, &mut bytes), 2);
+        assert_eq!(&bytes[..2], &[1, 2]);
+
+        let mut bytes = [0xff; 16];
+        assert_eq!(raw.ser(MavlinkVersion::V1, 6, &mut bytes), 6);
+        assert_eq!(&bytes[..6], &[1, 2, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Detection of lost frames from the sequence numbers of each source
+
+use std::collections::HashMap;
+
+use crate::MavHeader;
+
+/// Count of frames received from a source and apparently lost before reaching us
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SequenceStats {
+    pub received: u64,
+    pub lost: u64,
+    /// Frames received with the same sequence number as the previous one of the source
+    pub duplicates: u64,
+}
+
+impl SequenceStats {
+    /// Ratio of lost frames to the frames sent by the source, from 0 to 1
+    pub fn loss_ratio(&self) -> f32 {
+        let sent = self.received + self.lost;
+        if sent == 0 {
+            return 0.0;
+        }
+        self.lost as f32 / sent as f32
+    }
+}
+
+/// Tracks the sequence numbers of every `(system_id, component_id)` to report the frames lost
+/// between consecutive decoded frames.
+///
+/// Each source numbers its frames with a wrapping `u8`, so a gap is assumed to be the loss of the
+/// missing numbers. A frame delivered out of order therefore appears as a large loss, and more
+/// than 255 consecutive lost frames are not detected.
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    sources: HashMap<(u8, u8), (u8, SequenceStats)>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the reception of a frame, returning the count of frames of the same source lost
+    /// since the previous one
+    pub fn frame_received(&mut self, header: &MavHeader) -> u8 {
+        let key = (header.system_id, header.component_id);
+        let Some((last, stats)) = self.sources.get_mut(&key) else {
+            let stats = SequenceStats {
+                received: 1,
+                ..Default::default()
+            };
+            self.sources.insert(key, (header.sequence, stats));
+            return 0;
+        };
+
+        stats.received += 1;
+        if header.sequence == *last {
+            stats.duplicates += 1;
+            return 0;
+        }
+        let lost = header.sequence.wrapping_sub(*last).wrapping_sub(1);
+        stats.lost += u64::from(lost);
+        *last = header.sequence;
+        lost
+    }
+
+    /// Statistics of a source, if a frame was received from it
+    pub fn stats(&self, system_id: u8, component_id: u8) -> Option<SequenceStats> {
+        self.sources
+            .get(&(system_id, component_id))
+            .map(|(_, stats)| *stats)
+    }
+
+    /// Statistics of all the sources combined
+    pub fn total(&self) -> SequenceStats {
+        self.sources
+            .values()
+            .fold(SequenceStats::default(), |total, (_, stats)| {
+                SequenceStats {
+                    received: total.received + stats.received,
+                    lost: total.lost + stats.lost,
+                    duplicates: total.duplicates + stats.duplicates,
+                }
+            })
+    }
+
+    /// Sources seen so far with their statistics
+    pub fn sources(&self) -> impl Iterator<Item = ((u8, u8), SequenceStats)> + '_ {
+        self.sources
+            .iter()
+            .map(|(source, (_, stats))| (*source, *stats))
+    }
+
+    /// Forget every source, such as after reconnecting
+    pub fn clear(&mut self) {
+        self.sources.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(component_id: u8, sequence: u8) -> MavHeader {
+        MavHeader {
+            system_id: 1,
+            component_id,
+            sequence,
+        }
+    }
+
+    #[test]
+    fn test_gaps() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.frame_received(&header(1, 254)), 0);
+        assert_eq!(tracker.frame_received(&header(1, 255)), 0);
+        // wraps around, 0 and 1 are lost
+        assert_eq!(tracker.frame_received(&header(1, 2)), 2);
+        assert_eq!(tracker.frame_received(&header(1, 2)), 0);
+        // other sources are tracked separately
+        assert_eq!(tracker.frame_received(&header(2, 100)), 0);
+        assert_eq!(tracker.frame_received(&header(2, 103)), 2);
+
+        let stats = tracker.stats(1, 1).unwrap();
+        assert_eq!(
+            stats,
+            SequenceStats {
+                received: 4,
+                lost: 2,
+                duplicates: 1,
+            }
+        );
+        assert_eq!(stats.loss_ratio(), 2.0 / 6.0);
+        assert_eq!(tracker.total().lost, 4);
+        assert_eq!(tracker.sources().count(), 2);
+        assert!(tracker.stats(1, 3).is_none());
+
+        tracker.clear();
+        assert_eq!(tracker.total(), SequenceStats::default());
+    }
+}
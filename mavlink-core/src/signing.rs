@@ -1,29 +1,339 @@
 use crate::MAVLinkV2MessageRaw;
+#[cfg(feature = "std")]
+use crate::{MavFrameInfo, MavHeader, MavlinkVersion, Message};
 
-use std::time::SystemTime;
+use core::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::{Instant, SystemTime};
+#[cfg(feature = "std")]
 use std::{collections::HashMap, sync::Mutex};
 
+use sha2::{Digest, Sha256};
+
 use crate::MAVLINK_IFLAG_SIGNED;
 
+/// 1st January 2015 GMT, the epoch of signing timestamps, as UNIX time in microseconds
+const MAVLINK_EPOCH_UNIX_US: u64 = 1_420_070_400_000_000;
+/// 6th January 1980 GMT, the epoch of GPS time, as UNIX time in microseconds
+#[cfg(feature = "std")]
+const GPS_EPOCH_UNIX_US: u64 = 315_964_800_000_000;
+#[cfg(feature = "std")]
+const SYSTEM_TIME_ID: u32 = 2;
+/// Number of link, system and component streams whose timestamps are tracked without `std`
+#[cfg(not(feature = "std"))]
+const MAX_STREAMS: usize = 16;
+
+/// Shared handle to the signers, key stores and time sources of a [`SigningConfig`], an [`Arc`]
+/// with `std` and a `'static` reference without a heap
+#[cfg(feature = "std")]
+type Shared<T> = Arc<T>;
+#[cfg(not(feature = "std"))]
+type Shared<T> = &'static T;
+
+/// Computes the signature of MAVLink 2 frames, the first 48 bits of `sha256(secret_key + data)`.
+///
+/// Implementing this trait allows the secret key to be held outside of this library, for example
+/// in a secure element, TPM or HSM, or the hash to be computed by the crypto peripheral of a
+/// microcontroller.
+pub trait Mavlink2Signer: Send + Sync {
+    /// Write the signature of `data` into `signature`.
+    ///
+    /// `data` is the frame from the start marker up to and including the signature timestamp,
+    /// as returned by [`MAVLinkV2MessageRaw::signed_data`].
+    fn sign(&self, data: &[u8], signature: &mut [u8; 6]);
+}
+
+/// Software [`Mavlink2Signer`] keeping the secret key in memory.
+#[derive(Clone)]
+pub struct Sha256Signer {
+    secret_key: [u8; 32],
+}
+
+impl Sha256Signer {
+    pub fn new(secret_key: [u8; 32]) -> Self {
+        Self { secret_key }
+    }
+}
+
+impl Mavlink2Signer for Sha256Signer {
+    fn sign(&self, data: &[u8], signature: &mut [u8; 6]) {
+        let mut hasher = Sha256::new();
+        hasher.update(self.secret_key);
+        hasher.update(data);
+        signature.copy_from_slice(&hasher.finalize()[0..6]);
+    }
+}
+
+/// Signers used to verify received frames, selected by the link id of each frame.
+///
+/// A store lets a connection verify the traffic of several signed peers sharing it, each with
+/// its own key and link id, such as a ground station and a companion computer behind a router.
+/// A map from link ids to signers is a store:
+///
+/// ```ignore
+/// let keys = HashMap::from([(1, Sha256Signer::new(gcs_key)), (2, Sha256Signer::new(companion_key))]);
+/// let config = SigningConfig::new(own_key, 0, true, false).with_key_store(Arc::new(keys));
+/// ```
+pub trait SigningKeyStore: Send + Sync {
+    /// Signer of the frames of `link_id`, or `None` to reject them
+    fn signer(&self, link_id: u8) -> Option<&dyn Mavlink2Signer>;
+}
+
+#[cfg(feature = "std")]
+impl<S: Mavlink2Signer> SigningKeyStore for HashMap<u8, S> {
+    fn signer(&self, link_id: u8) -> Option<&dyn Mavlink2Signer> {
+        self.get(&link_id)
+            .map(|signer| signer as &dyn Mavlink2Signer)
+    }
+}
+
+impl<S: Mavlink2Signer, const N: usize> SigningKeyStore for [(u8, S); N] {
+    fn signer(&self, link_id: u8) -> Option<&dyn Mavlink2Signer> {
+        self.iter()
+            .find(|(id, _)| *id == link_id)
+            .map(|(_, signer)| signer as &dyn Mavlink2Signer)
+    }
+}
+
+/// Source of the current time for signing timestamps.
+///
+/// Implementing this trait allows devices without a battery-backed real time clock to sign
+/// with a time obtained elsewhere, see [`GpsTimestampSource`].
+pub trait SigningTimestampSource: Send + Sync {
+    /// Current UNIX time in microseconds, or `None` while it is unknown.
+    ///
+    /// Without a time, the timestamps only increase from the last one sent or received, which
+    /// peers may reject as too old when first hearing from this system.
+    fn unix_time_us(&self) -> Option<u64>;
+}
+
+/// [`SigningTimestampSource`] reading the system clock, the default.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClockTimestamp;
+
+#[cfg(feature = "std")]
+impl SigningTimestampSource for SystemClockTimestamp {
+    fn unix_time_us(&self) -> Option<u64> {
+        // the time is unknown if the system clock appears to be before epoch
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()
+            .map(|n| n.as_micros() as u64)
+    }
+}
+
+/// [`SigningTimestampSource`] deriving the time from GPS fixes or received `SYSTEM_TIME`
+/// messages, for devices without a battery-backed real time clock.
+///
+/// The last known time is extrapolated with the monotonic clock. The source is shared between
+/// the connection and the code receiving the time, for example:
+///
+/// ```ignore
+/// let time = Arc::new(GpsTimestampSource::new());
+/// let config = SigningConfig::new(key, 0, true, false).with_timestamp_source(time.clone());
+/// // in the receive loop
+/// time.handle_message(&message);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct GpsTimestampSource {
+    time: Mutex<Option<(Instant, u64)>>,
+}
+
+#[cfg(feature = "std")]
+impl GpsTimestampSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the current UNIX time in microseconds
+    pub fn set_unix_time_us(&self, unix_time_us: u64) {
+        *self.time.lock().unwrap() = Some((Instant::now(), unix_time_us));
+    }
+
+    /// Set the current time from a GPS receiver, as a GPS week and time of week.
+    ///
+    /// `leap_seconds` is the offset between GPS time and UTC, 18 s since 2017.
+    pub fn set_gps_time(&self, week: u16, time_of_week_ms: u32, leap_seconds: u8) {
+        let gps_us = (u64::from(week) * 7 * 24 * 3600 * 1000 + u64::from(time_of_week_ms)) * 1000;
+        let unix_us = GPS_EPOCH_UNIX_US + gps_us - u64::from(leap_seconds) * 1_000_000;
+        self.set_unix_time_us(unix_us);
+    }
+
+    /// Update the time from `message` if it is a `SYSTEM_TIME` holding a UNIX time
+    pub fn handle_message<M: Message>(&self, message: &M) {
+        if message.message_id() != SYSTEM_TIME_ID {
+            return;
+        }
+        let mut payload = [0u8; 255];
+        message.ser(MavlinkVersion::V2, &mut payload);
+        // time_unix_usec is the first field, 0 when the sender does not know the time either
+        let unix_time_us = u64::from_le_bytes(payload[..8].try_into().unwrap());
+        if unix_time_us != 0 {
+            self.set_unix_time_us(unix_time_us);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl SigningTimestampSource for GpsTimestampSource {
+    fn unix_time_us(&self) -> Option<u64> {
+        self.time
+            .lock()
+            .unwrap()
+            .map(|(instant, unix_time_us)| unix_time_us + instant.elapsed().as_micros() as u64)
+    }
+}
+
 /// Configuration used for MAVLink 2 messages signing as defined in <https://mavlink.io/en/guide/message_signing.html>.
-#[derive(Debug, Clone)]
+///
+/// Without `std` the configuration doesn't allocate: custom signers, key stores and timestamp
+/// sources are given as `'static` references, and there is no clock unless a timestamp source is
+/// set with [`SigningConfig::with_timestamp_source`].
+#[derive(Clone)]
 pub struct SigningConfig {
-    secret_key: [u8; 32],
+    signer: Signer,
+    key_store: Option<Shared<dyn SigningKeyStore>>,
+    timestamp_source: Option<Shared<dyn SigningTimestampSource>>,
     link_id: u8,
     pub(crate) sign_outgoing: bool,
     allow_unsigned: bool,
+    #[cfg(feature = "std")]
+    accept_unsigned: Option<Arc<UnsignedCallback>>,
+    pub(crate) report_rejected: bool,
 }
 
+// signer of a configuration, the secret key is kept inline so that it doesn't need a heap
+#[derive(Clone)]
+enum Signer {
+    Key(Sha256Signer),
+    #[cfg(feature = "std")]
+    Shared(Arc<dyn Mavlink2Signer>),
+    Static(&'static dyn Mavlink2Signer),
+}
+
+impl Signer {
+    fn get(&self) -> &dyn Mavlink2Signer {
+        match self {
+            Self::Key(signer) => signer,
+            #[cfg(feature = "std")]
+            Self::Shared(signer) => signer.as_ref(),
+            Self::Static(signer) => *signer,
+        }
+    }
+}
+
+/// Callback deciding whether an unsigned frame is accepted, from its header and message id
+#[cfg(feature = "std")]
+type UnsignedCallback = dyn Fn(&MavHeader, u32) -> bool + Send + Sync;
+
+/// Reason a received frame was rejected by [`SigningData::check_signature`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The frame isn't signed and the configuration doesn't accept it unsigned
+    Unsigned,
+    /// The timestamp isn't newer than the one of the previous frame of the same link, system and
+    /// component, as with a replayed frame
+    Replayed,
+    /// The first frame of a link, system and component is more than a minute older than the
+    /// latest known timestamp
+    Stale,
+    /// The signature doesn't match the secret key
+    Invalid,
+    /// The key store has no key for the link id of the frame
+    UnknownLink(u8),
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unsigned => write!(f, "unsigned frame"),
+            Self::Replayed => write!(f, "signature timestamp not newer than the previous one"),
+            Self::Stale => write!(f, "signature timestamp more than a minute old"),
+            Self::Invalid => write!(f, "signature does not match the secret key"),
+            Self::UnknownLink(link_id) => write!(f, "no signing key for link id {link_id}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignatureError {}
+
 // mutable state of signing per connection
 pub(crate) struct SigningState {
     timestamp: u64,
-    stream_timestamps: HashMap<(u8, u8, u8), u64>,
+    stream_timestamps: StreamTimestamps,
+}
+
+// latest timestamp of each link, system and component
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct StreamTimestamps(HashMap<(u8, u8, u8), u64>);
+
+// link, system and component id of a stream and its latest timestamp
+#[cfg(not(feature = "std"))]
+type StreamTimestamp = ((u8, u8, u8), u64);
+
+// without a heap only the latest `MAX_STREAMS` streams are tracked, a forgotten stream is
+// checked as a new one
+#[cfg(not(feature = "std"))]
+#[derive(Default)]
+struct StreamTimestamps([Option<StreamTimestamp>; MAX_STREAMS]);
+
+#[cfg(feature = "std")]
+impl StreamTimestamps {
+    fn get(&self, stream: &(u8, u8, u8)) -> Option<&u64> {
+        self.0.get(stream)
+    }
+
+    fn insert(&mut self, stream: (u8, u8, u8), timestamp: u64) {
+        self.0.insert(stream, timestamp);
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl StreamTimestamps {
+    fn get(&self, stream: &(u8, u8, u8)) -> Option<&u64> {
+        self.0
+            .iter()
+            .flatten()
+            .find(|(key, _)| key == stream)
+            .map(|(_, timestamp)| timestamp)
+    }
+
+    fn insert(&mut self, stream: (u8, u8, u8), timestamp: u64) {
+        let slot = match self
+            .0
+            .iter()
+            .position(|entry| entry.map_or(true, |(key, _)| key == stream))
+        {
+            Some(index) => index,
+            // replace the stream with the oldest timestamp
+            None => (0..MAX_STREAMS)
+                .min_by_key(|index| self.0[*index].map_or(0, |(_, timestamp)| timestamp))
+                .unwrap_or(0),
+        };
+        self.0[slot] = Some((stream, timestamp));
+    }
 }
 
 /// MAVLink 2 message signing data.
+///
+/// Without `std` the state is held in a [`RefCell`](core::cell::RefCell), so the signing data
+/// of a connection can't be shared between threads or interrupt handlers.
+///
+/// Without `std` the timestamps of only 16 link, system and component streams are tracked.
+/// Beyond that the stream with the oldest timestamp is forgotten and then checked as a new
+/// stream, so its frames of the last minute can be replayed.
 pub struct SigningData {
     pub(crate) config: SigningConfig,
+    #[cfg(feature = "std")]
     pub(crate) state: Mutex<SigningState>,
+    #[cfg(not(feature = "std"))]
+    pub(crate) state: core::cell::RefCell<SigningState>,
 }
 
 impl SigningConfig {
@@ -33,81 +343,286 @@ impl SigningConfig {
         sign_outgoing: bool,
         allow_unsigned: bool,
     ) -> Self {
+        Self::from_signer(
+            Signer::Key(Sha256Signer::new(secret_key)),
+            link_id,
+            sign_outgoing,
+            allow_unsigned,
+        )
+    }
+
+    /// Create a configuration computing signatures with `signer` instead of a secret key held
+    /// in memory.
+    #[cfg(feature = "std")]
+    pub fn with_signer<S: Mavlink2Signer + 'static>(
+        signer: S,
+        link_id: u8,
+        sign_outgoing: bool,
+        allow_unsigned: bool,
+    ) -> Self {
+        Self::from_signer(
+            Signer::Shared(Arc::new(signer)),
+            link_id,
+            sign_outgoing,
+            allow_unsigned,
+        )
+    }
+
+    /// Create a configuration computing signatures with a `'static` `signer`, such as a hardware
+    /// crypto peripheral, without allocating.
+    pub fn with_static_signer(
+        signer: &'static dyn Mavlink2Signer,
+        link_id: u8,
+        sign_outgoing: bool,
+        allow_unsigned: bool,
+    ) -> Self {
+        Self::from_signer(
+            Signer::Static(signer),
+            link_id,
+            sign_outgoing,
+            allow_unsigned,
+        )
+    }
+
+    fn from_signer(signer: Signer, link_id: u8, sign_outgoing: bool, allow_unsigned: bool) -> Self {
         Self {
-            secret_key,
+            signer,
+            key_store: None,
+            #[cfg(feature = "std")]
+            accept_unsigned: None,
+            #[cfg(feature = "std")]
+            timestamp_source: Some(Arc::new(SystemClockTimestamp)),
+            #[cfg(not(feature = "std"))]
+            timestamp_source: None,
             link_id,
             sign_outgoing,
             allow_unsigned,
+            report_rejected: false,
         }
     }
+
+    /// Return the frames failing signature verification from reads as
+    /// [`MessageReadError::Signature`](crate::error::MessageReadError::Signature) errors, instead
+    /// of silently skipping them
+    pub fn with_rejection_errors(mut self, report: bool) -> Self {
+        self.report_rejected = report;
+        self
+    }
+
+    /// Verify received frames with the signer `store` holds for their link id, instead of the
+    /// signer of this configuration, which still signs the sent frames
+    pub fn with_key_store(mut self, store: Shared<dyn SigningKeyStore>) -> Self {
+        self.key_store = Some(store);
+        self
+    }
+
+    /// Accept the unsigned frames for which `callback` returns `true`, given their header and
+    /// message id, when unsigned frames aren't allowed, like `allow_unsigned_callback` of
+    /// pymavlink. For example, to accept the `RADIO_STATUS` messages of SiK radios:
+    ///
+    /// ```ignore
+    /// let config = SigningConfig::new(key, 0, true, false)
+    ///     .with_unsigned_callback(|_header, message_id| message_id == RADIO_STATUS_DATA::ID);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_unsigned_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&MavHeader, u32) -> bool + Send + Sync + 'static,
+    {
+        self.accept_unsigned = Some(Arc::new(callback));
+        self
+    }
+
+    /// Take the time of signing timestamps from `source` instead of the system clock
+    pub fn with_timestamp_source(mut self, source: Shared<dyn SigningTimestampSource>) -> Self {
+        self.timestamp_source = Some(source);
+        self
+    }
+
+    /// Signing timestamp of the current time, in units of 10 microseconds since 2015
+    pub(crate) fn current_timestamp(&self) -> u64 {
+        // fallback to 0 if the time is unknown
+        let now = self
+            .timestamp_source
+            .as_ref()
+            .and_then(|source| source.unix_time_us())
+            .unwrap_or(0);
+        // use 1st January 2015 GMT as offset, fallback to 0 if before that date, the used 48bit of this will overflow in 2104
+        now.saturating_sub(MAVLINK_EPOCH_UNIX_US) / 10
+    }
+}
+
+impl SigningConfig {
+    #[cfg(feature = "std")]
+    fn accepts_some_unsigned(&self) -> bool {
+        self.accept_unsigned.is_some()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn accepts_some_unsigned(&self) -> bool {
+        false
+    }
+
+    /// Whether the unsigned `message` is accepted
+    fn accepts_unsigned(&self, message: &MAVLinkV2MessageRaw) -> bool {
+        #[cfg(feature = "std")]
+        if let Some(accept) = &self.accept_unsigned {
+            let info = MavFrameInfo::from(message);
+            if accept(&info.header, info.message_id) {
+                return true;
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = message;
+        self.allow_unsigned
+    }
+}
+
+impl Debug for SigningConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SigningConfig")
+            .field("link_id", &self.link_id)
+            .field("sign_outgoing", &self.sign_outgoing)
+            .field("allow_unsigned", &self.allow_unsigned)
+            .field("report_rejected", &self.report_rejected)
+            .field("key_store", &self.key_store.is_some())
+            .field("accept_unsigned", &self.accepts_some_unsigned())
+            .finish_non_exhaustive()
+    }
 }
 
 impl SigningData {
     pub fn from_config(config: SigningConfig) -> Self {
         Self {
             config,
-            state: Mutex::new(SigningState {
+            state: SigningState {
                 timestamp: 0,
-                stream_timestamps: HashMap::new(),
-            }),
+                stream_timestamps: StreamTimestamps::default(),
+            }
+            .into(),
         }
     }
 
+    // The code that holds the mutex lock is not expected to panic, therefore the expect is justified.
+    // The only issue that might cause a panic, presuming the opertions on the message buffer are sound,
+    // is the time source called in `current_timestamp()`.
+    #[cfg(feature = "std")]
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SigningState> {
+        self.state
+            .lock()
+            .expect("Code holding MutexGuard should not panic.")
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn lock_state(&self) -> core::cell::RefMut<'_, SigningState> {
+        self.state.borrow_mut()
+    }
+
     /// Verify the signature of a MAVLink 2 message.
     pub fn verify_signature(&self, message: &MAVLinkV2MessageRaw) -> bool {
-        // The code that holds the mutex lock is not expected to panic, therefore the expect is justified.
-        // The only issue that might cause a panic, presuming the opertions on the message buffer are sound,
-        // is the `SystemTime::now()` call in `get_current_timestamp()`.
-        let mut state = self
-            .state
-            .lock()
-            .expect("Code holding MutexGuard should not panic.");
-        if message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED > 0 {
-            state.timestamp = u64::max(state.timestamp, Self::get_current_timestamp());
-            let timestamp = message.signature_timestamp();
-            let src_system = message.system_id();
-            let src_component = message.component_id();
-            let stream_key = (message.signature_link_id(), src_system, src_component);
-            match state.stream_timestamps.get(&stream_key) {
-                Some(stream_timestamp) => {
-                    if timestamp <= *stream_timestamp {
-                        // reject old timestamp
-                        return false;
-                    }
+        self.check_signature(message).is_ok()
+    }
+
+    /// Verify the signature of a MAVLink 2 message, returning why it is rejected.
+    ///
+    /// The signer is the one of the key store for the link id of the frame if the configuration
+    /// has one, see [`SigningConfig::with_key_store`].
+    ///
+    /// The timestamp of a signed frame must be newer than the previous one of its link, system
+    /// and component. The first frame of each of them may be up to a minute older than the
+    /// latest timestamp. Accepted frames update the timestamps.
+    pub fn check_signature(&self, message: &MAVLinkV2MessageRaw) -> Result<(), SignatureError> {
+        let mut state = self.lock_state();
+        self.validate(&state, message)?;
+        self.record(&mut state, message);
+        Ok(())
+    }
+
+    /// Verify the signature of a MAVLink 2 message like [`check_signature`](Self::check_signature),
+    /// without updating the timestamps, so that a frame that is only peeked at is still accepted
+    /// when it is received.
+    pub fn validate_signature(&self, message: &MAVLinkV2MessageRaw) -> Result<(), SignatureError> {
+        let state = self.lock_state();
+        self.validate(&state, message)
+    }
+
+    /// Update the timestamps with a frame accepted by
+    /// [`validate_signature`](Self::validate_signature)
+    pub(crate) fn record_signature(&self, message: &MAVLinkV2MessageRaw) {
+        let mut state = self.lock_state();
+        self.record(&mut state, message);
+    }
+
+    fn validate(
+        &self,
+        state: &SigningState,
+        message: &MAVLinkV2MessageRaw,
+    ) -> Result<(), SignatureError> {
+        if message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED == 0 {
+            return if self.config.accepts_unsigned(message) {
+                Ok(())
+            } else {
+                Err(SignatureError::Unsigned)
+            };
+        }
+
+        let latest_timestamp = u64::max(state.timestamp, self.current_timestamp());
+        let timestamp = message.signature_timestamp();
+        let stream_key = (
+            message.signature_link_id(),
+            message.system_id(),
+            message.component_id(),
+        );
+        match state.stream_timestamps.get(&stream_key) {
+            Some(stream_timestamp) => {
+                if timestamp <= *stream_timestamp {
+                    // reject old timestamp
+                    return Err(SignatureError::Replayed);
                 }
-                None => {
-                    if timestamp + 60 * 1000 * 100 < state.timestamp {
-                        // bad new stream, more then a minute older the the last one
-                        return false;
-                    }
+            }
+            None => {
+                if timestamp + 60 * 1000 * 100 < latest_timestamp {
+                    // bad new stream, more then a minute older the the last one
+                    return Err(SignatureError::Stale);
                 }
             }
+        }
 
-            let mut signature_buffer = [0u8; 6];
-            message.calculate_signature(&self.config.secret_key, &mut signature_buffer);
-            let result = signature_buffer == message.signature_value();
-            if result {
-                // if signature is valid update timestamps
-                state.stream_timestamps.insert(stream_key, timestamp);
-                state.timestamp = u64::max(state.timestamp, timestamp)
-            }
-            result
-        } else {
-            self.config.allow_unsigned
+        let signer = match &self.config.key_store {
+            Some(store) => store
+                .signer(message.signature_link_id())
+                .ok_or(SignatureError::UnknownLink(message.signature_link_id()))?,
+            None => self.config.signer.get(),
+        };
+        let mut signature_buffer = [0u8; 6];
+        signer.sign(message.signed_data(), &mut signature_buffer);
+        if signature_buffer != message.signature_value() {
+            return Err(SignatureError::Invalid);
         }
+        Ok(())
+    }
+
+    // a valid signature updates the timestamps
+    fn record(&self, state: &mut SigningState, message: &MAVLinkV2MessageRaw) {
+        if message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED == 0 {
+            return;
+        }
+        let timestamp = message.signature_timestamp();
+        let stream_key = (
+            message.signature_link_id(),
+            message.system_id(),
+            message.component_id(),
+        );
+        state.stream_timestamps.insert(stream_key, timestamp);
+        state.timestamp = u64::max(state.timestamp, self.current_timestamp());
+        state.timestamp = u64::max(state.timestamp, timestamp);
     }
 
     /// Sign a MAVLink 2 message if its incompatibility flag is set accordingly.
     pub fn sign_message(&self, message: &mut MAVLinkV2MessageRaw) {
         if message.incompatibility_flags() & MAVLINK_IFLAG_SIGNED > 0 {
-            // The code that holds the mutex lock is not expected to panic, therefore the expect is justified.
-            // The only issue that might cause a panic, presuming the opertions on the message buffer are sound,
-            // is the `SystemTime::now()` call in `get_current_timestamp()`.
-            let mut state = self
-                .state
-                .lock()
-                .expect("Code holding MutexGuard should not panic.");
-            state.timestamp = u64::max(state.timestamp, Self::get_current_timestamp());
+            let mut state = self.lock_state();
+            state.timestamp = u64::max(state.timestamp, self.current_timestamp());
             let ts_bytes = u64::to_le_bytes(state.timestamp);
             message
                 .signature_timestamp_bytes_mut()
@@ -115,7 +630,10 @@ impl SigningData {
             *message.signature_link_id_mut() = self.config.link_id;
 
             let mut signature_buffer = [0u8; 6];
-            message.calculate_signature(&self.config.secret_key, &mut signature_buffer);
+            self.config
+                .signer
+                .get()
+                .sign(message.signed_data(), &mut signature_buffer);
 
             message
                 .signature_value_mut()
@@ -124,16 +642,7 @@ impl SigningData {
         }
     }
 
-    fn get_current_timestamp() -> u64 {
-        // fallback to 0 if the system time appears to be before epoch
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map(|n| n.as_micros())
-            .unwrap_or(0);
-        // use 1st January 2015 GMT as offset, fallback to 0 if before that date, the used 48bit of this will overflow in 2104
-        ((now
-            .checked_sub(1420070400u128 * 1000000u128)
-            .unwrap_or_default())
-            / 10u128) as u64
+    fn current_timestamp(&self) -> u64 {
+        self.config.current_timestamp()
     }
 }
@@ -0,0 +1,51 @@
+use crate::MavHeader;
+
+/// Receive-side filter dropping the frames sent by a set of systems.
+///
+/// When several links are bridged, a link may echo our own traffic back to us. Ignoring the
+/// frames whose source is the local system, or any system known to be reached through another
+/// link, prevents such feedback loops.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SourceFilter {
+    ignored: [u64; 4],
+}
+
+impl SourceFilter {
+    /// Ignore the frames sent by the local system `system_id`
+    pub fn new(system_id: u8) -> Self {
+        Self::default().ignore(system_id)
+    }
+
+    /// Also ignore the frames sent by `system_id`
+    pub fn ignore(mut self, system_id: u8) -> Self {
+        self.ignored[system_id as usize / 64] |= 1 << (system_id % 64);
+        self
+    }
+
+    pub fn ignores(&self, system_id: u8) -> bool {
+        self.ignored[system_id as usize / 64] & (1 << (system_id % 64)) != 0
+    }
+
+    /// Whether the frame with `header` was sent by a system that is not ignored
+    pub fn accepts(&self, header: &MavHeader) -> bool {
+        !self.ignores(header.system_id)
+    }
+
+    /// Whether the frame with `header` passes `filter`, accepting everything when there is no
+    /// filter
+    #[cfg(any(feature = "std", feature = "tokio-1"))]
+    pub(crate) fn allows(filter: Option<&Self>, header: &MavHeader) -> bool {
+        filter.map_or(true, |filter| filter.accepts(header))
+    }
+}
+
+impl FromIterator<u8> for SourceFilter {
+    /// Ignore the frames sent by any of the given systems
+    fn from_iter<I: IntoIterator<Item = u8>>(system_ids: I) -> Self {
+        system_ids
+            .into_iter()
+            .fold(Self::default(), |filter, system_id| {
+                filter.ignore(system_id)
+            })
+    }
+}
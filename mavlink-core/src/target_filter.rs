@@ -0,0 +1,39 @@
+use crate::Message;
+
+/// Receive-side filter delivering only the messages meant for a given component.
+///
+/// Messages without target fields, broadcasts (target system `0`) and messages addressed to
+/// every component of this system (target component `0`) are always accepted. Messages
+/// targeted at another system or component are rejected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TargetFilter {
+    pub system_id: u8,
+    pub component_id: u8,
+}
+
+impl TargetFilter {
+    pub fn new(system_id: u8, component_id: u8) -> Self {
+        Self {
+            system_id,
+            component_id,
+        }
+    }
+
+    /// Whether `message` is a broadcast or addressed to this component
+    pub fn accepts<M: Message>(&self, message: &M) -> bool {
+        match message.target_system_id() {
+            None | Some(0) => true,
+            Some(system_id) if system_id != self.system_id => false,
+            Some(_) => match message.target_component_id() {
+                None | Some(0) => true,
+                Some(component_id) => component_id == self.component_id,
+            },
+        }
+    }
+
+    /// Whether `message` passes `filter`, accepting everything when there is no filter
+    #[cfg(any(feature = "std", feature = "tokio-1"))]
+    pub(crate) fn allows<M: Message>(filter: Option<&Self>, message: &M) -> bool {
+        filter.map_or(true, |filter| filter.accepts(message))
+    }
+}
@@ -0,0 +1,229 @@
+//! Cache of the latest received instance of every message
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::error::MessageReadError;
+use crate::{MavConnection, MavHeader, MavlinkVersion, Message, MessageData, MAX_FRAME_SIZE};
+
+#[cfg(feature = "tokio-1")]
+use crate::AsyncMavConnection;
+#[cfg(feature = "tokio-1")]
+use std::marker::PhantomData;
+#[cfg(feature = "tokio-1")]
+use tokio::sync::watch;
+
+/// Message stored by a [`TelemetryCache`]
+#[derive(Debug, Clone)]
+pub struct CachedMessage<M> {
+    pub header: MavHeader,
+    pub message: M,
+    pub received: Instant,
+}
+
+struct Entries<M> {
+    latest: HashMap<u32, CachedMessage<M>>,
+    by_source: HashMap<(u8, u8, u32), CachedMessage<M>>,
+}
+
+/// Keeps the most recent instance of every message type received on a connection, so consumers
+/// can read the current state of a vehicle without processing the full stream.
+///
+/// The cache is fed with [`handle_message`](Self::handle_message), or by a thread or task running
+/// [`run`](Self::run) on a connection, and can be shared behind an [`Arc`](std::sync::Arc) with
+/// the code reading it:
+///
+/// ```ignore
+/// let cache = Arc::new(TelemetryCache::<MavMessage>::new());
+/// let feeder = cache.clone();
+/// std::thread::spawn(move || feeder.run(connection.as_ref()));
+/// // later
+/// let attitude = cache.get::<ATTITUDE_DATA>();
+/// ```
+pub struct TelemetryCache<M> {
+    per_source: bool,
+    entries: Mutex<Entries<M>>,
+    #[cfg(feature = "tokio-1")]
+    watchers: Mutex<HashMap<u32, watch::Sender<Option<CachedMessage<M>>>>>,
+}
+
+impl<M: Message + Clone> Default for TelemetryCache<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message + Clone> TelemetryCache<M> {
+    /// Cache keeping the latest message of each type, whichever system sent it
+    pub fn new() -> Self {
+        Self {
+            per_source: false,
+            entries: Mutex::new(Entries {
+                latest: HashMap::new(),
+                by_source: HashMap::new(),
+            }),
+            #[cfg(feature = "tokio-1")]
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Also keep the latest message of each type per sending system and component, to be read
+    /// with [`get_from`](Self::get_from)
+    pub fn with_per_source(mut self, per_source: bool) -> Self {
+        self.per_source = per_source;
+        self
+    }
+
+    /// Store `message` as the latest of its type
+    pub fn handle_message(&self, header: &MavHeader, message: &M) {
+        let message_id = message.message_id();
+        let cached = CachedMessage {
+            header: *header,
+            message: message.clone(),
+            received: Instant::now(),
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if self.per_source {
+            let key = (header.system_id, header.component_id, message_id);
+            entries.by_source.insert(key, cached.clone());
+        }
+        // notified while the entries are locked, so watchers see messages in order
+        #[cfg(feature = "tokio-1")]
+        if let Some(sender) = self.watchers.lock().unwrap().get(&message_id) {
+            sender.send_replace(Some(cached.clone()));
+        }
+        entries.latest.insert(message_id, cached);
+    }
+
+    /// Receive messages from `connection` into the cache until it returns an error
+    pub fn run<C: MavConnection<M> + ?Sized>(&self, connection: &C) -> MessageReadError {
+        loop {
+            match connection.recv() {
+                Ok((header, message)) => self.handle_message(&header, &message),
+                Err(e) => return e,
+            }
+        }
+    }
+
+    /// Receive messages from the async `connection` into the cache until it returns an error
+    #[cfg(feature = "tokio-1")]
+    pub async fn run_async<C>(&self, connection: &C) -> MessageReadError
+    where
+        M: Sync + Send,
+        C: AsyncMavConnection<M> + ?Sized,
+    {
+        loop {
+            match connection.recv().await {
+                Ok((header, message)) => self.handle_message(&header, &message),
+                Err(e) => return e,
+            }
+        }
+    }
+
+    /// Latest message with id `message_id` from any source
+    pub fn get_message(&self, message_id: u32) -> Option<CachedMessage<M>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .latest
+            .get(&message_id)
+            .cloned()
+    }
+
+    /// Latest message with id `message_id` sent by a component, if the cache keeps messages
+    /// per source
+    pub fn get_message_from(
+        &self,
+        system_id: u8,
+        component_id: u8,
+        message_id: u32,
+    ) -> Option<CachedMessage<M>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .by_source
+            .get(&(system_id, component_id, message_id))
+            .cloned()
+    }
+
+    /// Latest message of type `D` from any source
+    pub fn get<D: MessageData<Message = M>>(&self) -> Option<D> {
+        self.get_message(D::ID)
+            .and_then(|cached| message_data(&cached.message))
+    }
+
+    /// Latest message of type `D` sent by a component, if the cache keeps messages per source
+    pub fn get_from<D: MessageData<Message = M>>(
+        &self,
+        system_id: u8,
+        component_id: u8,
+    ) -> Option<D> {
+        self.get_message_from(system_id, component_id, D::ID)
+            .and_then(|cached| message_data(&cached.message))
+    }
+
+    /// Subscribe to the messages of type `D`, from any source.
+    ///
+    /// The subscription starts with the message currently in the cache, if any.
+    #[cfg(feature = "tokio-1")]
+    pub fn watch<D: MessageData<Message = M>>(&self) -> TelemetryWatch<M, D> {
+        // the entries stay locked so no message is missed before the channel exists
+        let entries = self.entries.lock().unwrap();
+        let receiver = self
+            .watchers
+            .lock()
+            .unwrap()
+            .entry(D::ID)
+            .or_insert_with(|| watch::channel(entries.latest.get(&D::ID).cloned()).0)
+            .subscribe();
+        TelemetryWatch {
+            receiver,
+            data: PhantomData,
+        }
+    }
+
+    /// Remove every cached message
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.latest.clear();
+        entries.by_source.clear();
+    }
+}
+
+/// Subscription to a message type of a [`TelemetryCache`], created by
+/// [`TelemetryCache::watch`]
+#[cfg(feature = "tokio-1")]
+pub struct TelemetryWatch<M, D> {
+    receiver: watch::Receiver<Option<CachedMessage<M>>>,
+    data: PhantomData<fn() -> D>,
+}
+
+#[cfg(feature = "tokio-1")]
+impl<M: Message + Clone, D: MessageData<Message = M>> TelemetryWatch<M, D> {
+    /// Wait for the next message, returns `None` once the cache is dropped
+    pub async fn changed(&mut self) -> Option<(MavHeader, D)> {
+        loop {
+            self.receiver.changed().await.ok()?;
+            if let Some(latest) = self.latest() {
+                return Some(latest);
+            }
+        }
+    }
+
+    /// Latest message with its header, without waiting
+    pub fn latest(&self) -> Option<(MavHeader, D)> {
+        let cached = self.receiver.borrow();
+        let cached = cached.as_ref()?;
+        message_data(&cached.message).map(|data| (cached.header, data))
+    }
+}
+
+fn message_data<M: Message, D: MessageData<Message = M>>(message: &M) -> Option<D> {
+    if message.message_id() != D::ID {
+        return None;
+    }
+    let mut payload = [0u8; MAX_FRAME_SIZE];
+    let len = message.ser(MavlinkVersion::V2, &mut payload);
+    D::deser(MavlinkVersion::V2, &payload[..len]).ok()
+}
@@ -0,0 +1,293 @@
+//! Conversion of remote message timestamps into local time
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// `time_usec` values above this are UNIX epoch times, values below are times since boot.
+///
+/// This is the threshold used by the MAVLink reference implementations (about 1 year after
+/// 1970, far beyond any plausible uptime).
+const UNIX_EPOCH_THRESHOLD_US: u64 = 365 * 24 * 3600 * 1_000_000;
+
+/// Weight of a new sample when correcting the offset estimate
+const OFFSET_GAIN: f64 = 0.2;
+/// Weight of a new sample when correcting the drift estimate
+const DRIFT_GAIN: f64 = 0.05;
+
+/// Interpretation of a `time_usec` field, which holds either a UNIX epoch time or the time
+/// since the boot of the sender
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MavTimestamp {
+    SinceBoot(Duration),
+    Unix(SystemTime),
+}
+
+impl MavTimestamp {
+    pub fn from_time_usec(time_usec: u64) -> Self {
+        let time = Duration::from_micros(time_usec);
+        if time_usec > UNIX_EPOCH_THRESHOLD_US {
+            Self::Unix(UNIX_EPOCH + time)
+        } else {
+            Self::SinceBoot(time)
+        }
+    }
+}
+
+/// Estimate of the clock of a remote system relative to the local clock.
+///
+/// The estimate is fed with the `TIMESYNC` exchange ([`Self::timesync_ts1`] and
+/// [`Self::handle_timesync`]) and tracks both the offset between the remote boot time and the
+/// local monotonic clock and the drift between the two clocks. `SYSTEM_TIME` messages passed
+/// to [`Self::handle_system_time`] additionally provide the UNIX time of the remote system.
+///
+/// The remote boot time is expressed in microseconds, `time_boot_ms` values can be converted
+/// with [`Self::boot_ms_to_instant`] and similar.
+#[derive(Debug, Clone)]
+pub struct TimeBase {
+    /// Local reference all local timestamps are relative to
+    epoch: Instant,
+    /// Wall clock time at `epoch`
+    epoch_system_time: SystemTime,
+    /// Last estimate of remote boot time minus local time, in microseconds
+    offset_us: Option<f64>,
+    /// Local time the offset estimate was last updated at, in microseconds
+    updated_us: f64,
+    /// Remote microseconds elapsed per local microsecond, minus one
+    drift: f64,
+    /// Remote UNIX time minus remote boot time, in microseconds
+    unix_offset_us: Option<i128>,
+}
+
+impl Default for TimeBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeBase {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            epoch_system_time: SystemTime::now(),
+            offset_us: None,
+            updated_us: 0.0,
+            drift: 0.0,
+            unix_offset_us: None,
+        }
+    }
+
+    /// Whether a clock offset was estimated yet
+    pub fn is_synchronized(&self) -> bool {
+        self.offset_us.is_some()
+    }
+
+    /// Current estimate of the remote clock drift, in parts per million
+    pub fn drift_ppm(&self) -> f64 {
+        self.drift * 1e6
+    }
+
+    /// Local timestamp to send as `ts1` (with `tc1` set to `0`) in a `TIMESYNC` request
+    pub fn timesync_ts1(&self, now: Instant) -> i64 {
+        self.local_us(now) as i64 * 1000
+    }
+
+    /// Update the estimate from a received `TIMESYNC` message.
+    ///
+    /// Only responses to requests created with [`Self::timesync_ts1`] are used, the remote
+    /// clock is assumed to have been sampled halfway through the round trip. Returns whether
+    /// the message was a response.
+    pub fn handle_timesync(&mut self, tc1: i64, ts1: i64, now: Instant) -> bool {
+        let sent_us = ts1 as f64 / 1000.0;
+        let now_us = self.local_us(now);
+        if tc1 == 0 || sent_us < 0.0 || sent_us > now_us {
+            return false;
+        }
+        let remote_us = tc1 as f64 / 1000.0;
+        self.add_sample(remote_us - (sent_us + now_us) / 2.0, now_us);
+        true
+    }
+
+    /// Record the remote UNIX time from a received `SYSTEM_TIME` message.
+    ///
+    /// A `time_unix_usec` of `0` means the remote system does not know the UNIX time and is
+    /// ignored.
+    pub fn handle_system_time(&mut self, time_unix_usec: u64, time_boot_ms: u32) {
+        if time_unix_usec != 0 {
+            self.unix_offset_us =
+                Some(i128::from(time_unix_usec) - i128::from(time_boot_ms) * 1000);
+        }
+    }
+
+    /// Local monotonic instant corresponding to a remote time since boot in microseconds
+    pub fn boot_us_to_instant(&self, time_boot_us: u64) -> Option<Instant> {
+        let offset_us = self.offset_us?;
+        // remote = local + offset + drift * (local - updated), solved for local
+        let local_us =
+            (time_boot_us as f64 - offset_us + self.drift * self.updated_us) / (1.0 + self.drift);
+        let local = Duration::from_micros(local_us.abs().round() as u64);
+        if local_us >= 0.0 {
+            self.epoch.checked_add(local)
+        } else {
+            self.epoch.checked_sub(local)
+        }
+    }
+
+    /// Local monotonic instant corresponding to a remote `time_boot_ms`
+    pub fn boot_ms_to_instant(&self, time_boot_ms: u32) -> Option<Instant> {
+        self.boot_us_to_instant(u64::from(time_boot_ms) * 1000)
+    }
+
+    /// Local wall clock time corresponding to a remote time since boot in microseconds
+    pub fn boot_us_to_system_time(&self, time_boot_us: u64) -> Option<SystemTime> {
+        let instant = self.boot_us_to_instant(time_boot_us)?;
+        match instant.checked_duration_since(self.epoch) {
+            Some(elapsed) => self.epoch_system_time.checked_add(elapsed),
+            None => self
+                .epoch_system_time
+                .checked_sub(self.epoch.duration_since(instant)),
+        }
+    }
+
+    /// Local wall clock time corresponding to a remote `time_boot_ms`
+    pub fn boot_ms_to_system_time(&self, time_boot_ms: u32) -> Option<SystemTime> {
+        self.boot_us_to_system_time(u64::from(time_boot_ms) * 1000)
+    }
+
+    /// UNIX time of the remote clock corresponding to a remote time since boot in
+    /// microseconds, as reported by `SYSTEM_TIME`
+    pub fn boot_us_to_remote_unix_time(&self, time_boot_us: u64) -> Option<SystemTime> {
+        let unix_us = i128::from(time_boot_us) + self.unix_offset_us?;
+        let unix_us = u64::try_from(unix_us).ok()?;
+        UNIX_EPOCH.checked_add(Duration::from_micros(unix_us))
+    }
+
+    /// Local monotonic instant corresponding to a `time_usec` field
+    ///
+    /// UNIX epoch times are converted using the local wall clock, times since boot using the
+    /// estimated remote clock offset.
+    pub fn time_usec_to_instant(&self, time_usec: u64) -> Option<Instant> {
+        match MavTimestamp::from_time_usec(time_usec) {
+            MavTimestamp::SinceBoot(_) => self.boot_us_to_instant(time_usec),
+            MavTimestamp::Unix(time) => match time.duration_since(self.epoch_system_time) {
+                Ok(elapsed) => self.epoch.checked_add(elapsed),
+                Err(e) => self.epoch.checked_sub(e.duration()),
+            },
+        }
+    }
+
+    /// Local wall clock time corresponding to a `time_usec` field
+    pub fn time_usec_to_system_time(&self, time_usec: u64) -> Option<SystemTime> {
+        match MavTimestamp::from_time_usec(time_usec) {
+            MavTimestamp::SinceBoot(_) => self.boot_us_to_system_time(time_usec),
+            MavTimestamp::Unix(time) => Some(time),
+        }
+    }
+
+    fn local_us(&self, instant: Instant) -> f64 {
+        match instant.checked_duration_since(self.epoch) {
+            Some(elapsed) => elapsed.as_secs_f64() * 1e6,
+            None => -(self.epoch.duration_since(instant).as_secs_f64() * 1e6),
+        }
+    }
+
+    fn add_sample(&mut self, offset_us: f64, now_us: f64) {
+        let Some(previous_us) = self.offset_us else {
+            self.offset_us = Some(offset_us);
+            self.updated_us = now_us;
+            return;
+        };
+        let elapsed_us = now_us - self.updated_us;
+        let predicted_us = previous_us + self.drift * elapsed_us;
+        let error_us = offset_us - predicted_us;
+        self.offset_us = Some(predicted_us + OFFSET_GAIN * error_us);
+        if elapsed_us > 0.0 {
+            self.drift += DRIFT_GAIN * error_us / elapsed_us;
+        }
+        self.updated_us = now_us;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn us(instant: Instant, base: &TimeBase) -> f64 {
+        base.local_us(instant)
+    }
+
+    #[test]
+    fn test_timesync_offset() {
+        let mut base = TimeBase::new();
+        assert!(base.boot_ms_to_instant(1000).is_none());
+
+        let sent = base.epoch + Duration::from_millis(10);
+        let received = sent + Duration::from_millis(20);
+        // remote clock sampled 20 s after its boot, halfway through the round trip
+        assert!(base.handle_timesync(20_000_000_000, base.timesync_ts1(sent), received));
+        assert!(base.is_synchronized());
+
+        let instant = base.boot_ms_to_instant(20_000).unwrap();
+        assert!((us(instant, &base) - 20_000.0).abs() < 1.0);
+
+        let instant = base.boot_ms_to_instant(21_000).unwrap();
+        assert!((us(instant, &base) - 1_020_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_timesync_ignores_requests() {
+        let mut base = TimeBase::new();
+        assert!(!base.handle_timesync(0, 1_000_000, base.epoch));
+        assert!(!base.is_synchronized());
+    }
+
+    #[test]
+    fn test_drift_tracking() {
+        let mut base = TimeBase::new();
+        // remote clock runs 100 ppm fast and booted 5 s before the local epoch
+        let remote_ns = |local_us: f64| ((local_us * (1.0 + 100e-6) + 5e6) * 1000.0) as i64;
+        for second in 0..200u64 {
+            let sent = base.epoch + Duration::from_secs(second);
+            let received = sent + Duration::from_millis(2);
+            let midpoint_us = us(sent, &base) + 1000.0;
+            base.handle_timesync(remote_ns(midpoint_us), base.timesync_ts1(sent), received);
+        }
+        assert!((base.drift_ppm() - 100.0).abs() < 5.0);
+
+        let local_us = 250e6;
+        let remote_us = remote_ns(local_us) as u64 / 1000;
+        let instant = base.boot_us_to_instant(remote_us).unwrap();
+        assert!((us(instant, &base) - local_us).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_system_time() {
+        let mut base = TimeBase::new();
+        base.handle_system_time(1_700_000_000_000_000, 10_000);
+        assert_eq!(
+            base.boot_us_to_remote_unix_time(11_000_000),
+            Some(UNIX_EPOCH + Duration::from_micros(1_700_000_001_000_000))
+        );
+
+        base.handle_system_time(0, 12_000);
+        assert!(base.boot_us_to_remote_unix_time(0).is_some());
+    }
+
+    #[test]
+    fn test_time_usec() {
+        let base = TimeBase::new();
+        let unix = 1_700_000_000_000_000;
+        assert_eq!(
+            MavTimestamp::from_time_usec(unix),
+            MavTimestamp::Unix(UNIX_EPOCH + Duration::from_micros(unix))
+        );
+        assert_eq!(
+            MavTimestamp::from_time_usec(42),
+            MavTimestamp::SinceBoot(Duration::from_micros(42))
+        );
+        assert_eq!(
+            base.time_usec_to_system_time(unix),
+            Some(UNIX_EPOCH + Duration::from_micros(unix))
+        );
+        // times since boot need a synchronized clock
+        assert!(base.time_usec_to_system_time(42).is_none());
+    }
+}
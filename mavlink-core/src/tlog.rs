@@ -0,0 +1,354 @@
+//! Reading, writing and playback of telemetry logs (tlog)
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::MessageWriteError;
+use crate::{
+    write_versioned_msg, MAVLinkV1MessageRaw, MAVLinkV2MessageRaw, MavHeader, MavlinkVersion,
+    Message, MAVLINK_IFLAG_SIGNED, MAV_STX, MAV_STX_V2,
+};
+
+/// Writer of telemetry logs, the format read by `file:` connections and most ground stations.
+///
+/// Each record is the UNIX time in microseconds as a big-endian `u64`, followed by the MAVLink
+/// frame. The output is flushed when the writer is dropped.
+///
+/// Logs are compressed by writing to a compressing encoder, for example a gzip or zstd encoder
+/// wrapping the file:
+///
+/// ```ignore
+/// let file = std::fs::File::create("flight.tlog.gz")?;
+/// let mut log = TlogWriter::new(flate2::write::GzEncoder::new(file, Default::default()));
+/// ```
+///
+/// The encoder is flushed on drop as well, encoders that need to be finished explicitly
+/// can be recovered with [`into_inner`](Self::into_inner).
+pub struct TlogWriter<W: Write> {
+    writer: Option<W>,
+}
+
+impl<W: Write> TlogWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+        }
+    }
+
+    /// Write a raw frame recorded at `timestamp_us`, in microseconds since the UNIX epoch
+    pub fn write_frame(&mut self, timestamp_us: u64, frame: &[u8]) -> io::Result<()> {
+        let writer = self.get_mut();
+        writer.write_all(&timestamp_us.to_be_bytes())?;
+        writer.write_all(frame)
+    }
+
+    /// Write `message` as a frame recorded now, returning the length of the frame
+    pub fn write_message<M: Message>(
+        &mut self,
+        version: MavlinkVersion,
+        header: MavHeader,
+        message: &M,
+    ) -> Result<usize, MessageWriteError> {
+        let mut frame = Vec::new();
+        let len = write_versioned_msg(&mut frame, version, header, message)?;
+        self.write_frame(now_us(), &frame)?;
+        Ok(len)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer
+            .as_ref()
+            .expect("writer is only taken by into_inner")
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken by into_inner")
+    }
+
+    /// Flush and return the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self
+            .writer
+            .take()
+            .expect("writer is only taken by into_inner"))
+    }
+}
+
+impl<W: Write> Drop for TlogWriter<W> {
+    fn drop(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            // errors can't be reported from drop, use into_inner to handle them
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Frame of a telemetry log with the time it was recorded at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlogRecord {
+    /// UNIX time in microseconds
+    pub timestamp_us: u64,
+    pub frame: Vec<u8>,
+}
+
+/// Reader of the records of a telemetry log, as written by [`TlogWriter`]
+pub struct TlogReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> TlogReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Read the next record, or `None` at the end of the log
+    pub fn read_record(&mut self) -> io::Result<Option<TlogRecord>> {
+        let mut timestamp = [0u8; 8];
+        match self.reader.read_exact(&mut timestamp) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let mut magic = [0u8; 1];
+        self.reader.read_exact(&mut magic)?;
+        let header_size = match magic[0] {
+            MAV_STX => MAVLinkV1MessageRaw::HEADER_SIZE,
+            MAV_STX_V2 => MAVLinkV2MessageRaw::HEADER_SIZE,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "tlog record does not contain a MAVLink frame",
+                ))
+            }
+        };
+        let mut frame = vec![0u8; 1 + header_size];
+        frame[0] = magic[0];
+        self.reader.read_exact(&mut frame[1..])?;
+
+        let payload_length = frame[1] as usize;
+        let signature_size = if magic[0] == MAV_STX_V2 && frame[2] & MAVLINK_IFLAG_SIGNED != 0 {
+            MAVLinkV2MessageRaw::SIGNATURE_SIZE
+        } else {
+            0
+        };
+        let header_len = frame.len();
+        frame.resize(header_len + payload_length + 2 + signature_size, 0);
+        self.reader.read_exact(&mut frame[header_len..])?;
+
+        Ok(Some(TlogRecord {
+            timestamp_us: u64::from_be_bytes(timestamp),
+            frame,
+        }))
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[derive(Debug)]
+struct PlaybackState {
+    speed: f32,
+    paused: bool,
+    seek: Option<Duration>,
+    /// Incremented on every change of the pace, so players restart their timing
+    generation: u64,
+}
+
+/// Handle controlling the pace of a [`TlogPlayer`], shared with the player
+#[derive(Debug, Clone)]
+pub struct PlaybackControl {
+    shared: Arc<(Mutex<PlaybackState>, Condvar)>,
+}
+
+impl Default for PlaybackControl {
+    /// Real time playback
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl PlaybackControl {
+    /// Play at `speed` times the recorded pace, `f32::INFINITY` plays as fast as possible
+    pub fn new(speed: f32) -> Self {
+        assert!(speed > 0.0, "playback speed must be positive");
+        Self {
+            shared: Arc::new((
+                Mutex::new(PlaybackState {
+                    speed,
+                    paused: false,
+                    seek: None,
+                    generation: 0,
+                }),
+                Condvar::new(),
+            )),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PlaybackState)) {
+        let (lock, condvar) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        f(&mut state);
+        state.generation += 1;
+        condvar.notify_all();
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.shared.0.lock().unwrap().speed
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        assert!(speed > 0.0, "playback speed must be positive");
+        self.update(|state| state.speed = speed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.0.lock().unwrap().paused
+    }
+
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.update(|state| state.paused = false);
+    }
+
+    /// Continue the playback from the first record at `offset` from the start of the log
+    pub fn seek(&self, offset: Duration) {
+        self.update(|state| state.seek = Some(offset));
+    }
+}
+
+/// Player of telemetry logs, releasing records at the pace they were recorded at.
+///
+/// The speed, pauses and seeks are controlled through a [`PlaybackControl`], which can be
+/// cloned to control the player from another thread. Seeking rewinds the log and skips the
+/// records before the requested time.
+pub struct TlogPlayer<R: Read + Seek> {
+    reader: TlogReader<R>,
+    control: PlaybackControl,
+    rewrite_timestamps: bool,
+    first_timestamp: Option<u64>,
+    /// Timestamp of the last released record
+    position: Option<u64>,
+    /// Time at which the record with the given timestamp was or would have been released
+    anchor: Option<(Instant, u64)>,
+    generation: u64,
+}
+
+impl<R: Read + Seek> TlogPlayer<R> {
+    pub fn new(reader: R, control: PlaybackControl) -> Self {
+        Self {
+            reader: TlogReader::new(reader),
+            control,
+            rewrite_timestamps: false,
+            first_timestamp: None,
+            position: None,
+            anchor: None,
+            generation: 0,
+        }
+    }
+
+    /// Replace the timestamps of the released records by the current time, so the played log
+    /// looks like live traffic when recorded again
+    pub fn with_rewrite_timestamps(mut self, rewrite: bool) -> Self {
+        self.rewrite_timestamps = rewrite;
+        self
+    }
+
+    pub fn control(&self) -> &PlaybackControl {
+        &self.control
+    }
+
+    /// Wait until the next record is due and return it, or `None` at the end of the log
+    pub fn next_record(&mut self) -> io::Result<Option<TlogRecord>> {
+        let Some(mut record) = self.reader.read_record()? else {
+            return Ok(None);
+        };
+        self.first_timestamp.get_or_insert(record.timestamp_us);
+
+        let shared = Arc::clone(&self.control.shared);
+        let (lock, condvar) = &*shared;
+        let mut state = lock.lock().unwrap();
+        loop {
+            if let Some(offset) = state.seek.take() {
+                drop(state);
+                record = match self.seek(offset)? {
+                    Some(record) => record,
+                    None => return Ok(None),
+                };
+                state = lock.lock().unwrap();
+                continue;
+            }
+            if state.paused {
+                state = condvar.wait(state).unwrap();
+                continue;
+            }
+
+            let now = Instant::now();
+            if state.generation != self.generation {
+                self.generation = state.generation;
+                self.anchor = self.position.map(|position| (now, position));
+            }
+            let (anchor_time, anchor_timestamp) =
+                *self.anchor.get_or_insert((now, record.timestamp_us));
+            let recorded_delay =
+                Duration::from_micros(record.timestamp_us.saturating_sub(anchor_timestamp));
+            let release = anchor_time + recorded_delay.div_f32(state.speed);
+            if now >= release {
+                break;
+            }
+            state = condvar.wait_timeout(state, release - now).unwrap().0;
+        }
+        drop(state);
+
+        self.position = Some(record.timestamp_us);
+        if self.rewrite_timestamps {
+            record.timestamp_us = now_us();
+        }
+        Ok(Some(record))
+    }
+
+    /// Rewind the log and return the first record at `offset` from its start
+    fn seek(&mut self, offset: Duration) -> io::Result<Option<TlogRecord>> {
+        let first_timestamp = self.first_timestamp.unwrap_or(0);
+        let target = first_timestamp.saturating_add(offset.as_micros() as u64);
+        self.reader.get_mut().seek(SeekFrom::Start(0))?;
+        loop {
+            let Some(record) = self.reader.read_record()? else {
+                return Ok(None);
+            };
+            if record.timestamp_us >= target {
+                self.position = Some(target);
+                self.anchor = Some((Instant::now(), target));
+                return Ok(Some(record));
+            }
+        }
+    }
+}
+
+/// Current UNIX time in microseconds, as recorded in telemetry logs
+pub(crate) fn now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_micros() as u64)
+        .unwrap_or(0)
+}
@@ -112,3 +112,18 @@ impl RustDefault for f64 {
         0.0
     }
 }
+
+/// Quoted string of a `char` array field padded with zeros, as printed by the generated
+/// `Display` of messages
+#[doc(hidden)]
+pub struct DisplayChars<'a>(pub &'a [u8]);
+
+impl core::fmt::Display for DisplayChars<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let len = self.0.iter().position(|c| *c == 0).unwrap_or(self.0.len());
+        match core::str::from_utf8(&self.0[..len]) {
+            Ok(text) => write!(f, "{text:?}"),
+            Err(_) => write!(f, "{:?}", &self.0[..len]),
+        }
+    }
+}
@@ -42,7 +42,35 @@ fn main() -> ExitCode {
 
     let out_dir = env::var("OUT_DIR").unwrap();
 
-    let result = match mavlink_bindgen::generate(definitions_dir, out_dir) {
+    let mut generator = mavlink_bindgen::Generator::new();
+
+    // custom dialects, generated before the bundled ones so they can replace them
+    println!("cargo:rerun-if-env-changed=MAVLINK_DIALECT_DIRS");
+    if let Some(dirs) = env::var_os("MAVLINK_DIALECT_DIRS") {
+        for dir in env::split_paths(&dirs) {
+            println!("cargo:rerun-if-changed={}", dir.display());
+            generator = generator.with_custom_dialects_dir(dir);
+        }
+    }
+    generator = generator.with_definitions_dir(definitions_dir);
+
+    // only generate the messages listed in this file, the others are kept as raw payloads
+    println!("cargo:rerun-if-env-changed=MAVLINK_MESSAGES_FILE");
+    if let Some(path) = env::var_os("MAVLINK_MESSAGES_FILE") {
+        println!("cargo:rerun-if-changed={}", Path::new(&path).display());
+        match mavlink_bindgen::read_message_list(&path) {
+            Ok(messages) => {
+                let messages: Vec<&str> = messages.iter().map(String::as_str).collect();
+                generator = generator.with_messages(&messages);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let result = match generator.generate(out_dir) {
         Ok(r) => r,
         Err(e) => {
             eprintln!("{e}");
@@ -82,6 +82,7 @@ pub fn request_parameters() -> mavlink::ardupilotmega::MavMessage {
 }
 
 /// Create a message enabling data streaming
+#[allow(deprecated)]
 pub fn request_stream() -> mavlink::ardupilotmega::MavMessage {
     mavlink::ardupilotmega::MavMessage::REQUEST_DATA_STREAM(
         mavlink::ardupilotmega::REQUEST_DATA_STREAM_DATA {
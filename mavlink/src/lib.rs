@@ -8,3 +8,17 @@ pub use mavlink_core::*;
 #[cfg(feature = "emit-extensions")]
 #[allow(unused_imports)]
 pub(crate) use mavlink_core::utils::RustDefault;
+
+/// Deprecated messages warn when they are used, while their replacements don't
+///
+/// ```compile_fail
+/// #![deny(deprecated)]
+/// let _ = mavlink::common::PING_DATA::default();
+/// ```
+///
+/// ```
+/// #![deny(deprecated)]
+/// let _ = mavlink::common::SYSTEM_TIME_DATA::default();
+/// ```
+#[cfg(all(doctest, feature = "common"))]
+pub struct DeprecatedDefinitions;
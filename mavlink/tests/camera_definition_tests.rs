@@ -0,0 +1,103 @@
+#[cfg(feature = "camera-definition")]
+mod camera_definition {
+    use mavlink::camera_definition::{
+        CameraDefinition, CameraDefinitionError, CameraDefinitionUri, CameraParameterType,
+        CameraParameterValue,
+    };
+
+    const CAMERA_DEFINITION: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<mavlinkcamera>
+    <definition version="3">
+        <model>SD15</model>
+        <vendor>Foo &amp; Bar</vendor>
+    </definition>
+    <parameters>
+        <parameter name="CAM_MODE" type="uint32" default="1" control="0">
+            <description>Camera Mode</description>
+            <options>
+                <option name="Photo" value="0">
+                    <exclusions>
+                        <exclude>CAM_VIDRES</exclude>
+                    </exclusions>
+                </option>
+                <option name="Video" value="1" />
+            </options>
+            <updates>
+                <update>CAM_SHUTTERSPD</update>
+            </updates>
+        </parameter>
+        <parameter name="CAM_EV" type="float" default="0" min="-2" max="2" step="0.5">
+            <description>Exposure Compensation</description>
+        </parameter>
+        <parameter name="CAM_RESET" type="bool" default="0" writeonly="1">
+            <description>Reset Camera</description>
+        </parameter>
+    </parameters>
+</mavlinkcamera>
+"#;
+
+    #[test]
+    pub fn test_parse_definition() {
+        let definition = CameraDefinition::parse(CAMERA_DEFINITION).unwrap();
+        assert_eq!(definition.version, 3);
+        assert_eq!(definition.model, "SD15");
+        assert_eq!(definition.vendor, "Foo & Bar");
+        assert_eq!(definition.parameters.len(), 3);
+
+        let mode = definition.parameter("CAM_MODE").unwrap();
+        assert_eq!(mode.param_type, CameraParameterType::Uint32);
+        assert_eq!(mode.default, CameraParameterValue::Integer(1));
+        assert_eq!(mode.description, "Camera Mode");
+        assert!(!mode.control);
+        assert_eq!(mode.options.len(), 2);
+        assert_eq!(mode.options[0].name, "Photo");
+        assert_eq!(mode.options[0].value, CameraParameterValue::Integer(0));
+        assert_eq!(mode.options[0].exclusions, ["CAM_VIDRES"]);
+        assert!(mode.options[1].exclusions.is_empty());
+        assert_eq!(mode.updates, ["CAM_SHUTTERSPD"]);
+
+        let ev = definition.parameter("CAM_EV").unwrap();
+        assert!(ev.control);
+        assert_eq!(ev.min, Some(CameraParameterValue::Float(-2.0)));
+        assert_eq!(ev.max, Some(CameraParameterValue::Float(2.0)));
+        assert_eq!(ev.step, Some(CameraParameterValue::Float(0.5)));
+        assert!(ev.options.is_empty());
+
+        let reset = definition.parameter("CAM_RESET").unwrap();
+        assert_eq!(reset.default, CameraParameterValue::Bool(false));
+        assert!(reset.write_only && !reset.read_only);
+    }
+
+    #[test]
+    pub fn test_invalid_definition() {
+        let invalid_value = CAMERA_DEFINITION.replace(r#"default="1""#, r#"default="-1""#);
+        assert!(matches!(
+            CameraDefinition::parse(&invalid_value),
+            Err(CameraDefinitionError::InvalidValue { .. })
+        ));
+
+        let missing_type = CAMERA_DEFINITION.replace(r#"type="float" "#, "");
+        assert!(matches!(
+            CameraDefinition::parse(&missing_type),
+            Err(CameraDefinitionError::MissingAttribute {
+                attribute: "type",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    pub fn test_definition_uri() {
+        let mut field = [0u8; 140];
+        field[..26].copy_from_slice(b"mftp://camera/SD15_v3.xml\0");
+        assert_eq!(
+            CameraDefinitionUri::parse(&field),
+            Some(CameraDefinitionUri::Ftp("camera/SD15_v3.xml"))
+        );
+        assert_eq!(
+            CameraDefinitionUri::parse(b"https://example.com/camera.xml"),
+            Some(CameraDefinitionUri::Http("https://example.com/camera.xml"))
+        );
+        assert_eq!(CameraDefinitionUri::parse(&[0u8; 140]), None);
+    }
+}
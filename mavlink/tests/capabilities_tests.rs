@@ -0,0 +1,109 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_capabilities {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::{
+        MavCmd, MavMessage, MavProtocolCapability, MavResult, AUTOPILOT_VERSION_DATA,
+        COMMAND_ACK_DATA,
+    };
+    use mavlink::{Capabilities, CapabilityCache, MavHeader, MessageData};
+
+    const VEHICLE_HEADER: MavHeader = MavHeader {
+        system_id: 1,
+        component_id: 1,
+        sequence: 0,
+    };
+
+    fn autopilot_version() -> AUTOPILOT_VERSION_DATA {
+        let mut version = AUTOPILOT_VERSION_DATA::DEFAULT;
+        version.capabilities = MavProtocolCapability::MAV_PROTOCOL_CAPABILITY_MISSION_INT
+            | MavProtocolCapability::MAV_PROTOCOL_CAPABILITY_FTP
+            | MavProtocolCapability::MAV_PROTOCOL_CAPABILITY_MAVLINK2;
+        version.flight_sw_version = 0x0405_0000;
+        version
+    }
+
+    #[test]
+    pub fn test_capabilities_from_message() {
+        let message = MavMessage::AUTOPILOT_VERSION(autopilot_version());
+        let capabilities = Capabilities::from_message(&message).unwrap();
+        assert!(capabilities.mission_int());
+        assert!(capabilities.ftp());
+        assert!(capabilities.mavlink2());
+        assert!(!capabilities.mission_float());
+        assert!(!capabilities.command_int());
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        assert_eq!(Capabilities::from_message(&heartbeat), None);
+
+        let mut cache = CapabilityCache::new();
+        cache.handle_message(&VEHICLE_HEADER, &heartbeat);
+        assert_eq!(cache.get(VEHICLE_HEADER.system_id), None);
+        cache.handle_message(&VEHICLE_HEADER, &message);
+        assert_eq!(cache.get(VEHICLE_HEADER.system_id), Some(capabilities));
+        cache.invalidate(VEHICLE_HEADER.system_id);
+        assert_eq!(cache.get(VEHICLE_HEADER.system_id), None);
+    }
+
+    #[test]
+    pub fn test_capability_cache_request() {
+        let vehicle = thread::spawn(move || {
+            let vehicle = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14564")
+                .expect("Couldn't create server");
+            loop {
+                let (_header, msg) = vehicle.recv().expect("Failed to receive request");
+                let MavMessage::COMMAND_LONG(command) = msg else {
+                    continue;
+                };
+                assert_eq!(command.command, MavCmd::MAV_CMD_REQUEST_MESSAGE);
+                assert_eq!(command.param1 as u32, AUTOPILOT_VERSION_DATA::ID);
+
+                let mut ack = COMMAND_ACK_DATA::DEFAULT;
+                ack.command = MavCmd::MAV_CMD_REQUEST_MESSAGE;
+                ack.result = MavResult::MAV_RESULT_ACCEPTED;
+                vehicle
+                    .send(&VEHICLE_HEADER, &MavMessage::COMMAND_ACK(ack))
+                    .unwrap();
+                vehicle
+                    .send(
+                        &VEHICLE_HEADER,
+                        &MavMessage::AUTOPILOT_VERSION(autopilot_version()),
+                    )
+                    .unwrap();
+                break;
+            }
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14564")
+            .expect("Couldn't create client");
+        let mut cache = CapabilityCache::new();
+        let capabilities = cache
+            .get_or_request(
+                &*gcs,
+                &MavHeader::default(),
+                VEHICLE_HEADER.system_id,
+                VEHICLE_HEADER.component_id,
+                Duration::from_secs(5),
+            )
+            .expect("Failed to request capabilities");
+        assert!(capabilities.mission_int());
+        assert!(capabilities.mavlink2());
+        vehicle.join().unwrap();
+
+        // answered from the cache, the vehicle is gone
+        let cached = cache
+            .get_or_request(
+                &*gcs,
+                &MavHeader::default(),
+                VEHICLE_HEADER.system_id,
+                VEHICLE_HEADER.component_id,
+                Duration::from_secs(5),
+            )
+            .unwrap();
+        assert_eq!(cached, capabilities);
+    }
+}
@@ -0,0 +1,98 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod test_datagram_connection {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::Mutex;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{DatagramConnection, DatagramTransport, MavConnection};
+
+    /// In-memory transport recording sent messages and replaying queued ones
+    #[derive(Default)]
+    struct QueueTransport {
+        incoming: Mutex<VecDeque<Vec<u8>>>,
+        sent: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl DatagramTransport for QueueTransport {
+        fn recv_datagram(&self, buf: &mut [u8]) -> io::Result<usize> {
+            let datagram = self
+                .incoming
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or(io::ErrorKind::UnexpectedEof)?;
+            let len = datagram.len().min(buf.len());
+            buf[..len].copy_from_slice(&datagram[..len]);
+            Ok(len)
+        }
+
+        fn send_datagram(&self, datagram: &[u8]) -> io::Result<()> {
+            self.sent.lock().unwrap().push(datagram.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Test that every frame is sent as its own message and can be read back by another connection
+    #[test]
+    pub fn test_datagram_connection_loopback() {
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+
+        let writer = DatagramConnection::new(QueueTransport::default());
+        for _ in 0..3 {
+            writer
+                .send(&crate::test_shared::COMMON_MSG_HEADER, &heartbeat)
+                .expect("Failed to send message");
+        }
+        let sent = writer.transport().sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 3);
+        for datagram in &sent {
+            assert_eq!(datagram[0], mavlink::MAV_STX_V2);
+            assert_eq!(datagram.len(), 12 + datagram[1] as usize);
+        }
+
+        let reader = DatagramConnection::new(QueueTransport::default());
+        reader.transport().incoming.lock().unwrap().extend(sent);
+        for sequence in 0..3 {
+            let (header, msg): (_, MavMessage) = reader.recv().expect("Failed to receive message");
+            assert_eq!(header.sequence, sequence);
+            assert_eq!(msg, heartbeat);
+        }
+
+        let eof = MavConnection::<MavMessage>::recv(&reader);
+        assert!(matches!(
+            eof,
+            Err(mavlink::error::MessageReadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    /// Test that garbage in a message does not prevent reading the frames following it
+    #[test]
+    pub fn test_datagram_connection_skips_invalid_data() {
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let mut datagram = vec![0xfd, 0x01, 0x02];
+        for _ in 0..2 {
+            mavlink::write_versioned_msg(
+                &mut datagram,
+                mavlink::MavlinkVersion::V2,
+                crate::test_shared::COMMON_MSG_HEADER,
+                &heartbeat,
+            )
+            .unwrap();
+        }
+
+        let connection = DatagramConnection::new(QueueTransport::default());
+        connection
+            .transport()
+            .incoming
+            .lock()
+            .unwrap()
+            .push_back(datagram);
+        for _ in 0..2 {
+            let (_, msg): (_, MavMessage) = connection.recv().expect("Failed to receive message");
+            assert_eq!(msg, heartbeat);
+        }
+    }
+}
@@ -21,3 +21,23 @@ mod test_direct_serial {
         assert!(conn_result.is_err(), "Invalid port should error");
     }
 }
+
+#[cfg(all(feature = "std", feature = "direct-serial", feature = "common"))]
+mod test_serial_autodetect {
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+
+    #[test]
+    pub fn test_probe_nonexistent_port() {
+        let port = "8d73ba8c-eb87-4105-8d0c-2931940e13be";
+        let found = mavlink::probe_serial::<MavMessage>(port, 57600, Duration::from_millis(100));
+        assert!(found.is_none(), "Invalid port should not be detected");
+    }
+
+    #[test]
+    pub fn test_serial_ports_sorted() {
+        let ports = mavlink::serial_ports();
+        assert!(ports.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,95 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_discovery {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::{
+        MavCmd, MavMessage, MavType, CAMERA_INFORMATION_DATA, GIMBAL_MANAGER_INFORMATION_DATA,
+    };
+    use mavlink::{DeviceKind, MavHeader, MessageData};
+
+    fn component(component_id: u8) -> MavHeader {
+        MavHeader {
+            system_id: 1,
+            component_id,
+            sequence: 0,
+        }
+    }
+
+    /// Test that cameras and gimbals are found from their heartbeats and information messages
+    #[test]
+    pub fn test_discover_devices() {
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let vehicle = thread::spawn(move || {
+            let vehicle = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14566")
+                .expect("Couldn't create server");
+            let mut heartbeat = crate::test_shared::get_heartbeat_msg();
+
+            // the autopilot is not a payload
+            vehicle
+                .send(&component(1), &MavMessage::HEARTBEAT(heartbeat.clone()))
+                .unwrap();
+
+            // a camera that answers its request, and a gimbal that only sends heartbeats
+            let mut requests = 0;
+            while requests < 18 {
+                let (_header, msg) = vehicle.recv().expect("Failed to receive request");
+                let MavMessage::COMMAND_LONG(command) = msg else {
+                    continue;
+                };
+                assert_eq!(command.command, MavCmd::MAV_CMD_REQUEST_MESSAGE);
+                requests += 1;
+                match (command.target_component, command.param1 as u32) {
+                    (100, CAMERA_INFORMATION_DATA::ID) => {
+                        let mut information = CAMERA_INFORMATION_DATA::DEFAULT;
+                        information.firmware_version = 42;
+                        vehicle
+                            .send(
+                                &component(100),
+                                &MavMessage::CAMERA_INFORMATION(information),
+                            )
+                            .unwrap();
+                    }
+                    (154, GIMBAL_MANAGER_INFORMATION_DATA::ID) => {
+                        heartbeat.mavtype = MavType::MAV_TYPE_GIMBAL;
+                        vehicle
+                            .send(&component(154), &MavMessage::HEARTBEAT(heartbeat.clone()))
+                            .unwrap();
+                    }
+                    _ => {}
+                }
+            }
+            done_rx.recv().unwrap();
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14566")
+            .expect("Couldn't create client");
+        let devices =
+            mavlink::discover_devices(&*gcs, &MavHeader::default(), 1, Duration::from_millis(500))
+                .expect("Discovery failed");
+        done_tx.send(()).unwrap();
+        vehicle.join().unwrap();
+
+        assert_eq!(devices.len(), 2);
+        let camera = &devices[0];
+        assert_eq!((camera.system_id, camera.component_id), (1, 100));
+        assert_eq!(camera.kind, DeviceKind::Camera);
+        assert_eq!(camera.mav_type, None);
+        let [MavMessage::CAMERA_INFORMATION(information)] = &camera.information[..] else {
+            panic!("Unexpected information {:?}", camera.information);
+        };
+        assert_eq!(information.firmware_version, 42);
+
+        let gimbal = &devices[1];
+        assert_eq!((gimbal.system_id, gimbal.component_id), (1, 154));
+        assert_eq!(gimbal.kind, DeviceKind::Gimbal);
+        assert_eq!(gimbal.mav_type, Some(MavType::MAV_TYPE_GIMBAL as u8));
+        assert!(gimbal.information.is_empty());
+    }
+}
@@ -149,4 +149,48 @@ mod test_encode_decode {
             _ => panic!("Decoded wrong message type"),
         }
     }
+
+    #[test]
+    pub fn test_lossy_enum_decoding() {
+        use mavlink::error::ParserError;
+        use mavlink::MessageData;
+
+        let heartbeat = crate::test_shared::get_heartbeat_msg();
+        let mut payload = [0u8; 9];
+        let len = heartbeat.ser(mavlink::MavlinkVersion::V2, &mut payload);
+        // MAV_TYPE, after the u32 custom_mode, set to a value unknown to the dialect
+        payload[4] = 250;
+
+        let strict = common::MavMessage::parse(mavlink::MavlinkVersion::V2, 0, &payload[..len]);
+        assert!(matches!(
+            strict,
+            Err(ParserError::InvalidEnum { value: 250, .. })
+        ));
+
+        let mut unknown_enums = 0;
+        let lossy = common::MavMessage::parse_lossy(
+            mavlink::MavlinkVersion::V2,
+            0,
+            &payload[..len],
+            &mut unknown_enums,
+        )
+        .expect("Failed to parse message");
+        assert_eq!(unknown_enums, 1);
+        let common::MavMessage::HEARTBEAT(lossy) = lossy else {
+            panic!("Decoded wrong message type");
+        };
+        assert_eq!(lossy.mavtype, common::MavType::DEFAULT);
+        assert_eq!(lossy.autopilot, heartbeat.autopilot);
+        assert_eq!(lossy.custom_mode, heartbeat.custom_mode);
+
+        // known values are not counted
+        let decoded = common::HEARTBEAT_DATA::deser_lossy(
+            mavlink::MavlinkVersion::V2,
+            &payload[..4],
+            &mut unknown_enums,
+        )
+        .expect("Failed to parse message");
+        assert_eq!(unknown_enums, 1);
+        assert_eq!(decoded.custom_mode, heartbeat.custom_mode);
+    }
 }
@@ -0,0 +1,109 @@
+mod test_shared;
+
+#[cfg(all(feature = "field-access", feature = "common"))]
+mod test_field_access {
+    use mavlink::common::{
+        MavMessage, MavModeFlag, MavParamType, MavType, HEARTBEAT_DATA, PARAM_SET_DATA,
+    };
+    use mavlink::{MavFieldError, MavFieldValue};
+
+    #[test]
+    pub fn test_get_field() {
+        let heartbeat = crate::test_shared::get_heartbeat_msg();
+        assert_eq!(
+            heartbeat.get_field("custom_mode"),
+            Some(MavFieldValue::U32(5))
+        );
+        // enum and bitmask fields are returned as their integer value
+        assert_eq!(
+            heartbeat.get_field("type"),
+            Some(MavFieldValue::U8(MavType::MAV_TYPE_QUADROTOR as u8))
+        );
+        assert_eq!(
+            heartbeat.get_field("base_mode"),
+            Some(MavFieldValue::U8(heartbeat.base_mode.bits()))
+        );
+        assert_eq!(heartbeat.get_field("mavtype"), None);
+        assert_eq!(heartbeat.get_field("unknown"), None);
+
+        assert!(HEARTBEAT_DATA::FIELD_NAMES.contains(&"type"));
+        assert_eq!(
+            HEARTBEAT_DATA::FIELD_NAMES.len(),
+            MavMessage::HEARTBEAT(heartbeat).field_names().len()
+        );
+    }
+
+    #[test]
+    pub fn test_set_field() {
+        let mut heartbeat = HEARTBEAT_DATA::default();
+        heartbeat
+            .set_field("custom_mode", MavFieldValue::U32(7))
+            .unwrap();
+        assert_eq!(heartbeat.custom_mode, 7);
+
+        heartbeat
+            .set_field(
+                "type",
+                MavFieldValue::U8(MavType::MAV_TYPE_FIXED_WING as u8),
+            )
+            .unwrap();
+        assert_eq!(heartbeat.mavtype, MavType::MAV_TYPE_FIXED_WING);
+
+        let flags =
+            MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED | MavModeFlag::MAV_MODE_FLAG_GUIDED_ENABLED;
+        heartbeat
+            .set_field("base_mode", MavFieldValue::U8(flags.bits()))
+            .unwrap();
+        assert_eq!(heartbeat.base_mode, flags);
+
+        assert_eq!(
+            heartbeat.set_field("custom_mode", MavFieldValue::U8(7)),
+            Err(MavFieldError::TypeMismatch)
+        );
+        assert_eq!(
+            heartbeat.set_field("type", MavFieldValue::U8(u8::MAX)),
+            Err(MavFieldError::InvalidValue)
+        );
+        assert_eq!(
+            heartbeat.set_field("unknown", MavFieldValue::U8(0)),
+            Err(MavFieldError::UnknownField)
+        );
+    }
+
+    #[test]
+    pub fn test_array_field() {
+        let mut message = MavMessage::PARAM_SET(PARAM_SET_DATA::default());
+        message
+            .set_field("param_id", MavFieldValue::CharArray(b"BATT_CAPACITY"))
+            .unwrap();
+        message
+            .set_field(
+                "param_type",
+                MavFieldValue::U8(MavParamType::MAV_PARAM_TYPE_INT32 as u8),
+            )
+            .unwrap();
+
+        let param_id = message.get_field("param_id").unwrap();
+        assert_eq!(param_id.as_str(), Some("BATT_CAPACITY"));
+        assert_eq!(
+            message
+                .get_field("param_type")
+                .and_then(|value| value.as_f64()),
+            Some(f64::from(MavParamType::MAV_PARAM_TYPE_INT32 as u8))
+        );
+
+        // a shorter value clears the rest of the array
+        message
+            .set_field("param_id", MavFieldValue::CharArray(b"ARMING_CHECK"))
+            .unwrap();
+        assert_eq!(
+            message.get_field("param_id").unwrap().as_str(),
+            Some("ARMING_CHECK")
+        );
+
+        assert_eq!(
+            message.set_field("param_id", MavFieldValue::CharArray(&[b'a'; 17])),
+            Err(MavFieldError::TypeMismatch)
+        );
+    }
+}
@@ -1,13 +1,14 @@
+mod test_shared;
+
 #[cfg(all(feature = "std", feature = "common"))]
 mod helper_tests {
     use mavlink::{common::MavMessage, Message};
 
     #[test]
+    #[allow(deprecated)]
     fn test_get_default_message_from_id() {
         let message_name = "PING";
-        let id: std::result::Result<u32, &'static str> =
-            MavMessage::message_id_from_name(message_name);
-        let id = id.unwrap();
+        let id = MavMessage::message_id_from_name(message_name).unwrap();
         assert!(id == 4, "Invalid id for message name: PING");
         let message = MavMessage::default_message_from_id(id);
         if !matches!(message, Ok(MavMessage::PING(_))) {
@@ -18,4 +19,327 @@ mod helper_tests {
             "Message name does not match"
         );
     }
+
+    #[test]
+    fn test_unknown_message_errors() {
+        use mavlink::error::ParserError;
+
+        assert!(matches!(
+            MavMessage::message_id_from_name("NOT_A_MESSAGE"),
+            Err(ParserError::UnknownMessageName)
+        ));
+        assert!(matches!(
+            MavMessage::default_message_from_id(u32::MAX),
+            Err(ParserError::UnknownMessage { id: u32::MAX })
+        ));
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        use mavlink::common::HEARTBEAT_DATA;
+        use mavlink::error::ParserError;
+        use mavlink::{MavlinkVersion, MessageData};
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let mut payload = [0u8; 255];
+        let len = heartbeat.ser(MavlinkVersion::V2, &mut payload);
+        assert_eq!(len, 9);
+        assert_eq!(
+            MavMessage::parse_strict(MavlinkVersion::V2, HEARTBEAT_DATA::ID, &payload[..len])
+                .unwrap(),
+            heartbeat
+        );
+
+        // the lenient parser fills the missing mavlink_version with zero
+        let truncated = &payload[..len - 1];
+        assert!(MavMessage::parse(MavlinkVersion::V2, HEARTBEAT_DATA::ID, truncated).is_ok());
+        assert!(matches!(
+            MavMessage::parse_strict(MavlinkVersion::V2, HEARTBEAT_DATA::ID, truncated),
+            Err(ParserError::Truncated {
+                needed: 9,
+                available: 8
+            })
+        ));
+
+        assert!(HEARTBEAT_DATA::is_field_present(
+            "custom_mode",
+            truncated.len()
+        ));
+        assert!(!HEARTBEAT_DATA::is_field_present(
+            "mavlink_version",
+            truncated.len()
+        ));
+        assert!(HEARTBEAT_DATA::is_field_present("mavlink_version", len));
+        assert!(!HEARTBEAT_DATA::is_field_present("unknown", len));
+    }
+
+    #[test]
+    fn test_display() {
+        use mavlink::common::{ATTITUDE_DATA, PARAM_SET_DATA};
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        assert_eq!(
+            heartbeat.to_string(),
+            "HEARTBEAT { custom_mode: 5, type: MAV_TYPE_QUADROTOR, \
+             autopilot: MAV_AUTOPILOT_ARDUPILOTMEGA, \
+             base_mode: MAV_MODE_FLAG_MANUAL_INPUT_ENABLED | MAV_MODE_FLAG_STABILIZE_ENABLED \
+             | MAV_MODE_FLAG_GUIDED_ENABLED | MAV_MODE_FLAG_CUSTOM_MODE_ENABLED, \
+             system_status: MAV_STATE_STANDBY, mavlink_version: 3 }"
+        );
+
+        let attitude = ATTITUDE_DATA {
+            time_boot_ms: 1000,
+            roll: 0.5,
+            ..ATTITUDE_DATA::DEFAULT
+        };
+        assert_eq!(
+            attitude.to_string(),
+            "ATTITUDE { time_boot_ms: 1000 ms, roll: 0.5 rad, pitch: 0 rad, yaw: 0 rad, \
+             rollspeed: 0 rad/s, pitchspeed: 0 rad/s, yawspeed: 0 rad/s }"
+        );
+
+        let mut param_set = PARAM_SET_DATA::DEFAULT;
+        param_set.param_id[..4].copy_from_slice(b"BATT");
+        assert_eq!(
+            MavMessage::PARAM_SET(param_set).to_string(),
+            "PARAM_SET { param_value: 0, target_system: 0, target_component: 0, \
+             param_id: \"BATT\", param_type: MAV_PARAM_TYPE_UINT8 }"
+        );
+    }
+
+    #[test]
+    fn test_message_info() {
+        use mavlink::common::{message_info, COMMAND_ACK_DATA, HEARTBEAT_DATA, MESSAGE_INFO};
+        use mavlink::MessageData;
+
+        assert!(MESSAGE_INFO.windows(2).all(|pair| pair[0].id < pair[1].id));
+        for info in MESSAGE_INFO {
+            assert_eq!(MavMessage::extra_crc(info.id), info.extra_crc);
+            assert_eq!(
+                MavMessage::message_id_from_name(info.name).ok(),
+                Some(info.id)
+            );
+            assert_eq!(MavMessage::message_name_from_id(info.id), Some(info.name));
+            assert!(info.min_payload_len <= info.max_payload_len);
+            assert!(info.max_payload_len <= info.extended_payload_len);
+            assert_eq!(
+                MavMessage::max_payload_len(info.id),
+                Some(usize::from(info.max_payload_len))
+            );
+        }
+
+        let heartbeat = message_info(HEARTBEAT_DATA::ID).expect("HEARTBEAT is missing");
+        assert_eq!(heartbeat.name, HEARTBEAT_DATA::NAME);
+        assert_eq!(heartbeat.extra_crc, HEARTBEAT_DATA::EXTRA_CRC);
+        assert_eq!(heartbeat.min_payload_len, 9);
+        assert_eq!(
+            heartbeat.max_payload_len as usize,
+            HEARTBEAT_DATA::ENCODED_LEN
+        );
+        assert_eq!(heartbeat.fields, HEARTBEAT_DATA::FIELDS);
+        assert!(message_info(u32::MAX).is_none());
+        assert_eq!(MavMessage::message_name_from_id(u32::MAX), None);
+        assert_eq!(MavMessage::max_payload_len(u32::MAX), None);
+
+        // the extension fields are counted whether they are generated or not
+        let command_ack = message_info(COMMAND_ACK_DATA::ID).expect("COMMAND_ACK is missing");
+        assert_eq!(COMMAND_ACK_DATA::EXTENDED_LEN, 10);
+        assert_eq!(command_ack.extended_payload_len, 10);
+        assert_eq!(
+            usize::from(command_ack.max_payload_len),
+            COMMAND_ACK_DATA::ENCODED_LEN
+        );
+    }
+
+    #[test]
+    fn test_field_info() {
+        use mavlink::common::{ATTITUDE_DATA, HEARTBEAT_DATA};
+        use mavlink::FieldInfo;
+
+        let names: Vec<_> = ATTITUDE_DATA::FIELDS.iter().map(|info| info.name).collect();
+        assert_eq!(
+            names,
+            [
+                "time_boot_ms",
+                "roll",
+                "pitch",
+                "yaw",
+                "rollspeed",
+                "pitchspeed",
+                "yawspeed"
+            ]
+        );
+        assert_eq!(ATTITUDE_DATA::FIELDS[1].units, Some("rad"));
+        assert_eq!(ATTITUDE_DATA::FIELDS[4].units, Some("rad/s"));
+
+        let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let base_mode = heartbeat
+            .fields()
+            .iter()
+            .find(|info| info.name == "base_mode")
+            .unwrap();
+        assert_eq!(base_mode.display, Some("bitmask"));
+        assert_eq!(base_mode.units, None);
+
+        let info = FieldInfo {
+            min_value: Some(0.0),
+            max_value: Some(100.0),
+            ..*base_mode
+        };
+        assert!(info.contains(0.0) && info.contains(100.0));
+        assert!(!info.contains(-1.0) && !info.contains(100.5));
+        assert!(base_mode.contains(f64::MAX));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        use mavlink::common::GLOBAL_POSITION_INT_DATA;
+
+        let mut position = GLOBAL_POSITION_INT_DATA {
+            hdg: 9000,
+            ..Default::default()
+        };
+        assert!(position.is_valid_hdg());
+        position.hdg = u16::MAX;
+        assert!(!position.is_valid_hdg());
+    }
+
+    #[test]
+    fn test_invalid_sentinels() {
+        use mavlink::common::RADIO_STATUS_DATA;
+
+        let mut status = RADIO_STATUS_DATA {
+            rssi: 180,
+            remrssi: u8::MAX,
+            noise: 0,
+            remnoise: u8::MAX,
+            ..Default::default()
+        };
+        assert!(status.is_valid_rssi() && status.is_valid_noise());
+        assert!(!status.is_valid_remrssi() && !status.is_valid_remnoise());
+        status.rssi = u8::MAX;
+        assert!(!status.is_valid_rssi());
+    }
+
+    #[test]
+    fn test_enum_names() {
+        use mavlink::common::{MavCmd, MavModeFlag, MavState};
+
+        assert_eq!(
+            MavCmd::from_name("MAV_CMD_NAV_TAKEOFF"),
+            Some(MavCmd::MAV_CMD_NAV_TAKEOFF)
+        );
+        assert_eq!(MavCmd::from_name("NOT_A_COMMAND"), None);
+        assert_eq!(MavCmd::MAV_CMD_NAV_TAKEOFF.as_name(), "MAV_CMD_NAV_TAKEOFF");
+
+        assert_eq!(MavState::ALL.len(), 5);
+        for state in MavState::ALL {
+            assert_eq!(MavState::from_name(state.as_name()), Some(*state));
+        }
+
+        let armed = MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED;
+        assert_eq!(armed.as_name(), Some("MAV_MODE_FLAG_SAFETY_ARMED"));
+        assert_eq!(
+            MavModeFlag::from_name("MAV_MODE_FLAG_SAFETY_ARMED"),
+            Some(armed)
+        );
+        assert!(MavModeFlag::ALL.contains(&armed));
+        // combinations of flags have no name
+        assert_eq!(
+            (armed | MavModeFlag::MAV_MODE_FLAG_MANUAL_INPUT_ENABLED).as_name(),
+            None
+        );
+    }
+}
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod target_filter_tests {
+    use mavlink::{
+        common::{MavMessage, COMMAND_LONG_DATA},
+        Message, TargetFilter,
+    };
+
+    fn command_long(target_system: u8, target_component: u8) -> MavMessage {
+        let mut command = COMMAND_LONG_DATA::DEFAULT;
+        command.target_system = target_system;
+        command.target_component = target_component;
+        MavMessage::COMMAND_LONG(command)
+    }
+
+    #[test]
+    fn test_target_ids() {
+        let msg = command_long(1, 2);
+        assert_eq!(msg.target_system_id(), Some(1));
+        assert_eq!(msg.target_component_id(), Some(2));
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        assert_eq!(heartbeat.target_system_id(), None);
+        assert_eq!(heartbeat.target_component_id(), None);
+    }
+
+    #[test]
+    fn test_targeted_message() {
+        use mavlink::{common::PARAM_SET_DATA, TargetedMessage};
+
+        fn is_for(message: &impl TargetedMessage, system_id: u8, component_id: u8) -> bool {
+            message.target_system() == system_id
+                && message
+                    .target_component()
+                    .map_or(true, |id| id == component_id)
+        }
+
+        let mut command = COMMAND_LONG_DATA::DEFAULT;
+        command.target_system = 1;
+        command.target_component = 2;
+        assert_eq!(command.target_system(), 1);
+        assert_eq!(command.target_component(), Some(2));
+        assert!(is_for(&command, 1, 2));
+
+        let param_set = PARAM_SET_DATA {
+            target_system: 3,
+            ..PARAM_SET_DATA::DEFAULT
+        };
+        assert!(!is_for(&param_set, 1, 0));
+    }
+
+    #[test]
+    fn test_target_filter() {
+        let filter = TargetFilter::new(1, 2);
+        assert!(filter.accepts(&command_long(1, 2)));
+        assert!(filter.accepts(&command_long(1, 0)));
+        assert!(filter.accepts(&command_long(0, 0)));
+        assert!(!filter.accepts(&command_long(1, 3)));
+        assert!(!filter.accepts(&command_long(2, 2)));
+        assert!(filter.accepts(&MavMessage::HEARTBEAT(
+            crate::test_shared::get_heartbeat_msg()
+        )));
+    }
+}
+
+#[cfg(feature = "std")]
+mod source_filter_tests {
+    use mavlink::{MavHeader, SourceFilter};
+
+    fn header(system_id: u8) -> MavHeader {
+        MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        }
+    }
+
+    #[test]
+    fn test_source_filter() {
+        let filter = SourceFilter::new(255).ignore(0).ignore(130);
+        assert!(!filter.accepts(&header(255)));
+        assert!(!filter.accepts(&header(0)));
+        assert!(!filter.accepts(&header(130)));
+        assert!(filter.accepts(&header(1)));
+        assert!(filter.accepts(&header(254)));
+
+        let filter: SourceFilter = [1, 64].into_iter().collect();
+        assert!(filter.ignores(1));
+        assert!(filter.ignores(64));
+        assert!(!filter.ignores(63));
+    }
 }
@@ -0,0 +1,92 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod test_inspector {
+    use std::time::{Duration, Instant};
+
+    use mavlink::common::{MavMessage, ATTITUDE_DATA, HEARTBEAT_DATA};
+    use mavlink::{Inspector, MavHeader, MessageData, StreamKey};
+
+    const HEADER: MavHeader = MavHeader {
+        system_id: 1,
+        component_id: 1,
+        sequence: 0,
+    };
+
+    fn attitude(roll: f32) -> MavMessage {
+        let mut attitude = ATTITUDE_DATA::DEFAULT;
+        attitude.roll = roll;
+        MavMessage::ATTITUDE(attitude)
+    }
+
+    #[test]
+    pub fn test_inspector_rates_and_last_values() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut inspector = Inspector::new().with_rate_window(Duration::from_secs(1));
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for i in 0..20 {
+            inspector.message_received(&HEADER, &attitude(i as f32), at(i * 100));
+            if i % 10 == 0 {
+                inspector.message_received(&HEADER, &heartbeat, at(i * 100));
+            }
+        }
+
+        let attitude_key = StreamKey {
+            system_id: 1,
+            component_id: 1,
+            message_id: ATTITUDE_DATA::ID,
+        };
+        let heartbeat_key = StreamKey {
+            message_id: HEARTBEAT_DATA::ID,
+            ..attitude_key
+        };
+        assert_eq!(inspector.rate(&attitude_key, at(1950)), 10.0);
+        assert_eq!(inspector.rate(&heartbeat_key, at(1950)), 1.0);
+        assert_eq!(inspector.rate(&attitude_key, at(5000)), 0.0);
+
+        let stats = inspector.get(&attitude_key).unwrap();
+        assert_eq!(stats.count(), 20);
+        assert_eq!(stats.message_name(), "ATTITUDE");
+        assert_eq!(stats.last_message(), &attitude(19.0));
+        assert_eq!(stats.last_seen(), at(1900));
+
+        // sorted by message id
+        let ids: Vec<u32> = inspector.streams().map(|(key, _)| key.message_id).collect();
+        assert_eq!(ids, vec![HEARTBEAT_DATA::ID, ATTITUDE_DATA::ID]);
+
+        inspector.remove_stale(at(2500), Duration::from_millis(1000));
+        assert!(inspector.get(&heartbeat_key).is_none());
+        assert!(inspector.get(&attitude_key).is_some());
+    }
+
+    #[test]
+    pub fn test_inspector_field_history() {
+        let start = Instant::now();
+        let mut inspector = Inspector::new().with_history_len(3);
+        inspector.watch(ATTITUDE_DATA::ID, "roll", |message| match message {
+            MavMessage::ATTITUDE(attitude) => Some(attitude.roll as f64),
+            _ => None,
+        });
+
+        for i in 0..5 {
+            inspector.message_received(&HEADER, &attitude(i as f32), start);
+        }
+
+        let key = StreamKey {
+            system_id: 1,
+            component_id: 1,
+            message_id: ATTITUDE_DATA::ID,
+        };
+        let stats = inspector.get(&key).unwrap();
+        let roll: Vec<f64> = stats
+            .history("roll")
+            .unwrap()
+            .iter()
+            .map(|(_, value)| *value)
+            .collect();
+        assert_eq!(roll, vec![2.0, 3.0, 4.0]);
+        assert!(stats.history("pitch").is_none());
+    }
+}
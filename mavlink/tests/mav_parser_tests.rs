@@ -0,0 +1,111 @@
+mod test_shared;
+
+#[cfg(feature = "common")]
+mod test_mav_parser {
+    use mavlink::common::MavMessage;
+    use mavlink::{MavParser, MavlinkVersion, RawFrame};
+
+    fn heartbeat_frame(version: MavlinkVersion) -> Vec<u8> {
+        let mut frame = vec![];
+        mavlink::write_versioned_msg(
+            &mut frame,
+            version,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg()),
+        )
+        .expect("Failed to write message");
+        frame
+    }
+
+    /// Noise, a MAVLink 2 frame, a MAVLink 1 frame, then a frame starting inside a corrupted one
+    fn stream() -> Vec<u8> {
+        let v2 = heartbeat_frame(MavlinkVersion::V2);
+        let v1 = heartbeat_frame(MavlinkVersion::V1);
+        let mut corrupted = v2.clone();
+        corrupted[4] ^= 0xff;
+
+        let mut stream = vec![0x00, mavlink::MAV_STX_V2, 0x42];
+        stream.extend_from_slice(&v2);
+        stream.extend_from_slice(&v1);
+        stream.extend_from_slice(&corrupted[..6]);
+        stream.extend_from_slice(&v2);
+        stream
+    }
+
+    fn versions(frames: &[RawFrame]) -> Vec<MavlinkVersion> {
+        frames.iter().map(RawFrame::protocol_version).collect()
+    }
+
+    #[test]
+    pub fn test_parse_any_chunking() {
+        let stream = stream();
+        for chunk_size in [1, 3, 17, 280, stream.len()] {
+            let mut parser = MavParser::<MavMessage>::new();
+            let mut frames = vec![];
+            for chunk in stream.chunks(chunk_size) {
+                frames.extend(parser.push_bytes(chunk));
+            }
+            assert_eq!(
+                versions(&frames),
+                [MavlinkVersion::V2, MavlinkVersion::V1, MavlinkVersion::V2],
+                "chunk size {chunk_size}"
+            );
+            for frame in &frames {
+                let (header, msg) = frame.parse::<MavMessage>().expect("Failed to parse");
+                assert_eq!(header, crate::test_shared::COMMON_MSG_HEADER);
+                assert!(matches!(msg, MavMessage::HEARTBEAT(_)));
+            }
+            assert_eq!(parser.buffered(), 0);
+        }
+    }
+
+    #[test]
+    pub fn test_parse_single_version() {
+        let mut parser = MavParser::<MavMessage>::with_version(MavlinkVersion::V1);
+        let frames: Vec<_> = parser.push_bytes(&stream()).collect();
+        assert_eq!(versions(&frames), [MavlinkVersion::V1]);
+        assert_eq!(
+            frames[0].raw_bytes(),
+            heartbeat_frame(MavlinkVersion::V1).as_slice()
+        );
+    }
+
+    #[test]
+    pub fn test_incomplete_frame_is_kept() {
+        let frame = heartbeat_frame(MavlinkVersion::V2);
+        let mut parser = MavParser::<MavMessage>::new();
+        assert_eq!(parser.push_bytes(&frame[..10]).count(), 0);
+        assert_eq!(parser.buffered(), 10);
+        assert_eq!(parser.push_bytes(&frame[10..]).count(), 1);
+
+        assert_eq!(parser.push_bytes(&frame[..10]).count(), 0);
+        parser.reset();
+        assert_eq!(parser.push_bytes(&frame[10..]).count(), 0);
+    }
+
+    #[test]
+    pub fn test_frame_iterator() {
+        use mavlink::MavFrameIterator;
+
+        let stream = stream();
+        let reader = crate::test_shared::BlockyReader::new(stream.as_slice());
+        // the reader blocks before every byte and fails at the end of the data
+        let frames: Vec<_> = MavFrameIterator::<_, MavMessage>::new(reader)
+            .take_while(
+                |frame| !matches!(frame, Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof),
+            )
+            .filter_map(Result::ok)
+            .collect();
+        assert_eq!(
+            versions(&frames),
+            [MavlinkVersion::V2, MavlinkVersion::V1, MavlinkVersion::V2]
+        );
+
+        let messages = MavFrameIterator::<_, MavMessage>::new(stream.as_slice())
+            .messages()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to read messages");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].0, crate::test_shared::COMMON_MSG_HEADER);
+    }
+}
@@ -0,0 +1,341 @@
+mod test_shared;
+
+#[cfg(all(feature = "serde", feature = "common"))]
+mod test_pymavlink {
+    use mavlink::common::{MavMessage, MavParamType, PARAM_VALUE_DATA};
+    use mavlink::Pymavlink;
+
+    use self::json::to_string;
+
+    /// Test that a message serializes as the `to_dict` of pymavlink represents it
+    #[test]
+    pub fn test_pymavlink_heartbeat() {
+        let message = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        assert_eq!(
+            to_string(&Pymavlink(&message)),
+            r#"{"mavpackettype":"HEARTBEAT","type":2,"autopilot":3,"base_mode":89,"custom_mode":5,"system_status":3,"mavlink_version":3}"#
+        );
+    }
+
+    /// Test that fields follow the order of the definitions, with char arrays as strings and enums
+    /// as their value
+    #[test]
+    pub fn test_pymavlink_param_value() {
+        let mut param_id = [0u8; 16];
+        param_id[..6].copy_from_slice(b"RATE_P");
+        let param_value = PARAM_VALUE_DATA {
+            param_id,
+            param_value: 0.5,
+            param_type: MavParamType::MAV_PARAM_TYPE_REAL32,
+            param_count: 10,
+            param_index: 3,
+        };
+        let expected = r#"{"mavpackettype":"PARAM_VALUE","param_id":"RATE_P","param_value":0.5,"param_type":9,"param_count":10,"param_index":3}"#;
+        assert_eq!(to_string(&Pymavlink(&param_value)), expected);
+        assert_eq!(
+            to_string(&Pymavlink(&MavMessage::PARAM_VALUE(param_value))),
+            expected
+        );
+    }
+
+    /// Compact JSON writer for the types written by [`Pymavlink`], standing in for `serde_json`,
+    /// which isn't a dependency
+    mod json {
+        use core::fmt::{self, Display};
+
+        use serde::ser::{self, Impossible, Serialize};
+
+        pub fn to_string<T: Serialize>(value: &T) -> String {
+            let mut json = Json(String::new());
+            value.serialize(&mut json).unwrap();
+            json.0
+        }
+
+        #[derive(Debug)]
+        pub struct Error(String);
+
+        impl Display for Error {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl std::error::Error for Error {}
+
+        impl ser::Error for Error {
+            fn custom<T: Display>(msg: T) -> Self {
+                Self(msg.to_string())
+            }
+        }
+
+        fn unsupported<T>() -> Result<T, Error> {
+            Err(ser::Error::custom("unsupported by the test JSON writer"))
+        }
+
+        pub struct Json(String);
+
+        impl Json {
+            fn number(&mut self, value: impl Display) -> Result<(), Error> {
+                self.0.push_str(&value.to_string());
+                Ok(())
+            }
+
+            fn float(&mut self, value: f64, debug: String) -> Result<(), Error> {
+                if value.is_finite() {
+                    self.0.push_str(&debug);
+                } else {
+                    self.0.push_str("null");
+                }
+                Ok(())
+            }
+        }
+
+        pub struct Compound<'a> {
+            json: &'a mut Json,
+            first: bool,
+            close: char,
+        }
+
+        impl Compound<'_> {
+            fn separate(&mut self) {
+                if !self.first {
+                    self.json.0.push(',');
+                }
+                self.first = false;
+            }
+
+            fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                self.separate();
+                value.serialize(&mut *self.json)
+            }
+
+            fn entry<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+                self.separate();
+                ser::Serializer::serialize_str(&mut *self.json, key)?;
+                self.json.0.push(':');
+                value.serialize(&mut *self.json)
+            }
+
+            fn end(self) -> Result<(), Error> {
+                self.json.0.push(self.close);
+                Ok(())
+            }
+        }
+
+        impl<'a> ser::Serializer for &'a mut Json {
+            type Ok = ();
+            type Error = Error;
+            type SerializeSeq = Compound<'a>;
+            type SerializeTuple = Compound<'a>;
+            type SerializeTupleStruct = Compound<'a>;
+            type SerializeTupleVariant = Impossible<(), Error>;
+            type SerializeMap = Compound<'a>;
+            type SerializeStruct = Compound<'a>;
+            type SerializeStructVariant = Impossible<(), Error>;
+
+            fn serialize_bool(self, v: bool) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_i8(self, v: i8) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_i16(self, v: i16) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_i32(self, v: i32) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_i64(self, v: i64) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_u8(self, v: u8) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_u16(self, v: u16) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_u32(self, v: u32) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_u64(self, v: u64) -> Result<(), Error> {
+                self.number(v)
+            }
+            fn serialize_f32(self, v: f32) -> Result<(), Error> {
+                self.float(v.into(), format!("{v:?}"))
+            }
+            fn serialize_f64(self, v: f64) -> Result<(), Error> {
+                self.float(v, format!("{v:?}"))
+            }
+            fn serialize_char(self, v: char) -> Result<(), Error> {
+                self.serialize_str(v.encode_utf8(&mut [0; 4]))
+            }
+            fn serialize_str(self, v: &str) -> Result<(), Error> {
+                self.0.push('"');
+                for c in v.chars() {
+                    match c {
+                        '"' => self.0.push_str("\\\""),
+                        '\\' => self.0.push_str("\\\\"),
+                        c if c.is_control() => self.0.push_str(&format!("\\u{:04x}", c as u32)),
+                        c => self.0.push(c),
+                    }
+                }
+                self.0.push('"');
+                Ok(())
+            }
+            fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+                ser::Serializer::collect_seq(self, v)
+            }
+            fn serialize_none(self) -> Result<(), Error> {
+                self.serialize_unit()
+            }
+            fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+                value.serialize(self)
+            }
+            fn serialize_unit(self) -> Result<(), Error> {
+                self.0.push_str("null");
+                Ok(())
+            }
+            fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+                self.serialize_unit()
+            }
+            fn serialize_unit_variant(
+                self,
+                _name: &'static str,
+                _index: u32,
+                variant: &'static str,
+            ) -> Result<(), Error> {
+                self.serialize_str(variant)
+            }
+            fn serialize_newtype_struct<T: Serialize + ?Sized>(
+                self,
+                _name: &'static str,
+                value: &T,
+            ) -> Result<(), Error> {
+                value.serialize(self)
+            }
+            fn serialize_newtype_variant<T: Serialize + ?Sized>(
+                self,
+                _name: &'static str,
+                _index: u32,
+                _variant: &'static str,
+                _value: &T,
+            ) -> Result<(), Error> {
+                unsupported()
+            }
+            fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+                self.0.push('[');
+                Ok(Compound {
+                    json: self,
+                    first: true,
+                    close: ']',
+                })
+            }
+            fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+                self.serialize_seq(Some(len))
+            }
+            fn serialize_tuple_struct(
+                self,
+                _name: &'static str,
+                len: usize,
+            ) -> Result<Compound<'a>, Error> {
+                self.serialize_seq(Some(len))
+            }
+            fn serialize_tuple_variant(
+                self,
+                _name: &'static str,
+                _index: u32,
+                _variant: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeTupleVariant, Error> {
+                unsupported()
+            }
+            fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+                self.0.push('{');
+                Ok(Compound {
+                    json: self,
+                    first: true,
+                    close: '}',
+                })
+            }
+            fn serialize_struct(
+                self,
+                _name: &'static str,
+                len: usize,
+            ) -> Result<Compound<'a>, Error> {
+                self.serialize_map(Some(len))
+            }
+            fn serialize_struct_variant(
+                self,
+                _name: &'static str,
+                _index: u32,
+                _variant: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeStructVariant, Error> {
+                unsupported()
+            }
+        }
+
+        impl ser::SerializeSeq for Compound<'_> {
+            type Ok = ();
+            type Error = Error;
+            fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                self.element(value)
+            }
+            fn end(self) -> Result<(), Error> {
+                Compound::end(self)
+            }
+        }
+
+        impl ser::SerializeTuple for Compound<'_> {
+            type Ok = ();
+            type Error = Error;
+            fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                self.element(value)
+            }
+            fn end(self) -> Result<(), Error> {
+                Compound::end(self)
+            }
+        }
+
+        impl ser::SerializeTupleStruct for Compound<'_> {
+            type Ok = ();
+            type Error = Error;
+            fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                self.element(value)
+            }
+            fn end(self) -> Result<(), Error> {
+                Compound::end(self)
+            }
+        }
+
+        impl ser::SerializeMap for Compound<'_> {
+            type Ok = ();
+            type Error = Error;
+            fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+                self.element(key)
+            }
+            fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                self.json.0.push(':');
+                value.serialize(&mut *self.json)
+            }
+            fn end(self) -> Result<(), Error> {
+                Compound::end(self)
+            }
+        }
+
+        impl ser::SerializeStruct for Compound<'_> {
+            type Ok = ();
+            type Error = Error;
+            fn serialize_field<T: Serialize + ?Sized>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Error> {
+                self.entry(key, value)
+            }
+            fn end(self) -> Result<(), Error> {
+                Compound::end(self)
+            }
+        }
+    }
+}
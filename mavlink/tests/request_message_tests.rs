@@ -0,0 +1,172 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_request_message {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::{
+        MavCmd, MavMessage, MavResult, COMMAND_ACK_DATA, GLOBAL_POSITION_INT_DATA,
+    };
+    use mavlink::{MavHeader, MessageData, RequestMessageError};
+
+    const VEHICLE_HEADER: MavHeader = MavHeader {
+        system_id: 1,
+        component_id: 1,
+        sequence: 0,
+    };
+
+    /// Serve a single `MAV_CMD_REQUEST_MESSAGE`, answering with an ack for each of `results` and,
+    /// if the last one is accepted, the requested GLOBAL_POSITION_INT
+    fn spawn_vehicle(
+        address: &'static str,
+        results: &'static [MavResult],
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let vehicle = mavlink::connect::<MavMessage>(address).expect("Couldn't create server");
+            loop {
+                let (_header, msg) = vehicle.recv().expect("Failed to receive request");
+                let MavMessage::COMMAND_LONG(command) = msg else {
+                    continue;
+                };
+                assert_eq!(command.command, MavCmd::MAV_CMD_REQUEST_MESSAGE);
+                assert_eq!(command.param1 as u32, GLOBAL_POSITION_INT_DATA::ID);
+                assert_eq!(command.target_system, VEHICLE_HEADER.system_id);
+
+                // unrelated traffic must be skipped
+                vehicle
+                    .send(
+                        &VEHICLE_HEADER,
+                        &MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg()),
+                    )
+                    .unwrap();
+
+                for &result in results {
+                    let mut ack = COMMAND_ACK_DATA::DEFAULT;
+                    ack.command = MavCmd::MAV_CMD_REQUEST_MESSAGE;
+                    ack.result = result;
+                    vehicle
+                        .send(&VEHICLE_HEADER, &MavMessage::COMMAND_ACK(ack))
+                        .unwrap();
+                }
+
+                if results.last() == Some(&MavResult::MAV_RESULT_ACCEPTED) {
+                    let mut position = GLOBAL_POSITION_INT_DATA::DEFAULT;
+                    position.lat = 473_977_420;
+                    position.lon = 85_455_940;
+                    vehicle
+                        .send(&VEHICLE_HEADER, &MavMessage::GLOBAL_POSITION_INT(position))
+                        .unwrap();
+                }
+                break;
+            }
+        })
+    }
+
+    #[test]
+    pub fn test_request_message() {
+        let vehicle = spawn_vehicle("tcpin:0.0.0.0:14553", &[MavResult::MAV_RESULT_ACCEPTED]);
+        thread::sleep(Duration::from_millis(100));
+
+        let gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14553")
+            .expect("Couldn't create client");
+        let position: GLOBAL_POSITION_INT_DATA = mavlink::request_message(
+            &*gcs,
+            &MavHeader::default(),
+            VEHICLE_HEADER.system_id,
+            VEHICLE_HEADER.component_id,
+            Duration::from_secs(5),
+        )
+        .expect("Failed to request message");
+
+        assert_eq!(position.lat, 473_977_420);
+        assert_eq!(position.lon, 85_455_940);
+        vehicle.join().unwrap();
+    }
+
+    #[test]
+    pub fn test_request_message_in_progress() {
+        let vehicle = spawn_vehicle(
+            "tcpin:0.0.0.0:14591",
+            &[
+                MavResult::MAV_RESULT_IN_PROGRESS,
+                MavResult::MAV_RESULT_ACCEPTED,
+            ],
+        );
+        thread::sleep(Duration::from_millis(100));
+
+        let gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14591")
+            .expect("Couldn't create client");
+        let position: GLOBAL_POSITION_INT_DATA = mavlink::request_message(
+            &*gcs,
+            &MavHeader::default(),
+            VEHICLE_HEADER.system_id,
+            VEHICLE_HEADER.component_id,
+            Duration::from_secs(5),
+        )
+        .expect("Failed to request message");
+
+        assert_eq!(position.lat, 473_977_420);
+        vehicle.join().unwrap();
+    }
+
+    #[test]
+    pub fn test_request_message_rejected() {
+        let vehicle = spawn_vehicle("tcpin:0.0.0.0:14554", &[MavResult::MAV_RESULT_DENIED]);
+        thread::sleep(Duration::from_millis(100));
+
+        let gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14554")
+            .expect("Couldn't create client");
+        let result = mavlink::request_message::<_, GLOBAL_POSITION_INT_DATA, _>(
+            &*gcs,
+            &MavHeader::default(),
+            VEHICLE_HEADER.system_id,
+            VEHICLE_HEADER.component_id,
+            Duration::from_secs(5),
+        );
+
+        assert!(matches!(
+            result,
+            Err(RequestMessageError::Rejected {
+                message_id: GLOBAL_POSITION_INT_DATA::ID,
+                result: 2,
+            })
+        ));
+        vehicle.join().unwrap();
+    }
+
+    #[test]
+    pub fn test_request_message_silent_link() {
+        // the vehicle connects but never answers
+        let vehicle = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            let _vehicle = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14582")
+                .expect("Couldn't create client");
+            thread::sleep(Duration::from_secs(2));
+        });
+
+        // a tcpin connection has no read timeout, receiving blocks until data arrives
+        let gcs =
+            mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14582").expect("Couldn't create server");
+        let start = std::time::Instant::now();
+        let result = mavlink::request_message::<_, GLOBAL_POSITION_INT_DATA, _>(
+            &*gcs,
+            &MavHeader::default(),
+            VEHICLE_HEADER.system_id,
+            VEHICLE_HEADER.component_id,
+            Duration::from_millis(300),
+        );
+
+        assert!(matches!(
+            result,
+            Err(RequestMessageError::Timeout {
+                acked: false,
+                received: false,
+                ..
+            })
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(gcs.read_timeout().unwrap(), None);
+        vehicle.join().unwrap();
+    }
+}
@@ -72,6 +72,134 @@ mod signing {
         );
     }
 
+    #[test]
+    pub fn test_check_signature_errors() {
+        use mavlink::SignatureError;
+
+        let signing_data = SigningData::from_config(SigningConfig::new(SECRET_KEY, 0, true, false));
+        let mut r = PeekReader::new(HEARTBEAT_SIGNED);
+        let msg = read_v2_raw_message::<mavlink::common::MavMessage, _>(&mut r).unwrap();
+
+        let mut tampered = msg;
+        tampered.signature_value_mut()[0] ^= 0xff;
+        assert_eq!(
+            signing_data.check_signature(&tampered),
+            Err(SignatureError::Invalid)
+        );
+        assert_eq!(signing_data.check_signature(&msg), Ok(()));
+        assert_eq!(
+            signing_data.check_signature(&msg),
+            Err(SignatureError::Replayed)
+        );
+
+        let mut unsigned = MAVLinkV2MessageRaw::new();
+        unsigned.serialize_message(
+            crate::test_shared::COMMON_MSG_HEADER,
+            &mavlink::common::MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+        );
+        assert_eq!(
+            signing_data.check_signature(&unsigned),
+            Err(SignatureError::Unsigned)
+        );
+    }
+
+    #[test]
+    pub fn test_rejection_errors() {
+        use mavlink::error::MessageReadError;
+        use mavlink::{read_versioned_msg_signed, MavlinkVersion, SignatureError};
+
+        let config = SigningConfig::new(SECRET_KEY, 0, true, false).with_rejection_errors(true);
+        let signing_data = SigningData::from_config(config);
+        // the same frame twice, the second one is a replay
+        let frames = [HEARTBEAT_SIGNED, HEARTBEAT_SIGNED].concat();
+        let mut r = PeekReader::new(frames.as_slice());
+
+        read_versioned_msg_signed::<mavlink::common::MavMessage, _>(
+            &mut r,
+            MavlinkVersion::V2,
+            Some(&signing_data),
+        )
+        .unwrap();
+        let result = read_versioned_msg_signed::<mavlink::common::MavMessage, _>(
+            &mut r,
+            MavlinkVersion::V2,
+            Some(&signing_data),
+        );
+        assert!(matches!(
+            result,
+            Err(MessageReadError::Signature(SignatureError::Replayed))
+        ));
+    }
+
+    #[test]
+    pub fn test_key_store() {
+        use mavlink::common::MavMessage;
+        use mavlink::{Sha256Signer, SignatureError};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let gcs_key = [1u8; 32];
+        let companion_key = [2u8; 32];
+        let signed_by = |key, link_id| {
+            let mut message = MAVLinkV2MessageRaw::new();
+            message.serialize_message_for_signing(
+                crate::test_shared::COMMON_MSG_HEADER,
+                &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            );
+            SigningData::from_config(SigningConfig::new(key, link_id, true, false))
+                .sign_message(&mut message);
+            message
+        };
+
+        let keys = HashMap::from([
+            (1, Sha256Signer::new(gcs_key)),
+            (2, Sha256Signer::new(companion_key)),
+        ]);
+        let config = SigningConfig::new(SECRET_KEY, 0, true, false).with_key_store(Arc::new(keys));
+        let signing_data = SigningData::from_config(config);
+
+        // a valid key used with the link id of another peer
+        assert_eq!(
+            signing_data.check_signature(&signed_by(companion_key, 1)),
+            Err(SignatureError::Invalid)
+        );
+        assert_eq!(signing_data.check_signature(&signed_by(gcs_key, 1)), Ok(()));
+        assert_eq!(
+            signing_data.check_signature(&signed_by(companion_key, 2)),
+            Ok(())
+        );
+        assert_eq!(
+            signing_data.check_signature(&signed_by(SECRET_KEY, 3)),
+            Err(SignatureError::UnknownLink(3))
+        );
+    }
+
+    #[test]
+    pub fn test_unsigned_callback() {
+        use mavlink::common::{MavMessage, RADIO_STATUS_DATA};
+        use mavlink::{MessageData, SignatureError};
+
+        let config = SigningConfig::new(SECRET_KEY, 0, true, false)
+            .with_unsigned_callback(|_header, message_id| message_id == RADIO_STATUS_DATA::ID);
+        let signing_data = SigningData::from_config(config);
+
+        let unsigned = |msg: &MavMessage| {
+            let mut message = MAVLinkV2MessageRaw::new();
+            message.serialize_message(crate::test_shared::COMMON_MSG_HEADER, msg);
+            message
+        };
+        let radio_status = MavMessage::RADIO_STATUS(RADIO_STATUS_DATA::default());
+        let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        assert_eq!(
+            signing_data.check_signature(&unsigned(&radio_status)),
+            Ok(())
+        );
+        assert_eq!(
+            signing_data.check_signature(&unsigned(&heartbeat)),
+            Err(SignatureError::Unsigned)
+        );
+    }
+
     #[test]
     pub fn test_sign_verify() {
         use mavlink::common::MavMessage;
@@ -97,4 +225,264 @@ mod signing {
             "Invalid message verified"
         );
     }
+
+    #[test]
+    pub fn test_custom_signer() {
+        use mavlink::common::MavMessage;
+        use mavlink::{Mavlink2Signer, Sha256Signer};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // signer standing in for a hardware key store
+        struct CountingSigner {
+            inner: Sha256Signer,
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl Mavlink2Signer for CountingSigner {
+            fn sign(&self, data: &[u8], signature: &mut [u8; 6]) {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                self.inner.sign(data, signature);
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let signer = CountingSigner {
+            inner: Sha256Signer::new(SECRET_KEY),
+            calls: calls.clone(),
+        };
+        let signing_data =
+            SigningData::from_config(SigningConfig::with_signer(signer, 0, true, false));
+
+        let heartbeat_message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let mut message = MAVLinkV2MessageRaw::new();
+        message.serialize_message_for_signing(
+            crate::test_shared::COMMON_MSG_HEADER,
+            &heartbeat_message,
+        );
+        signing_data.sign_message(&mut message);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let verifier = SigningData::from_config(SigningConfig::new(SECRET_KEY, 0, true, false));
+        assert!(
+            verifier.verify_signature(&message),
+            "Message verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_static_signer() {
+        use mavlink::common::MavMessage;
+        use mavlink::{Mavlink2Signer, Sha256Signer};
+        use std::sync::Arc;
+
+        // as a firmware would hold its crypto peripheral
+        let signer: &'static dyn Mavlink2Signer =
+            Box::leak(Box::new(Sha256Signer::new(SECRET_KEY)));
+        let signing_data =
+            SigningData::from_config(SigningConfig::with_static_signer(signer, 1, true, false));
+
+        let mut message = MAVLinkV2MessageRaw::new();
+        message.serialize_message_for_signing(
+            crate::test_shared::COMMON_MSG_HEADER,
+            &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+        );
+        signing_data.sign_message(&mut message);
+        assert_eq!(message.signature_link_id(), 1);
+
+        // an array of link ids and signers is a key store without a heap
+        let keys = [(1, Sha256Signer::new(SECRET_KEY))];
+        let verifier = SigningData::from_config(
+            SigningConfig::new([0u8; 32], 0, true, false).with_key_store(Arc::new(keys)),
+        );
+        assert!(
+            verifier.verify_signature(&message),
+            "Message verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_gps_timestamp_source() {
+        use mavlink::common::{MavMessage, SYSTEM_TIME_DATA};
+        use mavlink::{GpsTimestampSource, SigningTimestampSource};
+        use std::sync::Arc;
+
+        // 1st January 2015, the epoch of signing timestamps, plus 1 s
+        const UNIX_TIME_US: u64 = 1_420_070_401_000_000;
+
+        let sign = |signing_data: &SigningData| {
+            let mut message = MAVLinkV2MessageRaw::new();
+            message.serialize_message_for_signing(
+                crate::test_shared::COMMON_MSG_HEADER,
+                &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            );
+            signing_data.sign_message(&mut message);
+            message.signature_timestamp()
+        };
+
+        let time = Arc::new(GpsTimestampSource::new());
+        let signing_data = SigningData::from_config(
+            SigningConfig::new(SECRET_KEY, 0, true, false).with_timestamp_source(time.clone()),
+        );
+
+        // the time is unknown, timestamps only increase
+        assert_eq!(sign(&signing_data), 0);
+        assert_eq!(sign(&signing_data), 1);
+
+        let mut system_time = SYSTEM_TIME_DATA::DEFAULT;
+        system_time.time_unix_usec = UNIX_TIME_US;
+        time.handle_message(&MavMessage::SYSTEM_TIME(system_time));
+        // in units of 10 us, extrapolated by the time elapsed since
+        let timestamp = sign(&signing_data);
+        assert!(
+            (100_000..100_000 + 100_000).contains(&timestamp),
+            "{timestamp}"
+        );
+
+        // GPS week 1825 started on 28th December 2014, GPS time was 16 s ahead of UTC
+        let time = GpsTimestampSource::new();
+        time.set_gps_time(1825, 4 * 24 * 3600 * 1000 + 17_000, 16);
+        let unix_time_us = time.unix_time_us().unwrap();
+        assert!((UNIX_TIME_US..UNIX_TIME_US + 1_000_000).contains(&unix_time_us));
+    }
+
+    /// Test that UDP connections report a frame failing verification when asked to
+    #[cfg(all(feature = "udp", feature = "common"))]
+    #[test]
+    pub fn test_udp_rejection_errors() {
+        use mavlink::error::MessageReadError;
+        use mavlink::SignatureError;
+        use std::net::UdpSocket;
+        use std::time::Duration;
+
+        let mut server = mavlink::connect::<mavlink::common::MavMessage>("udpin:0.0.0.0:14587")
+            .expect("Couldn't create server");
+        server.setup_signing(Some(
+            SigningConfig::new([0xaa; 32], 0, true, false).with_rejection_errors(true),
+        ));
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        // signed with another key
+        let client = UdpSocket::bind("0.0.0.0:0").unwrap();
+        client.send_to(HEARTBEAT_SIGNED, "127.0.0.1:14587").unwrap();
+        assert!(matches!(
+            server.recv(),
+            Err(MessageReadError::Signature(SignatureError::Invalid))
+        ));
+    }
+
+    /// Test that async UDP connections report a frame failing verification when asked to
+    #[cfg(all(feature = "tokio-1", feature = "udp", feature = "common"))]
+    #[tokio::test]
+    pub async fn test_udp_rejection_errors_async() {
+        use mavlink::error::MessageReadError;
+        use mavlink::SignatureError;
+        use std::net::UdpSocket;
+        use std::time::Duration;
+
+        let mut server =
+            mavlink::connect_async::<mavlink::common::MavMessage>("udpin:0.0.0.0:14588")
+                .await
+                .expect("Couldn't create server");
+        server.setup_signing(Some(
+            SigningConfig::new([0xaa; 32], 0, true, false).with_rejection_errors(true),
+        ));
+
+        // signed with another key
+        let client = UdpSocket::bind("0.0.0.0:0").unwrap();
+        client.send_to(HEARTBEAT_SIGNED, "127.0.0.1:14588").unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), server.recv())
+            .await
+            .expect("No error before the timeout");
+        assert!(matches!(
+            result,
+            Err(MessageReadError::Signature(SignatureError::Invalid))
+        ));
+    }
+
+    #[cfg(feature = "tokio-1")]
+    #[tokio::test]
+    pub async fn test_write_signed_async() {
+        use mavlink::common::MavMessage;
+        let heartbeat_message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+
+        let signing_cfg = SigningConfig::new(SECRET_KEY, 0, true, false);
+        let signing_data = SigningData::from_config(signing_cfg.clone());
+        let mut buffer = Vec::new();
+        let len = mavlink::write_versioned_msg_async_signed(
+            &mut buffer,
+            mavlink::MavlinkVersion::V2,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &heartbeat_message,
+            Some(&signing_data),
+        )
+        .await
+        .expect("Failed to write message");
+        assert_eq!(len, buffer.len());
+
+        let mut r = PeekReader::new(&buffer[..]);
+        let msg = read_v2_raw_message::<MavMessage, _>(&mut r).unwrap();
+        let verifier = SigningData::from_config(signing_cfg);
+        assert!(
+            verifier.verify_signature(&msg),
+            "Message verification failed"
+        );
+    }
+}
+
+#[cfg(all(feature = "signing", feature = "tcp", feature = "common"))]
+mod provision_signing {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{MavHeader, SigningConfig};
+
+    const VEHICLE_HEADER: MavHeader = MavHeader {
+        system_id: 1,
+        component_id: 1,
+        sequence: 0,
+    };
+
+    #[test]
+    pub fn test_provision_signing() {
+        let vehicle_thread = thread::spawn(|| {
+            let mut vehicle = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14577")
+                .expect("Couldn't create server");
+            let setup = loop {
+                if let (_, MavMessage::SETUP_SIGNING(setup)) = vehicle.recv().unwrap() {
+                    break setup;
+                }
+            };
+            assert_eq!(setup.target_system, VEHICLE_HEADER.system_id);
+            assert!(setup.initial_timestamp > 0);
+
+            let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+            // unsigned traffic doesn't confirm the key
+            vehicle.send(&VEHICLE_HEADER, &heartbeat).unwrap();
+            vehicle.setup_signing(Some(SigningConfig::new(setup.secret_key, 0, true, false)));
+            vehicle.send(&VEHICLE_HEADER, &heartbeat).unwrap();
+            setup.secret_key
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let mut gcs = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14577")
+            .expect("Couldn't create client");
+        let key = mavlink::generate_secret_key().unwrap();
+        mavlink::provision_signing(
+            gcs.as_mut(),
+            &MavHeader::default(),
+            VEHICLE_HEADER.system_id,
+            VEHICLE_HEADER.component_id,
+            key,
+            0,
+            Duration::from_secs(5),
+        )
+        .expect("Provisioning failed");
+        assert_eq!(vehicle_thread.join().unwrap(), key);
+    }
 }
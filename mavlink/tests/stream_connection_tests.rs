@@ -0,0 +1,147 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod test_stream_connection {
+    use std::io;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{MavConnection, StreamConnection};
+
+    /// Test that frames sent over a StreamConnection are numbered and can be read back by another one
+    #[test]
+    pub fn test_stream_connection_loopback() {
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+
+        let mut buffer = Vec::new();
+        {
+            let writer = StreamConnection::new(io::empty(), &mut buffer);
+            for _ in 0..3 {
+                writer
+                    .send(&crate::test_shared::COMMON_MSG_HEADER, &heartbeat)
+                    .expect("Failed to send message");
+            }
+        }
+
+        let reader = StreamConnection::new(io::Cursor::new(buffer), io::sink());
+        for sequence in 0..3 {
+            let (header, msg): (_, MavMessage) = reader.recv().expect("Failed to receive message");
+            assert_eq!(header.sequence, sequence);
+            assert_eq!(
+                header.system_id,
+                crate::test_shared::COMMON_MSG_HEADER.system_id
+            );
+            assert_eq!(msg, heartbeat);
+        }
+
+        let eof = MavConnection::<MavMessage>::recv(&reader);
+        assert!(matches!(
+            eof,
+            Err(mavlink::error::MessageReadError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    /// Test that peeking reports the next frame without consuming it
+    #[test]
+    pub fn test_stream_connection_peek() {
+        let mut buffer = vec![0xfd, 0x01, 0x02];
+        mavlink::write_versioned_msg(
+            &mut buffer,
+            mavlink::MavlinkVersion::V2,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg()),
+        )
+        .unwrap();
+
+        let connection = StreamConnection::new(io::Cursor::new(buffer), io::sink());
+        for _ in 0..2 {
+            let info = MavConnection::<MavMessage>::peek(&connection).expect("Failed to peek");
+            assert_eq!(info.header, crate::test_shared::COMMON_MSG_HEADER);
+            assert_eq!(info.message_id, 0);
+            assert_eq!(info.protocol_version, mavlink::MavlinkVersion::V2);
+        }
+
+        let (header, msg): (_, MavMessage) = connection.recv().expect("Failed to receive");
+        assert_eq!(header, crate::test_shared::COMMON_MSG_HEADER);
+        assert!(matches!(msg, MavMessage::HEARTBEAT(_)));
+    }
+
+    /// Test that a peeked signed frame is not rejected as replayed once received
+    #[cfg(feature = "signing")]
+    #[test]
+    pub fn test_stream_connection_peek_signed() {
+        use mavlink::SigningConfig;
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+
+        let mut buffer = Vec::new();
+        {
+            let mut writer = StreamConnection::new(io::empty(), &mut buffer);
+            MavConnection::<MavMessage>::setup_signing(
+                &mut writer,
+                Some(SigningConfig::new(
+                    crate::test_shared::SECRET_KEY,
+                    0,
+                    true,
+                    false,
+                )),
+            );
+            for _ in 0..2 {
+                writer
+                    .send(&crate::test_shared::COMMON_MSG_HEADER, &heartbeat)
+                    .expect("Failed to send message");
+            }
+        }
+
+        let mut reader = StreamConnection::new(io::Cursor::new(buffer), io::sink());
+        MavConnection::<MavMessage>::setup_signing(
+            &mut reader,
+            Some(SigningConfig::new(
+                crate::test_shared::SECRET_KEY,
+                0,
+                false,
+                false,
+            )),
+        );
+        for sequence in 0..2 {
+            for _ in 0..2 {
+                let info = MavConnection::<MavMessage>::peek(&reader).expect("Failed to peek");
+                assert_eq!(info.header.sequence, sequence);
+            }
+            let (header, msg): (_, MavMessage) = reader.recv().expect("Failed to receive");
+            assert_eq!(header.sequence, sequence);
+            assert_eq!(msg, heartbeat);
+        }
+    }
+
+    /// Test that received frames carry their signature
+    #[cfg(feature = "signing")]
+    #[test]
+    pub fn test_stream_connection_recv_frame_signed() {
+        use mavlink::{MavlinkVersion, SigningConfig};
+
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let config = SigningConfig::new(crate::test_shared::SECRET_KEY, 4, true, false);
+
+        let mut buffer = Vec::new();
+        {
+            let mut writer = StreamConnection::new(io::empty(), &mut buffer);
+            MavConnection::<MavMessage>::setup_signing(&mut writer, Some(config));
+            writer
+                .send(&crate::test_shared::COMMON_MSG_HEADER, &heartbeat)
+                .expect("Failed to send message");
+        }
+
+        let sent = buffer.clone();
+        let reader = StreamConnection::new(io::Cursor::new(buffer), io::sink());
+        let frame = MavConnection::<MavMessage>::recv_frame(&reader).expect("Failed to receive");
+        assert_eq!(frame.msg, heartbeat);
+        assert_eq!(frame.protocol_version, MavlinkVersion::V2);
+        let signature = frame.signature.expect("Signature was dropped");
+        assert_eq!(signature.link_id, 4);
+
+        // the frame serializes back to the bytes received
+        let mut bytes = [0; 280];
+        let len = frame.ser(MavlinkVersion::V2, &mut bytes).unwrap();
+        assert_eq!(bytes[..len], sent[..]);
+    }
+}
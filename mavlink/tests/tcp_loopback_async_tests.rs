@@ -68,4 +68,385 @@ mod test_tcp_connections {
 
         server_thread.await.unwrap();
     }
+
+    /// Test whether the messages received by an async connection can be polled as a `Stream`
+    #[tokio::test]
+    pub async fn test_tcp_stream() {
+        use futures_core::Stream;
+        use std::pin::Pin;
+        use std::sync::Arc;
+
+        const RECEIVE_CHECK_COUNT: u8 = 3;
+
+        tokio::spawn(async move {
+            let msg =
+                mavlink::common::MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+            // Give some time for the server to connect
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            let client = mavlink::connect_async("tcpout:127.0.0.1:14580")
+                .await
+                .expect("Couldn't create client");
+            for sequence in 0..RECEIVE_CHECK_COUNT {
+                let header = mavlink::MavHeader {
+                    sequence,
+                    ..Default::default()
+                };
+                client.send(&header, &msg).await.unwrap();
+            }
+        });
+
+        let server = mavlink::connect_async::<mavlink::common::MavMessage>("tcpin:0.0.0.0:14580")
+            .await
+            .expect("Couldn't create server");
+        let server: Arc<dyn mavlink::AsyncMavConnection<_> + Sync + Send> = Arc::from(server);
+        let mut stream = mavlink::AsyncMavStream::new(server);
+
+        for sequence in 0..RECEIVE_CHECK_COUNT {
+            let next = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx));
+            let (header, msg) = next.await.unwrap().expect("Failed to receive message");
+            assert_eq!(header.sequence, sequence);
+            assert!(matches!(msg, mavlink::common::MavMessage::HEARTBEAT(_)));
+        }
+    }
+
+    /// Test whether messages can be sent through an async connection as a `Sink`
+    #[tokio::test]
+    pub async fn test_tcp_sink() {
+        use futures_sink::Sink;
+        use std::pin::Pin;
+        use std::sync::Arc;
+
+        const SEND_CHECK_COUNT: u8 = 3;
+
+        tokio::spawn(async move {
+            // Give some time for the server to connect
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            let client =
+                mavlink::connect_async::<mavlink::common::MavMessage>("tcpout:127.0.0.1:14581")
+                    .await
+                    .expect("Couldn't create client");
+            let client: Arc<dyn mavlink::AsyncMavConnection<_> + Sync + Send> = Arc::from(client);
+            let mut sink = mavlink::AsyncMavSink::new(client);
+
+            let msg =
+                mavlink::common::MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+            for sequence in 0..SEND_CHECK_COUNT {
+                std::future::poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+                    .await
+                    .unwrap();
+                let header = mavlink::MavHeader {
+                    sequence,
+                    ..Default::default()
+                };
+                Pin::new(&mut sink)
+                    .start_send((header, msg.clone()))
+                    .unwrap();
+            }
+            std::future::poll_fn(|cx| Pin::new(&mut sink).poll_flush(cx))
+                .await
+                .unwrap();
+        });
+
+        let server = mavlink::connect_async::<mavlink::common::MavMessage>("tcpin:0.0.0.0:14581")
+            .await
+            .expect("Couldn't create server");
+        for sequence in 0..SEND_CHECK_COUNT {
+            let (header, msg) = server.recv().await.expect("Failed to receive message");
+            assert_eq!(header.sequence, sequence);
+            assert!(matches!(msg, mavlink::common::MavMessage::HEARTBEAT(_)));
+        }
+    }
+
+    /// Test that a `tcpserver` connection receives from several clients and sends to all of them
+    #[tokio::test]
+    pub async fn test_tcp_server_multiple_clients() {
+        use mavlink::common::MavMessage;
+        use mavlink::MavHeader;
+
+        let server = mavlink::connect_async::<MavMessage>("tcpserver:127.0.0.1:14567")
+            .await
+            .expect("Couldn't create server");
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let mut clients = vec![];
+        for system_id in [1, 2] {
+            let client = mavlink::connect_async::<MavMessage>("tcpout:127.0.0.1:14567")
+                .await
+                .expect("Couldn't create client");
+            let header = MavHeader {
+                system_id,
+                ..Default::default()
+            };
+            client.send(&header, &msg).await.unwrap();
+            clients.push(client);
+        }
+
+        let mut senders = vec![];
+        for _ in 0..2 {
+            let (header, received) = server.recv().await.unwrap();
+            assert_eq!(received, msg);
+            senders.push(header.system_id);
+        }
+        senders.sort();
+        assert_eq!(senders, [1, 2]);
+
+        let header = MavHeader {
+            system_id: 255,
+            ..Default::default()
+        };
+        assert!(server.send(&header, &msg).await.unwrap() > 0);
+        for client in &clients {
+            let (header, received) = client.recv().await.unwrap();
+            assert_eq!(header.system_id, 255);
+            assert_eq!(received, msg);
+        }
+    }
+
+    /// Test that a `tcpserver` connection keeps sending while a client doesn't read, and
+    /// disconnects that client once it falls behind
+    #[tokio::test]
+    pub async fn test_tcp_server_stalled_client() {
+        use mavlink::common::MavMessage;
+        use mavlink::MavHeader;
+        use std::time::Duration;
+
+        const MESSAGE_COUNT: usize = 2_000;
+
+        // small socket buffers so that the kernel doesn't absorb the frames for the client
+        let options = mavlink::ConnectionOptions {
+            send_buffer_size: Some(4096),
+            ..Default::default()
+        };
+        let server =
+            mavlink::connect_async_with_options::<MavMessage>("tcpserver:127.0.0.1:14583", options)
+                .await
+                .expect("Couldn't create server");
+        // same for the client that never reads
+        let stalled = tokio::net::TcpSocket::new_v4().unwrap();
+        stalled.set_recv_buffer_size(4096).unwrap();
+        let mut stalled = stalled
+            .connect("127.0.0.1:14583".parse().unwrap())
+            .await
+            .unwrap()
+            .into_std()
+            .unwrap();
+        stalled.set_nonblocking(false).unwrap();
+        stalled
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let client = mavlink::connect_async::<MavMessage>("tcpout:127.0.0.1:14583")
+            .await
+            .expect("Couldn't create client");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let receiver = tokio::spawn(async move {
+            for _ in 0..MESSAGE_COUNT {
+                client.recv().await.unwrap();
+            }
+        });
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        tokio::time::timeout(Duration::from_secs(20), async {
+            for index in 0..MESSAGE_COUNT {
+                assert!(server.send(&MavHeader::default(), &msg).await.unwrap() > 0);
+                // let the reading client keep up
+                if index % 16 == 0 {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        })
+        .await
+        .expect("the stalled client blocked the server");
+        receiver.await.unwrap();
+
+        // the stalled client gets the frames written before it was disconnected
+        tokio::task::spawn_blocking(move || std::io::copy(&mut stalled, &mut std::io::sink()))
+            .await
+            .unwrap()
+            .expect("the stalled client wasn't disconnected");
+    }
+
+    /// Test that the halves of a split connection receive and send from separate tasks
+    #[tokio::test]
+    pub async fn test_tcp_split() {
+        use mavlink::common::MavMessage;
+
+        let server = tokio::spawn(async {
+            let server = mavlink::connect_async::<MavMessage>("tcpin:127.0.0.1:14568")
+                .await
+                .expect("Couldn't create server");
+            // echo the received messages back
+            for _ in 0..3 {
+                let (header, msg) = server.recv().await.unwrap();
+                server.send(&header, &msg).await.unwrap();
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let client = mavlink::connect_async::<MavMessage>("tcpout:127.0.0.1:14568")
+            .await
+            .expect("Couldn't create client");
+        let (reader, writer) = mavlink::into_split(client);
+
+        let receiver = tokio::spawn(async move {
+            for _ in 0..3 {
+                let (_, msg) = reader.recv().await.unwrap();
+                assert!(matches!(msg, MavMessage::HEARTBEAT(_)));
+            }
+        });
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for _ in 0..3 {
+            writer.send_default(&msg).await.unwrap();
+        }
+
+        receiver.await.unwrap();
+        server.await.unwrap();
+    }
+
+    /// Test that a receive with a timeout fails when nothing is sent, then gets the next message
+    #[tokio::test]
+    pub async fn test_tcp_recv_timeout() {
+        use mavlink::common::MavMessage;
+        use mavlink::error::MessageReadError;
+        use std::time::Duration;
+
+        let server = tokio::spawn(async {
+            let server = mavlink::connect_async::<MavMessage>("tcpin:127.0.0.1:14569")
+                .await
+                .expect("Couldn't create server");
+            let result = server.recv_timeout(Duration::from_millis(50)).await;
+            assert!(matches!(result, Err(MessageReadError::Timeout)));
+
+            let (_, msg) = server.recv_timeout(Duration::from_secs(5)).await.unwrap();
+            assert!(matches!(msg, MavMessage::HEARTBEAT(_)));
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let client = mavlink::connect_async::<MavMessage>("tcpout:127.0.0.1:14569")
+            .await
+            .expect("Couldn't create client");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).await.unwrap();
+
+        server.await.unwrap();
+    }
+
+    /// Test that a receive timing out in the middle of a frame keeps the bytes read so far
+    #[tokio::test]
+    pub async fn test_tcp_recv_timeout_mid_frame() {
+        use mavlink::common::MavMessage;
+        use mavlink::error::MessageReadError;
+        use std::time::Duration;
+        use tokio::io::AsyncWriteExt;
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let server = tokio::spawn({
+            let msg = msg.clone();
+            async move {
+                let server = mavlink::connect_async::<MavMessage>("tcpin:127.0.0.1:14584")
+                    .await
+                    .expect("Couldn't create server");
+                let result = server.recv_timeout(Duration::from_millis(100)).await;
+                assert!(matches!(result, Err(MessageReadError::Timeout)));
+
+                for sequence in [7, 8] {
+                    let (header, received) =
+                        server.recv_timeout(Duration::from_secs(5)).await.unwrap();
+                    assert_eq!(header.sequence, sequence);
+                    assert_eq!(received, msg);
+                }
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut frames = Vec::new();
+        for sequence in [7, 8] {
+            let header = mavlink::MavHeader {
+                sequence,
+                ..crate::test_shared::COMMON_MSG_HEADER
+            };
+            mavlink::write_versioned_msg(&mut frames, mavlink::MavlinkVersion::V2, header, &msg)
+                .unwrap();
+        }
+        let mut client = tokio::net::TcpStream::connect("127.0.0.1:14584")
+            .await
+            .unwrap();
+        // the header and part of the payload of the first frame, then the rest of it once the
+        // receive timed out
+        client.write_all(&frames[..15]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        client.write_all(&frames[15..]).await.unwrap();
+
+        server.await.unwrap();
+    }
+
+    /// Test that a connection opened with options exchanges messages
+    #[tokio::test]
+    pub async fn test_tcp_connection_options() {
+        use mavlink::common::MavMessage;
+        use mavlink::ConnectionOptions;
+        use std::time::Duration;
+
+        let options = ConnectionOptions {
+            connect_timeout: Some(Duration::from_secs(1)),
+            nodelay: Some(true),
+            recv_buffer_size: Some(64 * 1024),
+            ..Default::default()
+        };
+
+        let server = tokio::spawn({
+            let options = options.clone();
+            async move {
+                let server = mavlink::connect_async_with_options::<MavMessage>(
+                    "tcpin:127.0.0.1:14572",
+                    options,
+                )
+                .await
+                .expect("Couldn't create server");
+                server.recv().await.unwrap().1
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let client =
+            mavlink::connect_async_with_options::<MavMessage>("tcpout:127.0.0.1:14572", options)
+                .await
+                .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).await.unwrap();
+        assert_eq!(server.await.unwrap(), msg);
+    }
+
+    /// Test that a raw frame is received with the bytes it was sent with
+    #[tokio::test]
+    pub async fn test_tcp_raw_frames() {
+        use mavlink::common::MavMessage;
+        use mavlink::{MAVLinkV2MessageRaw, MavHeader, RawFrame};
+
+        let server = tokio::spawn(async {
+            let server = mavlink::connect_async::<MavMessage>("tcpin:127.0.0.1:14575")
+                .await
+                .expect("Couldn't create server");
+            server.recv_raw().await.unwrap()
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let client = mavlink::connect_async::<MavMessage>("tcpout:127.0.0.1:14575")
+            .await
+            .expect("Couldn't create client");
+        let header = MavHeader {
+            system_id: 7,
+            component_id: 3,
+            sequence: 42,
+        };
+        let mut message = MAVLinkV2MessageRaw::new();
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        message.serialize_message(header, &msg);
+        let sent = RawFrame::V2(message);
+        client.send_raw(&sent).await.unwrap();
+
+        let received = server.await.unwrap();
+        assert_eq!(received.raw_bytes(), sent.raw_bytes());
+        assert_eq!(received.info().header, header);
+    }
 }
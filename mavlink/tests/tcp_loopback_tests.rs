@@ -68,3 +68,346 @@ mod test_tcp_connections {
         server_thread.join().unwrap();
     }
 }
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_target_filter {
+    use std::thread;
+
+    use mavlink::common::{MavMessage, COMMAND_LONG_DATA};
+    use mavlink::TargetFilter;
+
+    /// Test that a connection with a target filter only delivers messages meant for it
+    #[test]
+    pub fn test_tcp_target_filter() {
+        let server_thread = thread::spawn(move || {
+            let mut server = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14555")
+                .expect("Couldn't create server");
+            server.set_target_filter(Some(TargetFilter::new(1, 1)));
+
+            for expected_command in [1.0, 2.0] {
+                let (_header, msg) = server.recv().expect("Failed to receive message");
+                let MavMessage::COMMAND_LONG(command) = msg else {
+                    panic!("Unexpected message {msg:?}");
+                };
+                assert_eq!(command.param1, expected_command);
+            }
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(std::time::Duration::from_millis(100));
+
+        let client = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14555")
+            .expect("Couldn't create client");
+        for (param1, target_system, target_component) in
+            [(0.0, 2, 1), (1.0, 1, 1), (0.0, 1, 2), (2.0, 0, 0)]
+        {
+            let mut command = COMMAND_LONG_DATA::DEFAULT;
+            command.param1 = param1;
+            command.target_system = target_system;
+            command.target_component = target_component;
+            client
+                .send_default(&MavMessage::COMMAND_LONG(command))
+                .unwrap();
+        }
+
+        server_thread.join().unwrap();
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_source_filter {
+    use std::thread;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{MavHeader, SourceFilter};
+
+    /// Test that a connection with a source filter drops the frames echoed back from our system
+    #[test]
+    pub fn test_tcp_source_filter() {
+        let server_thread = thread::spawn(move || {
+            let mut server = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14565")
+                .expect("Couldn't create server");
+            server.set_source_filter(Some(SourceFilter::new(255)));
+
+            for expected_system in [1, 2] {
+                let (header, _msg) = server.recv().expect("Failed to receive message");
+                assert_eq!(header.system_id, expected_system);
+            }
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(std::time::Duration::from_millis(100));
+
+        let client = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14565")
+            .expect("Couldn't create client");
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for system_id in [255, 1, 255, 2] {
+            let header = MavHeader {
+                system_id,
+                ..MavHeader::default()
+            };
+            client.send(&header, &heartbeat).unwrap();
+        }
+
+        server_thread.join().unwrap();
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_write_coalescing {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{Connectable, TcpConnectable};
+
+    /// Test that messages batched by write coalescing are all delivered, in order
+    #[test]
+    pub fn test_tcp_write_coalescing() {
+        let server_thread = thread::spawn(|| {
+            let server = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14563")
+                .expect("Couldn't create server");
+            (0..5)
+                .map(|_| server.recv().expect("Failed to receive message"))
+                .collect::<Vec<_>>()
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let client = TcpConnectable::new("127.0.0.1:14563".to_string(), true)
+            .with_write_coalescing(Duration::from_millis(5))
+            .connect::<MavMessage>()
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for _ in 0..5 {
+            client.send_default(&msg).expect("Failed to send message");
+        }
+
+        let received = server_thread.join().unwrap();
+        for (sequence, (header, received)) in received.into_iter().enumerate() {
+            assert_eq!(header.sequence as usize, sequence);
+            assert_eq!(received, msg);
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_server_reaccept {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::error::MessageReadError;
+
+    /// Test that a server accepts a new client after the previous one disconnected
+    #[test]
+    pub fn test_tcp_server_reaccept() {
+        let server_thread = thread::spawn(|| {
+            let server = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14570")
+                .expect("Couldn't create server");
+            let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+            for _ in 0..2 {
+                let (_, received) = server.recv().expect("Failed to receive message");
+                assert_eq!(received, msg);
+            }
+            server.send_default(&msg).expect("Failed to send message");
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let client = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14570")
+            .expect("Couldn't create client");
+        client.send_default(&msg).expect("Failed to send message");
+        drop(client);
+
+        thread::sleep(Duration::from_millis(100));
+        let client = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14570")
+            .expect("Couldn't create client");
+        client.send_default(&msg).expect("Failed to send message");
+        server_thread.join().unwrap();
+
+        let received = loop {
+            match client.recv() {
+                Err(MessageReadError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => break result.expect("Failed to receive message").1,
+            }
+        };
+        assert_eq!(received, msg);
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_connection_options {
+    use std::io;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use mavlink::common::MavMessage;
+    use mavlink::error::MessageReadError;
+    use mavlink::ConnectionOptions;
+
+    /// Test that a connection opened with options exchanges messages and honors the read timeout
+    #[test]
+    pub fn test_tcp_connection_options() {
+        let options = ConnectionOptions {
+            connect_timeout: Some(Duration::from_secs(1)),
+            read_timeout: Some(Duration::from_millis(300)),
+            nodelay: Some(true),
+            recv_buffer_size: Some(64 * 1024),
+            send_buffer_size: Some(64 * 1024),
+            ..Default::default()
+        };
+
+        let server_thread = thread::spawn({
+            let options = options.clone();
+            move || {
+                let server =
+                    mavlink::connect_with_options::<MavMessage>("tcpin:0.0.0.0:14571", options)
+                        .expect("Couldn't create server");
+                let (_, received) = server.recv().expect("Failed to receive message");
+                // the server is kept open so the client waits for data
+                (received, server)
+            }
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let client = mavlink::connect_with_options::<MavMessage>("tcpout:127.0.0.1:14571", options)
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).expect("Failed to send message");
+        let (received, _server) = server_thread.join().unwrap();
+        assert_eq!(received, msg);
+
+        let start = Instant::now();
+        match client.recv() {
+            Err(MessageReadError::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            result => panic!("Expected a read timeout, got {result:?}"),
+        }
+        assert!(start.elapsed() >= Duration::from_millis(250));
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_connectable_builder {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{Connectable, MavlinkVersion, TcpConnectable};
+
+    /// Test that connections built without an address string use the configured protocol version
+    #[test]
+    pub fn test_tcp_connectable_protocol_version() {
+        let server_thread = thread::spawn(|| {
+            let server = TcpConnectable::new("0.0.0.0:14573".to_string(), false)
+                .with_protocol_version(MavlinkVersion::V1)
+                .connect::<MavMessage>()
+                .expect("Couldn't create server");
+            assert_eq!(server.protocol_version(), MavlinkVersion::V1);
+            server.recv_frame().expect("Failed to receive message")
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let client = TcpConnectable::new("127.0.0.1:14573".to_string(), true)
+            .with_protocol_version(MavlinkVersion::V1)
+            .connect::<MavMessage>()
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).expect("Failed to send message");
+
+        let frame = server_thread.join().unwrap();
+        assert_eq!(frame.protocol_version, MavlinkVersion::V1);
+        assert_eq!(frame.msg, msg);
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_raw_frames {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{MAVLinkV2MessageRaw, MavHeader, RawFrame};
+
+    /// Test that raw frames are forwarded unchanged, keeping the sequence number of the sender
+    #[test]
+    pub fn test_tcp_raw_frames() {
+        let server_thread = thread::spawn(|| {
+            let server = mavlink::connect::<MavMessage>("tcpin:0.0.0.0:14574")
+                .expect("Couldn't create server");
+            let frame = server.recv_raw().expect("Failed to receive frame");
+            server.send_raw(&frame).expect("Failed to echo frame");
+            frame
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let client = mavlink::connect::<MavMessage>("tcpout:127.0.0.1:14574")
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let header = MavHeader {
+            system_id: 7,
+            component_id: 3,
+            sequence: 42,
+        };
+        let mut message = MAVLinkV2MessageRaw::new();
+        message.serialize_message(header, &msg);
+        let sent = RawFrame::V2(message);
+        let len = client.send_raw(&sent).expect("Failed to send frame");
+        assert_eq!(len, sent.raw_bytes().len());
+
+        let received = server_thread.join().unwrap();
+        assert_eq!(received.raw_bytes(), sent.raw_bytes());
+        assert_eq!(received.info().header, header);
+
+        let echoed = client.recv_raw().expect("Failed to receive echoed frame");
+        assert_eq!(echoed, sent);
+        assert_eq!(echoed.parse::<MavMessage>().unwrap(), (header, msg));
+    }
+}
+
+#[cfg(all(feature = "std", feature = "tcp", feature = "common"))]
+mod test_tcp_address_resolution {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+
+    /// Test that a host name resolving to several addresses, such as `localhost` with both IPv6
+    /// and IPv4 addresses, connects to the one with a listening server
+    #[test]
+    pub fn test_tcp_resolve_every_address() {
+        let server_thread = thread::spawn(|| {
+            let server = mavlink::connect::<MavMessage>("tcpin:127.0.0.1:14576")
+                .expect("Couldn't create server");
+            server.recv().expect("Failed to receive message").1
+        });
+
+        // Give some time for the server to connect
+        thread::sleep(Duration::from_millis(100));
+
+        let client = mavlink::connect::<MavMessage>("tcpout:localhost:14576")
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).expect("Failed to send message");
+        assert_eq!(server_thread.join().unwrap(), msg);
+    }
+
+    /// Test that an address without port is reported as an error
+    #[test]
+    pub fn test_tcp_invalid_address() {
+        assert!(mavlink::connect::<MavMessage>("tcpout:127.0.0.1").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod test_telemetry_cache {
+    use mavlink::common::{MavMessage, ATTITUDE_DATA, COMMAND_LONG_DATA, HEARTBEAT_DATA};
+    use mavlink::{MavHeader, MessageData, TelemetryCache};
+
+    fn header(system_id: u8) -> MavHeader {
+        MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        }
+    }
+
+    fn attitude(roll: f32) -> MavMessage {
+        let mut attitude = ATTITUDE_DATA::DEFAULT;
+        attitude.roll = roll;
+        MavMessage::ATTITUDE(attitude)
+    }
+
+    #[test]
+    pub fn test_latest_message() {
+        let cache = TelemetryCache::<MavMessage>::new();
+        assert!(cache.get::<ATTITUDE_DATA>().is_none());
+
+        let heartbeat = crate::test_shared::get_heartbeat_msg();
+        cache.handle_message(&header(1), &MavMessage::HEARTBEAT(heartbeat.clone()));
+        cache.handle_message(&header(1), &attitude(0.5));
+        cache.handle_message(&header(2), &attitude(1.5));
+
+        assert_eq!(cache.get::<HEARTBEAT_DATA>(), Some(heartbeat));
+        assert_eq!(cache.get::<ATTITUDE_DATA>().unwrap().roll, 1.5);
+        assert!(cache.get::<COMMAND_LONG_DATA>().is_none());
+        let cached = cache.get_message(ATTITUDE_DATA::ID).unwrap();
+        assert_eq!(cached.header.system_id, 2);
+
+        // sources are only kept apart on request
+        assert!(cache.get_from::<ATTITUDE_DATA>(1, 1).is_none());
+
+        cache.clear();
+        assert!(cache.get::<ATTITUDE_DATA>().is_none());
+    }
+
+    #[test]
+    pub fn test_per_source() {
+        let cache = TelemetryCache::<MavMessage>::new().with_per_source(true);
+        cache.handle_message(&header(1), &attitude(0.5));
+        cache.handle_message(&header(2), &attitude(1.5));
+
+        assert_eq!(cache.get_from::<ATTITUDE_DATA>(1, 1).unwrap().roll, 0.5);
+        assert_eq!(cache.get_from::<ATTITUDE_DATA>(2, 1).unwrap().roll, 1.5);
+        assert!(cache.get_from::<ATTITUDE_DATA>(3, 1).is_none());
+        assert_eq!(cache.get::<ATTITUDE_DATA>().unwrap().roll, 1.5);
+    }
+
+    #[cfg(feature = "tokio-1")]
+    #[tokio::test]
+    pub async fn test_watch() {
+        use std::sync::Arc;
+
+        let cache = Arc::new(TelemetryCache::<MavMessage>::new());
+        cache.handle_message(&header(1), &attitude(0.5));
+
+        let mut watch = cache.watch::<ATTITUDE_DATA>();
+        assert_eq!(watch.latest().unwrap().1.roll, 0.5);
+
+        let feeder = cache.clone();
+        tokio::spawn(async move {
+            feeder.handle_message(&header(1), &MavMessage::HEARTBEAT(HEARTBEAT_DATA::DEFAULT));
+            feeder.handle_message(&header(2), &attitude(1.5));
+        });
+        let (received_header, received) = watch.changed().await.unwrap();
+        assert_eq!(received_header.system_id, 2);
+        assert_eq!(received.roll, 1.5);
+
+        drop(cache);
+        assert!(watch.changed().await.is_none());
+    }
+}
@@ -0,0 +1,218 @@
+mod test_shared;
+
+#[cfg(all(feature = "std", feature = "common"))]
+mod tlog {
+    use std::io::{self, Cursor, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use mavlink::common::MavMessage;
+    use mavlink::peek_reader::PeekReader;
+    use mavlink::{
+        read_versioned_msg, Connectable, FileConnectable, MavlinkVersion, PlaybackControl,
+        TlogPlayer, TlogReader, TlogWriter, MAV_STX_V2,
+    };
+
+    /// Writer recording whether it was flushed
+    #[derive(Clone, Default)]
+    struct SharedBuffer {
+        data: Arc<Mutex<Vec<u8>>>,
+        flushed: Arc<Mutex<bool>>,
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            *self.flushed.lock().unwrap() = false;
+            self.data.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            *self.flushed.lock().unwrap() = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_write_tlog() {
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let header = crate::test_shared::COMMON_MSG_HEADER;
+
+        let mut log = TlogWriter::new(Vec::new());
+        log.write_frame(0x0102_0304_0506_0708, &[MAV_STX_V2])
+            .unwrap();
+        let len = log
+            .write_message(MavlinkVersion::V2, header, &heartbeat)
+            .unwrap();
+        let data = log.into_inner().unwrap();
+
+        assert_eq!(&data[..9], &[1, 2, 3, 4, 5, 6, 7, 8, MAV_STX_V2]);
+        assert_eq!(data.len(), 9 + 8 + len);
+
+        let mut reader = PeekReader::new(&data[17..]);
+        let (read_header, msg) =
+            read_versioned_msg::<MavMessage, _>(&mut reader, MavlinkVersion::V2).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(msg, heartbeat);
+    }
+
+    #[test]
+    pub fn test_flush_on_drop() {
+        let buffer = SharedBuffer::default();
+        let mut log = TlogWriter::new(buffer.clone());
+        log.write_frame(0, &[MAV_STX_V2]).unwrap();
+        assert!(!*buffer.flushed.lock().unwrap());
+
+        drop(log);
+        assert!(*buffer.flushed.lock().unwrap());
+        assert_eq!(buffer.data.lock().unwrap().len(), 9);
+    }
+
+    /// Log of heartbeats recorded every 100 ms, alternating between MAVLink 1 and 2
+    fn heartbeat_log(count: u64) -> Vec<u8> {
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let mut log = TlogWriter::new(Vec::new());
+        for i in 0..count {
+            let version = if i % 2 == 0 {
+                MavlinkVersion::V1
+            } else {
+                MavlinkVersion::V2
+            };
+            let mut header = crate::test_shared::COMMON_MSG_HEADER;
+            header.sequence = i as u8;
+            let mut frame = Vec::new();
+            mavlink::write_versioned_msg(&mut frame, version, header, &heartbeat).unwrap();
+            log.write_frame(1_000_000 + i * 100_000, &frame).unwrap();
+        }
+        log.into_inner().unwrap()
+    }
+
+    fn sequence(frame: &[u8]) -> u8 {
+        match frame[0] {
+            MAV_STX_V2 => frame[4],
+            _ => frame[2],
+        }
+    }
+
+    #[test]
+    pub fn test_read_tlog() {
+        let log = heartbeat_log(3);
+        let mut reader = TlogReader::new(&log[..]);
+        for i in 0..3 {
+            let record = reader.read_record().unwrap().unwrap();
+            assert_eq!(record.timestamp_us, 1_000_000 + i * 100_000);
+            assert_eq!(sequence(&record.frame), i as u8);
+        }
+        assert_eq!(reader.read_record().unwrap(), None);
+
+        // recorded by a ground station
+        let log = std::fs::read("tests/log.tlog").unwrap();
+        let mut reader = TlogReader::new(&log[..]);
+        let mut records = 0;
+        while let Some(record) = reader.read_record().unwrap() {
+            assert!(record.timestamp_us > 0);
+            records += 1;
+        }
+        assert!(records > 0);
+    }
+
+    #[test]
+    pub fn test_tlog_playback_pace() {
+        let control = PlaybackControl::new(2.0);
+        let mut player = TlogPlayer::new(Cursor::new(heartbeat_log(5)), control.clone());
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            player.next_record().unwrap().unwrap();
+        }
+        // 400 ms of recording played twice as fast
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(400), "{elapsed:?}");
+        assert_eq!(player.next_record().unwrap(), None);
+    }
+
+    #[test]
+    pub fn test_tlog_playback_seek() {
+        let control = PlaybackControl::new(f32::INFINITY);
+        let mut player = TlogPlayer::new(Cursor::new(heartbeat_log(5)), control.clone())
+            .with_rewrite_timestamps(true);
+
+        let record = player.next_record().unwrap().unwrap();
+        assert!(record.timestamp_us > 1_000_000_000_000, "not rewritten");
+        player.next_record().unwrap().unwrap();
+        player.next_record().unwrap().unwrap();
+
+        // back to the second record
+        control.seek(Duration::from_millis(50));
+        let record = player.next_record().unwrap().unwrap();
+        assert_eq!(sequence(&record.frame), 1);
+
+        control.pause();
+        assert!(control.is_paused());
+        let resumer = {
+            let control = control.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                control.resume();
+            })
+        };
+        let start = Instant::now();
+        player.next_record().unwrap().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        resumer.join().unwrap();
+    }
+
+    #[test]
+    pub fn test_file_connection_playback() {
+        let path =
+            std::env::temp_dir().join(format!("mavlink-playback-{}.tlog", std::process::id()));
+        std::fs::write(&path, heartbeat_log(4)).unwrap();
+
+        let connection = FileConnectable::new(path.to_str().unwrap().to_string())
+            .with_playback(PlaybackControl::new(4.0))
+            .connect::<MavMessage>()
+            .unwrap();
+        let start = Instant::now();
+        // the MAVLink 1 frames are skipped by the MAVLink 2 connection
+        for i in [1, 3] {
+            let (header, _) = connection.recv().unwrap();
+            assert_eq!(header.sequence, i);
+        }
+        // 300 ms of recording played four times as fast
+        assert!(start.elapsed() >= Duration::from_millis(75));
+        assert!(connection.recv().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    pub fn test_fileout_connection() {
+        let path =
+            std::env::temp_dir().join(format!("mavlink-fileout-{}.tlog", std::process::id()));
+        let address = format!("fileout:{}", path.to_str().unwrap());
+        let heartbeat = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+
+        // each connection appends to the log
+        for sequence in [7, 8] {
+            let connection = mavlink::connect::<MavMessage>(&address).unwrap();
+            let header = mavlink::MavHeader {
+                sequence,
+                ..Default::default()
+            };
+            connection.send(&header, &heartbeat).unwrap();
+            assert!(connection.recv().is_err());
+        }
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mut reader = TlogReader::new(file);
+        for sequence in [7, 8] {
+            let record = reader.read_record().unwrap().unwrap();
+            let mut frame = PeekReader::new(record.frame.as_slice());
+            let (header, msg) =
+                read_versioned_msg::<MavMessage, _>(&mut frame, MavlinkVersion::V2).unwrap();
+            assert_eq!(header.sequence, sequence);
+            assert_eq!(msg, heartbeat);
+        }
+        assert!(reader.read_record().unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
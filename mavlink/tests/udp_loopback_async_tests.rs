@@ -47,3 +47,73 @@ mod test_udp_connections {
         assert_eq!(recv_count, RECEIVE_CHECK_COUNT);
     }
 }
+
+#[cfg(all(feature = "tokio-1", feature = "udp", feature = "common"))]
+mod test_multi_connection {
+    use mavlink::common::MavMessage;
+    use mavlink::{AsyncMultiConnection, MavHeader};
+
+    /// Test that messages of several async connections are received and tagged with their source
+    #[tokio::test]
+    pub async fn test_recv_any() {
+        let mut connections = AsyncMultiConnection::<MavMessage>::new();
+        let first = connections.add(mavlink::connect_async("udpin:0.0.0.0:14559").await.unwrap());
+        let second = connections.add(mavlink::connect_async("udpin:0.0.0.0:14560").await.unwrap());
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for (port, system_id) in [(14559, 1), (14560, 2)] {
+            let client = mavlink::connect_async::<MavMessage>(&format!("udpout:127.0.0.1:{port}"))
+                .await
+                .expect("Couldn't create client");
+            let header = MavHeader {
+                system_id,
+                ..Default::default()
+            };
+            client.send(&header, &msg).await.unwrap();
+        }
+
+        let mut sources = Vec::new();
+        for _ in 0..2 {
+            let (index, result) = connections.recv_any().await;
+            let (header, _) = result.expect("Failed to receive message");
+            sources.push((index, header.system_id));
+        }
+        sources.sort();
+        assert_eq!(sources, [(first, 1), (second, 2)]);
+    }
+
+    /// Test that an error is returned once no connection of the set is left
+    #[cfg(feature = "tcp")]
+    #[tokio::test]
+    pub async fn test_recv_any_all_failed() {
+        use std::io::ErrorKind;
+
+        use mavlink::error::MessageReadError;
+
+        let mut connections = AsyncMultiConnection::<MavMessage>::new();
+        assert!(matches!(
+            connections.recv_any().await,
+            (0, Err(MessageReadError::Io(e))) if e.kind() == ErrorKind::NotConnected
+        ));
+
+        // a server closing the connection right away
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:14590")
+            .await
+            .unwrap();
+        let server = tokio::spawn(async move { drop(listener.accept().await.unwrap()) });
+        let index = connections.add(
+            mavlink::connect_async("tcpout:127.0.0.1:14590")
+                .await
+                .unwrap(),
+        );
+        server.await.unwrap();
+
+        let (failed, result) = connections.recv_any().await;
+        assert_eq!(failed, index);
+        assert!(matches!(result, Err(MessageReadError::Io(_))));
+        assert!(matches!(
+            connections.recv_any().await,
+            (1, Err(MessageReadError::Io(e))) if e.kind() == ErrorKind::NotConnected
+        ));
+    }
+}
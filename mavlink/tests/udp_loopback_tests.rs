@@ -45,3 +45,240 @@ mod test_udp_connections {
         assert_eq!(recv_count, RECEIVE_CHECK_COUNT);
     }
 }
+
+#[cfg(all(feature = "std", feature = "udp", feature = "common"))]
+mod test_udp_reorder {
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{Connectable, MavHeader, MavlinkVersion, UdpConnectable, UdpMode};
+
+    /// Test that a UDP connection with a reorder buffer delivers frames in sequence order
+    #[test]
+    pub fn test_udp_reorder() {
+        let server = UdpConnectable::new("0.0.0.0:14556".to_string(), UdpMode::Udpin)
+            .with_reorder_buffer(4, Duration::from_millis(200))
+            .connect::<MavMessage>()
+            .expect("Couldn't create server");
+
+        let client = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for sequence in [0, 2, 3, 1, 5] {
+            let header = MavHeader {
+                sequence,
+                ..crate::test_shared::COMMON_MSG_HEADER
+            };
+            let mut frame = Vec::new();
+            mavlink::write_versioned_msg(&mut frame, MavlinkVersion::V2, header, &msg).unwrap();
+            client.send_to(&frame, "127.0.0.1:14556").unwrap();
+        }
+
+        // sequence 4 never arrives, 5 is released once it waited for too long
+        for expected in [0, 1, 2, 3, 5] {
+            let (header, received) = server.recv().expect("Failed to receive message");
+            assert_eq!(header.sequence, expected);
+            assert_eq!(received, msg);
+        }
+    }
+
+    /// Test that a UDP connection with a reorder buffer still times out after the read timeout
+    #[test]
+    pub fn test_udp_reorder_read_timeout() {
+        use mavlink::error::MessageReadError;
+        use mavlink::ConnectionOptions;
+        use std::io;
+        use std::time::Instant;
+
+        let server = UdpConnectable::new("0.0.0.0:14585".to_string(), UdpMode::Udpin)
+            .with_reorder_buffer(4, Duration::from_millis(10))
+            .with_options(ConnectionOptions {
+                read_timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            })
+            .connect::<MavMessage>()
+            .expect("Couldn't create server");
+
+        let start = Instant::now();
+        let result = server.recv();
+        assert!(matches!(
+            result,
+            Err(MessageReadError::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        ));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Test that the read timeout of a UDP connection can be changed after it was opened
+    #[test]
+    pub fn test_udp_set_read_timeout() {
+        use mavlink::error::MessageReadError;
+        use std::io;
+
+        let server = UdpConnectable::new("0.0.0.0:14586".to_string(), UdpMode::Udpin)
+            .connect::<MavMessage>()
+            .expect("Couldn't create server");
+        assert_eq!(server.read_timeout().unwrap(), None);
+
+        server
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        assert_eq!(
+            server.read_timeout().unwrap(),
+            Some(Duration::from_millis(50))
+        );
+        assert!(matches!(
+            server.recv(),
+            Err(MessageReadError::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        ));
+
+        let client = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let mut frame = Vec::new();
+        mavlink::write_versioned_msg(
+            &mut frame,
+            MavlinkVersion::V2,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &msg,
+        )
+        .unwrap();
+        client.send_to(&frame, "127.0.0.1:14586").unwrap();
+        assert_eq!(server.recv().expect("Failed to receive message").1, msg);
+    }
+}
+
+#[cfg(all(feature = "std", feature = "udp", feature = "common"))]
+mod test_multi_connection {
+    use mavlink::common::MavMessage;
+    use mavlink::{MavHeader, MultiConnection};
+
+    /// Test that messages of several connections are received and tagged with their source
+    #[test]
+    pub fn test_recv_any() {
+        let mut connections = MultiConnection::<MavMessage>::new();
+        let first = connections.add(mavlink::connect("udpin:0.0.0.0:14557").unwrap());
+        let second = connections.add(mavlink::connect("udpin:0.0.0.0:14558").unwrap());
+        assert_eq!(connections.len(), 2);
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for (port, system_id) in [(14557, 1), (14558, 2)] {
+            let client = mavlink::connect::<MavMessage>(&format!("udpout:127.0.0.1:{port}"))
+                .expect("Couldn't create client");
+            let header = MavHeader {
+                system_id,
+                ..Default::default()
+            };
+            client.send(&header, &msg).unwrap();
+        }
+
+        let mut sources = Vec::new();
+        for _ in 0..2 {
+            let (index, result) = connections.recv_any();
+            let (header, _) = result.expect("Failed to receive message");
+            sources.push((index, header.system_id));
+        }
+        sources.sort();
+        assert_eq!(sources, [(first, 1), (second, 2)]);
+    }
+
+    /// Test that dropping the set stops reading its connections and closes them
+    #[test]
+    pub fn test_drop_stops_readers() {
+        let mut connections = MultiConnection::<MavMessage>::new();
+        connections.add(mavlink::connect("udpin:0.0.0.0:14589").unwrap());
+        drop(connections);
+
+        // the port is free again once the connection is closed
+        mavlink::connect::<MavMessage>("udpin:0.0.0.0:14589").expect("Connection wasn't closed");
+    }
+}
+
+#[cfg(all(feature = "std", feature = "udp", feature = "common"))]
+mod test_udp_rendezvous {
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::{Connectable, RendezvousServer, UdpConnectable, UdpMode};
+
+    /// Test that two udpout peers introduced by a rendezvous server exchange messages directly
+    #[test]
+    pub fn test_udp_rendezvous() {
+        let server = RendezvousServer::bind("127.0.0.1:14561").expect("Couldn't create server");
+        thread::spawn(move || server.run());
+
+        let peer = || {
+            UdpConnectable::new("127.0.0.1:14561".to_string(), UdpMode::Udpout)
+                .with_rendezvous("vehicle-1", Duration::from_millis(20))
+                .connect::<MavMessage>()
+                .expect("Couldn't create peer")
+        };
+        let vehicle = Arc::new(peer());
+        let gcs = peer();
+
+        // the peer addresses are learned while receiving
+        thread::spawn({
+            let vehicle = vehicle.clone();
+            move || while vehicle.recv().is_ok() {}
+        });
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            tx.send(gcs.recv().map(|(_, msg)| msg)).unwrap();
+        });
+
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        for _ in 0..100 {
+            // messages are dropped until the peers were introduced
+            vehicle.send_default(&msg).unwrap();
+            if let Ok(received) = rx.recv_timeout(Duration::from_millis(50)) {
+                assert_eq!(received.expect("Failed to receive message"), msg);
+                return;
+            }
+        }
+        panic!("peers were not introduced");
+    }
+}
+
+#[cfg(all(feature = "std", feature = "udp", feature = "common"))]
+mod test_udp_write_coalescing {
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+    use mavlink::peek_reader::PeekReader;
+    use mavlink::{Connectable, MavlinkVersion, UdpConnectable, UdpMode};
+
+    /// Test that messages sent in a burst are batched into a single datagram
+    #[test]
+    pub fn test_udp_write_coalescing() {
+        let server = UdpSocket::bind("127.0.0.1:14562").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let client = UdpConnectable::new("127.0.0.1:14562".to_string(), UdpMode::Udpout)
+            .with_write_coalescing(Duration::from_millis(500))
+            .connect::<MavMessage>()
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        let lengths: Vec<usize> = (0..5)
+            .map(|_| client.send_default(&msg).expect("Failed to send message"))
+            .collect();
+
+        let mut datagram = [0u8; 1500];
+        let len = server.recv(&mut datagram).expect("Failed to receive batch");
+        assert_eq!(len, lengths.iter().sum::<usize>());
+
+        let mut reader = PeekReader::new(&datagram[..len]);
+        for sequence in 0..5 {
+            let (header, received) =
+                mavlink::read_versioned_msg::<MavMessage, _>(&mut reader, MavlinkVersion::V2)
+                    .expect("Failed to parse batched frame");
+            assert_eq!(header.sequence, sequence);
+            assert_eq!(received, msg);
+        }
+    }
+}
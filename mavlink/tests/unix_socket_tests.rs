@@ -0,0 +1,80 @@
+mod test_shared;
+
+#[cfg(all(unix, feature = "std", feature = "unix-socket", feature = "common"))]
+mod test_unix_socket {
+    use std::thread;
+    use std::time::Duration;
+
+    use mavlink::common::MavMessage;
+
+    fn socket_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    /// Test that messages go both ways between a listening and a connecting socket
+    #[test]
+    pub fn test_unix_socket_loopback() {
+        let path = socket_path("mavlink-unix-loopback");
+        let server_thread = thread::spawn({
+            let address = format!("unixin:{path}");
+            move || {
+                let server =
+                    mavlink::connect::<MavMessage>(&address).expect("Couldn't create server");
+                let (header, msg) = server.recv().expect("Failed to receive message");
+                server.send(&header, &msg).expect("Failed to send message");
+            }
+        });
+
+        // Give some time for the server to create the socket
+        thread::sleep(Duration::from_millis(100));
+
+        let client = mavlink::connect::<MavMessage>(&format!("unix:{path}"))
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client.send_default(&msg).expect("Failed to send message");
+        let (_, received) = client.recv().expect("Failed to receive message");
+        assert_eq!(received, msg);
+
+        server_thread.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Test the same exchange between async connections
+    #[cfg(feature = "tokio-1")]
+    #[tokio::test]
+    pub async fn test_unix_socket_loopback_async() {
+        let path = socket_path("mavlink-unix-loopback-async");
+        let server_task = tokio::spawn({
+            let address = format!("unixin:{path}");
+            async move {
+                let server = mavlink::connect_async::<MavMessage>(&address)
+                    .await
+                    .expect("Couldn't create server");
+                let (header, msg) = server.recv().await.expect("Failed to receive message");
+                server
+                    .send(&header, &msg)
+                    .await
+                    .expect("Failed to send message");
+            }
+        });
+
+        // Give some time for the server to create the socket
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let client = mavlink::connect_async::<MavMessage>(&format!("unix:{path}"))
+            .await
+            .expect("Couldn't create client");
+        let msg = MavMessage::HEARTBEAT(crate::test_shared::get_heartbeat_msg());
+        client
+            .send_default(&msg)
+            .await
+            .expect("Failed to send message");
+        let (_, received) = client.recv().await.expect("Failed to receive message");
+        assert_eq!(received, msg);
+
+        server_task.await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
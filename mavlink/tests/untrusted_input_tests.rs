@@ -0,0 +1,132 @@
+mod test_shared;
+
+/// Parsing arbitrary input must fail with an error instead of panicking
+#[cfg(all(feature = "std", feature = "ardupilotmega"))]
+mod test_untrusted_input {
+    use mavlink::ardupilotmega::MavMessage;
+    use mavlink::error::MessageReadError;
+    use mavlink::peek_reader::PeekReader;
+    use mavlink::{
+        read_v1_raw_message, read_v2_raw_message, read_versioned_msg, MavFrame, MavlinkVersion,
+        Message, MAV_STX, MAV_STX_V2,
+    };
+
+    /// Deterministic xorshift generator, so failures can be reproduced
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_u8(&mut self) -> u8 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as u8
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next_u8()).collect()
+        }
+    }
+
+    /// Random bytes interleaved with frame starts claiming random, possibly oversized, lengths
+    fn stream(rng: &mut Rng, len: usize) -> Vec<u8> {
+        let mut data = rng.bytes(len);
+        for i in (0..len).step_by(37) {
+            data[i] = if rng.next_u8() & 1 == 0 {
+                MAV_STX
+            } else {
+                MAV_STX_V2
+            };
+        }
+        data
+    }
+
+    #[test]
+    pub fn test_parse_arbitrary_payloads() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let payloads: Vec<Vec<u8>> = [0, 1, 7, 64, 255, 300]
+            .into_iter()
+            .map(|len| rng.bytes(len))
+            .chain([vec![0xff; 255]])
+            .collect();
+
+        for id in 0..=u16::MAX as u32 {
+            for payload in &payloads {
+                for version in [MavlinkVersion::V1, MavlinkVersion::V2] {
+                    let _ = MavMessage::parse(version, id, payload);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_deser_arbitrary_frames() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for len in 0..300 {
+            let data = rng.bytes(len);
+            for version in [MavlinkVersion::V1, MavlinkVersion::V2] {
+                let _ = MavFrame::<MavMessage>::deser(version, &data);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_read_arbitrary_streams() {
+        let mut rng = Rng(0xd1b5_4a32_d192_ed03);
+        for len in [0, 1, 5, 11, 280, 4096, 65536] {
+            let data = stream(&mut rng, len);
+            for version in [MavlinkVersion::V1, MavlinkVersion::V2] {
+                let mut reader = PeekReader::new(&data[..]);
+                loop {
+                    match read_versioned_msg::<MavMessage, _>(&mut reader, version) {
+                        Err(MessageReadError::Io(_) | MessageReadError::Timeout) => break,
+                        Ok(_) | Err(MessageReadError::Parse(_)) => {}
+                        #[cfg(feature = "signing")]
+                        Err(MessageReadError::Signature(_)) => {
+                            unreachable!("signatures are not verified")
+                        }
+                    }
+                }
+            }
+
+            let mut reader = PeekReader::new(&data[..]);
+            while let Ok(raw) = read_v1_raw_message::<MavMessage, _>(&mut reader) {
+                let _ = (raw.payload(), raw.checksum(), raw.raw_bytes());
+                let _ = raw.has_valid_crc::<MavMessage>();
+            }
+
+            let mut reader = PeekReader::new(&data[..]);
+            while let Ok(raw) = read_v2_raw_message::<MavMessage, _>(&mut reader) {
+                let _ = (raw.payload(), raw.checksum(), raw.raw_bytes());
+                #[cfg(feature = "signing")]
+                let _ = (raw.signature_timestamp(), raw.signature_value());
+                let _ = raw.has_valid_crc::<MavMessage>();
+            }
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    pub fn test_read_arbitrary_signed_streams() {
+        use mavlink::{read_versioned_msg_signed, SigningConfig, SigningData};
+
+        let signing_data = SigningData::from_config(SigningConfig::new(
+            crate::test_shared::SECRET_KEY,
+            0,
+            false,
+            true,
+        ));
+        let mut rng = Rng(0x94d0_49bb_1331_11eb);
+        let data = stream(&mut rng, 65536);
+        let mut reader = PeekReader::new(&data[..]);
+        loop {
+            match read_versioned_msg_signed::<MavMessage, _>(
+                &mut reader,
+                MavlinkVersion::V2,
+                Some(&signing_data),
+            ) {
+                Err(MessageReadError::Io(_) | MessageReadError::Timeout) => break,
+                Ok(_) | Err(MessageReadError::Parse(_) | MessageReadError::Signature(_)) => {}
+            }
+        }
+    }
+}
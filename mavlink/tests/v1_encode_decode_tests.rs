@@ -59,6 +59,35 @@ mod test_v1_encode_decode {
         assert_eq!(&v[..], HEARTBEAT_V1);
     }
 
+    #[test]
+    pub fn test_write_unsupported_message_id() {
+        use mavlink::common::{MavMessage, CAMERA_INFORMATION_DATA};
+        use mavlink::error::MessageWriteError;
+
+        // CAMERA_INFORMATION has ID 259, which does not fit in a MAVLink 1 header
+        let msg = MavMessage::CAMERA_INFORMATION(CAMERA_INFORMATION_DATA::DEFAULT);
+        let mut v = vec![];
+        let result = mavlink::write_versioned_msg(
+            &mut v,
+            mavlink::MavlinkVersion::V1,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &msg,
+        );
+        assert!(matches!(
+            result,
+            Err(MessageWriteError::UnsupportedInVersion { msgid: 259 })
+        ));
+        assert!(v.is_empty());
+
+        mavlink::write_versioned_msg(
+            &mut v,
+            mavlink::MavlinkVersion::V2,
+            crate::test_shared::COMMON_MSG_HEADER,
+            &msg,
+        )
+        .expect("Failed to write message");
+    }
+
     #[test]
     #[cfg(not(feature = "emit-extensions"))]
     pub fn test_echo_servo_output_raw() {